@@ -0,0 +1,207 @@
+//! View-only paragraph alignment.
+//!
+//! Alignment is never written to the file; it lives with the open buffer and
+//! is used by the status bar and PDF export.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Horizontal alignment of a paragraph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParagraphAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl ParagraphAlign {
+    pub const ALL: [ParagraphAlign; 3] = [Self::Left, Self::Center, Self::Right];
+
+    /// Horizontal offset for a line of `text_width` inside `available` width.
+    pub fn offset(self, text_width: f32, available: f32) -> f32 {
+        let free = (available - text_width).max(0.0);
+        match self {
+            Self::Left => 0.0,
+            Self::Center => free / 2.0,
+            Self::Right => free,
+        }
+    }
+}
+
+impl fmt::Display for ParagraphAlign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Left => write!(f, "Left"),
+            Self::Center => write!(f, "Center"),
+            Self::Right => write!(f, "Right"),
+        }
+    }
+}
+
+/// Line range (inclusive) of the paragraph containing `line`.
+///
+/// Paragraphs are runs of non-blank lines; a blank line is its own paragraph.
+pub fn paragraph_bounds(text: &str, line: usize) -> (usize, usize) {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let line = line.min(lines.len().saturating_sub(1));
+    let is_blank = |i: usize| lines[i].trim().is_empty();
+    if is_blank(line) {
+        return (line, line);
+    }
+
+    let mut start = line;
+    while start > 0 && !is_blank(start - 1) {
+        start -= 1;
+    }
+    let mut end = line;
+    while end + 1 < lines.len() && !is_blank(end + 1) {
+        end += 1;
+    }
+    (start, end)
+}
+
+/// Alignment overrides keyed by the first line of each paragraph, moved
+/// along as lines are added or removed above them.
+#[derive(Debug, Clone, Default)]
+pub struct ParagraphAlignments {
+    by_start: BTreeMap<usize, ParagraphAlign>,
+    /// The text the keys are lines of, kept only while there are any.
+    text: String,
+}
+
+impl ParagraphAlignments {
+    /// Alignment of the paragraph containing `line`.
+    pub fn get(&self, text: &str, line: usize) -> ParagraphAlign {
+        if self.by_start.is_empty() {
+            return ParagraphAlign::Left;
+        }
+        let (start, _) = paragraph_bounds(text, line);
+        self.by_start.get(&start).copied().unwrap_or_default()
+    }
+
+    /// Set the alignment of the paragraph containing `line`.
+    pub fn set(&mut self, text: &str, line: usize, align: ParagraphAlign) {
+        let (start, _) = paragraph_bounds(text, line);
+        if align == ParagraphAlign::Left {
+            self.by_start.remove(&start);
+        } else {
+            self.by_start.insert(start, align);
+        }
+        self.text = if self.by_start.is_empty() { String::new() } else { text.to_string() };
+    }
+
+    /// Move the alignments to the same paragraphs in `text`, the buffer
+    /// after an edit. Deleted paragraphs lose theirs.
+    pub fn follow(&mut self, text: &str) {
+        if self.by_start.is_empty() {
+            return;
+        }
+        let old = std::mem::take(&mut self.text);
+        let prefix = old.bytes().zip(text.bytes()).take_while(|(a, b)| a == b).count();
+        let room = old.len().min(text.len()) - prefix;
+        let suffix = old.bytes().rev().zip(text.bytes().rev()).take(room).take_while(|(a, b)| a == b).count();
+        let count_lines = |s: &[u8]| s.iter().filter(|&&b| b == b'\n').count();
+        let edit_line = count_lines(&old.as_bytes()[..prefix]);
+        let removed = count_lines(&old.as_bytes()[prefix..old.len() - suffix]);
+        let inserted = count_lines(&text.as_bytes()[prefix..text.len() - suffix]);
+
+        let by_start = std::mem::take(&mut self.by_start);
+        for (start, align) in by_start {
+            let line = if start <= edit_line {
+                start
+            } else if start > edit_line + removed {
+                start - removed + inserted
+            } else {
+                continue;
+            };
+            // An edit can join the paragraph to the one above
+            let (start, _) = paragraph_bounds(text, line);
+            self.by_start.entry(start).or_insert(align);
+        }
+        if !self.by_start.is_empty() {
+            self.text = text.to_string();
+        }
+    }
+
+    /// Alignment of every line in `text`, in order.
+    pub fn per_line(&self, text: &str) -> Vec<ParagraphAlign> {
+        let count = text.split('\n').count();
+        let mut aligns = vec![ParagraphAlign::Left; count];
+        for (&start, &align) in &self.by_start {
+            if start >= count {
+                break;
+            }
+            let (_, end) = paragraph_bounds(text, start);
+            aligns[start..=end].fill(align);
+        }
+        aligns
+    }
+
+    pub fn clear(&mut self) {
+        self.by_start.clear();
+        self.text.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "one\ntwo\n\nthree\nfour\nfive";
+
+    #[test]
+    fn test_paragraph_bounds() {
+        assert_eq!(paragraph_bounds(TEXT, 1), (0, 1));
+        assert_eq!(paragraph_bounds(TEXT, 2), (2, 2));
+        assert_eq!(paragraph_bounds(TEXT, 4), (3, 5));
+    }
+
+    #[test]
+    fn test_set_applies_to_whole_paragraph() {
+        let mut aligns = ParagraphAlignments::default();
+        aligns.set(TEXT, 4, ParagraphAlign::Center);
+        assert_eq!(aligns.get(TEXT, 3), ParagraphAlign::Center);
+        assert_eq!(aligns.get(TEXT, 0), ParagraphAlign::Left);
+        assert_eq!(
+            aligns.per_line(TEXT),
+            vec![
+                ParagraphAlign::Left,
+                ParagraphAlign::Left,
+                ParagraphAlign::Left,
+                ParagraphAlign::Center,
+                ParagraphAlign::Center,
+                ParagraphAlign::Center,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_follow_moves_with_lines_above() {
+        let mut aligns = ParagraphAlignments::default();
+        aligns.set(TEXT, 4, ParagraphAlign::Right);
+        let edited = "zero\n\none\ntwo\n\nthree\nfour\nfive";
+        aligns.follow(edited);
+        assert_eq!(aligns.get(edited, 6), ParagraphAlign::Right);
+        assert_eq!(aligns.get(edited, 2), ParagraphAlign::Left);
+
+        // Typing inside the paragraph keeps it
+        let typed = "zero\n\none\ntwo\n\nthree!\nfour\nfive";
+        aligns.follow(typed);
+        assert_eq!(aligns.get(typed, 5), ParagraphAlign::Right);
+
+        // Deleting it drops its alignment
+        let cut = "zero\n\none\ntwo\n";
+        aligns.follow(cut);
+        assert_eq!(aligns.per_line(cut), vec![ParagraphAlign::Left; 5]);
+        assert!(aligns.text.is_empty());
+    }
+
+    #[test]
+    fn test_offset() {
+        assert_eq!(ParagraphAlign::Left.offset(40.0, 100.0), 0.0);
+        assert_eq!(ParagraphAlign::Center.offset(40.0, 100.0), 30.0);
+        assert_eq!(ParagraphAlign::Right.offset(40.0, 100.0), 60.0);
+        assert_eq!(ParagraphAlign::Right.offset(140.0, 100.0), 0.0);
+    }
+}
@@ -0,0 +1,238 @@
+//! Side notes on lines of a file, kept in the settings store and never
+//! written to the file itself. Each note remembers a hash of its line's
+//! text, so it follows the line when lines above it are added or removed.
+
+use gpui::*;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::highlighter::DiagnosticSeverity;
+use gpui_component::input::{Input, InputState};
+use gpui_component::notification::Notification;
+use gpui_component::{h_flex, WindowExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use super::{TextEditor, TextMark};
+use crate::settings::store;
+
+/// Where notes are kept in the settings store.
+const ANNOTATIONS_KEY: &str = "annotations.json";
+
+/// A note on one line.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Annotation {
+    /// 0-based line the note was written on.
+    pub line: usize,
+    /// `line_hash` of that line's text.
+    pub line_hash: u64,
+    pub note: String,
+}
+
+/// Notes for every file, saved to `annotations.json` in the settings
+/// store and keyed by absolute path.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AnnotationStore {
+    pub files: BTreeMap<PathBuf, Vec<Annotation>>,
+}
+
+impl AnnotationStore {
+    pub fn load() -> Self {
+        if let Some(contents) = store().read(ANNOTATIONS_KEY) {
+            if let Ok(store) = serde_json::from_str(&contents) {
+                return store;
+            }
+        }
+        Self::default()
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(err) = store().write(ANNOTATIONS_KEY, &json) {
+                    warn!(error = %err, "Failed to save annotations");
+                }
+            }
+            Err(err) => warn!(error = %err, "Failed to serialize annotations"),
+        }
+    }
+}
+
+/// The key a file's notes are stored under.
+fn file_key(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// FNV-1a hash of a line, ignoring surrounding whitespace. Stable across
+/// runs and builds, unlike the standard library's hasher.
+pub fn line_hash(line: &str) -> u64 {
+    line.trim().bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// The line each note now belongs to: its own line if unchanged, else the
+/// nearest line with the same text. Notes whose line is gone get `None`.
+pub fn anchor(annotations: &[Annotation], lines: &[&str]) -> Vec<Option<usize>> {
+    let hashes: Vec<u64> = lines.iter().map(|line| line_hash(line)).collect();
+    annotations
+        .iter()
+        .map(|a| {
+            (0..hashes.len())
+                .filter(|&i| hashes[i] == a.line_hash)
+                .min_by_key(|&i| i.abs_diff(a.line))
+        })
+        .collect()
+}
+
+impl TextEditor {
+    /// Load the notes for the open file.
+    pub(super) fn load_annotations(&mut self, cx: &App) {
+        self.annotations = match self.current_file(cx) {
+            Some(path) => AnnotationStore::load().files.remove(&file_key(path)).unwrap_or_default(),
+            None => Vec::new(),
+        };
+        self.refresh_annotated_lines(cx);
+    }
+
+    fn save_annotations(&self, cx: &App) {
+        let Some(path) = self.current_file(cx) else { return };
+        let mut store = AnnotationStore::load();
+        if self.annotations.is_empty() {
+            store.files.remove(&file_key(path));
+        } else {
+            store.files.insert(file_key(path), self.annotations.clone());
+        }
+        store.save();
+    }
+
+    /// Index of the note on `line`, after anchoring to `text`.
+    fn annotation_on(&self, text: &str, line: usize) -> Option<usize> {
+        let lines: Vec<&str> = text.split('\n').collect();
+        anchor(&self.annotations, &lines).into_iter().position(|l| l == Some(line))
+    }
+
+    /// Find the lines the notes are on again, after an edit or a change to
+    /// the notes.
+    pub(super) fn refresh_annotated_lines(&mut self, cx: &App) {
+        self.annotated_lines = if self.annotations.is_empty() {
+            BTreeSet::new()
+        } else {
+            let text = self.input_state.read(cx).value();
+            let lines: Vec<&str> = text.split('\n').collect();
+            anchor(&self.annotations, &lines).into_iter().flatten().collect()
+        };
+        self.refresh_caret_annotated(cx);
+    }
+
+    /// Note whether the caret's line has a note, for the status bar.
+    pub(super) fn refresh_caret_annotated(&mut self, cx: &App) {
+        self.caret_annotated = self.annotated_lines.contains(&self.caret_line_column(cx).0);
+    }
+
+    /// Markers under annotated lines, showing the note on hover.
+    pub(super) fn annotation_marks(&self, text: &str) -> Vec<TextMark> {
+        if self.annotations.is_empty() {
+            return Vec::new();
+        }
+        let lines: Vec<&str> = text.split('\n').collect();
+        let mut starts = Vec::with_capacity(lines.len());
+        let mut offset = 0;
+        for line in &lines {
+            starts.push(offset);
+            offset += line.len() + 1;
+        }
+        anchor(&self.annotations, &lines)
+            .into_iter()
+            .zip(&self.annotations)
+            .filter_map(|(line, a)| {
+                let line = line?;
+                let content = lines[line].trim_end_matches('\r');
+                let indent = content.len() - content.trim_start().len();
+                Some(TextMark {
+                    range: starts[line] + indent..starts[line] + content.len(),
+                    message: format!("Note: {}", a.note),
+                    severity: DiagnosticSeverity::Hint,
+                })
+            })
+            .collect()
+    }
+
+    /// Ask for a note on the caret's line, editing the existing one if any.
+    /// An empty note removes it.
+    pub fn annotate_line_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.current_file(cx).is_none() {
+            window.push_notification(Notification::warning("Save the document before annotating it."), cx);
+            return;
+        }
+        let text = self.input_state.read(cx).value().to_string();
+        let (line, _) = self.caret_line_column(cx);
+        let Some(line_text) = text.split('\n').nth(line).filter(|l| !l.trim().is_empty()) else {
+            window.push_notification(Notification::warning("Blank lines can't be annotated."), cx);
+            return;
+        };
+        let line_hash = line_hash(line_text);
+        let existing = self.annotation_on(&text, line).map(|i| self.annotations[i].note.clone()).unwrap_or_default();
+        let note_input = cx.new(|cx| InputState::new(window, cx).placeholder("Note").default_value(existing));
+        let editor = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let note_input_for_ok = note_input.clone();
+            let editor = editor.clone();
+            dialog
+                .title(format!("Annotate Line {}", line + 1))
+                .w(px(420.0))
+                .child(h_flex().gap_3().items_center().child("Note").child(div().flex_grow().child(Input::new(&note_input))))
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Save"))
+                .on_ok(move |_, window, cx| {
+                    let note = note_input_for_ok.read(cx).value().trim().to_string();
+                    let _ = editor.update(cx, |ed, cx| ed.set_annotation(line, line_hash, note, window, cx));
+                    true
+                })
+        });
+    }
+
+    fn set_annotation(&mut self, line: usize, line_hash: u64, note: String, window: &mut Window, cx: &mut Context<Self>) {
+        let text = self.input_state.read(cx).value().to_string();
+        if let Some(i) = self.annotation_on(&text, line) {
+            self.annotations.remove(i);
+        }
+        if !note.is_empty() {
+            self.annotations.push(Annotation { line, line_hash, note });
+        }
+        debug!(line, notes = self.annotations.len(), "Updated annotation");
+        self.save_annotations(cx);
+        self.refresh_annotated_lines(cx);
+        self.sync_input_mode(window, cx);
+        self.refresh_diagnostics(cx);
+        cx.notify();
+    }
+
+    /// Remove the note on the caret's line.
+    pub fn remove_line_annotation(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let (line, _) = self.caret_line_column(cx);
+        self.set_annotation(line, 0, String::new(), window, cx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{anchor, line_hash, Annotation};
+
+    #[test]
+    fn test_line_hash_ignores_surrounding_whitespace() {
+        assert_eq!(line_hash("  let x = 1;\r"), line_hash("let x = 1;"));
+        assert_ne!(line_hash("let x = 1;"), line_hash("let x = 2;"));
+    }
+
+    #[test]
+    fn test_anchor_follows_moved_lines() {
+        let note = |line, text| Annotation { line, line_hash: line_hash(text), note: String::new() };
+        let notes = [note(1, "b"), note(0, "a"), note(2, "gone")];
+        // A line was inserted at the top
+        let lines = ["new", "a", "b", "c"];
+        assert_eq!(anchor(&notes, &lines), [Some(2), Some(1), None]);
+        // The nearest of several identical lines wins
+        assert_eq!(anchor(&[note(3, "x")], &["x", "y", "y", "y", "x"]), [Some(4)]);
+    }
+}
@@ -0,0 +1,136 @@
+//! Teleprompter-style reading: the text scrolls up a line at a time at an
+//! adjustable speed. While it runs, Up and Down change the speed, Space
+//! pauses and Escape stops.
+
+use gpui::*;
+use gpui_component::input::Position;
+use gpui_component::RopeExt;
+use std::ops::Range;
+use std::time::Duration;
+
+use super::TextEditor;
+
+/// Speeds to step through, in lines per minute.
+const SPEEDS: [u32; 9] = [10, 15, 20, 30, 45, 60, 90, 120, 180];
+/// Where a new auto-scroll starts.
+const DEFAULT_SPEED: u32 = 30;
+
+/// A running auto-scroll.
+pub struct AutoScroll {
+    pub lines_per_minute: u32,
+    pub paused: bool,
+    /// The selection when auto-scroll started. Scrolling moves the caret,
+    /// since the input keeps its scroll offset private, so stopping puts
+    /// the caret back.
+    selection: Range<usize>,
+    _task: Task<()>,
+}
+
+/// The next speed up or down from `current`, staying at the ends.
+fn step_speed(current: u32, faster: bool) -> u32 {
+    if faster {
+        SPEEDS.iter().copied().find(|&s| s > current).unwrap_or(SPEEDS[SPEEDS.len() - 1])
+    } else {
+        SPEEDS.iter().copied().rev().find(|&s| s < current).unwrap_or(SPEEDS[0])
+    }
+}
+
+impl TextEditor {
+    pub fn auto_scroll_enabled(&self) -> bool {
+        self.auto_scroll.is_some()
+    }
+
+    pub fn toggle_auto_scroll(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.auto_scroll.is_some() {
+            self.stop_auto_scroll(window, cx);
+        } else {
+            let selection = self.selection_range(window, cx).unwrap_or_default();
+            let task = cx.spawn_in(window, async move |this, cx| loop {
+                let Ok(Some(speed)) = this.update(cx, |this, _| this.auto_scroll.as_ref().map(|a| a.lines_per_minute)) else {
+                    break;
+                };
+                cx.background_executor().timer(Duration::from_millis(60_000 / speed as u64)).await;
+                let scrolled = this.update_in(cx, |this, window, cx| match &this.auto_scroll {
+                    Some(auto) if auto.paused => true,
+                    Some(_) => this.scroll_one_line(window, cx),
+                    None => false,
+                });
+                if !matches!(scrolled, Ok(true)) {
+                    let _ = this.update_in(cx, |this, window, cx| this.stop_auto_scroll(window, cx));
+                    break;
+                }
+            });
+            self.auto_scroll = Some(AutoScroll { lines_per_minute: DEFAULT_SPEED, paused: false, selection, _task: task });
+        }
+        cx.notify();
+    }
+
+    /// End auto-scroll, with the selection back where it started.
+    fn stop_auto_scroll(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(auto) = self.auto_scroll.take() else { return };
+        let text = self.input_state.read(cx).value();
+        // Edits while it ran may have shortened the buffer
+        let clamp = |offset: usize| (0..=offset.min(text.len())).rev().find(|&i| text.is_char_boundary(i)).unwrap_or(0);
+        let selection = clamp(auto.selection.start)..clamp(auto.selection.end);
+        self.select_range(selection, window, cx);
+        cx.notify();
+    }
+
+    /// Bring the line below the screen into view; false at the end.
+    fn scroll_one_line(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let Some(viewport) = self.viewport(window, cx) else { return false };
+        self.input_state.update(cx, |state, cx| {
+            let next = viewport.last + 1;
+            if next >= state.text().lines_len() {
+                return false;
+            }
+            state.set_cursor_position(Position { line: next as u32, character: 0 }, window, cx);
+            true
+        })
+    }
+
+    /// Speed, pause and stop keys while auto-scroll runs.
+    pub(super) fn auto_scroll_key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if self.auto_scroll.is_none() || !self.input_focused(window, cx) {
+            return;
+        }
+        let Some(auto) = self.auto_scroll.as_mut() else { return };
+        let keystroke = &event.keystroke;
+        if keystroke.modifiers.modified() {
+            return;
+        }
+        match keystroke.key.as_str() {
+            "up" => auto.lines_per_minute = step_speed(auto.lines_per_minute, true),
+            "down" => auto.lines_per_minute = step_speed(auto.lines_per_minute, false),
+            "space" => auto.paused = !auto.paused,
+            "escape" => self.stop_auto_scroll(window, cx),
+            _ => return,
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    /// Status bar text while auto-scroll runs.
+    pub(super) fn auto_scroll_label(&self) -> Option<String> {
+        self.auto_scroll.as_ref().map(|auto| match auto.paused {
+            true => "Auto-scroll paused (Space resumes)".to_string(),
+            false => format!("Auto-scroll {} lines/min (Up/Down, Space, Esc)", auto.lines_per_minute),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::step_speed;
+
+    #[test]
+    fn test_step_speed() {
+        assert_eq!(step_speed(30, true), 45);
+        assert_eq!(step_speed(30, false), 20);
+        assert_eq!(step_speed(180, true), 180);
+        assert_eq!(step_speed(10, false), 10);
+        // From a speed off the list, to its neighbours on it
+        assert_eq!(step_speed(50, true), 60);
+        assert_eq!(step_speed(50, false), 45);
+    }
+}
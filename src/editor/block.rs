@@ -0,0 +1,340 @@
+//! Column (block) selection: a rectangle of lines × character columns that
+//! is typed into, deleted, copied and pasted as a whole.
+
+use gpui::*;
+use gpui_component::input::{Copy as CopyAction, Cut as CutAction};
+use gpui_component::RopeExt;
+
+use super::{BlockSelectDown, BlockSelectLeft, BlockSelectRight, BlockSelectUp, TextEditor};
+
+/// Corners of a block as (line, column) pairs; columns count characters and
+/// may lie past the end of short lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockSelection {
+    pub anchor: (usize, usize),
+    pub head: (usize, usize),
+}
+
+impl BlockSelection {
+    pub fn at(line: usize, column: usize) -> Self {
+        Self { anchor: (line, column), head: (line, column) }
+    }
+
+    pub fn lines(&self) -> std::ops::RangeInclusive<usize> {
+        self.anchor.0.min(self.head.0)..=self.anchor.0.max(self.head.0)
+    }
+
+    pub fn columns(&self) -> std::ops::Range<usize> {
+        self.anchor.1.min(self.head.1)..self.anchor.1.max(self.head.1)
+    }
+
+    /// A zero-width block of carets at `column` on the same lines.
+    fn collapsed(&self, column: usize) -> Self {
+        Self {
+            anchor: (self.anchor.0, column),
+            head: (self.head.0, column),
+        }
+    }
+}
+
+/// Byte offset of char `column` in `line`, if the line is that long.
+fn byte_at(line: &str, column: usize) -> Option<usize> {
+    line.char_indices().map(|(i, _)| i).chain([line.len()]).nth(column)
+}
+
+/// Apply `edit` to the block's part of each line, as byte offsets. With
+/// `pad`, lines ending left of the block are first padded out to it.
+fn edit_lines(text: &str, block: &BlockSelection, pad: bool, mut edit: impl FnMut(&mut String, usize, usize)) -> String {
+    let columns = block.columns();
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            if !block.lines().contains(&i) {
+                return line.to_string();
+            }
+            let (body, cr) = match line.strip_suffix('\r') {
+                Some(body) => (body, "\r"),
+                None => (line, ""),
+            };
+            let mut body = body.to_string();
+            let length = body.chars().count();
+            if pad && length < columns.start {
+                body.push_str(&" ".repeat(columns.start - length));
+            }
+            if let Some(start) = byte_at(&body, columns.start) {
+                let end = byte_at(&body, columns.end).unwrap_or(body.len());
+                edit(&mut body, start, end);
+            }
+            body + cr
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replace the block on every line with `insert`, leaving carets after it.
+pub fn block_insert(text: &str, block: &BlockSelection, insert: &str) -> (String, BlockSelection) {
+    let edited = edit_lines(text, block, !insert.is_empty(), |line, start, end| line.replace_range(start..end, insert));
+    (edited, block.collapsed(block.columns().start + insert.chars().count()))
+}
+
+/// Backspace: delete the block, or the character before each caret.
+pub fn block_backspace(text: &str, block: &BlockSelection) -> (String, BlockSelection) {
+    let columns = block.columns();
+    if !columns.is_empty() {
+        return block_insert(text, block, "");
+    }
+    let Some(column) = columns.start.checked_sub(1) else {
+        return (text.to_string(), *block);
+    };
+    block_insert(text, &BlockSelection { anchor: (block.anchor.0, column), head: (block.head.0, columns.start) }, "")
+}
+
+/// Delete: delete the block, or the character after each caret.
+pub fn block_delete(text: &str, block: &BlockSelection) -> (String, BlockSelection) {
+    let columns = block.columns();
+    if !columns.is_empty() {
+        return block_insert(text, block, "");
+    }
+    block_insert(text, &BlockSelection { anchor: block.anchor, head: (block.head.0, columns.start + 1) }, "")
+}
+
+/// The block's text, one line per row.
+pub fn block_text(text: &str, block: &BlockSelection) -> String {
+    let mut rows = Vec::new();
+    edit_lines(text, block, false, |line, start, end| rows.push(line[start..end].to_string()));
+    rows.join("\n")
+}
+
+/// Paste `clip` into the block: one clipboard line per row when the counts
+/// match, otherwise the whole clipboard on every row.
+pub fn block_paste(text: &str, block: &BlockSelection, clip: &str) -> (String, BlockSelection) {
+    let rows: Vec<&str> = clip.lines().collect();
+    if rows.len() != block.lines().count() || rows.len() < 2 {
+        return block_insert(text, block, clip);
+    }
+    let mut widest = 0;
+    let mut row = 0;
+    let columns = block.columns();
+    let edited = edit_lines(text, block, true, |line, start, end| {
+        line.replace_range(start..end, rows[row]);
+        widest = widest.max(rows[row].chars().count());
+        row += 1;
+    });
+    (edited, block.collapsed(columns.start + widest))
+}
+
+impl TextEditor {
+    /// Start or extend the block selection by one line or column.
+    fn extend_block(&mut self, lines: isize, columns: isize, window: &mut Window, cx: &mut Context<Self>) {
+        let text = self.input_state.read(cx).text().clone();
+        let mut block = self.block.unwrap_or_else(|| {
+            let position = self.input_state.read(cx).cursor_position();
+            BlockSelection::at(position.line as usize, position.character as usize)
+        });
+        let last_line = text.lines_len().saturating_sub(1);
+        block.head.0 = block.head.0.saturating_add_signed(lines).min(last_line);
+        block.head.1 = block.head.1.saturating_add_signed(columns);
+        self.set_block(Some(block), window, cx);
+    }
+
+    pub fn block_select_up(&mut self, _: &BlockSelectUp, window: &mut Window, cx: &mut Context<Self>) {
+        self.extend_block(-1, 0, window, cx);
+    }
+
+    pub fn block_select_down(&mut self, _: &BlockSelectDown, window: &mut Window, cx: &mut Context<Self>) {
+        self.extend_block(1, 0, window, cx);
+    }
+
+    pub fn block_select_left(&mut self, _: &BlockSelectLeft, window: &mut Window, cx: &mut Context<Self>) {
+        self.extend_block(0, -1, window, cx);
+    }
+
+    pub fn block_select_right(&mut self, _: &BlockSelectRight, window: &mut Window, cx: &mut Context<Self>) {
+        self.extend_block(0, 1, window, cx);
+    }
+
+    /// Replace the block selection, mirroring its head row in the input's
+    /// own selection so the active edge stays visible.
+    fn set_block(&mut self, block: Option<BlockSelection>, window: &mut Window, cx: &mut Context<Self>) {
+        self.block = block;
+        if let Some(block) = block {
+            let text = self.input_state.read(cx).text().clone();
+            let line = block.head.0;
+            let start = text.line_start_offset(line);
+            let row = text.slice(start..text.line_end_offset(line)).to_string();
+            let offset = |column: usize| start + byte_at(&row, column).unwrap_or(row.len());
+            let columns = block.columns();
+            self.select_range(offset(columns.start)..offset(columns.end), window, cx);
+        }
+        cx.notify();
+    }
+
+    /// Replace the lines under the block with the result of `edit`, as one
+    /// undo step, and keep the returned block.
+    fn edit_block(&mut self, edit: impl FnOnce(&str, &BlockSelection) -> (String, BlockSelection), window: &mut Window, cx: &mut Context<Self>) {
+        let Some(block) = self.block else { return };
+        if self.read_only {
+            return;
+        }
+        let text = self.input_state.read(cx).text().clone();
+        let first = *block.lines().start();
+        let span = text.line_start_offset(first)..text.line_end_offset(*block.lines().end());
+        let relative = BlockSelection {
+            anchor: (block.anchor.0 - first, block.anchor.1),
+            head: (block.head.0 - first, block.head.1),
+        };
+        let (edited, moved) = edit(&text.slice(span.clone()).to_string(), &relative);
+        self.transform_range(span, |_| edited, window, cx);
+        let moved = BlockSelection {
+            anchor: (moved.anchor.0 + first, moved.anchor.1),
+            head: (moved.head.0 + first, moved.head.1),
+        };
+        self.set_block(Some(moved), window, cx);
+    }
+
+    /// Keys typed while a block is selected go to every row; anything but
+    /// text editing keys ends block mode.
+    pub(super) fn block_key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if self.block.is_none() || !self.input_focused(window, cx) {
+            return;
+        }
+        let keystroke = &event.keystroke;
+        let modifiers = keystroke.modifiers;
+        if modifiers.alt && modifiers.shift {
+            return;
+        }
+        if modifiers.control || modifiers.platform || modifiers.alt {
+            // Shortcuts such as copy and paste are handled as actions
+            return;
+        }
+        match keystroke.key.as_str() {
+            "backspace" => self.edit_block(block_backspace, window, cx),
+            "delete" => self.edit_block(block_delete, window, cx),
+            "escape" => self.set_block(None, window, cx),
+            _ => match keystroke.key_char.clone().filter(|c| !c.chars().any(char::is_control)) {
+                Some(typed) => self.edit_block(|text, block| block_insert(text, block, &typed), window, cx),
+                None => {
+                    // Plain navigation leaves block mode and moves as usual
+                    self.block = None;
+                    cx.notify();
+                    return;
+                }
+            },
+        }
+        cx.stop_propagation();
+    }
+
+    /// Copy the block instead of the input's selection.
+    pub(super) fn block_copy(&mut self, _: &CopyAction, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(block) = self.block else { return };
+        let text = self.input_state.read(cx).value();
+        cx.write_to_clipboard(ClipboardItem::new_string(block_text(&text, &block)));
+        cx.stop_propagation();
+    }
+
+    pub(super) fn block_cut(&mut self, _: &CutAction, window: &mut Window, cx: &mut Context<Self>) {
+        if self.block.is_none() {
+            return;
+        }
+        self.block_copy(&CopyAction, window, cx);
+        self.edit_block(|text, block| block_insert(text, block, ""), window, cx);
+    }
+
+    /// Paste into every row of the block; returns false outside block mode.
+    pub(super) fn block_paste(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        if self.block.is_none() {
+            return false;
+        }
+        if let Some(clip) = cx.read_from_clipboard().and_then(|item| item.text()) {
+            self.edit_block(|text, block| block_paste(text, block, &clip), window, cx);
+        }
+        true
+    }
+
+    /// Buffer position under a window point, as (line, column).
+    fn block_position_at(&self, point: Point<Pixels>, window: &mut Window, cx: &mut Context<Self>) -> Option<(usize, usize)> {
+        self.input_state.update(cx, |state, cx| {
+            let offset = state.character_index_for_point(point, window, cx)?;
+            let text = state.text();
+            let position = text.offset_to_position(text.offset_utf16_to_offset(offset));
+            Some((position.line as usize, position.character as usize))
+        })
+    }
+
+    /// Alt+click starts a block at the pointer.
+    pub(super) fn block_mouse_down(&mut self, event: &MouseDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if event.button != MouseButton::Left || !event.modifiers.alt {
+            if self.block.take().is_some() {
+                cx.notify();
+            }
+            return;
+        }
+        let Some((line, column)) = self.block_position_at(event.position, window, cx) else { return };
+        self.block_dragging = true;
+        self.set_block(Some(BlockSelection::at(line, column)), window, cx);
+        cx.stop_propagation();
+    }
+
+    /// Alt+drag extends the block to the pointer.
+    pub(super) fn block_mouse_move(&mut self, event: &MouseMoveEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.block_dragging || event.pressed_button != Some(MouseButton::Left) {
+            self.block_dragging = false;
+            return;
+        }
+        let Some(head) = self.block_position_at(event.position, window, cx) else { return };
+        if let Some(mut block) = self.block.filter(|b| b.head != head) {
+            block.head = head;
+            self.set_block(Some(block), window, cx);
+        }
+    }
+
+    /// Status bar text for the block, e.g. "Block 3 × 5".
+    pub(super) fn block_label(&self) -> Option<String> {
+        let block = self.block?;
+        Some(format!("Block {} × {}", block.lines().count(), block.columns().len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{block_backspace, block_delete, block_insert, block_paste, block_text, BlockSelection};
+
+    const TEXT: &str = "key1 = a\nk2 = bb\nkey3 = c";
+
+    fn block(lines: (usize, usize), columns: (usize, usize)) -> BlockSelection {
+        BlockSelection { anchor: (lines.0, columns.0), head: (lines.1, columns.1) }
+    }
+
+    #[test]
+    fn test_block_text() {
+        assert_eq!(block_text(TEXT, &block((0, 2), (0, 2))), "ke\nk2\nke");
+        assert_eq!(block_text(TEXT, &block((0, 1), (7, 9))), "a\n");
+    }
+
+    #[test]
+    fn test_block_insert_types_on_every_row() {
+        let (text, carets) = block_insert(TEXT, &block((0, 1), (0, 0)), "# ");
+        assert_eq!(text, "# key1 = a\n# k2 = bb\nkey3 = c");
+        assert_eq!(carets, block((0, 1), (2, 2)));
+        // Short lines are padded out to the block
+        let (text, _) = block_insert("abc\na", &block((0, 1), (2, 2)), "|");
+        assert_eq!(text, "ab|c\na |");
+    }
+
+    #[test]
+    fn test_block_delete_and_backspace() {
+        assert_eq!(block_backspace(TEXT, &block((0, 2), (0, 2))).0, "y1 = a\n = bb\ny3 = c");
+        let (text, carets) = block_backspace(TEXT, &block((0, 1), (1, 1)));
+        assert_eq!(text, "ey1 = a\n2 = bb\nkey3 = c");
+        assert_eq!(carets, block((0, 1), (0, 0)));
+        assert_eq!(block_delete("ab\ncd\r\nef", &block((0, 1), (0, 0))).0, "b\nd\r\nef");
+    }
+
+    #[test]
+    fn test_block_paste() {
+        let (text, carets) = block_paste("a\nb", &block((0, 1), (1, 1)), "1\n22");
+        assert_eq!(text, "a1\nb22");
+        assert_eq!(carets, block((0, 1), (3, 3)));
+        assert_eq!(block_paste("a\nb", &block((0, 1), (0, 0)), "x").0, "xa\nxb");
+    }
+}
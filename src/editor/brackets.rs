@@ -0,0 +1,241 @@
+//! Bracket and quote pairing: typing an opening bracket adds its closer,
+//! and the bracket next to the caret has its match underlined.
+
+use gpui::*;
+use gpui_component::highlighter::DiagnosticSeverity;
+use gpui_component::RopeExt;
+use std::ops::Range;
+
+use super::{TextEditor, TextMark};
+
+/// Brackets that are paired and matched, opening then closing.
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+/// Quotes are paired but not matched, as they open and close alike.
+const QUOTES: [char; 3] = ['"', '\'', '`'];
+/// Bytes searched for a match, so huge files stay responsive.
+const SCAN_LIMIT: usize = 256 * 1024;
+
+/// A replacement and the selection that follows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+    pub selection: Range<usize>,
+}
+
+fn closer(open: char) -> Option<char> {
+    BRACKETS.iter().find(|(o, _)| *o == open).map(|(_, c)| *c).or_else(|| QUOTES.contains(&open).then_some(open))
+}
+
+fn is_closer(c: char) -> bool {
+    BRACKETS.iter().any(|(_, close)| *close == c) || QUOTES.contains(&c)
+}
+
+/// What typing `typed` over `selection` should do instead of inserting it:
+/// wrap the selection, add the closer, or step over a closer already there.
+pub fn on_type(text: &str, selection: Range<usize>, typed: char) -> Option<PairEdit> {
+    let caret = selection.end;
+    let next = text[caret..].chars().next();
+    if selection.is_empty() && is_closer(typed) && next == Some(typed) {
+        let after = caret + typed.len_utf8();
+        return Some(PairEdit { range: caret..caret, replacement: String::new(), selection: after..after });
+    }
+    let close = closer(typed)?;
+    let open_len = typed.len_utf8();
+    if !selection.is_empty() {
+        let replacement = format!("{}{}{}", typed, &text[selection.clone()], close);
+        let inner = selection.start + open_len..selection.end + open_len;
+        return Some(PairEdit { range: selection, replacement, selection: inner });
+    }
+    // Only pair where nothing follows that the pair would swallow
+    if next.is_some_and(|c| !c.is_whitespace() && !is_closer(c)) {
+        return None;
+    }
+    if QUOTES.contains(&typed) {
+        // An apostrophe inside a word, or a quote closing one
+        let prev = text[..caret].chars().next_back();
+        if prev.is_some_and(|c| c.is_alphanumeric() || c == typed) {
+            return None;
+        }
+    }
+    let inside = caret + open_len;
+    Some(PairEdit { range: caret..caret, replacement: format!("{}{}", typed, close), selection: inside..inside })
+}
+
+/// Backspace between an empty pair removes both halves.
+pub fn on_backspace(text: &str, selection: Range<usize>) -> Option<PairEdit> {
+    if !selection.is_empty() {
+        return None;
+    }
+    let caret = selection.start;
+    let prev = text[..caret].chars().next_back()?;
+    let next = text[caret..].chars().next()?;
+    if closer(prev) != Some(next) {
+        return None;
+    }
+    let start = caret - prev.len_utf8();
+    Some(PairEdit { range: start..caret + next.len_utf8(), replacement: String::new(), selection: start..start })
+}
+
+/// Offsets of the bracket at or just before `caret` and of its match.
+pub fn matching_bracket(text: &str, caret: usize) -> Option<(usize, usize)> {
+    let at = text[caret..].chars().next().map(|c| (caret, c));
+    let before = text[..caret].char_indices().next_back();
+    [at, before].into_iter().flatten().find_map(|(offset, c)| {
+        if let Some(&(open, close)) = BRACKETS.iter().find(|(o, _)| *o == c) {
+            let end = (offset + SCAN_LIMIT).min(text.len());
+            let mut depth = 0usize;
+            for (i, ch) in text[offset..end].char_indices() {
+                if ch == open {
+                    depth += 1;
+                } else if ch == close {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some((offset, offset + i));
+                    }
+                }
+            }
+            None
+        } else if let Some(&(open, close)) = BRACKETS.iter().find(|(_, cl)| *cl == c) {
+            let start = offset.saturating_sub(SCAN_LIMIT);
+            let start = (start..offset).find(|&i| text.is_char_boundary(i)).unwrap_or(offset);
+            let mut depth = 0usize;
+            for (i, ch) in text[start..offset + c.len_utf8()].char_indices().rev() {
+                if ch == close {
+                    depth += 1;
+                } else if ch == open {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some((offset, start + i));
+                    }
+                }
+            }
+            None
+        } else {
+            None
+        }
+    })
+}
+
+impl TextEditor {
+    pub fn set_bracket_options(&mut self, auto_pair: bool, highlight: bool, window: &mut Window, cx: &mut Context<Self>) {
+        self.auto_pair = auto_pair;
+        self.match_brackets = highlight;
+        self.sync_input_mode(window, cx);
+        self.refresh_bracket_match(cx);
+        self.push_marks(cx);
+    }
+
+    /// Pair, wrap or step over brackets and quotes as they are typed.
+    pub(super) fn bracket_key_down(&mut self, event: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.auto_pair || self.read_only || self.large_file || self.block.is_some() || !self.input_focused(window, cx) {
+            return;
+        }
+        let keystroke = &event.keystroke;
+        let modifiers = keystroke.modifiers;
+        // AltGr, which types brackets on many layouts, arrives as ctrl+alt
+        if modifiers.platform || modifiers.control != modifiers.alt {
+            return;
+        }
+        let Some(selection) = self.selection_range(window, cx) else { return };
+        let text = self.input_state.read(cx).value();
+        let edit = match keystroke.key.as_str() {
+            "backspace" => on_backspace(&text, selection),
+            _ => {
+                let mut typed = keystroke.key_char.as_deref().unwrap_or_default().chars();
+                match (typed.next(), typed.next()) {
+                    (Some(c), None) => on_type(&text, selection, c),
+                    _ => None,
+                }
+            }
+        };
+        let Some(edit) = edit else { return };
+        cx.stop_propagation();
+        if edit.range.is_empty() && edit.replacement.is_empty() {
+            self.select_range(edit.selection, window, cx);
+            return;
+        }
+        let replacement = edit.replacement;
+        self.input_state.update(cx, |state, cx| {
+            let text = state.text().clone();
+            let range = text.offset_to_offset_utf16(edit.range.start)..text.offset_to_offset_utf16(edit.range.end);
+            state.replace_text_in_range(Some(range), &replacement, window, cx);
+        });
+        self.select_range(edit.selection, window, cx);
+    }
+
+    /// Recompute the bracket pair at the caret; returns whether it changed.
+    pub(super) fn refresh_bracket_match(&mut self, cx: &App) -> bool {
+        let state = self.input_state.read(cx);
+        let caret = state.cursor();
+        let found = if self.match_brackets && !self.large_file {
+            matching_bracket(&state.value(), caret)
+        } else {
+            None
+        };
+        self.bracket_caret = Some(caret);
+        let changed = found != self.bracket_match;
+        self.bracket_match = found;
+        changed
+    }
+
+    /// Marks underlining the matched pair.
+    pub(super) fn bracket_marks(&self) -> Vec<TextMark> {
+        let Some((a, b)) = self.bracket_match else { return Vec::new() };
+        [a, b]
+            .into_iter()
+            .map(|offset| TextMark {
+                range: offset..offset + 1,
+                message: "Matching bracket".to_string(),
+                severity: DiagnosticSeverity::Hint,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{matching_bracket, on_backspace, on_type, PairEdit};
+
+    fn edit(range: std::ops::Range<usize>, replacement: &str, caret: usize) -> Option<PairEdit> {
+        Some(PairEdit { range, replacement: replacement.to_string(), selection: caret..caret })
+    }
+
+    #[test]
+    fn test_typing_pairs() {
+        assert_eq!(on_type("f", 1..1, '('), edit(1..1, "()", 2));
+        assert_eq!(on_type("f )", 1..1, '['), edit(1..1, "[]", 2));
+        // Would swallow the word
+        assert_eq!(on_type("word", 0..0, '('), None);
+        // Steps over a closer already there
+        assert_eq!(on_type("()", 1..1, ')'), edit(1..1, "", 2));
+        assert_eq!(on_type("x", 0..1, '"'), Some(PairEdit { range: 0..1, replacement: "\"x\"".into(), selection: 1..2 }));
+        assert_eq!(on_type("x", 1..1, 'x'), None);
+    }
+
+    #[test]
+    fn test_quotes_skip_apostrophes() {
+        assert_eq!(on_type("don", 3..3, '\''), None);
+        assert_eq!(on_type("say ", 4..4, '\''), edit(4..4, "''", 5));
+        assert_eq!(on_type("\"a\"", 2..2, '"'), edit(2..2, "", 3));
+    }
+
+    #[test]
+    fn test_backspace_removes_empty_pair() {
+        assert_eq!(on_backspace("a()", 2..2), edit(1..3, "", 1));
+        assert_eq!(on_backspace("a(x)", 2..2), None);
+        assert_eq!(on_backspace("a()", 1..3), None);
+    }
+
+    #[test]
+    fn test_matching_bracket() {
+        let text = "f(a[1], (b))";
+        assert_eq!(matching_bracket(text, 1), Some((1, 11)));
+        // Just after a bracket counts too
+        assert_eq!(matching_bracket(text, 12), Some((11, 1)));
+        assert_eq!(matching_bracket(text, 3), Some((3, 5)));
+        assert_eq!(matching_bracket(text, 0), None);
+        assert_eq!(matching_bracket("(é)", 4), Some((3, 0)));
+        assert_eq!(matching_bracket("((", 0), None);
+    }
+}
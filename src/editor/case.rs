@@ -0,0 +1,152 @@
+//! Case conversion of the selection or the word at the caret.
+
+use gpui::*;
+use std::fmt;
+use std::ops::Range;
+
+use super::{LowerCaseAction, SentenceCaseAction, TextEditor, TitleCaseAction, UpperCaseAction};
+
+/// A letter case to convert text to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Upper,
+    Lower,
+    Title,
+    Sentence,
+}
+
+impl Case {
+    pub const ALL: [Case; 4] = [Case::Upper, Case::Lower, Case::Title, Case::Sentence];
+
+    /// The action converting to this case, for its key binding.
+    pub fn action(self) -> Box<dyn Action> {
+        match self {
+            Case::Upper => Box::new(UpperCaseAction),
+            Case::Lower => Box::new(LowerCaseAction),
+            Case::Title => Box::new(TitleCaseAction),
+            Case::Sentence => Box::new(SentenceCaseAction),
+        }
+    }
+
+    /// `text` in this case.
+    pub fn convert(self, text: &str) -> String {
+        match self {
+            Case::Upper => text.to_uppercase(),
+            Case::Lower => text.to_lowercase(),
+            Case::Title => capitalize_where(text, |prev| !prev.is_some_and(|c| c.is_alphanumeric() || c == '\'' || c == '’')),
+            Case::Sentence => {
+                let mut sentence_start = true;
+                let mut result = String::with_capacity(text.len());
+                for c in text.chars() {
+                    if c.is_alphanumeric() && sentence_start {
+                        result.extend(c.to_uppercase());
+                        sentence_start = false;
+                    } else {
+                        result.extend(c.to_lowercase());
+                    }
+                    if matches!(c, '.' | '!' | '?') {
+                        sentence_start = true;
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Case::Upper => "UPPERCASE",
+            Case::Lower => "lowercase",
+            Case::Title => "Title Case",
+            Case::Sentence => "Sentence case",
+        })
+    }
+}
+
+/// Lowercase `text`, uppercasing each letter whose preceding character
+/// satisfies `starts_word`.
+fn capitalize_where(text: &str, starts_word: impl Fn(Option<char>) -> bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut prev = None;
+    for c in text.chars() {
+        if starts_word(prev) {
+            result.extend(c.to_uppercase());
+        } else {
+            result.extend(c.to_lowercase());
+        }
+        prev = Some(c);
+    }
+    result
+}
+
+/// Byte range of the word touching `offset`, or an empty range between words.
+pub fn word_at(text: &str, offset: usize) -> Range<usize> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+    let start = text[..offset].char_indices().rev().take_while(|&(_, c)| is_word(c)).last().map_or(offset, |(i, _)| i);
+    let end = text[offset..].char_indices().find(|&(_, c)| !is_word(c)).map_or(text.len(), |(i, _)| offset + i);
+    start..end
+}
+
+impl TextEditor {
+    /// Convert the selection, or the word at the caret, to `case` as one
+    /// undo step, leaving the converted text selected.
+    pub fn convert_case(&mut self, case: Case, window: &mut Window, cx: &mut Context<Self>) {
+        let range = match self.selection_range(window, cx).filter(|r| !r.is_empty()) {
+            Some(range) => range,
+            None => {
+                let state = self.input_state.read(cx);
+                let offset = state.cursor();
+                word_at(&state.value(), offset)
+            }
+        };
+        if range.is_empty() || self.read_only {
+            return;
+        }
+        let converted = case.convert(&self.input_state.read(cx).value()[range.clone()]);
+        let len = converted.len();
+        self.transform_range(range.clone(), |_| converted, window, cx);
+        self.select_range(range.start..range.start + len, window, cx);
+    }
+
+    pub fn upper_case(&mut self, _: &UpperCaseAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.convert_case(Case::Upper, window, cx);
+    }
+
+    pub fn lower_case(&mut self, _: &LowerCaseAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.convert_case(Case::Lower, window, cx);
+    }
+
+    pub fn title_case(&mut self, _: &TitleCaseAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.convert_case(Case::Title, window, cx);
+    }
+
+    pub fn sentence_case(&mut self, _: &SentenceCaseAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.convert_case(Case::Sentence, window, cx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{word_at, Case};
+
+    #[test]
+    fn test_convert_case() {
+        let text = "the QUICK brown fox. it's o'clock! done";
+        assert_eq!(Case::Upper.convert(text), "THE QUICK BROWN FOX. IT'S O'CLOCK! DONE");
+        assert_eq!(Case::Lower.convert(text), "the quick brown fox. it's o'clock! done");
+        assert_eq!(Case::Title.convert(text), "The Quick Brown Fox. It's O'clock! Done");
+        assert_eq!(Case::Sentence.convert(text), "The quick brown fox. It's o'clock! Done");
+        assert_eq!(Case::Upper.convert("straße"), "STRASSE");
+    }
+
+    #[test]
+    fn test_word_at() {
+        let text = "say héllo_there, ok";
+        assert_eq!(&text[word_at(text, 7)], "héllo_there");
+        assert_eq!(&text[word_at(text, 4)], "héllo_there");
+        assert_eq!(&text[word_at(text, 3)], "say");
+        assert_eq!(word_at(text, 17), 17..17);
+    }
+}
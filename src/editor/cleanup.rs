@@ -0,0 +1,318 @@
+//! Whitespace cleanup with a per-fix preview.
+
+use gpui::*;
+use gpui_component::checkbox::Checkbox;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::{v_flex, Disableable, RopeExt, WindowExt};
+use tracing::info;
+
+use super::{normalize_tabs, TextEditor};
+
+const BOM: char = '\u{FEFF}';
+
+/// Which cleanups to apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CleanupFixes {
+    pub trailing_whitespace: bool,
+    pub mixed_indentation: bool,
+    pub blank_lines: bool,
+    pub bom: bool,
+}
+
+impl Default for CleanupFixes {
+    fn default() -> Self {
+        Self {
+            trailing_whitespace: true,
+            mixed_indentation: true,
+            blank_lines: true,
+            bom: true,
+        }
+    }
+}
+
+/// How much of each problem a text has.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// Lines ending in spaces or tabs.
+    pub trailing_whitespace: usize,
+    /// Lines indented with both tabs and spaces.
+    pub mixed_indentation: usize,
+    /// Runs of two or more blank lines.
+    pub blank_runs: usize,
+    pub bom: bool,
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+fn is_mixed(indent: &str) -> bool {
+    indent.contains('\t') && indent.contains(' ')
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+impl CleanupReport {
+    pub fn of(text: &str) -> Self {
+        let mut report = CleanupReport {
+            bom: text.starts_with(BOM),
+            ..Default::default()
+        };
+        let mut blank_run = 0;
+        for line in text.split('\n') {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if line.ends_with([' ', '\t']) {
+                report.trailing_whitespace += 1;
+            }
+            if is_mixed(indentation(line)) {
+                report.mixed_indentation += 1;
+            }
+            if is_blank(line) {
+                blank_run += 1;
+                if blank_run == 2 {
+                    report.blank_runs += 1;
+                }
+            } else {
+                blank_run = 0;
+            }
+        }
+        report
+    }
+
+    pub fn is_clean(&self) -> bool {
+        *self == CleanupReport::default()
+    }
+}
+
+/// `text` with the selected `fixes` applied. Tabs in mixed indentation
+/// become spaces up to the next stop every `tab_width` columns.
+pub fn clean_up(text: &str, fixes: CleanupFixes, tab_width: usize) -> String {
+    let text = if fixes.bom { text.trim_start_matches(BOM) } else { text };
+    let mut lines: Vec<String> = Vec::new();
+    let mut previous_blank = false;
+    for line in text.split('\n') {
+        let (line, cr) = match line.strip_suffix('\r') {
+            Some(line) => (line, "\r"),
+            None => (line, ""),
+        };
+        let mut line = line.to_string();
+        if fixes.trailing_whitespace {
+            line.truncate(line.trim_end_matches([' ', '\t']).len());
+        }
+        if fixes.mixed_indentation && is_mixed(indentation(&line)) {
+            let indent = normalize_tabs(indentation(&line), tab_width);
+            line = format!("{}{}", indent, line.trim_start_matches([' ', '\t']));
+        }
+        let blank = is_blank(&line);
+        if fixes.blank_lines && blank && previous_blank {
+            continue;
+        }
+        previous_blank = blank;
+        lines.push(line + cr);
+    }
+    lines.join("\n")
+}
+
+/// `text` as it should be written: with trailing whitespace trimmed and/or
+/// ending in exactly one line break, keeping its line ending style.
+pub fn save_transforms(text: &str, trim_trailing_whitespace: bool, final_newline: bool) -> String {
+    let mut text = if trim_trailing_whitespace {
+        text.split('\n')
+            .map(|line| match line.strip_suffix('\r') {
+                Some(line) => format!("{}\r", line.trim_end_matches([' ', '\t'])),
+                None => line.trim_end_matches([' ', '\t']).to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        text.to_string()
+    };
+    if final_newline && !text.is_empty() {
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        text.truncate(text.trim_end_matches(['\r', '\n']).len());
+        text.push_str(newline);
+    }
+    text
+}
+
+/// Dialog body listing each problem with a checkbox to fix it.
+struct CleanupPanel {
+    report: CleanupReport,
+    fixes: CleanupFixes,
+}
+
+impl CleanupPanel {
+    fn fix(
+        id: &'static str,
+        label: String,
+        found: bool,
+        checked: bool,
+        cx: &mut Context<Self>,
+        toggle: fn(&mut CleanupFixes, bool),
+    ) -> impl IntoElement {
+        Checkbox::new(id)
+            .label(label)
+            .disabled(!found)
+            .checked(found && checked)
+            .on_click(cx.listener(move |this, checked: &bool, _, cx| {
+                toggle(&mut this.fixes, *checked);
+                cx.notify();
+            }))
+    }
+}
+
+impl Render for CleanupPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let report = self.report;
+        let fixes = self.fixes;
+        v_flex()
+            .gap_3()
+            .child(Self::fix(
+                "cleanup-trailing",
+                format!("Trailing whitespace ({} lines)", report.trailing_whitespace),
+                report.trailing_whitespace > 0,
+                fixes.trailing_whitespace,
+                cx,
+                |f, on| f.trailing_whitespace = on,
+            ))
+            .child(Self::fix(
+                "cleanup-indentation",
+                format!("Mixed tabs and spaces in indentation ({} lines)", report.mixed_indentation),
+                report.mixed_indentation > 0,
+                fixes.mixed_indentation,
+                cx,
+                |f, on| f.mixed_indentation = on,
+            ))
+            .child(Self::fix(
+                "cleanup-blank-lines",
+                format!("Multiple blank lines ({} places)", report.blank_runs),
+                report.blank_runs > 0,
+                fixes.blank_lines,
+                cx,
+                |f, on| f.blank_lines = on,
+            ))
+            .child(Self::fix(
+                "cleanup-bom",
+                "Byte order mark".to_string(),
+                report.bom,
+                fixes.bom,
+                cx,
+                |f, on| f.bom = on,
+            ))
+    }
+}
+
+impl TextEditor {
+    /// Apply the on-save transforms to the buffer as one undo step, keeping
+    /// the caret on its line.
+    pub fn apply_save_transforms(&mut self, trim_trailing_whitespace: bool, final_newline: bool, window: &mut Window, cx: &mut Context<Self>) {
+        if !trim_trailing_whitespace && !final_newline {
+            return;
+        }
+        let position = self.input_state.read(cx).cursor_position();
+        let len = self.input_state.read(cx).text().len();
+        self.transform_range(0..len, |text| save_transforms(text, trim_trailing_whitespace, final_newline), window, cx);
+        self.input_state.update(cx, |state, cx| {
+            let text = state.text();
+            let line = position.line.min(text.lines_len().saturating_sub(1) as u32);
+            let line_text = text.slice(text.line_start_offset(line as usize)..text.line_end_offset(line as usize)).to_string();
+            let character = position.character.min(line_text.chars().count() as u32);
+            state.set_cursor_position(gpui_component::input::Position { line, character }, window, cx);
+        });
+    }
+
+    /// Report whitespace problems and apply the chosen fixes as one undoable edit.
+    pub fn whitespace_cleanup(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let report = CleanupReport::of(&self.input_state.read(cx).value());
+        let read_only = self.read_only;
+        let panel = cx.new(|_| CleanupPanel { report, fixes: CleanupFixes::default() });
+        let editor = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let dialog = dialog.title("Clean Up Whitespace").w(px(420.0));
+            if report.is_clean() {
+                return dialog.child("No whitespace problems found.").alert();
+            }
+            if read_only {
+                return dialog.child(panel.clone()).child("The document is read-only.").alert();
+            }
+            let panel_for_ok = panel.clone();
+            let editor = editor.clone();
+            dialog
+                .child(panel.clone())
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Clean Up"))
+                .on_ok(move |_, window, cx| {
+                    let fixes = panel_for_ok.read(cx).fixes;
+                    let _ = editor.update(cx, |ed, cx| {
+                        let tab_width = ed.tab_width;
+                        ed.input_state.update(cx, |state, cx| {
+                            let cleaned = clean_up(&state.value(), fixes, tab_width);
+                            if cleaned != state.value().as_ref() {
+                                let end = state.text().offset_to_offset_utf16(state.text().len());
+                                state.replace_text_in_range(Some(0..end), &cleaned, window, cx);
+                            }
+                        });
+                    });
+                    info!(?fixes, "Cleaned up whitespace");
+                    true
+                })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clean_up, save_transforms, CleanupFixes, CleanupReport};
+
+    #[test]
+    fn test_save_transforms() {
+        assert_eq!(save_transforms("a  \nb\t\n\n\n", true, true), "a\nb\n");
+        assert_eq!(save_transforms("a \r\nb", false, true), "a \r\nb\r\n");
+        assert_eq!(save_transforms("a \nb", true, false), "a\nb");
+        assert_eq!(save_transforms("", true, true), "");
+    }
+
+    const TEXT: &str = "\u{FEFF}first  \n\t  mixed\n\n\n\nlast\t\n";
+
+    #[test]
+    fn test_report() {
+        let report = CleanupReport::of(TEXT);
+        assert_eq!(report.trailing_whitespace, 2);
+        assert_eq!(report.mixed_indentation, 1);
+        assert_eq!(report.blank_runs, 1);
+        assert!(report.bom);
+        assert!(CleanupReport::of("clean\ntext\n").is_clean());
+    }
+
+    #[test]
+    fn test_clean_up_everything() {
+        assert_eq!(clean_up(TEXT, CleanupFixes::default(), 2), "first\n    mixed\n\nlast\n");
+    }
+
+    #[test]
+    fn test_clean_up_expands_tabs_to_stops() {
+        let fixes = CleanupFixes::default();
+        assert_eq!(clean_up(" \tx\n", fixes, 4), "    x\n");
+        assert_eq!(clean_up("\t \tx\n", fixes, 4), "        x\n");
+        assert_eq!(clean_up("\t  x\n", fixes, 8), "          x\n");
+    }
+
+    #[test]
+    fn test_clean_up_selected_fixes_only() {
+        let fixes = CleanupFixes {
+            trailing_whitespace: true,
+            mixed_indentation: false,
+            blank_lines: false,
+            bom: false,
+        };
+        assert_eq!(clean_up(TEXT, fixes, 2), "\u{FEFF}first\n\t  mixed\n\n\n\nlast\n");
+    }
+
+    #[test]
+    fn test_keeps_crlf_line_endings() {
+        assert_eq!(clean_up("a \r\nb\r\n", CleanupFixes::default(), 2), "a\r\nb\r\n");
+    }
+}
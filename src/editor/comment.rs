@@ -0,0 +1,121 @@
+//! Commenting and uncommenting lines.
+
+use gpui::*;
+use gpui_component::RopeExt;
+
+use super::language::{CommentStyle, Language};
+use super::{TextEditor, ToggleCommentAction};
+
+/// `line` with the comment markers removed, if it is commented.
+fn uncomment_line(line: &str, style: CommentStyle) -> Option<String> {
+    let indent = line.len() - line.trim_start().len();
+    let (lead, body) = line.split_at(indent);
+    let inner = match style {
+        CommentStyle::Line(token) => body.strip_prefix(token)?,
+        CommentStyle::Block(open, close) => body.trim_end().strip_prefix(open)?.strip_suffix(close)?,
+    };
+    let inner = match style {
+        CommentStyle::Line(_) => inner.strip_prefix(' ').unwrap_or(inner),
+        CommentStyle::Block(..) => {
+            let inner = inner.strip_prefix(' ').unwrap_or(inner);
+            inner.strip_suffix(' ').unwrap_or(inner)
+        }
+    };
+    Some(format!("{}{}", lead, inner))
+}
+
+/// Comment every non-blank line of `lines`, or uncomment them all if each
+/// one already is. Line comments line up at the shallowest indentation.
+pub fn toggle_comments(lines: &str, style: CommentStyle) -> String {
+    let is_blank = |line: &str| line.trim().is_empty();
+    let all_commented = lines.split('\n').filter(|l| !is_blank(l)).all(|l| uncomment_line(l, style).is_some());
+    let any_text = lines.split('\n').any(|l| !is_blank(l));
+
+    if any_text && all_commented {
+        return lines
+            .split('\n')
+            .map(|line| uncomment_line(line, style).unwrap_or_else(|| line.to_string()))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    // The leading whitespace all the lines share, compared by character so
+    // mixed or multibyte indentation is never split
+    let indent = lines
+        .split('\n')
+        .filter(|l| !is_blank(l))
+        .map(|l| &l[..l.len() - l.trim_start().len()])
+        .reduce(|shared, lead| {
+            let end = shared
+                .char_indices()
+                .zip(lead.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(shared.len().min(lead.len()), |((i, _), _)| i);
+            &shared[..end]
+        })
+        .map_or(0, str::len);
+    lines
+        .split('\n')
+        .map(|line| {
+            if is_blank(line) && any_text {
+                return line.to_string();
+            }
+            let (lead, body) = line.split_at(indent.min(line.len()));
+            match style {
+                CommentStyle::Line(token) => format!("{}{} {}", lead, token, body),
+                CommentStyle::Block(open, close) => format!("{}{} {} {}", lead, open, body, close),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl TextEditor {
+    /// Language of the current document.
+    pub fn language(&self, cx: &App) -> Language {
+        let text = self.input_state.read(cx).text();
+        let first_line = text.slice(0..text.line_end_offset(0)).to_string();
+        Language::detect(self.current_file(cx), &first_line)
+    }
+
+    /// Comment or uncomment the selected lines (or the caret's line).
+    pub fn toggle_comment(&mut self, _: &ToggleCommentAction, window: &mut Window, cx: &mut Context<Self>) {
+        let style = self.language(cx).comment();
+        let range = match self.selection_range(window, cx).filter(|r| !r.is_empty()) {
+            Some(_) => self.selected_lines_range(window, cx),
+            None => {
+                let state = self.input_state.read(cx);
+                let line = state.cursor_position().line as usize;
+                state.text().line_start_offset(line)..state.text().line_end_offset(line)
+            }
+        };
+        self.transform_range(range, |text| toggle_comments(text, style), window, cx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{toggle_comments, CommentStyle};
+
+    #[test]
+    fn test_toggle_line_comments() {
+        let style = CommentStyle::Line("//");
+        let code = "    let a = 1;\n\n  let b = 2;";
+        let commented = toggle_comments(code, style);
+        assert_eq!(commented, "  //   let a = 1;\n\n  // let b = 2;");
+        assert_eq!(toggle_comments(&commented, style), code);
+        // Partly commented lines get commented again
+        assert_eq!(toggle_comments("# a\nb", CommentStyle::Line("#")), "# # a\n# b");
+        // Indents that differ in kind share no prefix
+        assert_eq!(toggle_comments("\u{3000}a\n b", style), "// \u{3000}a\n//  b");
+        assert_eq!(toggle_comments("\t a\n\tb", style), "\t//  a\n\t// b");
+    }
+
+    #[test]
+    fn test_toggle_block_comments() {
+        let style = CommentStyle::Block("<!--", "-->");
+        assert_eq!(toggle_comments("<p>hi</p>", style), "<!-- <p>hi</p> -->");
+        assert_eq!(toggle_comments("<!-- <p>hi</p> -->", style), "<p>hi</p>");
+        assert_eq!(toggle_comments("", CommentStyle::Line("#")), "# ");
+    }
+}
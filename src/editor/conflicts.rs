@@ -0,0 +1,262 @@
+//! Detection and resolution of merge conflict markers.
+
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::highlighter::DiagnosticSeverity;
+use gpui_component::{h_flex, Disableable, RopeExt, Sizable, Theme};
+use std::ops::Range;
+
+use super::{TextEditor, TextMark};
+
+/// Which side(s) of a conflict to keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Current,
+    Incoming,
+    Both,
+}
+
+/// A `<<<<<<<` ... `=======` ... `>>>>>>>` region, as byte offsets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The whole region, from the `<<<<<<<` line through the `>>>>>>>` line.
+    pub range: Range<usize>,
+    /// Lines between `<<<<<<<` and `=======` (or a `|||||||` base section).
+    pub current: Range<usize>,
+    /// Lines between `|||||||` and `=======`, in diff3-style conflicts.
+    pub base: Option<Range<usize>>,
+    /// Lines between `=======` and `>>>>>>>`.
+    pub incoming: Range<usize>,
+}
+
+impl Conflict {
+    /// Text that replaces the whole region for `resolution`.
+    pub fn resolved(&self, text: &str, resolution: Resolution) -> String {
+        let current = &text[self.current.clone()];
+        let incoming = &text[self.incoming.clone()];
+        match resolution {
+            Resolution::Current => current.to_string(),
+            Resolution::Incoming => incoming.to_string(),
+            Resolution::Both => format!("{}{}", current, incoming),
+        }
+    }
+}
+
+/// All complete conflict regions in `text`, in order.
+pub fn find_conflicts(text: &str) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut start = None;
+    let mut current = None;
+    let mut base = None;
+    let mut separator = None;
+
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let line_end = offset + line.len();
+        if line.starts_with("<<<<<<<") {
+            start = Some(offset);
+            current = None;
+            base = None;
+            separator = None;
+        } else if line.starts_with("|||||||") && start.is_some() && separator.is_none() {
+            // diff3 base section: the current side ends here
+            if current.is_none() {
+                current = Some(offset);
+                base = Some(line_end..line_end);
+            }
+        } else if line.starts_with("=======") && start.is_some() && separator.is_none() {
+            current.get_or_insert(offset);
+            if let Some(base) = &mut base {
+                base.end = offset;
+            }
+            separator = Some(line_end);
+        } else if line.starts_with(">>>>>>>") {
+            if let (Some(region_start), Some(current_end), Some(incoming_start)) = (start, current, separator) {
+                let current_start = region_start + text[region_start..].find('\n').map_or(0, |i| i + 1);
+                conflicts.push(Conflict {
+                    range: region_start..line_end,
+                    current: current_start..current_end,
+                    base: base.take(),
+                    incoming: incoming_start..offset,
+                });
+            }
+            start = None;
+            current = None;
+            base = None;
+            separator = None;
+        }
+        offset = line_end;
+    }
+    conflicts
+}
+
+/// Marks showing each side of each conflict: ours, the base and theirs.
+pub(super) fn conflict_marks(conflicts: &[Conflict]) -> Vec<TextMark> {
+    conflicts
+        .iter()
+        .flat_map(|conflict| {
+            [
+                (Some(conflict.current.clone()), "Current change", DiagnosticSeverity::Info),
+                (conflict.base.clone(), "Common ancestor", DiagnosticSeverity::Warning),
+                (Some(conflict.incoming.clone()), "Incoming change", DiagnosticSeverity::Hint),
+            ]
+        })
+        .filter_map(|(range, message, severity)| {
+            let range = range.filter(|r| !r.is_empty())?;
+            Some(TextMark { range, message: message.to_string(), severity })
+        })
+        .collect()
+}
+
+impl TextEditor {
+    /// Rescan the buffer for conflict markers and mark the sides of each.
+    pub(super) fn refresh_conflicts(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let conflicts = if self.large_file {
+            Vec::new()
+        } else {
+            find_conflicts(&self.input_state.read(cx).value())
+        };
+        if conflicts == self.conflicts {
+            return;
+        }
+        self.conflicts = conflicts;
+        // The marks need the code editor, which goes once the last conflict is resolved
+        self.sync_input_mode(window, cx);
+        self.push_marks(cx);
+    }
+
+    /// Index of the conflict containing the cursor.
+    fn conflict_at_cursor(&self, cx: &App) -> Option<usize> {
+        let cursor = self.input_state.read(cx).cursor();
+        self.conflicts.iter().position(|c| c.range.contains(&cursor))
+    }
+
+    /// Replace the conflict under the cursor with the chosen side(s).
+    pub fn resolve_conflict(&mut self, resolution: Resolution, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(index) = self.conflict_at_cursor(cx) else { return };
+        let conflict = self.conflicts[index].clone();
+        let replacement = conflict.resolved(&self.input_state.read(cx).value(), resolution);
+        self.transform_range(conflict.range, |_| replacement, window, cx);
+    }
+
+    /// Move the cursor to the next (or previous) conflict, wrapping around.
+    pub fn goto_conflict(&mut self, forward: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let cursor = self.input_state.read(cx).cursor();
+        let anchor = self.conflict_at_cursor(cx).map_or(cursor, |i| self.conflicts[i].range.start);
+        let target = if forward {
+            self.conflicts.iter().find(|c| c.range.start > anchor).or(self.conflicts.first())
+        } else {
+            self.conflicts.iter().rev().find(|c| c.range.start < anchor).or(self.conflicts.last())
+        };
+        let Some(start) = target.map(|c| c.range.start) else { return };
+        self.input_state.update(cx, |state, cx| {
+            let position = state.text().offset_to_position(start);
+            state.set_cursor_position(position, window, cx);
+        });
+    }
+
+    /// Bar with conflict navigation and resolution, shown while markers remain.
+    pub(super) fn render_conflict_bar(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if self.conflicts.is_empty() {
+            return None;
+        }
+        let colors = Theme::global(cx).colors;
+        let current = self.conflict_at_cursor(cx);
+        let label = match current {
+            Some(i) => format!("Conflict {} of {}", i + 1, self.conflicts.len()),
+            None => format!("{} merge conflict(s)", self.conflicts.len()),
+        };
+        let resolve_button = |id: &'static str, label: &'static str, resolution: Resolution| {
+            Button::new(id)
+                .label(label)
+                .small()
+                .ghost()
+                .disabled(current.is_none())
+                .on_click(cx.listener(move |this, _, window, cx| this.resolve_conflict(resolution, window, cx)))
+        };
+
+        Some(
+            h_flex()
+                .gap_2()
+                .px_2()
+                .py_1()
+                .bg(colors.muted)
+                .border_b_1()
+                .border_color(colors.border)
+                .child(div().text_color(colors.warning).child(label))
+                .child(Button::new("conflict-prev").label("Previous").small().ghost()
+                    .on_click(cx.listener(|this, _, window, cx| this.goto_conflict(false, window, cx))))
+                .child(Button::new("conflict-next").label("Next").small().ghost()
+                    .on_click(cx.listener(|this, _, window, cx| this.goto_conflict(true, window, cx))))
+                .child(resolve_button("conflict-current", "Accept Current", Resolution::Current))
+                .child(resolve_button("conflict-incoming", "Accept Incoming", Resolution::Incoming))
+                .child(resolve_button("conflict-both", "Accept Both", Resolution::Both)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{conflict_marks, find_conflicts, Resolution};
+    use gpui_component::highlighter::DiagnosticSeverity;
+
+    const TEXT: &str = "\
+before
+<<<<<<< HEAD
+mine
+=======
+theirs
+>>>>>>> branch
+after
+";
+
+    #[test]
+    fn test_find_conflicts() {
+        let conflicts = find_conflicts(TEXT);
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts[0];
+        assert_eq!(&TEXT[conflict.current.clone()], "mine\n");
+        assert_eq!(&TEXT[conflict.incoming.clone()], "theirs\n");
+        assert!(TEXT[conflict.range.clone()].starts_with("<<<<<<<"));
+        assert!(TEXT[conflict.range.clone()].ends_with("branch\n"));
+    }
+
+    #[test]
+    fn test_resolutions() {
+        let conflict = &find_conflicts(TEXT)[0];
+        assert_eq!(conflict.resolved(TEXT, Resolution::Current), "mine\n");
+        assert_eq!(conflict.resolved(TEXT, Resolution::Incoming), "theirs\n");
+        assert_eq!(conflict.resolved(TEXT, Resolution::Both), "mine\ntheirs\n");
+    }
+
+    #[test]
+    fn test_diff3_base_is_dropped() {
+        let text = "<<<<<<< ours\nmine\n||||||| base\nold\n=======\ntheirs\n>>>>>>> theirs\n";
+        let conflict = &find_conflicts(text)[0];
+        assert_eq!(&text[conflict.current.clone()], "mine\n");
+        assert_eq!(&text[conflict.base.clone().unwrap()], "old\n");
+        assert_eq!(&text[conflict.incoming.clone()], "theirs\n");
+    }
+
+    #[test]
+    fn test_conflict_marks_cover_each_side() {
+        let text = "<<<<<<< ours\nmine\n||||||| base\nold\n=======\ntheirs\n>>>>>>> theirs\n";
+        let marks = conflict_marks(&find_conflicts(text));
+        let sides: Vec<_> = marks.iter().map(|m| (&text[m.range.clone()], m.severity)).collect();
+        assert_eq!(sides, [
+            ("mine\n", DiagnosticSeverity::Info),
+            ("old\n", DiagnosticSeverity::Warning),
+            ("theirs\n", DiagnosticSeverity::Hint),
+        ]);
+        // Without a base section, and with an empty side
+        let marks = conflict_marks(&find_conflicts("<<<<<<< ours\n=======\ntheirs\n>>>>>>> theirs\n"));
+        assert_eq!(marks.len(), 1);
+        assert_eq!(marks[0].message, "Incoming change");
+    }
+
+    #[test]
+    fn test_incomplete_markers_are_ignored() {
+        assert!(find_conflicts("<<<<<<< HEAD\nmine\n=======\ntheirs\n").is_empty());
+        assert!(find_conflicts("=======\n>>>>>>> x\n").is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! Line-level diffing between two versions of a document.
+
+/// Above this many comparison cells the middle section is not aligned and
+/// every line in it counts as changed.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// One step of turning `old` into `new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineOp {
+    Same(usize, usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// Steps turning the lines of `old` into those of `new`, in order.
+fn line_ops(old: &[&str], new: &[&str]) -> Vec<LineOp> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let suffix = old.iter().rev()
+        .zip(new.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    let mut ops: Vec<LineOp> = (0..prefix).map(|i| LineOp::Same(i, i)).collect();
+
+    if old_mid.is_empty() || new_mid.is_empty() || old_mid.len().saturating_mul(new_mid.len()) > MAX_LCS_CELLS {
+        ops.extend((prefix..prefix + old_mid.len()).map(LineOp::Removed));
+        ops.extend((prefix..prefix + new_mid.len()).map(LineOp::Added));
+    } else {
+        // lcs[i][j] = LCS length of old_mid[i..] and new_mid[j..]
+        let width = new_mid.len() + 1;
+        let mut lcs = vec![0u32; (old_mid.len() + 1) * width];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old_mid.len() || j < new_mid.len() {
+            if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+                ops.push(LineOp::Same(prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if i < old_mid.len() && (j == new_mid.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+                ops.push(LineOp::Removed(prefix + i));
+                i += 1;
+            } else {
+                ops.push(LineOp::Added(prefix + j));
+                j += 1;
+            }
+        }
+    }
+
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+    ops.extend((0..suffix).map(|k| LineOp::Same(old_end + k, new_end + k)));
+    ops
+}
+
+/// Indices of lines in `new` that were added or modified relative to `old`.
+pub fn changed_lines(old: &str, new: &str) -> Vec<usize> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    line_ops(&old, &new)
+        .into_iter()
+        .filter_map(|op| match op {
+            LineOp::Added(j) => Some(j),
+            _ => None,
+        })
+        .collect()
+}
+
+/// How a row of a side-by-side diff differs between the two sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowKind {
+    Same,
+    Changed,
+    Removed,
+    Added,
+}
+
+/// One row of a side-by-side diff: a zero-based line index and its text on
+/// each side, or `None` where that side has no counterpart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffRow {
+    pub left: Option<(usize, String)>,
+    pub right: Option<(usize, String)>,
+    pub kind: RowKind,
+}
+
+/// Align `old` and `new` line by line for showing side by side. Removed
+/// lines directly followed by added ones are paired up as changed.
+pub fn side_by_side(old: &str, new: &str) -> Vec<DiffRow> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let left = |i: usize| Some((i, old[i].to_string()));
+    let right = |j: usize| Some((j, new[j].to_string()));
+
+    let ops = line_ops(&old, &new);
+    let mut rows = Vec::with_capacity(ops.len());
+    let mut at = 0;
+    while at < ops.len() {
+        if let LineOp::Same(i, j) = ops[at] {
+            rows.push(DiffRow { left: left(i), right: right(j), kind: RowKind::Same });
+            at += 1;
+            continue;
+        }
+        // A run of removals and additions between two unchanged lines
+        let end = ops[at..].iter().position(|op| matches!(op, LineOp::Same(..))).map_or(ops.len(), |n| at + n);
+        let removed: Vec<usize> = ops[at..end].iter().filter_map(|op| match op {
+            LineOp::Removed(i) => Some(*i),
+            _ => None,
+        }).collect();
+        let added: Vec<usize> = ops[at..end].iter().filter_map(|op| match op {
+            LineOp::Added(j) => Some(*j),
+            _ => None,
+        }).collect();
+        for k in 0..removed.len().max(added.len()) {
+            let (l, r) = (removed.get(k).copied(), added.get(k).copied());
+            let kind = match (l, r) {
+                (Some(_), Some(_)) => RowKind::Changed,
+                (Some(_), None) => RowKind::Removed,
+                _ => RowKind::Added,
+            };
+            rows.push(DiffRow { left: l.and_then(left), right: r.and_then(right), kind });
+        }
+        at = end;
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changed_lines, side_by_side, RowKind};
+
+    #[test]
+    fn test_identical_texts_have_no_changes() {
+        assert!(changed_lines("a\nb\nc", "a\nb\nc").is_empty());
+    }
+
+    #[test]
+    fn test_modified_and_inserted_lines() {
+        let old = "one\ntwo\nthree\nfour";
+        let new = "one\nTWO\nthree\nnew line\nfour";
+        assert_eq!(changed_lines(old, new), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_deleted_lines_are_not_reported() {
+        assert!(changed_lines("a\nb\nc", "a\nc").is_empty());
+    }
+
+    #[test]
+    fn test_everything_is_new_against_empty_text() {
+        assert_eq!(changed_lines("", "x\ny"), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_side_by_side_pairs_changed_lines() {
+        let rows = side_by_side("one\ntwo\nthree\nfour", "one\nTWO\nthree\nfour\nfive");
+        let kinds: Vec<RowKind> = rows.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, vec![RowKind::Same, RowKind::Changed, RowKind::Same, RowKind::Same, RowKind::Added]);
+        assert_eq!(rows[1].left, Some((1, "two".to_string())));
+        assert_eq!(rows[1].right, Some((1, "TWO".to_string())));
+        assert_eq!(rows[4].left, None);
+        assert_eq!(rows[4].right, Some((4, "five".to_string())));
+    }
+
+    #[test]
+    fn test_side_by_side_removed_lines() {
+        let rows = side_by_side("a\nb\nc\nd", "a\nd");
+        let kinds: Vec<RowKind> = rows.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, vec![RowKind::Same, RowKind::Removed, RowKind::Removed, RowKind::Same]);
+        assert_eq!(rows[3].left, Some((3, "d".to_string())));
+        assert_eq!(rows[3].right, Some((1, "d".to_string())));
+    }
+}
@@ -0,0 +1,237 @@
+//! The document an editor shows: its file, text, encoding, line endings
+//! and save state, kept apart from the widget so other views (and code
+//! without a window) can read and observe it.
+
+use gpui::*;
+use gpui_component::Rope;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::types::{Encoding, LineEnding};
+
+/// What changed about a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentEvent {
+    /// The text changed and the revision went up.
+    Edited,
+    /// The document now belongs to another file, or to none.
+    PathChanged,
+    /// It gained or lost unsaved changes.
+    DirtyChanged,
+    /// It will be saved in another encoding or with other line endings.
+    FormatChanged,
+}
+
+/// Untitled-N numbers held by open documents, across all windows.
+static UNTITLED_NUMBERS: Mutex<BTreeSet<usize>> = Mutex::new(BTreeSet::new());
+
+/// The lowest number from 1 up that isn't in `taken`.
+fn lowest_free(taken: &BTreeSet<usize>) -> usize {
+    (1..).find(|n| !taken.contains(n)).unwrap_or(1)
+}
+
+fn claim_untitled_number() -> usize {
+    let mut taken = UNTITLED_NUMBERS.lock().unwrap_or_else(|e| e.into_inner());
+    let number = lowest_free(&taken);
+    taken.insert(number);
+    number
+}
+
+fn release_untitled_number(number: usize) {
+    UNTITLED_NUMBERS.lock().unwrap_or_else(|e| e.into_inner()).remove(&number);
+}
+
+#[derive(Default)]
+pub struct Document {
+    path: Option<PathBuf>,
+    /// N in "Untitled-N" while the document has no file. Freed numbers are
+    /// handed out again, lowest first.
+    untitled: Option<usize>,
+    /// Set for read-only text that isn't a file the user opened, such as
+    /// the license: its name. Such documents never save over their source.
+    virtual_name: Option<String>,
+    text: Rope,
+    encoding: Encoding,
+    /// Encoding of the file on disk, so changing it counts as an edit.
+    saved_encoding: Encoding,
+    line_ending: LineEnding,
+    dirty: bool,
+    /// Goes up with every change to the text.
+    revision: u64,
+}
+
+impl EventEmitter<DocumentEvent> for Document {}
+
+impl Document {
+    /// A new document without a file, named Untitled-N.
+    pub fn untitled() -> Self {
+        let mut document = Self::default();
+        document.untitled = Some(claim_untitled_number());
+        document
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Whether this is read-only text shown in place of a file.
+    pub fn is_virtual(&self) -> bool {
+        self.virtual_name.is_some()
+    }
+
+    /// The file name, or Untitled-N for a document without a file.
+    pub fn display_name(&self) -> String {
+        if let Some(name) = &self.virtual_name {
+            return name.clone();
+        }
+        match (&self.path, self.untitled) {
+            (Some(path), _) => path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+            (None, Some(number)) => format!("Untitled-{}", number),
+            (None, None) => "Untitled".to_string(),
+        }
+    }
+
+    pub fn text(&self) -> &Rope {
+        &self.text
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// Whether there are changes the file doesn't have.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn set_path(&mut self, path: Option<PathBuf>, cx: &mut Context<Self>) {
+        if self.path != path || self.virtual_name.is_some() {
+            self.virtual_name = None;
+            match (&path, self.untitled) {
+                (Some(_), Some(number)) => {
+                    release_untitled_number(number);
+                    self.untitled = None;
+                }
+                (None, None) => self.untitled = Some(claim_untitled_number()),
+                _ => {}
+            }
+            self.path = path;
+            cx.emit(DocumentEvent::PathChanged);
+        }
+    }
+
+    pub fn set_text(&mut self, text: Rope, cx: &mut Context<Self>) {
+        self.text = text;
+        self.revision += 1;
+        cx.emit(DocumentEvent::Edited);
+    }
+
+    /// The file is now `path` on disk, in `encoding`, with nothing unsaved.
+    pub fn reset(&mut self, path: Option<PathBuf>, encoding: Encoding, line_ending: LineEnding, cx: &mut Context<Self>) {
+        self.set_path(path, cx);
+        self.saved_encoding = encoding;
+        self.set_encoding(encoding, cx);
+        self.set_line_ending(line_ending, cx);
+        self.set_dirty(false, cx);
+    }
+
+    /// Now showing read-only text called `name` that belongs to no file.
+    pub fn reset_virtual(&mut self, name: String, line_ending: LineEnding, cx: &mut Context<Self>) {
+        if let Some(number) = self.untitled.take() {
+            release_untitled_number(number);
+        }
+        self.path = None;
+        self.virtual_name = Some(name);
+        self.saved_encoding = Encoding::default();
+        self.set_encoding(Encoding::default(), cx);
+        self.set_line_ending(line_ending, cx);
+        self.set_dirty(false, cx);
+        cx.emit(DocumentEvent::PathChanged);
+    }
+
+    /// Save in `encoding` from now on; differing from the file's counts as
+    /// an unsaved change.
+    pub fn set_encoding(&mut self, encoding: Encoding, cx: &mut Context<Self>) {
+        if self.encoding != encoding {
+            self.encoding = encoding;
+            cx.emit(DocumentEvent::FormatChanged);
+        }
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: LineEnding, cx: &mut Context<Self>) {
+        if self.line_ending != line_ending {
+            self.line_ending = line_ending;
+            cx.emit(DocumentEvent::FormatChanged);
+        }
+    }
+
+    /// Recompute the dirty flag from whether the text differs from the
+    /// saved text.
+    pub fn update_dirty(&mut self, text_changed: bool, cx: &mut Context<Self>) {
+        self.set_dirty(text_changed || self.encoding != self.saved_encoding, cx);
+    }
+
+    /// Just written to disk as it is now.
+    pub fn mark_saved(&mut self, cx: &mut Context<Self>) {
+        self.saved_encoding = self.encoding;
+        self.set_dirty(false, cx);
+    }
+
+    fn set_dirty(&mut self, dirty: bool, cx: &mut Context<Self>) {
+        if self.dirty != dirty {
+            self.dirty = dirty;
+            cx.emit(DocumentEvent::DirtyChanged);
+        }
+    }
+}
+
+impl Drop for Document {
+    fn drop(&mut self) {
+        if let Some(number) = self.untitled {
+            release_untitled_number(number);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lowest_free, Document, DocumentEvent, Encoding, LineEnding};
+    use gpui::{AppContext, TestAppContext};
+    use std::cell::RefCell;
+    use std::collections::BTreeSet;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_lowest_free_reuses_gaps() {
+        assert_eq!(lowest_free(&BTreeSet::new()), 1);
+        assert_eq!(lowest_free(&BTreeSet::from([1, 2])), 3);
+        // Closing Untitled-1 while Untitled-2 is open frees 1 for the next one
+        assert_eq!(lowest_free(&BTreeSet::from([2])), 1);
+        assert_eq!(lowest_free(&BTreeSet::from([1, 3])), 2);
+    }
+
+    #[gpui::test]
+    fn test_format_changes_are_announced(cx: &mut TestAppContext) {
+        let document = cx.new(|_| Document::default());
+        let events = Rc::new(RefCell::new(Vec::new()));
+        cx.update(|cx| {
+            let events = events.clone();
+            cx.subscribe(&document, move |_, event: &DocumentEvent, _| events.borrow_mut().push(*event)).detach();
+        });
+        document.update(cx, |doc, cx| {
+            doc.set_line_ending(LineEnding::Crlf, cx);
+            doc.set_line_ending(LineEnding::Crlf, cx);
+            doc.set_encoding(Encoding::Windows1252, cx);
+        });
+        assert_eq!(*events.borrow(), [DocumentEvent::FormatChanged, DocumentEvent::FormatChanged]);
+    }
+}
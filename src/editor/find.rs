@@ -0,0 +1,347 @@
+//! Find bar with regex, match-case and find-in-selection modes.
+
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::{h_flex, Disableable, Selectable, Sizable, Theme};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+use super::{CloseFindAction, FindNextAction, FindPreviousAction, TextEditor};
+
+/// Compile `query`; plain-text queries match literally.
+pub fn build_pattern(query: &str, regex: bool, match_case: bool) -> Result<Regex, regex::Error> {
+    let pattern = if regex { query.to_string() } else { regex::escape(query) };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!match_case)
+        .multi_line(true)
+        .build()
+}
+
+/// Non-empty matches of `pattern` lying within `scope` of `text`.
+pub fn find_matches(text: &str, pattern: &Regex, scope: Range<usize>) -> Vec<Range<usize>> {
+    let mut matches = Vec::new();
+    let mut at = scope.start;
+    while at <= scope.end {
+        let Some(found) = pattern.find_at(text, at) else { break };
+        if found.end() > scope.end {
+            break;
+        }
+        if found.is_empty() {
+            // Step over the next character so empty matches don't stall
+            at = text[found.end()..].chars().next().map_or(usize::MAX, |c| found.end() + c.len_utf8());
+            continue;
+        }
+        matches.push(found.range());
+        at = found.end();
+    }
+    matches
+}
+
+/// What `matched` (at `start` in `text`) becomes; regex replacements may
+/// refer to groups as `$1` or `${name}`.
+fn expand(text: &str, pattern: &Regex, start: usize, replacement: &str, regex: bool) -> String {
+    if !regex {
+        return replacement.to_string();
+    }
+    let mut out = String::new();
+    if let Some(captures) = pattern.captures_at(text, start) {
+        captures.expand(replacement, &mut out);
+    }
+    out
+}
+
+/// `scope` of `text` with every match in `matches` replaced.
+pub fn replace_matches(text: &str, pattern: &Regex, matches: &[Range<usize>], scope: Range<usize>, replacement: &str, regex: bool) -> String {
+    let mut out = String::new();
+    let mut last = scope.start;
+    for m in matches.iter().filter(|m| m.start >= scope.start && m.end <= scope.end) {
+        out.push_str(&text[last..m.start]);
+        out.push_str(&expand(text, pattern, m.start, replacement, regex));
+        last = m.end;
+    }
+    out.push_str(&text[last..scope.end]);
+    out
+}
+
+/// State of the open find bar.
+pub(super) struct FindBar {
+    pub(super) query: Entity<InputState>,
+    replacement: Entity<InputState>,
+    regex: bool,
+    match_case: bool,
+    /// Byte range searched in find-in-selection mode.
+    scope: Option<Range<usize>>,
+    pub(super) pattern: Option<Regex>,
+    pub(super) matches: Vec<Range<usize>>,
+    /// Index of the selected match.
+    current: Option<usize>,
+    _subscription: Subscription,
+}
+
+impl FindBar {
+    fn label(&self, query_empty: bool) -> String {
+        match (&self.pattern, self.current) {
+            _ if query_empty => String::new(),
+            (None, _) => "Invalid pattern".to_string(),
+            _ if self.matches.is_empty() => "No results".to_string(),
+            (_, Some(i)) => format!("{} of {}", i + 1, self.matches.len()),
+            (_, None) => format!("{} matches", self.matches.len()),
+        }
+    }
+}
+
+impl TextEditor {
+    /// Show the find bar, seeded from the selection: a multi-line selection
+    /// becomes the search scope, anything shorter the query.
+    pub fn open_search(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let selection = self.selection_range(window, cx).filter(|r| !r.is_empty());
+        let selected = selection.clone().map(|r| self.input_state.read(cx).value()[r].to_string());
+        let multi_line = selected.as_ref().is_some_and(|s| s.contains('\n'));
+
+        if self.find.is_none() {
+            let query = cx.new(|cx| InputState::new(window, cx).placeholder("Find"));
+            let replacement = cx.new(|cx| InputState::new(window, cx).placeholder("Replace"));
+            let subscription = cx.subscribe_in(&query, window, |this, _, ev: &InputEvent, window, cx| match ev {
+                InputEvent::Change => {
+                    this.refresh_find(cx);
+                    cx.notify();
+                }
+                InputEvent::PressEnter { secondary } => this.step_match(!secondary, window, cx),
+                _ => {}
+            });
+            self.find = Some(FindBar {
+                query,
+                replacement,
+                regex: false,
+                match_case: false,
+                scope: None,
+                pattern: None,
+                matches: Vec::new(),
+                current: None,
+                _subscription: subscription,
+            });
+        }
+
+        let Some(bar) = self.find.as_mut() else { return };
+        if multi_line {
+            bar.scope = selection;
+        } else if let Some(text) = selected {
+            bar.query.update(cx, |state, cx| state.set_value(text, window, cx));
+        }
+        let query = bar.query.clone();
+        query.update(cx, |state, cx| state.focus(window, cx));
+        self.refresh_find(cx);
+        cx.notify();
+    }
+
+    pub fn close_find(&mut self, _: &CloseFindAction, window: &mut Window, cx: &mut Context<Self>) {
+        if self.find.take().is_some() {
+            self.input_state.update(cx, |state, cx| state.focus(window, cx));
+            cx.notify();
+        }
+    }
+
+    /// F3: next match, opening the find bar if needed.
+    pub fn find_next(&mut self, _: &FindNextAction, window: &mut Window, cx: &mut Context<Self>) {
+        match self.find {
+            Some(_) => self.step_match(true, window, cx),
+            None => self.open_search(window, cx),
+        }
+    }
+
+    /// Shift-F3: previous match.
+    pub fn find_previous(&mut self, _: &FindPreviousAction, window: &mut Window, cx: &mut Context<Self>) {
+        match self.find {
+            Some(_) => self.step_match(false, window, cx),
+            None => self.open_search(window, cx),
+        }
+    }
+
+    /// Re-run the search after the query, options or buffer changed.
+    pub(super) fn refresh_find(&mut self, cx: &App) {
+        let Some(bar) = self.find.as_mut() else { return };
+        let text = self.input_state.read(cx).value();
+        let query = bar.query.read(cx).value();
+        // Edits may have shortened the buffer under the scope
+        let clamp = |offset: usize| (0..=offset.min(text.len())).rev().find(|&i| text.is_char_boundary(i)).unwrap_or(0);
+        let scope = bar.scope.clone().map_or(0..text.len(), |s| clamp(s.start)..clamp(s.end));
+
+        bar.pattern = build_pattern(&query, bar.regex, bar.match_case).ok();
+        bar.matches = match &bar.pattern {
+            Some(pattern) if !query.is_empty() => find_matches(&text, pattern, scope),
+            _ => Vec::new(),
+        };
+        let cursor = self.input_state.read(cx).cursor();
+        bar.current = bar.matches.iter().position(|m| m.contains(&cursor) || m.end == cursor);
+    }
+
+    /// Select the next (or previous) match, wrapping around.
+    fn step_match(&mut self, forward: bool, window: &mut Window, cx: &mut Context<Self>) {
+        self.refresh_find(cx);
+        let selection = self.selection_range(window, cx).unwrap_or_default();
+        let Some(bar) = self.find.as_mut() else { return };
+        let target = if forward {
+            bar.matches.iter().position(|m| m.start >= selection.end)
+                .or((!bar.matches.is_empty()).then_some(0))
+        } else {
+            bar.matches.iter().rposition(|m| m.end <= selection.start)
+                .or(bar.matches.len().checked_sub(1))
+        };
+        let Some(index) = target else { return };
+        bar.current = Some(index);
+        let range = bar.matches[index].clone();
+        let query = bar.query.clone();
+        let refocus = query.read(cx).focus_handle(cx).is_focused(window);
+
+        self.select_range(range, window, cx);
+        if refocus {
+            query.update(cx, |state, cx| state.focus(window, cx));
+        }
+        cx.notify();
+    }
+
+    /// Replace the selected match, then move to the next one.
+    fn replace_current(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.refresh_find(cx);
+        let selection = self.selection_range(window, cx).unwrap_or_default();
+        let Some(bar) = self.find.as_ref() else { return };
+        let Some(pattern) = bar.pattern.clone() else { return };
+        if !bar.matches.contains(&selection) {
+            self.step_match(true, window, cx);
+            return;
+        }
+        let text = self.input_state.read(cx).value().to_string();
+        let replacement = bar.replacement.read(cx).value().to_string();
+        let new_text = expand(&text, &pattern, selection.start, &replacement, bar.regex);
+        self.shift_find_scope(new_text.len() as isize - selection.len() as isize);
+        self.transform_range(selection, |_| new_text, window, cx);
+        self.step_match(true, window, cx);
+    }
+
+    /// Replace every match in the search scope as one undo step.
+    fn replace_all(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.refresh_find(cx);
+        let Some(bar) = self.find.as_ref() else { return };
+        let Some(pattern) = bar.pattern.clone() else { return };
+        if bar.matches.is_empty() {
+            return;
+        }
+        let text = self.input_state.read(cx).value().to_string();
+        let replacement = bar.replacement.read(cx).value().to_string();
+        let scope = bar.scope.clone().unwrap_or(0..text.len());
+        let replaced = replace_matches(&text, &pattern, &bar.matches, scope.clone(), &replacement, bar.regex);
+        self.shift_find_scope(replaced.len() as isize - scope.len() as isize);
+        self.transform_range(scope, |_| replaced, window, cx);
+        self.refresh_find(cx);
+        cx.notify();
+    }
+
+    /// Keep the find-in-selection scope covering its text after an edit
+    /// inside it changed the length by `delta`.
+    fn shift_find_scope(&mut self, delta: isize) {
+        if let Some(scope) = self.find.as_mut().and_then(|bar| bar.scope.as_mut()) {
+            scope.end = scope.end.saturating_add_signed(delta);
+        }
+    }
+
+    fn toggle_find_option(&mut self, update: impl FnOnce(&mut FindBar, Range<usize>), window: &mut Window, cx: &mut Context<Self>) {
+        let selection = self.selection_range(window, cx).unwrap_or_default();
+        if let Some(bar) = self.find.as_mut() {
+            update(bar, selection);
+        }
+        self.refresh_find(cx);
+        cx.notify();
+    }
+
+    /// The find bar, while open.
+    pub(super) fn render_find_bar(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let bar = self.find.as_ref()?;
+        let colors = Theme::global(cx).colors;
+        let label = bar.label(bar.query.read(cx).value().is_empty());
+        let no_matches = bar.matches.is_empty();
+
+        Some(
+            h_flex()
+                .key_context("FindBar")
+                .on_action(cx.listener(Self::close_find))
+                .gap_2()
+                .px_2()
+                .py_1()
+                .bg(colors.muted)
+                .border_b_1()
+                .border_color(colors.border)
+                .child(div().w(px(220.0)).child(Input::new(&bar.query).small()))
+                .child(Button::new("find-regex").label(".*").small().ghost().selected(bar.regex)
+                    .tooltip("Regular expression")
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.toggle_find_option(|bar, _| bar.regex = !bar.regex, window, cx)
+                    })))
+                .child(Button::new("find-case").label("Aa").small().ghost().selected(bar.match_case)
+                    .tooltip("Match case")
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.toggle_find_option(|bar, _| bar.match_case = !bar.match_case, window, cx)
+                    })))
+                .child(Button::new("find-in-selection").label("In selection").small().ghost().selected(bar.scope.is_some())
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.toggle_find_option(|bar, selection| {
+                            bar.scope = match bar.scope {
+                                Some(_) => None,
+                                None => Some(selection),
+                            };
+                        }, window, cx)
+                    })))
+                .child(div().min_w(px(90.0)).text_color(colors.muted_foreground).child(label))
+                .child(Button::new("find-prev").label("Previous").small().ghost().disabled(no_matches)
+                    .on_click(cx.listener(|this, _, window, cx| this.step_match(false, window, cx))))
+                .child(Button::new("find-next").label("Next").small().ghost().disabled(no_matches)
+                    .on_click(cx.listener(|this, _, window, cx| this.step_match(true, window, cx))))
+                .child(Button::new("find-all").label("Find All").small().ghost().disabled(no_matches)
+                    .on_click(cx.listener(|this, _, _, cx| this.find_all(cx))))
+                .child(div().w(px(160.0)).child(Input::new(&bar.replacement).small()))
+                .child(Button::new("find-replace").label("Replace").small().ghost().disabled(no_matches || self.read_only)
+                    .on_click(cx.listener(|this, _, window, cx| this.replace_current(window, cx))))
+                .child(Button::new("find-replace-all").label("Replace All").small().ghost().disabled(no_matches || self.read_only)
+                    .on_click(cx.listener(|this, _, window, cx| this.replace_all(window, cx))))
+                .child(div().flex_grow())
+                .child(Button::new("find-close").label("Close").small().ghost()
+                    .on_click(cx.listener(|this, _, window, cx| this.close_find(&CloseFindAction, window, cx)))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_pattern, find_matches, replace_matches};
+
+    #[test]
+    fn test_plain_query_is_literal() {
+        let pattern = build_pattern("a.b", false, false).unwrap();
+        assert_eq!(find_matches("a.b axb A.B", &pattern, 0..11), vec![0..3, 8..11]);
+    }
+
+    #[test]
+    fn test_regex_and_case() {
+        let pattern = build_pattern(r"^\w+", true, true).unwrap();
+        assert_eq!(find_matches("one\nTwo\n", &pattern, 0..8), vec![0..3, 4..7]);
+        assert!(build_pattern("(", true, false).is_err());
+    }
+
+    #[test]
+    fn test_matches_stay_in_scope() {
+        let pattern = build_pattern("x", false, false).unwrap();
+        assert_eq!(find_matches("x x x x", &pattern, 2..5), vec![2..3, 4..5]);
+        // Empty matches are skipped rather than looping forever
+        let empty = build_pattern("y*", true, false).unwrap();
+        assert_eq!(find_matches("ayyb", &empty, 0..4), vec![1..3]);
+    }
+
+    #[test]
+    fn test_replace_matches_with_groups() {
+        let text = "key=1, other=2";
+        let pattern = build_pattern(r"(\w+)=(\d)", true, false).unwrap();
+        let matches = find_matches(text, &pattern, 0..text.len());
+        assert_eq!(replace_matches(text, &pattern, &matches, 0..text.len(), "$2:$1", true), "1:key, 2:other");
+        assert_eq!(replace_matches(text, &pattern, &matches, 0..5, "$2", false), "$2");
+    }
+}
@@ -0,0 +1,191 @@
+//! Find All: every match of the find bar's query listed in a panel below
+//! the editor, with its line, for jumping to or copying out.
+
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::{h_flex, v_flex, Disableable, Sizable, Theme};
+use std::ops::Range;
+
+use super::{SaveFindResultsAction, TextEditor};
+
+/// Characters of a matching line shown in the panel.
+const SNIPPET_LIMIT: usize = 200;
+
+/// A match listed by Find All.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FindResult {
+    /// 0-based line.
+    pub line: usize,
+    /// 0-based column, in characters.
+    pub column: usize,
+    /// Byte range of the match in the text searched.
+    pub range: Range<usize>,
+    /// The line the match is on, trimmed and shortened.
+    pub snippet: String,
+}
+
+/// `matches` of `text` (in order) with their lines, counted in one pass.
+pub fn collect_results(text: &str, matches: &[Range<usize>]) -> Vec<FindResult> {
+    let mut results = Vec::with_capacity(matches.len());
+    let mut line = 0;
+    let mut scanned = 0;
+    for range in matches {
+        line += text[scanned..range.start].matches('\n').count();
+        scanned = range.start;
+        let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[range.start..].find('\n').map_or(text.len(), |i| range.start + i);
+        let line_text = text[line_start..line_end].trim_end_matches('\r');
+        let mut snippet: String = line_text.trim().chars().take(SNIPPET_LIMIT).collect();
+        if line_text.trim().chars().count() > SNIPPET_LIMIT {
+            snippet.push('\u{2026}');
+        }
+        results.push(FindResult {
+            line,
+            column: text[line_start..range.start].chars().count(),
+            range: range.clone(),
+            snippet,
+        });
+    }
+    results
+}
+
+/// "1 match", "2 matches" and so on.
+fn match_count(count: usize) -> String {
+    format!("{} {}", count, if count == 1 { "match" } else { "matches" })
+}
+
+/// The results as plain text, one `line:column: snippet` per match, under
+/// a heading naming the query.
+pub fn results_text(query: &str, results: &[FindResult]) -> String {
+    let mut out = format!("Find All \"{}\": {}\n", query, match_count(results.len()));
+    for result in results {
+        out.push_str(&format!("{}:{}: {}\n", result.line + 1, result.column + 1, result.snippet));
+    }
+    out
+}
+
+/// State of the open Find All panel.
+pub(super) struct FindResultsPanel {
+    query: String,
+    results: Vec<FindResult>,
+    /// Document revision searched; after edits the ranges may be stale, so
+    /// clicks go by line and column instead.
+    revision: u64,
+}
+
+impl TextEditor {
+    /// List every match of the find bar's query in the results panel.
+    pub(super) fn find_all(&mut self, cx: &mut Context<Self>) {
+        self.refresh_find(cx);
+        let Some(bar) = self.find.as_ref() else { return };
+        let query = bar.query.read(cx).value().to_string();
+        if query.is_empty() || bar.pattern.is_none() {
+            return;
+        }
+        let text = self.input_state.read(cx).value();
+        let results = collect_results(&text, &bar.matches);
+        let revision = self.document.read(cx).revision();
+        self.find_results = Some(FindResultsPanel { query, results, revision });
+        cx.notify();
+    }
+
+    pub fn close_find_results(&mut self, cx: &mut Context<Self>) {
+        if self.find_results.take().is_some() {
+            cx.notify();
+        }
+    }
+
+    /// The open results as text, for copying or a new document.
+    pub fn find_results_text(&self) -> Option<String> {
+        self.find_results.as_ref().map(|panel| results_text(&panel.query, &panel.results))
+    }
+
+    fn jump_to_result(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(panel) = &self.find_results else { return };
+        let Some(result) = panel.results.get(index).cloned() else { return };
+        if panel.revision == self.document.read(cx).revision() {
+            self.select_range(result.range, window, cx);
+        } else {
+            self.go_to_line_column(result.line, result.column, window, cx);
+        }
+        self.input_state.update(cx, |state, cx| state.focus(window, cx));
+    }
+
+    fn copy_find_results(&mut self, cx: &mut Context<Self>) {
+        if let Some(text) = self.find_results_text() {
+            cx.write_to_clipboard(ClipboardItem::new_string(text));
+        }
+    }
+
+    pub(super) fn render_find_results(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let panel = self.find_results.as_ref()?;
+        let colors = Theme::global(cx).colors;
+        let count = panel.results.len();
+        let title = format!("Find All \"{}\" ({})", panel.query, match_count(count));
+
+        Some(
+            v_flex()
+                .border_t_1()
+                .border_color(colors.border)
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .px_2()
+                        .py_1()
+                        .bg(colors.muted)
+                        .child(div().flex_1().overflow_hidden().whitespace_nowrap().child(title))
+                        .child(Button::new("find-results-copy").label("Copy").small().ghost().disabled(count == 0)
+                            .on_click(cx.listener(|this, _, _, cx| this.copy_find_results(cx))))
+                        .child(Button::new("find-results-save").label("New Document").small().ghost().disabled(count == 0)
+                            .on_click(|_, window, cx| window.dispatch_action(Box::new(SaveFindResultsAction), cx)))
+                        .child(Button::new("find-results-close").label("Close").small().ghost()
+                            .on_click(cx.listener(|this, _, _, cx| this.close_find_results(cx)))),
+                )
+                .child(
+                    uniform_list("find-results", count, cx.processor(|this, range: Range<usize>, _window, cx| {
+                        let Some(panel) = &this.find_results else { return Vec::new() };
+                        let colors = Theme::global(cx).colors;
+                        range.filter_map(|index| {
+                            let result = panel.results.get(index)?;
+                            Some(
+                                h_flex()
+                                    .id(("find-result", index))
+                                    .gap_2()
+                                    .px_2()
+                                    .cursor_pointer()
+                                    .hover(|this| this.bg(colors.accent))
+                                    .on_click(cx.listener(move |this, _, window, cx| this.jump_to_result(index, window, cx)))
+                                    .child(div().w(px(70.0)).flex_none().text_color(colors.muted_foreground).child(format!("Ln {}", result.line + 1)))
+                                    .child(div().flex_1().overflow_hidden().whitespace_nowrap().child(result.snippet.clone())),
+                            )
+                        }).collect()
+                    }))
+                    .h(px(160.0)),
+                ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect_results, results_text};
+    use std::ops::Range;
+
+    #[test]
+    fn test_collect_results() {
+        let text = "alpha\n  error: one\nbeta\r\nerror two error\n";
+        let matches: Vec<_> = text.match_indices("error").map(|(at, m)| at..at + m.len()).collect();
+        let results = collect_results(text, &matches);
+        let lines: Vec<_> = results.iter().map(|r| (r.line, r.column, r.snippet.as_str())).collect();
+        assert_eq!(lines, [(1, 2, "error: one"), (3, 0, "error two error"), (3, 10, "error two error")]);
+    }
+
+    #[test]
+    fn test_results_text() {
+        let text = "a\nfind me\n";
+        let results = collect_results(text, &[Range { start: 2, end: 6 }]);
+        assert_eq!(results_text("find", &results), "Find All \"find\": 1 match\n2:1: find me\n");
+        let results = collect_results("find find", &[0..4, 5..9]);
+        assert_eq!(results_text("find", &results), "Find All \"find\": 2 matches\n1:1: find find\n1:6: find find\n");
+    }
+}
@@ -0,0 +1,361 @@
+//! Pretty-printing and minifying JSON and XML.
+
+use gpui::*;
+use gpui_component::notification::Notification;
+use gpui_component::WindowExt;
+use std::fmt;
+use std::path::Path;
+use tracing::debug;
+
+use super::{TabHandling, TextEditor};
+
+/// Structured formats the Format menu understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Json,
+    Xml,
+}
+
+impl DataFormat {
+    /// Guess from `path`'s extension, then from the first non-blank character.
+    pub fn detect(path: Option<&Path>, text: &str) -> Option<Self> {
+        let extension = path.and_then(|p| p.extension()).and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("json" | "jsonc" | "geojson" | "har" | "ipynb") => return Some(DataFormat::Json),
+            Some("xml" | "svg" | "xsd" | "xsl" | "xslt" | "xaml" | "plist" | "csproj" | "rss" | "atom") => {
+                return Some(DataFormat::Xml);
+            }
+            _ => {}
+        }
+        match text.trim_start().chars().next()? {
+            '{' | '[' => Some(DataFormat::Json),
+            '<' => Some(DataFormat::Xml),
+            _ => None,
+        }
+    }
+
+    /// `text` reformatted one level of `indent` per nesting level, or on a
+    /// single line if `indent` is `None`. Errors name the 1-based line.
+    pub fn reformat(self, text: &str, indent: Option<&str>) -> Result<String, FormatError> {
+        let mut result = match self {
+            DataFormat::Json => reformat_json(text, indent)?,
+            DataFormat::Xml => reformat_xml(text, indent)?,
+        };
+        if text.ends_with('\n') {
+            result.push('\n');
+        }
+        Ok(result)
+    }
+}
+
+impl fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DataFormat::Json => "JSON",
+            DataFormat::Xml => "XML",
+        })
+    }
+}
+
+/// Why a document couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+fn push_line(out: &mut String, indent: &str, depth: usize) {
+    out.push('\n');
+    for _ in 0..depth {
+        out.push_str(indent);
+    }
+}
+
+/// Rewrite the whitespace between JSON tokens, keeping keys in order and
+/// numbers exactly as written.
+fn reformat_json(text: &str, indent: Option<&str>) -> Result<String, FormatError> {
+    if let Err(err) = serde_json::from_str::<serde::de::IgnoredAny>(text) {
+        let message = err.to_string();
+        let message = message.split(" at line ").next().unwrap_or(&message).to_string();
+        return Err(FormatError { line: err.line().max(1), message });
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    out.push(c);
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            c if c.is_whitespace() => {}
+            '{' | '[' => {
+                out.push(c);
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if matches!(chars.peek(), Some('}' | ']')) {
+                    out.extend(chars.next());
+                } else if let Some(indent) = indent {
+                    depth += 1;
+                    push_line(&mut out, indent, depth);
+                }
+            }
+            '}' | ']' => {
+                if let Some(indent) = indent {
+                    depth -= 1;
+                    push_line(&mut out, indent, depth);
+                }
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                if let Some(indent) = indent {
+                    push_line(&mut out, indent, depth);
+                }
+            }
+            ':' => out.push_str(if indent.is_some() { ": " } else { ":" }),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// A piece of an XML document.
+#[derive(Debug, PartialEq, Eq)]
+enum XmlToken<'a> {
+    Open(&'a str),
+    Close(&'a str),
+    /// Self-closing tags, comments, CDATA, processing instructions and
+    /// doctypes: copied as they are.
+    Other,
+    Text,
+}
+
+/// The element name at the start of a tag's contents.
+fn tag_name(tag: &str) -> &str {
+    tag.split(|c: char| c.is_whitespace() || c == '/' || c == '>').next().unwrap_or("")
+}
+
+/// Split `text` into tokens with their source, checking that tags nest.
+fn tokenize_xml(text: &str) -> Result<Vec<(XmlToken<'_>, &str)>, FormatError> {
+    let error = |offset: usize, message: String| FormatError { line: line_of(text, offset), message };
+    let mut tokens = Vec::new();
+    let mut open: Vec<(&str, usize)> = Vec::new();
+    let mut pos = 0;
+    while pos < text.len() {
+        let rest = &text[pos..];
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            tokens.push((XmlToken::Text, &rest[..end]));
+            pos += end;
+            continue;
+        }
+        let terminator = if rest.starts_with("<!--") {
+            "-->"
+        } else if rest.starts_with("<![CDATA[") {
+            "]]>"
+        } else if rest.starts_with("<?") {
+            "?>"
+        } else if rest.starts_with("<!") && rest.find('[').is_some_and(|b| rest.find('>').is_some_and(|g| b < g)) {
+            "]>"
+        } else {
+            ""
+        };
+        let end = if terminator.is_empty() {
+            // Find the closing '>' outside quoted attribute values
+            let mut quote = None;
+            rest.char_indices().find_map(|(i, c)| match (quote, c) {
+                (None, '"' | '\'') => {
+                    quote = Some(c);
+                    None
+                }
+                (Some(q), c) if c == q => {
+                    quote = None;
+                    None
+                }
+                (None, '>') => Some(i + 1),
+                _ => None,
+            })
+        } else {
+            rest.find(terminator).map(|i| i + terminator.len())
+        };
+        let Some(end) = end else {
+            return Err(error(pos, "Unterminated tag".to_string()));
+        };
+        let raw = &rest[..end];
+        let token = if !terminator.is_empty() || raw.starts_with("<!") || raw.ends_with("/>") {
+            XmlToken::Other
+        } else if let Some(tag) = raw.strip_prefix("</") {
+            let tag = tag_name(tag);
+            match open.pop() {
+                Some((expected, _)) if expected == tag => XmlToken::Close(tag),
+                Some((expected, _)) => return Err(error(pos, format!("Expected </{}>, found </{}>", expected, tag))),
+                None => return Err(error(pos, format!("Unexpected </{}>", tag))),
+            }
+        } else {
+            let tag = tag_name(&raw[1..]);
+            if tag.is_empty() {
+                return Err(error(pos, "Tag without a name".to_string()));
+            }
+            open.push((tag, pos));
+            XmlToken::Open(tag)
+        };
+        tokens.push((token, raw));
+        pos += end;
+    }
+    if let Some((tag, offset)) = open.pop() {
+        return Err(error(offset, format!("<{}> is never closed", tag)));
+    }
+    Ok(tokens)
+}
+
+/// Put each tag on its own line, keeping elements that hold only text on
+/// one line. Whitespace between tags is dropped.
+fn reformat_xml(text: &str, indent: Option<&str>) -> Result<String, FormatError> {
+    let tokens: Vec<_> = tokenize_xml(text)?
+        .into_iter()
+        .filter(|(token, raw)| *token != XmlToken::Text || !raw.trim().is_empty())
+        .collect();
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let (token, raw) = &tokens[i];
+        let raw = if *token == XmlToken::Text && indent.is_some() { raw.trim() } else { raw };
+        let closes = matches!(token, XmlToken::Close(_));
+        if closes {
+            depth -= 1;
+        }
+        if let (Some(indent), false) = (indent, out.is_empty()) {
+            push_line(&mut out, indent, depth);
+        }
+        out.push_str(raw);
+        if let XmlToken::Open(_) = token {
+            // <tag>text</tag> and <tag></tag> stay on one line
+            let inline_len = match tokens.get(i + 1..i + 3) {
+                Some([(XmlToken::Text, text), (XmlToken::Close(_), close)]) => {
+                    out.push_str(if indent.is_some() { text.trim() } else { text });
+                    out.push_str(close);
+                    2
+                }
+                _ => match tokens.get(i + 1) {
+                    Some((XmlToken::Close(_), close)) => {
+                        out.push_str(close);
+                        1
+                    }
+                    _ => 0,
+                },
+            };
+            if inline_len == 0 {
+                depth += 1;
+            }
+            i += inline_len;
+        }
+        i += 1;
+    }
+    Ok(out)
+}
+
+impl TextEditor {
+    /// Pretty-print (or minify) the selection, or the whole document, as one
+    /// undo step. `format` defaults to the one detected for the document.
+    /// Parse errors are shown as a notification.
+    pub fn reformat(&mut self, format: Option<DataFormat>, pretty: bool, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
+        let selection = self.selection_range(window, cx).filter(|r| !r.is_empty());
+        let text = self.input_state.read(cx).value();
+        let range = selection.clone().unwrap_or(0..text.len());
+        let Some(format) = format.or_else(|| DataFormat::detect(self.current_file(cx), &text[range.clone()])) else {
+            window.push_notification(Notification::warning("Couldn't tell whether this is JSON or XML."), cx);
+            return;
+        };
+        let indent = match self.tab_handling {
+            TabHandling::Preserve => "\t".to_string(),
+            _ => " ".repeat(self.tab_width.max(1)),
+        };
+        let first_line = line_of(&text, range.start);
+        match format.reformat(&text[range.clone()], pretty.then_some(indent.as_str())) {
+            Ok(formatted) => {
+                debug!(%format, pretty, "Reformatted document");
+                let len = formatted.len();
+                self.transform_range(range.clone(), |_| formatted, window, cx);
+                if selection.is_some() {
+                    self.select_range(range.start..range.start + len, window, cx);
+                }
+            }
+            Err(err) => {
+                let err = FormatError { line: err.line + first_line - 1, ..err };
+                window.push_notification(Notification::error(err.to_string()).title(format!("Invalid {}", format)), cx);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DataFormat;
+    use std::path::Path;
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(DataFormat::detect(Some(Path::new("a.JSON")), "x"), Some(DataFormat::Json));
+        assert_eq!(DataFormat::detect(Some(Path::new("a.txt")), "  <root/>"), Some(DataFormat::Xml));
+        assert_eq!(DataFormat::detect(None, "plain"), None);
+    }
+
+    #[test]
+    fn test_reformat_json() {
+        let text = "{\"b\": [1, 2.50, {}], \"a\" :\"x, y: {z}\\\"\", \"c\": []}\n";
+        let pretty = DataFormat::Json.reformat(text, Some("  ")).unwrap();
+        assert_eq!(pretty, "{\n  \"b\": [\n    1,\n    2.50,\n    {}\n  ],\n  \"a\": \"x, y: {z}\\\"\",\n  \"c\": []\n}\n");
+        let minified = DataFormat::Json.reformat(&pretty, None).unwrap();
+        assert_eq!(minified, "{\"b\":[1,2.50,{}],\"a\":\"x, y: {z}\\\"\",\"c\":[]}\n");
+    }
+
+    #[test]
+    fn test_json_errors() {
+        let err = DataFormat::Json.reformat("{\n  \"a\": 1,\n}", Some("  ")).unwrap_err();
+        assert_eq!(err.line, 3);
+    }
+
+    #[test]
+    fn test_reformat_xml() {
+        let text = "<?xml version=\"1.0\"?><root a=\"1>2\"><item>one</item><empty></empty><br/>\n  <!-- note --><list><x>2</x></list></root>";
+        let pretty = DataFormat::Xml.reformat(text, Some("  ")).unwrap();
+        assert_eq!(
+            pretty,
+            "<?xml version=\"1.0\"?>\n<root a=\"1>2\">\n  <item>one</item>\n  <empty></empty>\n  <br/>\n  <!-- note -->\n  <list>\n    <x>2</x>\n  </list>\n</root>"
+        );
+        let minified = DataFormat::Xml.reformat(&pretty, None).unwrap();
+        assert_eq!(minified, "<?xml version=\"1.0\"?><root a=\"1>2\"><item>one</item><empty></empty><br/><!-- note --><list><x>2</x></list></root>");
+    }
+
+    #[test]
+    fn test_xml_errors() {
+        let err = DataFormat::Xml.reformat("<a>\n<b>\n</a>", Some("  ")).unwrap_err();
+        assert_eq!((err.line, err.message.as_str()), (3, "Expected </b>, found </a>"));
+        assert_eq!(DataFormat::Xml.reformat("<a>\n<b/>", None).unwrap_err().message, "<a> is never closed");
+    }
+}
@@ -1,7 +1,41 @@
 //! FPS tracking for the status bar display.
 
 use std::collections::VecDeque;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+
+/// Without input for this long, the editor counts as idle.
+pub const IDLE_AFTER: Duration = Duration::from_secs(5);
+
+/// How the editor schedules redraws while the FPS counter is shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FramePacing {
+    /// Most frames per second to draw (0 for no cap).
+    pub max_fps: u32,
+    /// Stop redrawing while the window is unfocused or idle.
+    pub power_saving: bool,
+}
+
+/// When to draw the next frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NextFrame {
+    /// On the next display refresh.
+    Immediate,
+    After(Duration),
+    /// Only when something changes; the FPS tracker pauses meanwhile.
+    Paused,
+}
+
+impl FramePacing {
+    pub fn next_frame(self, active: bool, idle: bool) -> NextFrame {
+        if self.power_saving && (!active || idle) {
+            NextFrame::Paused
+        } else if self.max_fps > 0 {
+            NextFrame::After(Duration::from_secs(1) / self.max_fps)
+        } else {
+            NextFrame::Immediate
+        }
+    }
+}
 
 /// Sliding window FPS calculator for status bar display.
 pub struct FpsTracker {
@@ -43,6 +77,12 @@ impl FpsTracker {
         self.current_fps
     }
 
+    /// Forget the samples, so the rate restarts from zero when frames resume.
+    pub fn pause(&mut self) {
+        self.samples.clear();
+        self.current_fps = 0.0;
+    }
+
     #[allow(dead_code)]
     pub fn current(&self) -> f32 {
         self.current_fps
@@ -54,3 +94,21 @@ impl Default for FpsTracker {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{FramePacing, NextFrame};
+    use std::time::Duration;
+
+    #[test]
+    fn test_next_frame() {
+        let uncapped = FramePacing::default();
+        assert_eq!(uncapped.next_frame(false, true), NextFrame::Immediate);
+        let capped = FramePacing { max_fps: 20, power_saving: false };
+        assert_eq!(capped.next_frame(true, false), NextFrame::After(Duration::from_millis(50)));
+        let saving = FramePacing { max_fps: 0, power_saving: true };
+        assert_eq!(saving.next_frame(true, false), NextFrame::Immediate);
+        assert_eq!(saving.next_frame(false, false), NextFrame::Paused);
+        assert_eq!(saving.next_frame(true, true), NextFrame::Paused);
+    }
+}
@@ -0,0 +1,59 @@
+//! Freeze Top Line: once the first line scrolls out of view it stays
+//! pinned above the text, like a frozen header row in a spreadsheet.
+
+use gpui::*;
+use gpui_component::{RopeExt, Theme};
+
+use super::TextEditor;
+
+impl TextEditor {
+    pub fn toggle_freeze_top_line(&mut self, cx: &mut Context<Self>) {
+        self.freeze_top_line = !self.freeze_top_line;
+        cx.notify();
+    }
+
+    pub fn freeze_top_line_enabled(&self) -> bool {
+        self.freeze_top_line
+    }
+
+    /// Whether the first line is above the visible text.
+    fn top_line_scrolled_off(&self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        self.viewport(window, cx).is_some_and(|v| v.first > 0)
+    }
+
+    /// The pinned first line, while it is scrolled out of view.
+    pub(super) fn render_frozen_line(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if !self.freeze_top_line {
+            return None;
+        }
+        // The viewport comes from the last layout, so look again once this
+        // frame has been laid out in case scrolling just crossed the line
+        let pinned = self.top_line_scrolled_off(window, cx);
+        cx.on_next_frame(window, move |this, window, cx| {
+            if this.freeze_top_line && this.top_line_scrolled_off(window, cx) != pinned {
+                cx.notify();
+            }
+        });
+        if !pinned {
+            return None;
+        }
+
+        let text = self.input_state.read(cx).text().clone();
+        let line = text.slice(0..text.line_end_offset(0)).to_string();
+        let indent = self.text_indent(window, cx);
+        let colors = Theme::global(cx).colors;
+        Some(
+            div()
+                .flex_none()
+                .pl(indent)
+                .py_1()
+                .border_b_1()
+                .border_color(colors.border)
+                .bg(colors.muted)
+                .text_color(colors.foreground)
+                .overflow_hidden()
+                .whitespace_nowrap()
+                .child(line.trim_end_matches('\r').to_string()),
+        )
+    }
+}
@@ -1,20 +1,100 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 use tracing::debug;
 
+/// Default window in which consecutive keystrokes share one undo step.
+pub const DEFAULT_GROUP_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Default cap on the text held by undo/redo steps.
+pub const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Document state restored by undo/redo.
 #[derive(Clone, Debug)]
 pub struct Snapshot {
     pub text: String,
+    /// Selection anchor; the same as `cursor_head` when nothing is selected.
     pub cursor_anchor: usize,
     pub cursor_head: usize,
 }
 
+/// One undo step: `removed` at `start` was replaced by `inserted`.
+#[derive(Debug)]
+struct Edit {
+    start: usize,
+    removed: String,
+    inserted: String,
+    /// Cursor (anchor, head) before and after the edit.
+    cursor_before: (usize, usize),
+    cursor_after: (usize, usize),
+    /// When the edit was made, or last grew by a typed character.
+    at: Instant,
+}
+
+impl Edit {
+    fn size(&self) -> usize {
+        self.removed.len() + self.inserted.len()
+    }
+
+    fn apply(&self, text: &mut String) {
+        text.replace_range(self.start..self.start + self.removed.len(), &self.inserted);
+    }
+
+    fn revert(&self, text: &mut String) {
+        text.replace_range(self.start..self.start + self.inserted.len(), &self.removed);
+    }
+
+    /// Short description like `+"word"`, `-"word"` or `"old" -> "new"`.
+    fn summary(&self) -> String {
+        match (self.removed.is_empty(), self.inserted.is_empty()) {
+            (true, _) => format!("+\"{}\"", clip(&self.inserted)),
+            (_, true) => format!("-\"{}\"", clip(&self.removed)),
+            _ => format!("\"{}\" -> \"{}\"", clip(&self.removed), clip(&self.inserted)),
+        }
+    }
+}
+
+/// `text` on one line, cut short with an ellipsis.
+fn clip(text: &str) -> String {
+    const MAX_CHARS: usize = 24;
+    let one_line = text.replace('\r', "").replace('\n', "\\n").replace('\t', "\\t");
+    match one_line.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}...", &one_line[..end]),
+        None => one_line,
+    }
+}
+
+/// A state in the undo history, as shown in the history panel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+    /// When the edit leading to this state was made.
+    pub at: Instant,
+    /// What the edit changed, shortened.
+    pub summary: String,
+}
+
+/// Undo history stored as edits against the current text, so memory grows
+/// with the size of each change rather than the size of the document.
 pub struct History {
-    stack: Vec<Snapshot>,
-    /// Index of the current state in the stack.
-    /// If current_index == 0, we are at the initial state.
-    /// stack[current_index] is the current state.
+    /// Current document text.
+    text: String,
+    /// Cursor of the initial state, before any recorded edit.
+    base_cursor: (usize, usize),
+    edits: VecDeque<Edit>,
+    /// Number of edits applied; edits[current_index..] can be redone.
+    /// If current_index == 0, we are at the oldest reachable state.
     pub current_index: usize,
-    /// The index that matches the saved state on disk.
-    pub saved_index: usize,
+    /// The index that matches the saved state on disk, if still reachable.
+    pub saved_index: Option<usize>,
+    /// Consecutive typed characters within this window are grouped
+    /// into one undo step. Zero disables grouping.
+    group_timeout: Duration,
+    /// When the top edit last absorbed a typed character, if it is
+    /// still open for grouping.
+    open_group: Option<Instant>,
+    /// Bytes of text held by `edits`.
+    memory: usize,
+    /// Oldest edits are dropped once `memory` exceeds this.
+    memory_limit: usize,
 }
 
 impl Default for History {
@@ -26,84 +106,285 @@ impl Default for History {
 impl History {
     pub fn new() -> Self {
         Self {
-            stack: vec![Snapshot {
-                text: String::new(),
-                cursor_anchor: 0,
-                cursor_head: 0,
-            }],
+            text: String::new(),
+            base_cursor: (0, 0),
+            edits: VecDeque::new(),
             current_index: 0,
-            saved_index: 0,
+            saved_index: Some(0),
+            group_timeout: DEFAULT_GROUP_TIMEOUT,
+            open_group: None,
+            memory: 0,
+            memory_limit: DEFAULT_MEMORY_LIMIT,
         }
     }
 
+    pub fn set_group_timeout(&mut self, timeout: Duration) {
+        self.group_timeout = timeout;
+        self.open_group = None;
+    }
+
+    /// Cap the bytes kept for undo/redo; the oldest steps are dropped first.
+    pub fn set_memory_limit(&mut self, bytes: usize) {
+        self.memory_limit = bytes;
+        self.enforce_memory_limit();
+    }
+
     /// Reset with new content (e.g. on file load).
     pub fn clear(&mut self, text: String) {
-        self.stack = vec![Snapshot {
-            text,
-            cursor_anchor: 0,
-            cursor_head: 0,
-        }];
+        self.text = text;
+        self.base_cursor = (0, 0);
+        self.edits.clear();
         self.current_index = 0;
-        self.saved_index = 0;
+        self.saved_index = Some(0);
+        self.open_group = None;
+        self.memory = 0;
     }
 
     /// Push new state, invalidates redo stack.
     pub fn push(&mut self, text: String, anchor: usize, head: usize) {
+        self.push_at(text, anchor, head, Instant::now());
+    }
+
+    /// Push new state as of `now`, merging typed characters into the open group.
+    pub fn push_at(&mut self, text: String, anchor: usize, head: usize, now: Instant) {
+        let before = self.cursor();
+
         // Debounce / deduplicate: if text unchanged, just update cursor position
-        if let Some(top) = self.stack.get_mut(self.current_index) {
-            if top.text == text {
-                // Text unmodified, just update cursor
-                top.cursor_anchor = anchor;
-                top.cursor_head = head;
-                debug!("History update cursor: index {}", self.current_index);
-                return;
+        if self.text == text {
+            self.set_cursor((anchor, head));
+            debug!("History update cursor: index {}", self.current_index);
+            return;
+        }
+
+        let typed = typed_char(&self.text, before.1, &text, head);
+        if let Some(ch) = typed {
+            let group_open = self.open_group
+                .is_some_and(|last| now.saturating_duration_since(last) <= self.group_timeout);
+            let at_redo_tip = self.current_index == self.edits.len();
+            if group_open && at_redo_tip && self.saved_index != Some(self.current_index) {
+                if let Some(top) = self.edits.back_mut() {
+                    if top.start + top.inserted.len() == head - ch.len_utf8() {
+                        top.inserted.push(ch);
+                        top.cursor_after = (anchor, head);
+                        top.at = now;
+                        self.memory += ch.len_utf8();
+                        self.text = text;
+                        // A word boundary ends the group after absorbing the separator
+                        self.open_group = (!ch.is_whitespace()).then_some(now);
+                        debug!("History merge: index {}", self.current_index);
+                        self.enforce_memory_limit();
+                        return;
+                    }
+                }
             }
         }
-        
+
         // Truncate redo history
-        if self.current_index < self.stack.len() - 1 {
-            self.stack.truncate(self.current_index + 1);
+        for edit in self.edits.drain(self.current_index..) {
+            self.memory -= edit.size();
+        }
+        if self.saved_index.is_some_and(|saved| saved > self.current_index) {
+            self.saved_index = None;
         }
 
-        self.stack.push(Snapshot {
-            text,
-            cursor_anchor: anchor,
-            cursor_head: head,
-        });
+        let edit = match typed {
+            Some(ch) => Edit {
+                start: head - ch.len_utf8(),
+                removed: String::new(),
+                inserted: ch.to_string(),
+                cursor_before: before,
+                cursor_after: (anchor, head),
+                at: now,
+            },
+            None => {
+                let (start, removed, inserted) = diff(&self.text, &text);
+                Edit {
+                    start,
+                    removed: removed.to_string(),
+                    inserted: inserted.to_string(),
+                    cursor_before: before,
+                    cursor_after: (anchor, head),
+                    at: now,
+                }
+            }
+        };
+        self.memory += edit.size();
+        self.edits.push_back(edit);
         self.current_index += 1;
-        debug!("History push: index {}, stack size {}", self.current_index, self.stack.len());
+        self.text = text;
+        self.open_group = match typed {
+            Some(ch) if !ch.is_whitespace() && !self.group_timeout.is_zero() => Some(now),
+            _ => None,
+        };
+        debug!("History push: index {}, edits {}, bytes {}", self.current_index, self.edits.len(), self.memory);
+        self.enforce_memory_limit();
     }
 
-    pub fn undo(&mut self) -> Option<&Snapshot> {
+    pub fn undo(&mut self) -> Option<Snapshot> {
+        self.open_group = None;
         if self.current_index > 0 {
             self.current_index -= 1;
+            let edit = &self.edits[self.current_index];
+            edit.revert(&mut self.text);
             debug!("Undo: index {}", self.current_index);
-            self.stack.get(self.current_index)
+            Some(self.snapshot(edit.cursor_before))
         } else {
             None
         }
     }
 
-    pub fn redo(&mut self) -> Option<&Snapshot> {
-        if self.current_index < self.stack.len() - 1 {
+    pub fn redo(&mut self) -> Option<Snapshot> {
+        self.open_group = None;
+        if self.current_index < self.edits.len() {
+            let edit = &self.edits[self.current_index];
+            edit.apply(&mut self.text);
             self.current_index += 1;
             debug!("Redo: index {}", self.current_index);
-            self.stack.get(self.current_index)
+            Some(self.snapshot(edit.cursor_after))
         } else {
             None
         }
     }
 
+    /// The states after each kept edit, oldest first; entry `i` is state
+    /// `i + 1`, state 0 being the oldest reachable one.
+    pub fn entries(&self) -> impl Iterator<Item = HistoryEntry> + '_ {
+        self.edits.iter().map(|edit| HistoryEntry { at: edit.at, summary: edit.summary() })
+    }
+
+    /// Undo or redo as many steps as it takes to reach state `index`.
+    pub fn jump_to(&mut self, index: usize) -> Option<Snapshot> {
+        if index > self.edits.len() || index == self.current_index {
+            return None;
+        }
+        self.open_group = None;
+        let mut cursor = self.cursor();
+        while self.current_index > index {
+            self.current_index -= 1;
+            let edit = &self.edits[self.current_index];
+            edit.revert(&mut self.text);
+            cursor = edit.cursor_before;
+        }
+        while self.current_index < index {
+            let edit = &self.edits[self.current_index];
+            edit.apply(&mut self.text);
+            self.current_index += 1;
+            cursor = edit.cursor_after;
+        }
+        debug!("History jump: index {}", self.current_index);
+        Some(self.snapshot(cursor))
+    }
+
     /// Mark current state as saved.
     pub fn mark_saved(&mut self) {
-        self.saved_index = self.current_index;
+        // Keep later typing out of the saved snapshot
+        self.open_group = None;
+        self.saved_index = Some(self.current_index);
     }
 
     pub fn is_dirty(&self) -> bool {
-        self.current_index != self.saved_index
+        self.saved_index != Some(self.current_index)
+    }
+
+    /// Text of the saved state, if it is still within the kept history.
+    pub fn saved_text(&self) -> Option<String> {
+        let saved = self.saved_index?;
+        let mut text = self.text.clone();
+        if saved < self.current_index {
+            for edit in self.edits.range(saved..self.current_index).rev() {
+                edit.revert(&mut text);
+            }
+        } else {
+            for edit in self.edits.range(self.current_index..saved) {
+                edit.apply(&mut text);
+            }
+        }
+        Some(text)
+    }
+
+    fn snapshot(&self, (anchor, head): (usize, usize)) -> Snapshot {
+        Snapshot {
+            text: self.text.clone(),
+            cursor_anchor: anchor,
+            cursor_head: head,
+        }
+    }
+
+    /// Cursor of the current state.
+    fn cursor(&self) -> (usize, usize) {
+        match self.current_index {
+            0 => self.base_cursor,
+            i => self.edits[i - 1].cursor_after,
+        }
+    }
+
+    /// Record a selection change that came without an edit, so undoing the
+    /// next edit brings the selection back. Offsets that don't fit the
+    /// current text are ignored.
+    pub fn set_selection(&mut self, anchor: usize, head: usize) {
+        if self.text.is_char_boundary(anchor) && self.text.is_char_boundary(head) {
+            self.set_cursor((anchor, head));
+        }
+    }
+
+    fn set_cursor(&mut self, cursor: (usize, usize)) {
+        match self.current_index {
+            0 => self.base_cursor = cursor,
+            i => self.edits[i - 1].cursor_after = cursor,
+        }
+        if let Some(next) = self.edits.get_mut(self.current_index) {
+            next.cursor_before = cursor;
+        }
+    }
+
+    fn enforce_memory_limit(&mut self) {
+        while self.memory > self.memory_limit && self.current_index > 0 {
+            let Some(oldest) = self.edits.pop_front() else { break };
+            self.memory -= oldest.size();
+            self.base_cursor = oldest.cursor_after;
+            self.current_index -= 1;
+            self.saved_index = self.saved_index.and_then(|saved| saved.checked_sub(1));
+            self.open_group = None;
+            debug!("History dropped oldest step, bytes {}", self.memory);
+        }
     }
 }
 
+/// Changed region between two texts as (start, removed, inserted).
+fn diff<'a>(old: &'a str, new: &'a str) -> (usize, &'a str, &'a str) {
+    let mut prefix = old.bytes().zip(new.bytes()).take_while(|(a, b)| a == b).count();
+    while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let mut suffix = old.bytes().rev()
+        .zip(new.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+
+    (prefix, &old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix])
+}
+
+/// The single character typed at the cursor to turn `old` into `new`, if
+/// that is the whole difference.
+fn typed_char(old: &str, old_head: usize, new: &str, new_head: usize) -> Option<char> {
+    let start = new_head.checked_sub(new.len().checked_sub(old.len())?)?;
+    if start != old_head || !new.is_char_boundary(start) || !new.is_char_boundary(new_head) {
+        return None;
+    }
+    let mut inserted = new[start..new_head].chars();
+    let ch = inserted.next()?;
+    if inserted.next().is_some() || ch == '\n' {
+        return None;
+    }
+    (old.get(..start)? == &new[..start] && old.get(start..)? == &new[new_head..]).then_some(ch)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,4 +481,246 @@ mod tests {
         assert!(!history.is_dirty());
         assert!(history.undo().is_none());
     }
+
+    fn type_str(history: &mut History, start: &str, typed: &str, at: Instant) -> String {
+        let mut text = start.to_string();
+        for ch in typed.chars() {
+            text.push(ch);
+            history.push_at(text.clone(), text.len(), text.len(), at);
+        }
+        text
+    }
+
+    #[test]
+    fn test_typing_a_word_is_one_undo_step() {
+        let mut history = History::new();
+        let now = Instant::now();
+        type_str(&mut history, "", "hello", now);
+
+        assert_eq!(history.undo().unwrap().text, "");
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn test_word_boundary_starts_new_group() {
+        let mut history = History::new();
+        let now = Instant::now();
+        let text = type_str(&mut history, "", "hello ", now);
+        type_str(&mut history, &text, "world", now);
+
+        assert_eq!(history.undo().unwrap().text, "hello ");
+        assert_eq!(history.undo().unwrap().text, "");
+    }
+
+    #[test]
+    fn test_pause_starts_new_group() {
+        let mut history = History::new();
+        let now = Instant::now();
+        let text = type_str(&mut history, "", "ab", now);
+        type_str(&mut history, &text, "c", now + DEFAULT_GROUP_TIMEOUT * 2);
+
+        assert_eq!(history.undo().unwrap().text, "ab");
+    }
+
+    #[test]
+    fn test_newline_is_its_own_step() {
+        let mut history = History::new();
+        let now = Instant::now();
+        let text = type_str(&mut history, "", "ab", now);
+        type_str(&mut history, &text, "\ncd", now);
+
+        assert_eq!(history.undo().unwrap().text, "ab\n");
+        assert_eq!(history.undo().unwrap().text, "ab");
+    }
+
+    #[test]
+    fn test_zero_timeout_disables_grouping() {
+        let mut history = History::new();
+        history.set_group_timeout(Duration::ZERO);
+        type_str(&mut history, "", "ab", Instant::now());
+
+        assert_eq!(history.undo().unwrap().text, "a");
+    }
+
+    #[test]
+    fn test_saved_state_is_not_merged_into() {
+        let mut history = History::new();
+        let now = Instant::now();
+        let text = type_str(&mut history, "", "ab", now);
+        history.mark_saved();
+        type_str(&mut history, &text, "c", now);
+
+        assert!(history.is_dirty());
+        assert_eq!(history.undo().unwrap().text, "ab");
+        assert!(!history.is_dirty());
+    }
+
+    #[test]
+    fn test_typed_char_requires_single_insertion_at_cursor() {
+        assert_eq!(typed_char("ac", 1, "abc", 2), Some('b'));
+        assert_eq!(typed_char("a", 1, "abc", 3), None);
+        assert_eq!(typed_char("ab", 2, "a", 1), None);
+        assert_eq!(typed_char("ac", 0, "abc", 2), None);
+    }
+
+    #[test]
+    fn test_diff_finds_changed_region() {
+        assert_eq!(diff("hello world", "hello there world"), (6, "", "there "));
+        assert_eq!(diff("abc", "aXc"), (1, "b", "X"));
+        assert_eq!(diff("héllo", "hallo"), (1, "é", "a"));
+    }
+
+    /// Reference model: the previous full-snapshot history.
+    struct SnapshotHistory {
+        stack: Vec<String>,
+        index: usize,
+    }
+
+    impl SnapshotHistory {
+        fn push(&mut self, text: &str) {
+            if self.stack[self.index] == text {
+                return;
+            }
+            self.stack.truncate(self.index + 1);
+            self.stack.push(text.to_string());
+            self.index += 1;
+        }
+
+        fn undo(&mut self) -> Option<String> {
+            (self.index > 0).then(|| {
+                self.index -= 1;
+                self.stack[self.index].clone()
+            })
+        }
+
+        fn redo(&mut self) -> Option<String> {
+            (self.index + 1 < self.stack.len()).then(|| {
+                self.index += 1;
+                self.stack[self.index].clone()
+            })
+        }
+    }
+
+    #[test]
+    fn test_undo_redo_matches_snapshot_history() {
+        let mut history = History::new();
+        history.set_group_timeout(Duration::ZERO);
+        let mut reference = SnapshotHistory { stack: vec![String::new()], index: 0 };
+
+        enum Op { Push(&'static str), Undo, Redo }
+        let ops = [
+            Op::Push("hello"), Op::Push("hello world"), Op::Push("héllo world"),
+            Op::Undo, Op::Undo, Op::Redo, Op::Push("hello, world"), Op::Redo,
+            Op::Push("x"), Op::Push(""), Op::Undo, Op::Undo, Op::Undo, Op::Undo,
+            Op::Redo, Op::Redo, Op::Redo, Op::Redo,
+        ];
+        for op in ops {
+            match op {
+                Op::Push(text) => {
+                    history.push(text.into(), 0, 0);
+                    reference.push(text);
+                }
+                Op::Undo => assert_eq!(history.undo().map(|s| s.text), reference.undo()),
+                Op::Redo => assert_eq!(history.redo().map(|s| s.text), reference.redo()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_undo_restores_cursor_before_edit() {
+        let mut history = History::new();
+        history.push("ab".into(), 2, 2);
+        history.push("ab".into(), 1, 1); // cursor moved
+        history.push("aXb".into(), 2, 2);
+
+        assert_eq!(history.undo().unwrap().cursor_head, 1);
+        assert_eq!(history.redo().unwrap().cursor_head, 2);
+    }
+
+    #[test]
+    fn test_undo_and_redo_restore_selection() {
+        let mut history = History::new();
+        history.push("one two".into(), 7, 7);
+        // "two" selected backwards, then replaced
+        history.set_selection(7, 4);
+        history.push("one 2".into(), 5, 5);
+
+        let undone = history.undo().unwrap();
+        assert_eq!((undone.cursor_anchor, undone.cursor_head), (7, 4));
+        let redone = history.redo().unwrap();
+        assert_eq!((redone.cursor_anchor, redone.cursor_head), (5, 5));
+
+        // Offsets past the text are dropped rather than stored
+        history.set_selection(40, 2);
+        assert_eq!(history.undo().unwrap().cursor_head, 4);
+    }
+
+    #[test]
+    fn test_memory_limit_drops_oldest_steps() {
+        let mut history = History::new();
+        history.set_memory_limit(10);
+        history.push("aaaaa".into(), 5, 5);
+        history.push("aaaaabbbbb".into(), 10, 10);
+        history.push("aaaaabbbbbccccc".into(), 15, 15);
+
+        assert_eq!(history.undo().unwrap().text, "aaaaabbbbb");
+        assert_eq!(history.undo().unwrap().text, "aaaaa");
+        assert!(history.undo().is_none());
+        assert!(history.is_dirty());
+    }
+
+    #[test]
+    fn test_dropping_saved_state_stays_dirty() {
+        let mut history = History::new();
+        history.set_memory_limit(4);
+        history.mark_saved();
+        history.push("aaaa".into(), 4, 4);
+        history.push("aaaabbbb".into(), 8, 8);
+        history.undo();
+
+        assert!(history.is_dirty());
+    }
+
+    #[test]
+    fn test_saved_text_after_edits_and_undo() {
+        let mut history = History::new();
+        history.push("saved".into(), 5, 5);
+        history.mark_saved();
+        history.push("saved!".into(), 6, 6);
+        assert_eq!(history.saved_text().as_deref(), Some("saved"));
+
+        history.undo();
+        history.undo();
+        assert_eq!(history.saved_text().as_deref(), Some("saved"));
+    }
+
+    #[test]
+    fn test_entries_summarize_edits() {
+        let mut history = History::new();
+        history.clear("hello".to_string());
+        let start = Instant::now();
+        history.push_at("hello world".to_string(), 11, 11, start);
+        history.push_at("hello".to_string(), 5, 5, start);
+        history.push_at("help\nme".to_string(), 7, 7, start);
+        let summaries: Vec<String> = history.entries().map(|e| e.summary).collect();
+        assert_eq!(summaries, ["+\" world\"", "-\" world\"", "\"lo\" -> \"p\\nme\""]);
+        assert!(history.entries().all(|e| e.at == start));
+        assert_eq!(clip(&"x".repeat(30)), format!("{}...", "x".repeat(24)));
+    }
+
+    #[test]
+    fn test_jump_to_moves_through_states() {
+        let mut history = History::new();
+        history.push("a".to_string(), 1, 1);
+        history.push("a b".to_string(), 3, 3);
+        history.push("a b c".to_string(), 5, 5);
+        let snapshot = history.jump_to(1).unwrap();
+        assert_eq!((snapshot.text.as_str(), snapshot.cursor_head), ("a", 1));
+        assert_eq!(history.current_index, 1);
+        let snapshot = history.jump_to(3).unwrap();
+        assert_eq!(snapshot.text, "a b c");
+        assert!(history.jump_to(3).is_none());
+        assert!(history.jump_to(4).is_none());
+        assert_eq!(history.jump_to(0).unwrap().text, "");
+    }
 }
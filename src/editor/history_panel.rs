@@ -0,0 +1,90 @@
+//! Side panel listing the undo history, newest first, with a click on an
+//! entry moving the document to that state.
+
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::{h_flex, v_flex, Sizable, Theme};
+use std::time::Instant;
+
+use super::TextEditor;
+
+/// Wall-clock time of `at`, as `HH:MM:SS`.
+fn clock_time(at: Instant) -> String {
+    let age = chrono::Duration::from_std(at.elapsed()).unwrap_or_default();
+    (chrono::Local::now() - age).format("%H:%M:%S").to_string()
+}
+
+impl TextEditor {
+    pub fn toggle_history_panel(&mut self, cx: &mut Context<Self>) {
+        self.history_panel = !self.history_panel;
+        cx.notify();
+    }
+
+    pub fn history_panel_open(&self) -> bool {
+        self.history_panel
+    }
+
+    /// Move the document to state `index` of the undo history.
+    fn jump_to_history(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
+        if let Some(snapshot) = self.history.jump_to(index) {
+            self.restore_snapshot(snapshot, window, cx);
+            self.input_state.update(cx, |state, cx| state.focus(window, cx));
+            cx.notify();
+        }
+    }
+
+    pub(super) fn render_history_panel(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if !self.history_panel {
+            return None;
+        }
+        let colors = Theme::global(cx).colors;
+        let current = self.history.current_index;
+        let saved = self.history.saved_index;
+        let entries: Vec<(usize, String, String)> = self.history
+            .entries()
+            .enumerate()
+            .map(|(i, entry)| (i + 1, clock_time(entry.at), entry.summary))
+            .chain(std::iter::once((0, String::new(), "Oldest kept state".to_string())))
+            .collect();
+        let count = entries.len() - 1;
+        // Newest first, like a timeline
+        let rows = entries.into_iter().rev().map(|(index, time, summary)| {
+            let marker = if saved == Some(index) { "Saved" } else { "" };
+            h_flex()
+                .id(("history-entry", index))
+                .gap_2()
+                .px_2()
+                .cursor_pointer()
+                .when(index == current, |this| this.bg(colors.accent))
+                // Undone states can still be redone, so they stay listed
+                .when(index > current, |this| this.text_color(colors.muted_foreground))
+                .hover(|this| this.bg(colors.accent))
+                .on_click(cx.listener(move |this, _, window, cx| this.jump_to_history(index, window, cx)))
+                .child(div().w(px(60.0)).text_color(colors.muted_foreground).child(time))
+                .child(div().flex_1().overflow_hidden().whitespace_nowrap().child(summary))
+                .child(div().text_color(colors.success).child(marker))
+        });
+
+        Some(
+            v_flex()
+                .w(px(260.0))
+                .border_l_1()
+                .border_color(colors.border)
+                .text_sm()
+                .child(
+                    h_flex()
+                        .px_2()
+                        .py_1()
+                        .bg(colors.muted)
+                        .child(div().flex_1().child(format!("History ({})", count)))
+                        .child(Button::new("history-close").label("Close").small().ghost()
+                            .on_click(cx.listener(|this, _, _, cx| this.toggle_history_panel(cx)))),
+                )
+                .child(div().id("history-entries").flex_1().overflow_y_scroll().children(rows)),
+        )
+    }
+}
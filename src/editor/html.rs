@@ -0,0 +1,161 @@
+//! HTML export: the document as a standalone page in the theme's colors,
+//! with whitespace kept and source code optionally colored.
+
+use gpui::*;
+use gpui_component::notification::Notification;
+use gpui_component::{Theme, WindowExt};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use super::{hsla_to_rgb_u8, syntax_colors, Rgb, TextEditor};
+use crate::workspace::write_atomic;
+
+/// How the exported page looks.
+pub struct HtmlConfig {
+    /// Page title, usually the file name.
+    pub title: String,
+    /// CSS font family; monospace is always the fallback.
+    pub font_family: Option<String>,
+    pub background_rgb: Rgb,
+    pub text_rgb: Rgb,
+    /// Colors for byte ranges of the content, sorted and non-overlapping.
+    pub token_colors: Vec<(Range<usize>, Rgb)>,
+}
+
+/// `text` with the characters HTML treats specially escaped.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn css_color((r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// A complete HTML page showing `content` as written.
+pub fn render_html(content: &str, config: &HtmlConfig) -> String {
+    let font = match &config.font_family {
+        Some(family) => format!("\"{}\", monospace", escape(family)),
+        None => "monospace".to_string(),
+    };
+    let mut body = String::with_capacity(content.len() * 2);
+    let mut offset = 0;
+    for (range, color) in &config.token_colors {
+        // Ranges off character boundaries or out of order are left uncolored
+        if range.start < offset || !content.is_char_boundary(range.start) || !content.is_char_boundary(range.end) {
+            continue;
+        }
+        body.push_str(&escape(&content[offset..range.start]));
+        body.push_str(&format!("<span style=\"color: {}\">{}</span>", css_color(*color), escape(&content[range.clone()])));
+        offset = range.end;
+    }
+    body.push_str(&escape(&content[offset..]));
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ margin: 0; background: {}; color: {}; }}\n\
+         pre {{ margin: 0; padding: 1em; font-family: {}; white-space: pre-wrap; overflow-wrap: anywhere; tab-size: 4; }}\n\
+         </style>\n</head>\n<body>\n<pre>{}</pre>\n</body>\n</html>\n",
+        escape(&config.title),
+        css_color(config.background_rgb),
+        css_color(config.text_rgb),
+        font,
+        body,
+    )
+}
+
+impl TextEditor {
+    /// Export the document as HTML via a save dialog, colored like the
+    /// editor's highlighter when `syntax` is set.
+    pub fn export_html(&mut self, syntax: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let text = self.input_state.read(cx).value().to_string();
+        let theme = Theme::global(cx);
+        // Parsed after the dialog, off the UI thread
+        let syntax = syntax.then(|| (theme.highlight_theme.clone(), self.current_file(cx).map(Path::to_path_buf)));
+        let title = self.document.read(cx).display_name();
+        let mut config = HtmlConfig {
+            title: title.clone(),
+            font_family: self.font.as_ref().map(|(family, _)| family.to_string()),
+            background_rgb: hsla_to_rgb_u8(theme.colors.background),
+            text_rgb: hsla_to_rgb_u8(theme.colors.foreground),
+            token_colors: Vec::new(),
+        };
+
+        cx.spawn_in(window, move |this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let dialog_task = cx.background_spawn(async move {
+                    rfd::AsyncFileDialog::new()
+                        .add_filter("HTML", &["html", "htm"])
+                        .set_file_name(format!("{}.html", title))
+                        .save_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                });
+                let Some(path): Option<PathBuf> = dialog_task.await else { return };
+                let written = cx.background_spawn({
+                    let path = path.clone();
+                    async move {
+                        if let Some((theme, file)) = syntax {
+                            config.token_colors = syntax_colors(&text, file.as_deref(), &theme);
+                        }
+                        write_atomic(&path, render_html(&text, &config).as_bytes(), false)
+                    }
+                });
+                match written.await {
+                    Ok(()) => info!(path = ?path, "Exported HTML"),
+                    Err(err) => {
+                        warn!(path = ?path, error = %err, "Failed to export HTML");
+                        let _ = this.update_in(&mut cx, |_, window, cx| {
+                            window.push_notification(Notification::error(format!("Couldn't write {}: {}", path.display(), err)), cx);
+                        });
+                    }
+                }
+            }
+        })
+        .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_html, HtmlConfig, Rgb};
+    use std::ops::Range;
+
+    fn config(token_colors: Vec<(Range<usize>, Rgb)>) -> HtmlConfig {
+        HtmlConfig {
+            title: "a <b>.txt".to_string(),
+            font_family: None,
+            background_rgb: (0, 0, 0),
+            text_rgb: (255, 255, 255),
+            token_colors,
+        }
+    }
+
+    #[test]
+    fn test_render_html_escapes_and_keeps_whitespace() {
+        let html = render_html("if a < b &&\n\tc", &config(Vec::new()));
+        assert!(html.contains("<title>a &lt;b&gt;.txt</title>"));
+        assert!(html.contains("<pre>if a &lt; b &amp;&amp;\n\tc</pre>"));
+        assert!(html.contains("background: #000000; color: #ffffff;"));
+    }
+
+    #[test]
+    fn test_render_html_colors_tokens() {
+        let html = render_html("let x = \"<\";", &config(vec![(0..3, (255, 0, 0)), (8..11, (0, 128, 0))]));
+        assert!(html.contains(
+            "<pre><span style=\"color: #ff0000\">let</span> x = <span style=\"color: #008000\">&quot;&lt;&quot;</span>;</pre>"
+        ));
+    }
+}
@@ -0,0 +1,137 @@
+//! Language detection from the file name and first line, and the comment
+//! syntax each language uses.
+
+use std::path::Path;
+
+/// How a language writes comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentStyle {
+    /// Everything after the token to the end of the line.
+    Line(&'static str),
+    /// Text between an opening and a closing token.
+    Block(&'static str, &'static str),
+}
+
+/// Languages the editor knows anything about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    PlainText,
+    /// C, C++, C#, Java, JavaScript, TypeScript, Go, Rust and friends.
+    CLike,
+    Css,
+    Markup,
+    Hash,
+    DoubleDash,
+    Semicolon,
+    Percent,
+}
+
+impl Language {
+    /// Detect from `path`'s extension, falling back to a `#!` line.
+    pub fn detect(path: Option<&Path>, first_line: &str) -> Self {
+        let extension = path
+            .and_then(|p| p.extension())
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let by_extension = match extension.as_deref() {
+            Some("rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "java" | "js" | "mjs" | "jsx" | "ts" | "tsx"
+                | "go" | "swift" | "kt" | "kts" | "scala" | "dart" | "php" | "jsonc" | "zig") => Language::CLike,
+            Some("css" | "scss" | "less") => Language::Css,
+            Some("html" | "htm" | "xml" | "svg" | "xaml" | "vue" | "md" | "markdown") => Language::Markup,
+            Some("py" | "sh" | "bash" | "zsh" | "fish" | "rb" | "pl" | "r" | "toml" | "yaml" | "yml" | "conf"
+                | "cfg" | "ps1" | "mk" | "cmake" | "nix" | "tf" | "jl" | "ex" | "exs") => Language::Hash,
+            Some("sql" | "lua" | "hs" | "elm" | "ada" | "vhd") => Language::DoubleDash,
+            Some("ini" | "lisp" | "el" | "clj" | "scm" | "asm" | "s") => Language::Semicolon,
+            Some("tex" | "sty" | "m" | "erl") => Language::Percent,
+            _ => Language::PlainText,
+        };
+        if by_extension != Language::PlainText {
+            return by_extension;
+        }
+        // Extensionless scripts: the interpreter names the language
+        let shebang = first_line.strip_prefix("#!").unwrap_or_default();
+        if shebang.contains("node") || shebang.contains("deno") {
+            Language::CLike
+        } else if shebang.contains("lua") {
+            Language::DoubleDash
+        } else if !shebang.is_empty()
+            || path.and_then(|p| p.file_name()).is_some_and(|n| n == "Makefile" || n == "Dockerfile")
+        {
+            Language::Hash
+        } else {
+            Language::PlainText
+        }
+    }
+
+    /// The comment syntax; plain text uses `#` like most config files.
+    pub fn comment(self) -> CommentStyle {
+        match self {
+            Language::CLike => CommentStyle::Line("//"),
+            Language::Css => CommentStyle::Block("/*", "*/"),
+            Language::Markup => CommentStyle::Block("<!--", "-->"),
+            Language::Hash | Language::PlainText => CommentStyle::Line("#"),
+            Language::DoubleDash => CommentStyle::Line("--"),
+            Language::Semicolon => CommentStyle::Line(";"),
+            Language::Percent => CommentStyle::Line("%"),
+        }
+    }
+}
+
+/// The highlighter grammar for `path`, by the name the highlighter
+/// registers it under.
+pub fn grammar_name(path: Option<&Path>) -> Option<&'static str> {
+    let extension = path?.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "rs" => "rust",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "hpp" => "cpp",
+        "cs" => "csharp",
+        "go" => "go",
+        "java" => "java",
+        "js" | "mjs" | "jsx" => "javascript",
+        "ts" => "typescript",
+        "tsx" => "tsx",
+        "json" | "jsonc" => "json",
+        "py" => "python",
+        "rb" => "ruby",
+        "sh" | "bash" | "zsh" => "bash",
+        "css" | "scss" => "css",
+        "html" | "htm" => "html",
+        "md" | "markdown" => "markdown",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "sql" => "sql",
+        "swift" => "swift",
+        "scala" => "scala",
+        "zig" => "zig",
+        "ex" | "exs" => "elixir",
+        "proto" => "proto",
+        "diff" | "patch" => "diff",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{grammar_name, CommentStyle, Language};
+    use std::path::Path;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(Language::detect(Some(Path::new("main.RS")), ""), Language::CLike);
+        assert_eq!(Language::detect(Some(Path::new("notes.md")), ""), Language::Markup);
+        assert_eq!(Language::detect(Some(Path::new("deploy")), "#!/usr/bin/env bash"), Language::Hash);
+        assert_eq!(Language::detect(Some(Path::new("Makefile")), "all:"), Language::Hash);
+        assert_eq!(Language::detect(None, "hello"), Language::PlainText);
+        assert_eq!(Language::Css.comment(), CommentStyle::Block("/*", "*/"));
+    }
+
+    #[test]
+    fn test_grammar_name() {
+        assert_eq!(grammar_name(Some(Path::new("main.RS"))), Some("rust"));
+        assert_eq!(grammar_name(Some(Path::new("data.jsonc"))), Some("json"));
+        assert_eq!(grammar_name(Some(Path::new("fix.patch"))), Some("diff"));
+        assert_eq!(grammar_name(Some(Path::new("notes.txt"))), None);
+        assert_eq!(grammar_name(None), None);
+    }
+}
@@ -0,0 +1,178 @@
+//! Whole-line commands: duplicate, move up/down and delete.
+
+use gpui::*;
+use gpui_component::RopeExt;
+use std::ops::{Range, RangeInclusive};
+
+use super::{DeleteLineAction, DuplicateLineAction, MoveLineDownAction, MoveLineUpAction, TextEditor};
+
+/// One line command: replace `range` with `text`, then shift the selection
+/// by `shift` bytes (or put the caret at `range.start` when `None`).
+#[derive(Debug, PartialEq, Eq)]
+pub struct LineEdit {
+    pub range: Range<usize>,
+    pub text: String,
+    pub shift: Option<isize>,
+}
+
+/// Byte offset where each line of `text` starts.
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect()
+}
+
+/// Byte range of `lines`, without the final line break.
+fn span(text: &str, starts: &[usize], lines: &RangeInclusive<usize>) -> Range<usize> {
+    let end = starts.get(lines.end() + 1).map_or(text.len(), |next| next - 1);
+    starts[*lines.start()]..end
+}
+
+/// Copy `lines` below themselves; the selection follows the copy.
+pub fn duplicate_lines(text: &str, lines: RangeInclusive<usize>) -> LineEdit {
+    let starts = line_starts(text);
+    let range = span(text, &starts, &lines);
+    let block = &text[range.clone()];
+    LineEdit {
+        text: format!("{}\n{}", block, block),
+        shift: Some(block.len() as isize + 1),
+        range,
+    }
+}
+
+/// Swap `lines` with the line above or below. `None` at the edge of the text.
+pub fn move_lines(text: &str, lines: RangeInclusive<usize>, up: bool) -> Option<LineEdit> {
+    let starts = line_starts(text);
+    let (first, last) = (*lines.start(), *lines.end());
+    if up {
+        let above = first.checked_sub(1)?;
+        let neighbour = &text[span(text, &starts, &(above..=above))];
+        let block = &text[span(text, &starts, &lines)];
+        Some(LineEdit {
+            range: span(text, &starts, &(above..=last)),
+            text: format!("{}\n{}", block, neighbour),
+            shift: Some(-(neighbour.len() as isize + 1)),
+        })
+    } else {
+        let below = last + 1;
+        if below >= starts.len() {
+            return None;
+        }
+        let neighbour = &text[span(text, &starts, &(below..=below))];
+        let block = &text[span(text, &starts, &lines)];
+        Some(LineEdit {
+            range: span(text, &starts, &(first..=below)),
+            text: format!("{}\n{}", neighbour, block),
+            shift: Some(neighbour.len() as isize + 1),
+        })
+    }
+}
+
+/// Remove `lines` along with one adjoining line break.
+pub fn delete_lines(text: &str, lines: RangeInclusive<usize>) -> LineEdit {
+    let starts = line_starts(text);
+    let (first, last) = (*lines.start(), *lines.end());
+    let range = match starts.get(last + 1) {
+        Some(&next) => starts[first]..next,
+        None if first > 0 => starts[first] - 1..text.len(),
+        None => 0..text.len(),
+    };
+    LineEdit { range, text: String::new(), shift: None }
+}
+
+impl TextEditor {
+    pub fn duplicate_line(&mut self, _: &DuplicateLineAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.edit_lines(window, cx, |text, lines| Some(duplicate_lines(text, lines)));
+    }
+
+    pub fn move_line_up(&mut self, _: &MoveLineUpAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.edit_lines(window, cx, |text, lines| move_lines(text, lines, true));
+    }
+
+    pub fn move_line_down(&mut self, _: &MoveLineDownAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.edit_lines(window, cx, |text, lines| move_lines(text, lines, false));
+    }
+
+    pub fn delete_line(&mut self, _: &DeleteLineAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.edit_lines(window, cx, |text, lines| Some(delete_lines(text, lines)));
+    }
+
+    /// Apply a line command to the selected lines (or the caret's line) as
+    /// one undo step, keeping the selection on the same text.
+    fn edit_lines(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+        command: impl FnOnce(&str, RangeInclusive<usize>) -> Option<LineEdit>,
+    ) {
+        if self.read_only {
+            return;
+        }
+        let cursor = self.input_state.read(cx).cursor();
+        let selection = self.selection_range(window, cx).unwrap_or(cursor..cursor);
+        let rope = self.input_state.read(cx).text().clone();
+        let first = rope.offset_to_position(selection.start).line as usize;
+        let mut last = rope.offset_to_position(selection.end).line as usize;
+        // A selection ending at the start of a line doesn't include that line
+        if last > first && rope.offset_to_position(selection.end).character == 0 {
+            last -= 1;
+        }
+        let text = rope.to_string();
+        let Some(edit) = command(&text, first..=last) else {
+            return;
+        };
+
+        let replacement = edit.text.clone();
+        self.transform_range(edit.range.clone(), |_| replacement, window, cx);
+        match edit.shift {
+            Some(shift) => {
+                let moved = |offset: usize| offset.saturating_add_signed(shift);
+                self.select_range(moved(selection.start)..moved(selection.end), window, cx);
+            }
+            None => {
+                let len = self.input_state.read(cx).text().len();
+                self.select_range(edit.range.start.min(len)..edit.range.start.min(len), window, cx);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delete_lines, duplicate_lines, move_lines, LineEdit};
+
+    fn apply(text: &str, edit: &LineEdit) -> String {
+        let mut out = text.to_string();
+        out.replace_range(edit.range.clone(), &edit.text);
+        out
+    }
+
+    #[test]
+    fn test_duplicate_lines() {
+        let text = "a\nbb\nc";
+        let edit = duplicate_lines(text, 1..=1);
+        assert_eq!(apply(text, &edit), "a\nbb\nbb\nc");
+        assert_eq!(edit.shift, Some(3));
+        assert_eq!(apply(text, &duplicate_lines(text, 0..=2)), "a\nbb\nc\na\nbb\nc");
+    }
+
+    #[test]
+    fn test_move_lines() {
+        let text = "a\nbb\nc";
+        let up = move_lines(text, 1..=2, true).unwrap();
+        assert_eq!(apply(text, &up), "bb\nc\na");
+        assert_eq!(up.shift, Some(-2));
+        let down = move_lines(text, 0..=0, false).unwrap();
+        assert_eq!(apply(text, &down), "bb\na\nc");
+        assert_eq!(down.shift, Some(3));
+        assert!(move_lines(text, 0..=1, true).is_none());
+        assert!(move_lines(text, 2..=2, false).is_none());
+    }
+
+    #[test]
+    fn test_delete_lines() {
+        let text = "a\nbb\nc";
+        assert_eq!(apply(text, &delete_lines(text, 0..=0)), "bb\nc");
+        assert_eq!(apply(text, &delete_lines(text, 1..=2)), "a");
+        assert_eq!(apply(text, &delete_lines(text, 0..=2)), "");
+        assert_eq!(apply("a\n", &delete_lines("a\n", 1..=1)), "a");
+    }
+}
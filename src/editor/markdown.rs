@@ -0,0 +1,629 @@
+//! Markdown assistance: heading levels, pipe table formatting and cell
+//! navigation.
+
+use gpui::*;
+use gpui_component::input::OutdentInline;
+use gpui_component::RopeExt;
+use std::path::Path;
+
+use super::language::grammar_name;
+use super::{DemoteHeadingAction, PreviousCellAction, PromoteHeadingAction, TextEditor};
+
+/// Deepest heading level markdown allows.
+const MAX_HEADING_LEVEL: usize = 6;
+
+/// Whether `path` names a Markdown file.
+pub fn is_markdown_file(path: Option<&Path>) -> bool {
+    grammar_name(path) == Some("markdown")
+}
+
+/// Whether `line` is a row of a pipe table.
+pub fn is_table_row(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+/// Level of an ATX heading line (`## Title` is 2).
+fn heading_level(line: &str) -> Option<usize> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    let rest = &line[hashes..];
+    let is_heading = (1..=MAX_HEADING_LEVEL).contains(&hashes)
+        && (rest.is_empty() || rest.starts_with(' ') || rest.starts_with('\t') || rest == "\r");
+    is_heading.then_some(hashes)
+}
+
+/// Raise (`promote`) or lower every heading in `text` by one level, leaving
+/// level 1 and level 6 headings and all other lines untouched.
+pub fn shift_headings(text: &str, promote: bool) -> String {
+    text.split('\n')
+        .map(|line| match heading_level(line) {
+            Some(level) if promote && level > 1 => line[1..].to_string(),
+            Some(level) if !promote && level < MAX_HEADING_LEVEL => format!("#{}", line),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Lines `first..end` of the table containing line `line`, if any.
+pub fn table_at(text: &str, line: usize) -> Option<std::ops::Range<usize>> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    if !lines.get(line).is_some_and(|l| is_table_row(l)) {
+        return None;
+    }
+    let first = (0..line).rev().take_while(|&i| is_table_row(lines[i])).last().unwrap_or(line);
+    let end = (line..lines.len()).take_while(|&i| is_table_row(lines[i])).last().unwrap_or(line) + 1;
+    Some(first..end)
+}
+
+/// Byte offsets of the unescaped pipes in `row`.
+fn pipe_offsets(row: &str) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut escaped = false;
+    for (i, c) in row.char_indices() {
+        if c == '|' && !escaped {
+            offsets.push(i);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    offsets
+}
+
+/// Trimmed cell contents of `row`, without the outer pipes.
+fn split_cells(row: &str) -> Vec<String> {
+    let row = row.trim();
+    if row.is_empty() {
+        return Vec::new();
+    }
+    let pipes = pipe_offsets(row);
+    let mut bounds: Vec<usize> = pipes.clone();
+    // A row may omit its closing pipe
+    if pipes.last() != Some(&(row.len() - 1)) {
+        bounds.push(row.len());
+    }
+    bounds.windows(2).map(|w| row[w[0] + 1..w[1]].trim().to_string()).collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Align {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+/// Column alignment if `cell` is a `---` / `:---:` separator.
+fn separator_align(cell: &str) -> Option<Align> {
+    let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+    if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+        return None;
+    }
+    Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+        (true, true) => Align::Center,
+        (true, false) => Align::Left,
+        (false, true) => Align::Right,
+        (false, false) => Align::None,
+    })
+}
+
+fn is_separator_row(cells: &[String]) -> bool {
+    !cells.is_empty() && cells.iter().all(|c| separator_align(c).is_some())
+}
+
+fn pad(cell: &str, width: usize, align: Align) -> String {
+    let gap = width.saturating_sub(cell.chars().count());
+    let (left, right) = match align {
+        Align::Right => (gap, 0),
+        Align::Center => (gap / 2, gap - gap / 2),
+        _ => (0, gap),
+    };
+    format!("{}{}{}", " ".repeat(left), cell, " ".repeat(right))
+}
+
+/// Rewrite `table` with aligned pipes and every row padded to the same
+/// number of cells. Keeps the first row's indentation.
+pub fn format_table(table: &str) -> String {
+    let indent = &table[..table.len() - table.trim_start().len()];
+    let crlf = table.split('\n').next().is_some_and(|l| l.ends_with('\r'));
+    let rows: Vec<Vec<String>> = table.split('\n').map(split_cells).collect();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut aligns = vec![Align::None; columns];
+    let mut widths = vec![3; columns];
+    for row in &rows {
+        if is_separator_row(row) {
+            for (i, cell) in row.iter().enumerate() {
+                aligns[i] = separator_align(cell).unwrap_or(Align::None);
+            }
+        } else {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            let separator = is_separator_row(row);
+            let cells: Vec<String> = (0..columns)
+                .map(|i| {
+                    if separator {
+                        let dashes = "-".repeat(widths[i]);
+                        return match aligns[i] {
+                            Align::Left => format!(":{}", &dashes[1..]),
+                            Align::Right => format!("{}:", &dashes[1..]),
+                            Align::Center => format!(":{}:", &dashes[2..]),
+                            Align::None => dashes,
+                        };
+                    }
+                    pad(row.get(i).map(String::as_str).unwrap_or(""), widths[i], aligns[i])
+                })
+                .collect();
+            format!("{}| {} |{}", indent, cells.join(" | "), if crlf { "\r" } else { "" })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Index of the cell at char column `column` of `row`.
+fn cell_at(row: &str, column: usize) -> usize {
+    let byte = row.char_indices().nth(column).map_or(row.len(), |(i, _)| i);
+    pipe_offsets(row).iter().filter(|&&p| p < byte).count().saturating_sub(1)
+}
+
+/// Move from cell `cell` of row `row` in a formatted table to the next (or
+/// previous) cell, skipping the separator row. Tabbing past the last cell
+/// adds an empty row. Returns the table and the caret's byte offset in it.
+pub fn step_cell(table: &str, row: usize, cell: usize, forward: bool) -> (String, usize) {
+    let mut out = table.to_string();
+    let rows: Vec<&str> = table.split('\n').collect();
+    let columns = split_cells(rows[0]).len().max(1);
+    let is_content = |r: usize| !is_separator_row(&split_cells(rows[r]));
+
+    let target = if forward {
+        if cell + 1 < columns {
+            Some((row, cell + 1))
+        } else {
+            (row + 1..rows.len()).find(|&r| is_content(r)).map(|r| (r, 0))
+        }
+    } else if cell > 0 {
+        Some((row, cell - 1))
+    } else {
+        (0..row).rev().find(|&r| is_content(r)).map(|r| (r, columns - 1))
+    };
+
+    let (row, cell) = match target {
+        Some(target) => target,
+        None if forward => {
+            let template = rows[0].trim_end_matches('\r');
+            let indent = &template[..template.len() - template.trim_start().len()];
+            let empty = vec![String::new(); columns];
+            let new_row = format!("\n{}| {} |", indent, empty.join(" | "));
+            out = format_table(&format!("{}{}", table, new_row));
+            (rows.len(), 0)
+        }
+        None => (row, cell),
+    };
+
+    let rows: Vec<&str> = out.split('\n').collect();
+    let row_start: usize = rows[..row].iter().map(|r| r.len() + 1).sum();
+    let pipes = pipe_offsets(rows[row]);
+    let in_row = pipes.get(cell).map_or(0, |p| (p + 2).min(rows[row].len()));
+    (out, row_start + in_row)
+}
+
+/// A block of a Markdown document, for printing it laid out.
+#[derive(Debug, PartialEq)]
+pub enum MarkdownBlock {
+    Heading(usize, String),
+    Paragraph(String),
+    /// A list item: nesting depth, the marker to print and its text.
+    ListItem(usize, String, String),
+    /// The lines of a fenced or indented code block, as written.
+    Code(Vec<String>),
+    Quote(String),
+    Rule,
+}
+
+/// How a run of inline Markdown is emphasized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Emphasis {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+}
+
+/// Width of a line's leading whitespace, counting a tab as four columns.
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// `line` without up to `width` columns of leading whitespace.
+fn strip_indent(line: &str, width: usize) -> &str {
+    let mut columns = 0;
+    for (i, c) in line.char_indices() {
+        if columns >= width || (c != ' ' && c != '\t') {
+            return &line[i..];
+        }
+        columns += if c == '\t' { 4 } else { 1 };
+    }
+    ""
+}
+
+/// The fence opening or closing a code block (three or more backticks or
+/// tildes) at the start of `trimmed`.
+fn code_fence(trimmed: &str) -> Option<&str> {
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.len() - trimmed.trim_start_matches(marker).len();
+    (len >= 3).then(|| &trimmed[..len])
+}
+
+/// Whether `trimmed` is a thematic break: three or more `-`, `*` or `_`,
+/// optionally spaced out.
+fn is_rule(trimmed: &str) -> bool {
+    let marks: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && ["-", "*", "_"].iter().any(|m| marks.trim_start_matches(m).is_empty())
+}
+
+/// A list item's printed marker and text: `-`, `*` and `+` become a bullet
+/// and ordered items keep their number.
+pub(super) fn list_item(trimmed: &str) -> Option<(String, &str)> {
+    if let Some(text) = ["- ", "* ", "+ "].iter().find_map(|m| trimmed.strip_prefix(m)) {
+        return Some(("\u{2022}".to_string(), text));
+    }
+    let digits = trimmed.len() - trimmed.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = &trimmed[digits..];
+    let text = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") "))?;
+    (1..=9).contains(&digits).then(|| (format!("{}.", &trimmed[..digits]), text))
+}
+
+/// Split Markdown into the blocks a printed page shows: ATX and setext
+/// headings, paragraphs, list items, quotes, code blocks and rules. Inline
+/// markup is left in the text for [`parse_inline`].
+pub fn parse_blocks(text: &str) -> Vec<MarkdownBlock> {
+    let mut blocks = Vec::new();
+    // The paragraph, list item or quote still taking lines
+    let mut open: Option<MarkdownBlock> = None;
+    // Indents of the enclosing list items, outermost first
+    let mut list_indents: Vec<usize> = Vec::new();
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if let Some(fence) = code_fence(trimmed) {
+            blocks.extend(open.take());
+            let code = lines.by_ref().take_while(|l| !l.trim_start().starts_with(fence)).map(str::to_string).collect();
+            blocks.push(MarkdownBlock::Code(code));
+            continue;
+        }
+        if trimmed.is_empty() {
+            blocks.extend(open.take());
+            continue;
+        }
+        // An underline turns the paragraph above it into a heading
+        if let Some(MarkdownBlock::Paragraph(heading)) = &mut open {
+            let underline = ['=', '-'].into_iter().find(|&c| trimmed.trim_start_matches(c).is_empty());
+            if let Some(c) = underline {
+                let level = if c == '=' { 1 } else { 2 };
+                blocks.push(MarkdownBlock::Heading(level, std::mem::take(heading)));
+                open = None;
+                continue;
+            }
+        }
+        if let Some(level) = heading_level(line.trim_start()) {
+            blocks.extend(open.take());
+            list_indents.clear();
+            let title = trimmed[level..].trim().trim_end_matches('#').trim_end();
+            blocks.push(MarkdownBlock::Heading(level, title.to_string()));
+            continue;
+        }
+        if is_rule(trimmed) {
+            blocks.extend(open.take());
+            list_indents.clear();
+            blocks.push(MarkdownBlock::Rule);
+            continue;
+        }
+        if let Some((marker, item)) = list_item(trimmed) {
+            blocks.extend(open.take());
+            let indent = indent_width(line);
+            while list_indents.last().is_some_and(|&i| i > indent) {
+                list_indents.pop();
+            }
+            if list_indents.last() != Some(&indent) {
+                list_indents.push(indent);
+            }
+            open = Some(MarkdownBlock::ListItem(list_indents.len() - 1, marker, item.trim().to_string()));
+            continue;
+        }
+        if let Some(quoted) = trimmed.strip_prefix('>') {
+            let quoted = quoted.trim();
+            match &mut open {
+                Some(MarkdownBlock::Quote(text)) => {
+                    if !text.is_empty() && !quoted.is_empty() {
+                        text.push(' ');
+                    }
+                    text.push_str(quoted);
+                }
+                _ => {
+                    blocks.extend(open.take());
+                    open = Some(MarkdownBlock::Quote(quoted.to_string()));
+                }
+            }
+            continue;
+        }
+        if open.is_none() && indent_width(line) >= 4 {
+            list_indents.clear();
+            let mut code = vec![strip_indent(line, 4).to_string()];
+            while let Some(next) = lines.next_if(|l| indent_width(l) >= 4) {
+                code.push(strip_indent(next, 4).to_string());
+            }
+            blocks.push(MarkdownBlock::Code(code));
+            continue;
+        }
+        match &mut open {
+            Some(MarkdownBlock::Paragraph(text) | MarkdownBlock::ListItem(_, _, text) | MarkdownBlock::Quote(text)) => {
+                text.push(' ');
+                text.push_str(trimmed);
+            }
+            _ => {
+                list_indents.clear();
+                open = Some(MarkdownBlock::Paragraph(trimmed.to_string()));
+            }
+        }
+    }
+    blocks.extend(open);
+    blocks
+}
+
+/// Add `text` to `runs`, extending the last run when the emphasis matches.
+fn push_run(runs: &mut Vec<(String, Emphasis)>, text: &str, emphasis: Emphasis) {
+    match runs.last_mut() {
+        Some((last, style)) if *style == emphasis => last.push_str(text),
+        _ if text.is_empty() => {}
+        _ => runs.push((text.to_string(), emphasis)),
+    }
+}
+
+/// The label of a `[label](target)` link at the start of `text` and the
+/// link's length in bytes.
+fn link(text: &str) -> Option<(&str, usize)> {
+    let close = text.find("](")?;
+    let end = text[close..].find(')')? + close + 1;
+    Some((&text[1..close], end))
+}
+
+/// Split inline Markdown into runs of plain text: `**bold**`, `*italic*`
+/// (either with underscores), `` `code` `` and links, which keep only their
+/// label. Markers without a partner are kept as written.
+pub fn parse_inline(text: &str) -> Vec<(String, Emphasis)> {
+    let mut runs = Vec::new();
+    let mut style = Emphasis::default();
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+        if c == '\\' {
+            if let Some(escaped) = rest[1..].chars().next().filter(char::is_ascii_punctuation) {
+                push_run(&mut runs, &rest[1..2], style);
+                i += 1 + escaped.len_utf8();
+                continue;
+            }
+        }
+        if c == '`' {
+            let ticks = &rest[..rest.len() - rest.trim_start_matches('`').len()];
+            if let Some(end) = rest[ticks.len()..].find(ticks) {
+                let code = &rest[ticks.len()..ticks.len() + end];
+                push_run(&mut runs, code.trim(), Emphasis { code: true, ..style });
+                i += ticks.len() * 2 + end;
+                continue;
+            }
+        }
+        let image = c == '!' && rest[1..].starts_with('[');
+        if c == '[' || image {
+            let skip = usize::from(image);
+            if let Some((label, len)) = link(&rest[skip..]) {
+                for (text, inner) in parse_inline(label) {
+                    let merged = Emphasis {
+                        bold: style.bold || inner.bold,
+                        italic: style.italic || inner.italic,
+                        code: style.code || inner.code,
+                    };
+                    push_run(&mut runs, &text, merged);
+                }
+                i += skip + len;
+                continue;
+            }
+        }
+        if c == '*' || c == '_' {
+            let len = if rest[1..].starts_with(c) { 2 } else { 1 };
+            let marker = &rest[..len];
+            let on = if len == 2 { style.bold } else { style.italic };
+            let after = &rest[len..];
+            // Underscores inside words (snake_case) aren't emphasis
+            let in_word = c == '_' && text[..i].chars().next_back().is_some_and(char::is_alphanumeric);
+            let opens = !in_word && after.chars().next().is_some_and(|n| !n.is_whitespace()) && after.contains(marker);
+            if on || opens {
+                if len == 2 {
+                    style.bold = !on;
+                } else {
+                    style.italic = !on;
+                }
+                i += len;
+                continue;
+            }
+        }
+        push_run(&mut runs, &rest[..c.len_utf8()], style);
+        i += c.len_utf8();
+    }
+    runs
+}
+
+impl TextEditor {
+    pub fn set_markdown_assist(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        self.markdown_assist = enabled;
+        cx.notify();
+    }
+
+    /// Promote the headings on the selected lines (or the caret's line).
+    pub fn promote_heading(&mut self, _: &PromoteHeadingAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.shift_heading(true, window, cx);
+    }
+
+    pub fn demote_heading(&mut self, _: &DemoteHeadingAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.shift_heading(false, window, cx);
+    }
+
+    fn shift_heading(&mut self, promote: bool, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.markdown_assist {
+            return;
+        }
+        let range = match self.selection_range(window, cx).filter(|r| !r.is_empty()) {
+            Some(_) => self.selected_lines_range(window, cx),
+            None => {
+                let state = self.input_state.read(cx);
+                let line = state.cursor_position().line as usize;
+                state.text().line_start_offset(line)..state.text().line_end_offset(line)
+            }
+        };
+        self.transform_range(range, |text| shift_headings(text, promote), window, cx);
+    }
+
+    /// Align the pipes of the table under the caret.
+    pub fn format_table(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.markdown_assist {
+            return;
+        }
+        let text = self.input_state.read(cx).text().clone();
+        let line = self.input_state.read(cx).cursor_position().line as usize;
+        let Some(lines) = table_at(&text.to_string(), line) else {
+            return;
+        };
+        let range = text.line_start_offset(lines.start)..text.line_end_offset(lines.end - 1);
+        self.transform_range(range, format_table, window, cx);
+    }
+
+    /// Format the table under the caret and move to the next or previous
+    /// cell. Returns false when the caret is not in a table.
+    pub(super) fn step_table_cell(&mut self, forward: bool, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        if !self.markdown_assist || self.read_only {
+            return false;
+        }
+        self.input_state.update(cx, |state, cx| {
+            let text = state.text().clone();
+            let position = state.cursor_position();
+            let Some(lines) = table_at(&text.to_string(), position.line as usize) else {
+                return false;
+            };
+            let start = text.line_start_offset(lines.start);
+            let end = text.line_end_offset(lines.end - 1);
+            let original = text.slice(start..end).to_string();
+            let row = position.line as usize - lines.start;
+            let cell = cell_at(original.split('\n').nth(row).unwrap_or(""), position.character as usize);
+
+            let (table, caret) = step_cell(&format_table(&original), row, cell, forward);
+            if table != original {
+                let range = text.offset_to_offset_utf16(start)..text.offset_to_offset_utf16(end);
+                state.replace_text_in_range(Some(range), &table, window, cx);
+            }
+            let position = state.text().offset_to_position(start + caret);
+            state.set_cursor_position(position, window, cx);
+            true
+        })
+    }
+
+    /// Shift-Tab: previous table cell, otherwise the input's own outdent.
+    pub fn previous_cell(&mut self, _: &PreviousCellAction, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.step_table_cell(false, window, cx) {
+            self.dispatch_to_input(&OutdentInline, window, cx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cell_at, format_table, parse_blocks, parse_inline, shift_headings, split_cells, step_cell, table_at, Emphasis,
+        MarkdownBlock,
+    };
+
+    #[test]
+    fn test_shift_headings() {
+        let text = "# Top\n## Sub\nplain\n#hashtag\n###### Deep";
+        assert_eq!(shift_headings(text, true), "# Top\n# Sub\nplain\n#hashtag\n##### Deep");
+        assert_eq!(shift_headings(text, false), "## Top\n### Sub\nplain\n#hashtag\n###### Deep");
+    }
+
+    #[test]
+    fn test_split_cells() {
+        assert_eq!(split_cells("| a | b\\|c |"), vec!["a", "b\\|c"]);
+        assert_eq!(split_cells("|a|b"), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_table_at() {
+        let text = "intro\n| a |\n|---|\n| 1 |\nafter";
+        assert_eq!(table_at(text, 2), Some(1..4));
+        assert_eq!(table_at(text, 0), None);
+    }
+
+    #[test]
+    fn test_format_table() {
+        let table = "|Name|Qty|\n|:-|-:|\n|apple|3|\n|kiwi|";
+        assert_eq!(
+            format_table(table),
+            "| Name  | Qty |\n| :---- | --: |\n| apple |   3 |\n| kiwi  |     |"
+        );
+    }
+
+    #[test]
+    fn test_step_cell() {
+        let table = "| a   | b   |\n| --- | --- |\n| 1   | 2   |";
+        assert_eq!(step_cell(table, 0, 0, true), (table.to_string(), 8));
+        // The separator row is skipped
+        assert_eq!(step_cell(table, 0, 1, true).1, 30);
+        assert_eq!(step_cell(table, 2, 0, false).1, 8);
+        let (grown, caret) = step_cell(table, 2, 1, true);
+        assert_eq!(grown, format!("{table}\n|     |     |"));
+        assert_eq!(caret, 44);
+        assert_eq!(cell_at("| a   | b   |", 8), 1);
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let text = "# Title #\n\nSome text\nwrapped.\n\nSetext\n===\n- one\n  - nested\n2) two\n> quoted\n> more\n\n```rust\nfn main() {}\n\n```\n    indented\n***";
+        assert_eq!(
+            parse_blocks(text),
+            [
+                MarkdownBlock::Heading(1, "Title".to_string()),
+                MarkdownBlock::Paragraph("Some text wrapped.".to_string()),
+                MarkdownBlock::Heading(1, "Setext".to_string()),
+                MarkdownBlock::ListItem(0, "\u{2022}".to_string(), "one".to_string()),
+                MarkdownBlock::ListItem(1, "\u{2022}".to_string(), "nested".to_string()),
+                MarkdownBlock::ListItem(0, "2.".to_string(), "two".to_string()),
+                MarkdownBlock::Quote("quoted more".to_string()),
+                MarkdownBlock::Code(vec!["fn main() {}".to_string(), String::new()]),
+                MarkdownBlock::Code(vec!["indented".to_string()]),
+                MarkdownBlock::Rule,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_inline() {
+        let plain = Emphasis::default();
+        let bold = Emphasis { bold: true, ..plain };
+        let italic = Emphasis { italic: true, ..plain };
+        let code = Emphasis { code: true, ..plain };
+        let runs = parse_inline("a **b** _c_ `d*` [e](http://x) snake_case 2 * 3 \\*f");
+        let expected = [
+            ("a ", plain),
+            ("b", bold),
+            (" ", plain),
+            ("c", italic),
+            (" ", plain),
+            ("d*", code),
+            (" e snake_case 2 * 3 *f", plain),
+        ];
+        assert_eq!(runs, expected.map(|(text, style)| (text.to_string(), style)));
+    }
+}
@@ -1,67 +1,271 @@
 use gpui::*;
+use gpui::prelude::FluentBuilder;
+use gpui_component::highlighter::{Diagnostic, DiagnosticSeverity, HighlightTheme, LanguageRegistry, SyntaxHighlighter};
 use gpui_component::{
-    Theme, input::{
+    Rope, RopeExt, Theme, input::{
         Copy as CopyAction,
         Cut as CutAction,
+        CursorShape,
         Input,
         InputEvent,
         InputState,
         Paste as PasteAction,
-        Search as SearchAction,
         SelectAll as SelectAllAction,
         Position,
     }
 };
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use tracing::{debug, warn, info};
-use crate::ExportPdfAction;
+use crate::extensions::Extensions;
+use crate::keymap;
+use crate::settings::{PdfExportSettings, StatusBarSegments};
+use crate::style::ChromeStyle;
 
+mod alignment;
+mod annotations;
+mod autoscroll;
+mod block;
+mod brackets;
+mod case;
+mod cleanup;
+mod comment;
+mod conflicts;
+mod diff;
+mod document;
+mod find;
+mod find_results;
+mod format;
 mod fps;
+mod freeze;
+mod html;
+mod language;
+mod lines;
+mod markdown;
+mod mojibake;
 mod pdf;
+mod quote;
+mod references;
+mod reflow;
+mod ruler;
+mod slots;
+mod sort;
+mod spell;
+mod stats;
+mod status;
+mod tabs;
+mod todo;
+mod transform;
 mod types;
+mod view;
+mod wrap;
 
-pub use fps::FpsTracker;
+pub use alignment::ParagraphAlign;
+pub use case::Case;
+pub use conflicts::Resolution;
+pub use diff::{side_by_side, DiffRow, RowKind};
+pub use document::{Document, DocumentEvent};
+pub use format::DataFormat;
+pub use fps::{FpsTracker, FramePacing};
+pub use markdown::is_markdown_file;
+use fps::NextFrame;
+pub use mojibake::decode_windows_1252;
+pub use pdf::{export_to_pdf, Orientation, PageSize, PdfConfig, Rgb};
+pub use slots::{ClipboardSlots, SLOT_COUNT};
+pub use sort::LineOperation;
+pub use spell::SpellChecker;
+use spell::spelling_marks;
+pub use tabs::TabHandling;
+pub use transform::Transform;
 pub use types::{LineEnding, Encoding};
+pub use wrap::WrapIndent;
 
 mod history;
-use history::History;
+mod history_panel;
+use alignment::ParagraphAlignments;
+use conflicts::{conflict_marks, Conflict};
+use history::{History, Snapshot};
+use stats::{Budget, BudgetLevel};
+pub use stats::TextStats;
 
 // Actions
-actions!(editor, [UndoAction, RedoAction, NormalizePasteAction]);
+actions!(editor, [UndoAction, RedoAction, NormalizePasteAction, InsertTabAction, PreviousCellAction, FindNextAction, FindPreviousAction, CloseFindAction, SaveFindResultsAction,
+    BlockSelectUp, BlockSelectDown, BlockSelectLeft, BlockSelectRight,
+    PromoteHeadingAction, DemoteHeadingAction,
+    DuplicateLineAction, MoveLineUpAction, MoveLineDownAction, DeleteLineAction, ToggleCommentAction,
+    UpperCaseAction, LowerCaseAction, TitleCaseAction, SentenceCaseAction,
+    CopyToSlot1, CopyToSlot2, CopyToSlot3, PasteFromSlot1, PasteFromSlot2, PasteFromSlot3, GoToLineAction]);
+
+/// How long typing must pause before spelling and notes are rechecked.
+const MARKS_DELAY: Duration = Duration::from_millis(300);
+/// The input's own blink rate, until settings say otherwise.
+const DEFAULT_CURSOR_BLINK: Duration = Duration::from_millis(500);
+
+/// A multi-line input for the editor. Code-editor mode, highlighting
+/// `code_language`, is only for what plain text can't draw: diagnostics,
+/// which carry the text marks, the line number gutter and colored patch
+/// lines.
+fn build_input(code_language: Option<&'static str>, line_numbers: bool, soft_wrap: bool, window: &mut Window, cx: &mut Context<InputState>) -> InputState {
+    let state = InputState::new(window, cx).multi_line(true);
+    let state = match code_language {
+        Some(language) => state.code_editor(language).line_number(line_numbers).indent_guides(false).searchable(false),
+        None => state,
+    };
+    let mut state = state.soft_wrap(soft_wrap);
+    state.lsp.code_action_providers.push(std::rc::Rc::new(spell::SpellingActions));
+    state
+}
+
+/// The caret and current line settings an input is built without.
+#[derive(Clone, Copy)]
+struct CaretStyle {
+    shape: CursorShape,
+    blink: Option<Duration>,
+    highlight_line: bool,
+}
+
+fn style_input(state: &mut InputState, caret: CaretStyle, cx: &mut Context<InputState>) {
+    state.set_cursor_shape(caret.shape, cx);
+    state.set_cursor_blink_interval(caret.blink, cx);
+    state.set_highlight_current_line(Some(caret.highlight_line), cx);
+}
+
+/// A stretch of text underlined in the editor, with a message shown on hover.
+#[derive(Clone)]
+pub(super) struct TextMark {
+    range: std::ops::Range<usize>,
+    message: String,
+    severity: DiagnosticSeverity,
+}
 
 /// Main text editor component with multi-line input, undo/redo, and status bar.
 pub struct TextEditor {
     /// The underlying input state entity.
     pub(crate) input_state: Entity<InputState>,
-    /// Path to the currently open file, if any.
-    pub(crate) current_file: Option<PathBuf>,
-    encoding: Encoding,
-    line_ending: LineEnding,
+    /// The file, text and save state shown; this editor is a view over it.
+    pub(crate) document: Entity<Document>,
     /// Whether soft wrap is enabled.
     pub(crate) soft_wrap: bool,
     /// Whether the content allows edits.
-    #[allow(dead_code)]
     pub read_only: bool,
-    /// Whether the content has unsaved changes.
-    pub is_dirty: bool,
+    /// Whether the open file exceeded the large-file threshold; such files
+    /// are read-only and keep no undo history.
+    pub(crate) large_file: bool,
+    /// Size in bytes at which files open in large-file mode (0 disables it).
+    large_file_threshold: u64,
+    tab_handling: TabHandling,
+    /// Columns per tab stop when tabs become spaces.
+    tab_width: usize,
+    /// Whether markdown helpers (table formatting, cell navigation) are on.
+    markdown_assist: bool,
+    /// Whether unknown words are underlined.
+    spell_check: bool,
+    /// Notes on lines of the open file, kept outside the file.
+    annotations: Vec<annotations::Annotation>,
+    /// Lines the notes are on in the current text.
+    annotated_lines: BTreeSet<usize>,
+    /// Whether the caret's line has a note.
+    caret_annotated: bool,
+    /// The TODO panel, while open.
+    todos: Option<todo::TodoPanel>,
+    /// Whether the undo history panel is showing.
+    history_panel: bool,
+    /// Words the TODO panel lists.
+    todo_markers: Vec<String>,
+    /// Editor font; `None` uses the theme font.
+    font: Option<(SharedString, Pixels)>,
+    /// Whether a save is writing the file.
+    saving: bool,
+    /// When the buffer was last edited by the user.
+    pub(crate) last_edited: Option<SystemTime>,
     /// Whether to ignore input events (e.g. during file load).
     ignore_input_events: bool,
+    /// Set while taking in an edit made in another view of the document,
+    /// so it isn't handed back.
+    following_document: bool,
+    /// Document revision this view last sent or took in.
+    document_revision: u64,
     /// Whether the status bar is visible.
     pub(crate) show_status_bar: bool,
+    /// Which optional status bar segments are visible.
+    status_segments: StatusBarSegments,
+    /// View-only paragraph alignment; never written to the file.
+    paragraph_alignments: ParagraphAlignments,
+    /// Alignment of the paragraph under the caret.
+    caret_align: ParagraphAlign,
+    /// Selection anchor and caret the caret-dependent state was last
+    /// brought up to date for.
+    observed_selection: Option<(usize, usize)>,
+    /// Target length shown in the status bar.
+    budget: Option<Budget>,
+    /// Counts for the current text, redone on each change. Large files
+    /// only get their characters counted.
+    stats: TextStats,
+    /// Monospace font and a column ruler, for aligning columns by hand.
+    monospace: bool,
+    /// Teleprompter scrolling, while on.
+    auto_scroll: Option<autoscroll::AutoScroll>,
+    /// Keep the first line above the text once it scrolls out of view.
+    freeze_top_line: bool,
+    /// Band behind the caret's line.
+    highlight_line: bool,
+    /// Whether line numbers are wanted beside the text.
+    show_line_numbers: bool,
+    /// How the caret is drawn.
+    cursor_shape: CursorShape,
+    /// Time between caret blinks, `None` for a steady caret.
+    cursor_blink: Option<Duration>,
+    /// Indent of soft wrapped lines, and its columns when hanging.
+    wrap_indent: (WrapIndent, usize),
+    /// The language the input highlights in code-editor mode, which it is
+    /// in for its diagnostics, gutter and patch colors; `None` in plain-text mode.
+    code_language: Option<&'static str>,
+    /// Whether the input's gutter is on, to tell it only of changes.
+    line_numbers: bool,
+    /// Whether typed brackets and quotes get their closer.
+    auto_pair: bool,
+    /// Whether the bracket at the caret has its match underlined.
+    match_brackets: bool,
+    /// Caret the bracket match was last found for.
+    bracket_caret: Option<usize>,
+    /// Offsets of the bracket at the caret and its match.
+    bracket_match: Option<(usize, usize)>,
+    /// Spelling and note marks, kept to redraw with a new bracket match.
+    text_marks: Vec<TextMark>,
+    /// Pending recheck of the text marks, waiting for typing to pause.
+    marks_task: Option<Task<()>>,
+    /// Column selection, while one is active.
+    block: Option<block::BlockSelection>,
+    /// Whether an alt+drag is extending the block.
+    block_dragging: bool,
+    /// The find bar, while open.
+    find: Option<find::FindBar>,
+    /// The Find All results panel, while open.
+    find_results: Option<find_results::FindResultsPanel>,
+    /// Merge conflict regions in the buffer.
+    conflicts: Vec<Conflict>,
     fps_tracker: FpsTracker,
+    frame_pacing: FramePacing,
+    /// Last key press, pointer movement or input event, for idle detection.
+    last_activity: Instant,
+    /// Pending capped redraw.
+    frame_task: Option<Task<()>>,
     history: History,
     _subscriptions: Vec<Subscription>,
 }
 
 impl TextEditor {
     pub fn new(window: &mut Window, cx: &mut Context<Self>, initial_text: String) -> Self {
-        // Create InputState with multi-line support
-        let input_state = cx.new(|cx| {
-            InputState::new(window, cx)
-                .multi_line(true)
-                .searchable(true)
-                .soft_wrap(true)
-        });
+        let document = cx.new(|_| Document::default());
+        Self::with_document(document, window, cx, initial_text)
+    }
+
+    /// An editor showing `document`, which starts out holding `initial_text`.
+    pub fn with_document(document: Entity<Document>, window: &mut Window, cx: &mut Context<Self>, initial_text: String) -> Self {
+        // Plain text until something needs code-editor mode
+        let input_state = cx.new(|cx| build_input(None, false, true, window, cx));
 
         // Set initial text if provided
         if !initial_text.is_empty() {
@@ -70,35 +274,122 @@ impl TextEditor {
             });
         }
 
+        if !initial_text.is_empty() {
+            let text = input_state.read(cx).text().clone();
+            document.update(cx, |doc, cx| doc.set_text(text, cx));
+        }
+
         // Subscribe to input events
         let _subscriptions = vec![
+            // Redraw the status bar's chord hint as chords start and end
+            cx.observe_pending_input(window, |_, _, cx| cx.notify()),
             cx.subscribe_in(&input_state, window, {
-                move |this, _, _ev: &InputEvent, _window, cx| {
-                    if !this.ignore_input_events {
+                move |this, _, ev: &InputEvent, window, cx| {
+                    this.mark_activity(cx);
+                    if matches!(ev, InputEvent::Change) {
+                        if !this.following_document {
+                            let text = this.input_state.read(cx).text().clone();
+                            this.document_revision = this.document.update(cx, |doc, cx| {
+                                doc.set_text(text, cx);
+                                doc.revision()
+                            });
+                        }
+                        this.refresh_conflicts(window, cx);
+                        this.refresh_find(cx);
+                        this.refresh_diagnostics(cx);
+                        this.refresh_annotated_lines(cx);
+                        this.refresh_stats(cx);
+                        let text = this.input_state.read(cx).value();
+                        this.paragraph_alignments.follow(&text);
+                        this.refresh_caret_align(cx);
+                        this.schedule_todo_refresh(cx);
+                    }
+                    if !this.ignore_input_events && !this.following_document && !this.large_file {
+                        if matches!(ev, InputEvent::Change) {
+                            this.last_edited = Some(SystemTime::now());
+                        }
+
                         // Capture snapshot
-                        let state = this.input_state.read(cx);
-                        let text = state.value().to_string();
-                        let cursor = state.cursor();
-                        
-                        this.history.push(text, cursor, cursor);
+                        let text = this.input_state.read(cx).value().to_string();
+                        let (anchor, head) = this.selection_anchor_head(window, cx);
+                        this.history.push(text, anchor, head);
                         this.update_dirty_state(cx);
                     }
                     cx.notify();
                 }
-            })
+            }),
+            cx.observe_global::<SpellChecker>(|this, cx| this.refresh_diagnostics(cx)),
+            // Moving the caret raises no input event, only a redraw
+            cx.observe_in(&input_state, window, |this, _, window, cx| this.caret_moved(window, cx)),
+            cx.subscribe_in(&document, window, |this, document, ev: &DocumentEvent, window, cx| match ev {
+                DocumentEvent::Edited => this.follow_document(window, cx),
+                DocumentEvent::PathChanged => {
+                    this.load_annotations(cx);
+                    this.sync_input_mode(window, cx);
+                }
+                // Saved from another view: this one's undo history is at the same point
+                DocumentEvent::DirtyChanged if !document.read(cx).is_dirty() => this.history.mark_saved(),
+                // Shown in the status bar
+                DocumentEvent::FormatChanged => cx.notify(),
+                _ => {}
+            }),
         ];
 
         Self {
             input_state,
-            current_file: None,
-            encoding: Encoding::default(),
-            line_ending: LineEnding::default(),
+            document,
             soft_wrap: true,
             read_only: false,
-            is_dirty: false,
+            large_file: false,
+            large_file_threshold: 0,
+            font: None,
+            markdown_assist: true,
+            spell_check: false,
+            annotations: Vec::new(),
+            annotated_lines: BTreeSet::new(),
+            caret_annotated: false,
+            todos: None,
+            history_panel: false,
+            todo_markers: Vec::new(),
+            tab_handling: TabHandling::default(),
+            tab_width: 2,
+            saving: false,
+            last_edited: None,
             ignore_input_events: false,
+            following_document: false,
+            document_revision: 0,
             show_status_bar: true,
+            status_segments: StatusBarSegments::default(),
+            paragraph_alignments: ParagraphAlignments::default(),
+            caret_align: ParagraphAlign::Left,
+            observed_selection: None,
+            budget: None,
+            stats: TextStats::default(),
+            monospace: false,
+            auto_scroll: None,
+            freeze_top_line: false,
+            highlight_line: false,
+            show_line_numbers: false,
+            cursor_shape: CursorShape::default(),
+            cursor_blink: Some(DEFAULT_CURSOR_BLINK),
+            wrap_indent: (WrapIndent::None, 0),
+            code_language: None,
+            line_numbers: false,
+            auto_pair: false,
+            match_brackets: false,
+            bracket_caret: None,
+            bracket_match: None,
+            text_marks: Vec::new(),
+            marks_task: None,
+            block: None,
+            block_dragging: false,
+            find: None,
+            find_results: None,
+            conflicts: Vec::new(),
             fps_tracker: FpsTracker::new(),
+            frame_pacing: FramePacing::default(),
+            last_activity: Instant::now(),
+            frame_task: None,
             history: History::new(),
             _subscriptions,
         }
@@ -109,7 +400,27 @@ impl TextEditor {
             Some(c) => c,
             None => std::fs::read_to_string(&path)?,
         };
-        let content = normalize_tabs(&content);
+        self.open_decoded(path, content, Encoding::Utf8, window, cx);
+        Ok(())
+    }
+
+    /// Show `content`, read from `path` in `encoding`, as that file.
+    pub fn open_decoded(&mut self, path: PathBuf, content: String, encoding: Encoding, window: &mut Window, cx: &mut Context<Self>) {
+        // The mark is remembered as the encoding rather than shown as text
+        let (content, encoding) = match content.strip_prefix(types::BOM) {
+            Some(rest) if encoding == Encoding::Utf8 => (rest.to_string(), Encoding::Utf8Bom),
+            _ => (content, encoding),
+        };
+        self.large_file = self.large_file_threshold > 0 && content.len() as u64 >= self.large_file_threshold;
+        // Files we could not save back open read-only
+        let write_protected = std::fs::metadata(&path).is_ok_and(|m| m.permissions().readonly());
+        self.read_only = self.large_file || write_protected;
+        // Large files are shown as-is; normalizing would copy the whole buffer
+        let content = if self.large_file || !self.tab_handling.converts() {
+            content
+        } else {
+            normalize_tabs(&content, self.tab_width)
+        };
 
         self.ignore_input_events = true;
         self.input_state.update(cx, |state, cx| {
@@ -121,21 +432,74 @@ impl TextEditor {
             this.ignore_input_events = false;
         });
 
-        self.current_file = Some(path);
-        self.line_ending = LineEnding::detect(&content);
-        self.encoding = Encoding::default();
+        let line_ending = LineEnding::detect(&content);
+        self.document.update(cx, |doc, cx| doc.reset(Some(path), encoding, line_ending, cx));
+        self.last_edited = None;
+        self.paragraph_alignments.clear();
+        self.find_results = None;
+        self.load_annotations(cx);
+        self.sync_input_mode(window, cx);
+        self.refresh_conflicts(window, cx);
+        self.refresh_diagnostics(cx);
+        self.refresh_todos(cx);
         
-        self.history.clear(content);
+        if self.large_file {
+            info!(path = ?self.current_file(cx), bytes = content.len(), "Opened in large-file mode");
+            self.history.clear(String::new());
+        } else {
+            self.history.clear(content);
+        }
         self.update_dirty_state(cx);
         
         cx.notify();
-        Ok(())
+    }
+
+    /// Show `content` read-only under `name`, as a virtual document with no
+    /// file behind it. Saving it always asks for a new file.
+    pub fn open_virtual(&mut self, name: String, content: String, window: &mut Window, cx: &mut Context<Self>) {
+        self.ignore_input_events = true;
+        self.input_state.update(cx, |state, cx| {
+            state.set_value(&content, window, cx);
+        });
+        cx.on_next_frame(window, |this: &mut Self, _window: &mut Window, _cx| {
+            this.ignore_input_events = false;
+        });
+
+        let line_ending = LineEnding::detect(&content);
+        self.document.update(cx, |doc, cx| doc.reset_virtual(name, line_ending, cx));
+        self.large_file = false;
+        self.read_only = true;
+        self.last_edited = None;
+        self.paragraph_alignments.clear();
+        self.find_results = None;
+        self.annotations.clear();
+        self.refresh_conflicts(window, cx);
+        self.refresh_diagnostics(cx);
+        self.refresh_todos(cx);
+        self.history.clear(content);
+        self.update_dirty_state(cx);
+        cx.notify();
     }
 
     /// Mark as saved (clears dirty flag).
-    pub fn mark_clean(&mut self) {
+    pub fn mark_clean(&mut self, cx: &mut Context<Self>) {
         self.history.mark_saved();
-        self.is_dirty = false;
+        self.document.update(cx, |doc, cx| doc.mark_saved(cx));
+    }
+
+    /// Path of the open file, if any.
+    pub fn current_file<'a>(&self, cx: &'a App) -> Option<&'a Path> {
+        self.document.read(cx).path()
+    }
+
+    /// Whether the content has unsaved changes.
+    pub fn is_dirty(&self, cx: &App) -> bool {
+        self.document.read(cx).is_dirty()
+    }
+
+    /// Tie the document to `path` (or to no file) without touching its text.
+    pub fn set_current_file(&mut self, path: Option<PathBuf>, cx: &mut Context<Self>) {
+        self.document.update(cx, |doc, cx| doc.set_path(path, cx));
     }
 
     pub fn close_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
@@ -151,9 +515,14 @@ impl TextEditor {
         });
 
         // Clear current file reference
-        self.current_file = None;
-        self.line_ending = LineEnding::default();
-        self.encoding = Encoding::default();
+        self.document.update(cx, |doc, cx| doc.reset(None, Encoding::default(), LineEnding::default(), cx));
+        self.last_edited = None;
+        self.paragraph_alignments.clear();
+        self.find_results = None;
+        self.annotations.clear();
+        self.refresh_conflicts(window, cx);
+        self.large_file = false;
+        self.read_only = false;
         
         self.history.clear(String::new());
         self.update_dirty_state(cx);
@@ -161,18 +530,94 @@ impl TextEditor {
         cx.notify();
     }
 
+    /// A second view of `document`, as it is now.
+    pub fn view_of(document: Entity<Document>, read_only: bool, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let mut editor = Self::with_document(document, window, cx, String::new());
+        editor.set_read_only(read_only, cx);
+        editor.follow_document(window, cx);
+        // Taking in the text isn't an edit to undo here
+        editor.history.clear(editor.document.read(cx).text().to_string());
+        editor.load_annotations(cx);
+        editor.sync_input_mode(window, cx);
+        editor
+    }
+
+    /// Take in the document's text after another view of it changed it.
+    /// The caret stays at the same offset and the selection is dropped;
+    /// the edit goes into this view's undo history too, so undoing in
+    /// either view walks back through both.
+    fn follow_document(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let (revision, text, clean) = {
+            let doc = self.document.read(cx);
+            (doc.revision(), doc.text().clone(), !doc.is_dirty())
+        };
+        if revision == self.document_revision {
+            return;
+        }
+        self.document_revision = revision;
+        let content = text.to_string();
+        let caret = (0..=self.input_state.read(cx).cursor().min(content.len()))
+            .rev()
+            .find(|&i| content.is_char_boundary(i))
+            .unwrap_or(0);
+
+        self.following_document = true;
+        let read_only = self.read_only;
+        self.input_state.update(cx, |state, cx| {
+            // A read-only input takes no edits, only a new value
+            if read_only {
+                state.set_value(&content, window, cx);
+                return;
+            }
+            let end = state.text().offset_to_offset_utf16(state.text().len());
+            state.replace_text_in_range(Some(0..end), &content, window, cx);
+            // An empty edit puts the caret back without focusing this view
+            let caret = text.offset_to_offset_utf16(caret);
+            state.replace_text_in_range(Some(caret..caret), "", window, cx);
+        });
+        // Runs after the input's change events above are handled
+        cx.defer_in(window, |this: &mut Self, _window, _cx| {
+            this.following_document = false;
+        });
+
+        if !self.large_file {
+            self.history.push(content, caret, caret);
+            if clean {
+                self.history.mark_saved();
+            }
+        }
+        cx.notify();
+    }
+
     #[allow(dead_code)]
     pub fn set_text(&mut self, content: String, window: &mut Window, cx: &mut Context<Self>) {
         debug!(
             len = content.len(),
-            path = ?self.current_file,
+            path = ?self.current_file(cx),
             "Setting editor text"
         );
         self.input_state.update(cx, |state, cx| {
             state.set_value(&content, window, cx);
         });
-        self.line_ending = LineEnding::detect(&content);
-        self.encoding = Encoding::default();
+        let line_ending = LineEnding::detect(&content);
+        self.document.update(cx, |doc, cx| {
+            doc.set_line_ending(line_ending, cx);
+            doc.set_encoding(Encoding::default(), cx);
+        });
+        cx.notify();
+    }
+
+    /// Start an untitled document holding `content`. It counts as unsaved,
+    /// so closing it prompts like any other edit.
+    pub fn new_with_text(&mut self, content: String, window: &mut Window, cx: &mut Context<Self>) {
+        self.close_file(window, cx);
+        let content = if self.tab_handling.converts() { normalize_tabs(&content, self.tab_width) } else { content };
+        let line_ending = LineEnding::detect(&content);
+        self.document.update(cx, |doc, cx| doc.set_line_ending(line_ending, cx));
+        // Input events are ignored until the next frame, so record the edit here
+        self.input_state.update(cx, |state, cx| state.set_value(&content, window, cx));
+        self.history.push(content, 0, 0);
+        self.update_dirty_state(cx);
         cx.notify();
     }
 
@@ -184,26 +629,271 @@ impl TextEditor {
     }
 
     pub fn cut(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
         self.dispatch_to_input(&CutAction, window, cx);
     }
 
     pub fn paste(&mut self, _: &NormalizePasteAction, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only || self.block_paste(window, cx) {
+            return;
+        }
         // Normalize tabs in clipboard content before pasting
-        if let Some(item) = cx.read_from_clipboard() {
+        if let Some(item) = cx.read_from_clipboard().filter(|_| self.tab_handling.converts()) {
             if let Some(text) = item.text() {
-                let normalized = normalize_tabs(&text);
+                let normalized = normalize_tabs(&text, self.tab_width);
                 cx.write_to_clipboard(ClipboardItem::new_string(normalized));
             }
         }
         self.dispatch_to_input(&PasteAction, window, cx);
     }
 
-    pub fn select_all(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self.dispatch_to_input(&SelectAllAction, window, cx);
+    /// Copy the lines added or changed since the last save.
+    pub fn copy_modified_lines(&mut self, cx: &mut Context<Self>) {
+        if !self.is_dirty(cx) {
+            return;
+        }
+        let Some(saved) = self.history.saved_text() else {
+            warn!("Saved state is no longer in the undo history");
+            return;
+        };
+        let current = self.input_state.read(cx).value();
+        let lines: Vec<&str> = current.lines().collect();
+        let modified: Vec<&str> = diff::changed_lines(&saved, &current)
+            .into_iter()
+            .map(|i| lines[i])
+            .collect();
+        debug!(lines = modified.len(), "Copying modified lines");
+        cx.write_to_clipboard(ClipboardItem::new_string(modified.join("\n")));
+    }
+
+    /// Whether typed keys go to the text, rather than the find bar or
+    /// another input inside the editor.
+    pub(super) fn input_focused(&self, window: &Window, cx: &App) -> bool {
+        self.input_state.focus_handle(cx).is_focused(window)
+    }
+
+    /// Byte range of the current selection, ordered start to end.
+    pub(crate) fn selection_range(&self, window: &mut Window, cx: &mut Context<Self>) -> Option<std::ops::Range<usize>> {
+        self.input_state.update(cx, |state, cx| {
+            let selection = state.selected_text_range(true, window, cx)?;
+            let text = state.text();
+            let start = selection.range.start.min(selection.range.end);
+            let end = selection.range.start.max(selection.range.end);
+            Some(text.offset_utf16_to_offset(start)..text.offset_utf16_to_offset(end))
+        })
+    }
+
+    /// Byte range of the whole lines under the selection, or of the whole buffer.
+    fn selected_lines_range(&self, window: &mut Window, cx: &mut Context<Self>) -> std::ops::Range<usize> {
+        let selection = self.selection_range(window, cx).filter(|r| !r.is_empty());
+        let text = self.input_state.read(cx).text().clone();
+        match selection {
+            Some(range) => {
+                let first = text.offset_to_position(range.start).line as usize;
+                let last = text.offset_to_position(range.end).line as usize;
+                text.line_start_offset(first)..text.line_end_offset(last)
+            }
+            None => 0..text.len(),
+        }
     }
 
-    pub fn open_search(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self.dispatch_to_input(&SearchAction, window, cx);
+    /// Replace `range` with `f` applied to it, as one undo step.
+    fn transform_range(&mut self, range: std::ops::Range<usize>, f: impl FnOnce(&str) -> String, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
+        self.input_state.update(cx, |state, cx| {
+            let text = state.text().clone();
+            let original = text.slice(range.clone()).to_string();
+            let replaced = f(&original);
+            if replaced != original {
+                let range = text.offset_to_offset_utf16(range.start)..text.offset_to_offset_utf16(range.end);
+                state.replace_text_in_range(Some(range), &replaced, window, cx);
+            }
+        });
+    }
+
+    /// Select the byte range `range` and scroll it into view.
+    pub(crate) fn select_range(&self, range: std::ops::Range<usize>, window: &mut Window, cx: &mut Context<Self>) {
+        self.input_state.update(cx, |state, cx| {
+            let text = state.text().clone();
+            state.set_cursor_position(text.offset_to_position(range.start), window, cx);
+            if range.is_empty() {
+                return;
+            }
+            // InputState has no selection setter. Re-marking the range with its
+            // own text selects it (gpui-component 0.5.1 ends the new selection
+            // at the marked range's end), and unmarking keeps the selection.
+            let utf16 = text.offset_to_offset_utf16(range.start)..text.offset_to_offset_utf16(range.end);
+            let same = text.slice(range).to_string();
+            state.replace_and_mark_text_in_range(Some(utf16), &same, Some(0..0), window, cx);
+            state.unmark_text(window, cx);
+        });
+    }
+
+    /// Caret line and column, both 0-based, with the column in characters.
+    pub(crate) fn caret_line_column(&self, cx: &App) -> (usize, usize) {
+        let state = self.input_state.read(cx);
+        let text = state.text();
+        let cursor = state.cursor();
+        let line = text.offset_to_position(cursor).line as usize;
+        let column = text.slice(text.line_start_offset(line)..cursor).to_string().chars().count();
+        (line, column)
+    }
+
+    /// Put the caret at 0-based `line` and `column` (in characters), clamped
+    /// to the text, and scroll it into view.
+    pub(crate) fn go_to_line_column(&self, line: usize, column: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let text = self.input_state.read(cx).text().clone();
+        let line = line.min(text.lines_len().saturating_sub(1));
+        let start = text.line_start_offset(line);
+        let line_text = text.slice(start..text.line_end_offset(line)).to_string();
+        let offset = start + line_text.char_indices().nth(column).map_or(line_text.len(), |(i, _)| i);
+        self.select_range(offset..offset, window, cx);
+    }
+
+    /// Insert `text` at the caret, replacing the selection, as one undo step.
+    pub(crate) fn insert_text(&mut self, text: &str, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
+        self.input_state.update(cx, |state, cx| state.replace_text_in_range(None, text, window, cx));
+    }
+
+    /// Replace the input's diagnostics with the spelling squiggles and
+    /// annotation markers for the current text, once typing pauses. The
+    /// spelling is checked off the UI thread.
+    fn refresh_diagnostics(&mut self, cx: &mut Context<Self>) {
+        let checker = self.spell_checker(cx);
+        // Replacing the task cancels the previous wait
+        self.marks_task = Some(cx.spawn(async move |this: WeakEntity<Self>, cx: &mut AsyncApp| {
+            cx.background_executor().timer(MARKS_DELAY).await;
+            let Ok(text) = this.update(cx, |this, cx| this.input_state.read(cx).value()) else { return };
+            let spelling = cx.background_spawn({
+                let text = text.clone();
+                async move { checker.map(|checker| spelling_marks(&text, &checker)).unwrap_or_default() }
+            }).await;
+            let _ = this.update(cx, |this, cx| {
+                let mut marks = spelling;
+                marks.extend(this.annotation_marks(&text));
+                this.text_marks = marks;
+                this.refresh_bracket_match(cx);
+                this.push_marks(cx);
+            });
+        }));
+    }
+
+    /// Bring what follows the caret up to date once it has moved.
+    fn caret_moved(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        // The input also redraws for every caret blink, and a selection can
+        // change at its anchor without the caret moving
+        let (anchor, caret) = self.selection_anchor_head(window, cx);
+        let observed = self.observed_selection.replace((anchor, caret));
+        if observed == Some((anchor, caret)) {
+            return;
+        }
+        // Selecting raises no input event, so keep the undo history's copy current here
+        if !self.ignore_input_events && !self.large_file {
+            self.history.set_selection(anchor, caret);
+        }
+        if observed.is_some_and(|(_, head)| head == caret) {
+            return;
+        }
+        if self.bracket_caret != Some(caret) && self.refresh_bracket_match(cx) {
+            self.push_marks(cx);
+        }
+        self.refresh_caret_annotated(cx);
+        self.refresh_caret_align(cx);
+    }
+
+    /// The language for the input's code-editor mode, if anything shown
+    /// needs it: patches color their added, removed and hunk header lines.
+    fn wanted_code_language(&self, cx: &App) -> Option<&'static str> {
+        if !self.large_file && language::grammar_name(self.document.read(cx).path()) == Some("diff") {
+            return Some("diff");
+        }
+        let marks = self.spell_check || self.match_brackets || !self.annotations.is_empty() || !self.conflicts.is_empty();
+        (marks || self.show_line_numbers).then_some("text")
+    }
+
+    /// Put the input in code-editor mode while spelling, notes, conflicts,
+    /// the bracket match, line numbers or a patch are shown, and back in
+    /// plain-text mode, with its plain Enter, padding and background,
+    /// otherwise.
+    fn sync_input_mode(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let code_language = self.wanted_code_language(cx);
+        let code_editor = code_language.is_some();
+        let line_numbers = code_editor && self.show_line_numbers;
+        if code_language == self.code_language {
+            if line_numbers != self.line_numbers {
+                self.line_numbers = line_numbers;
+                self.input_state.update(cx, |state, cx| state.set_line_number(line_numbers, window, cx));
+            }
+            return;
+        }
+        debug!(language = ?code_language, "Switching input mode");
+        // The mode is fixed when the input is built, so build it again
+        let (anchor, head) = self.selection_anchor_head(window, cx);
+        let soft_wrap = self.soft_wrap;
+        let caret = self.caret_style();
+        let wrap_indent = self.wrap_indent_fn();
+        self.ignore_input_events = true;
+        self.input_state.update(cx, |state, cx| {
+            let text = state.value();
+            let focused = state.focus_handle(cx).is_focused(window);
+            *state = build_input(code_language, line_numbers, soft_wrap, window, cx);
+            style_input(state, caret, cx);
+            if wrap_indent.is_some() {
+                state.set_wrap_indent(wrap_indent, cx);
+            }
+            state.set_value(&text, window, cx);
+            if focused {
+                state.focus(window, cx);
+            }
+        });
+        cx.on_next_frame(window, |this: &mut Self, _window: &mut Window, _cx| {
+            this.ignore_input_events = false;
+        });
+        self.code_language = code_language;
+        self.line_numbers = line_numbers;
+        self.select_range(anchor.min(head)..anchor.max(head), window, cx);
+        self.push_marks(cx);
+        cx.notify();
+    }
+
+    /// Show the text marks, conflict sides and bracket match as diagnostics.
+    fn push_marks(&mut self, cx: &mut Context<Self>) {
+        let mut marks = self.text_marks.clone();
+        marks.extend(conflict_marks(&self.conflicts));
+        marks.extend(self.bracket_marks());
+        marks.sort_by_key(|mark| mark.range.start);
+        self.input_state.update(cx, |state, cx| {
+            let rope = state.text().clone();
+            let Some(diagnostics) = state.diagnostics_mut() else {
+                return;
+            };
+            if diagnostics.is_empty() && marks.is_empty() {
+                return;
+            }
+            diagnostics.reset(&rope);
+            for mark in marks {
+                let range = rope.offset_to_position(mark.range.start)..rope.offset_to_position(mark.range.end);
+                diagnostics.push(Diagnostic::new(range, mark.message).with_severity(mark.severity));
+            }
+            cx.notify();
+        });
+    }
+
+    /// The selected text, if any.
+    pub(crate) fn selected_text(&self, window: &mut Window, cx: &mut Context<Self>) -> Option<String> {
+        let range = self.selection_range(window, cx)?;
+        Some(self.input_state.read(cx).value()[range].to_string())
+    }
+
+    pub fn select_all(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.dispatch_to_input(&SelectAllAction, window, cx);
     }
 
     /// Focus input and dispatch action.
@@ -221,28 +911,193 @@ impl TextEditor {
         cx.notify();
     }
 
+    /// Highlight the caret's line in the theme's active line color.
+    pub fn set_highlight_current_line(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        if self.highlight_line != enabled {
+            self.highlight_line = enabled;
+            self.input_state.update(cx, |state, cx| state.set_highlight_current_line(Some(enabled), cx));
+            cx.notify();
+        }
+    }
+
+    /// Show or hide the line number gutter.
+    pub fn set_line_numbers(&mut self, enabled: bool, window: &mut Window, cx: &mut Context<Self>) {
+        if self.show_line_numbers != enabled {
+            self.show_line_numbers = enabled;
+            self.sync_input_mode(window, cx);
+            cx.notify();
+        }
+    }
+
+    /// Draw the caret as `shape`, blinking every `blink`, or steady for `None`.
+    pub fn set_cursor_style(&mut self, shape: CursorShape, blink: Option<Duration>, cx: &mut Context<Self>) {
+        if (self.cursor_shape, self.cursor_blink) != (shape, blink) {
+            self.cursor_shape = shape;
+            self.cursor_blink = blink;
+            let caret = self.caret_style();
+            self.input_state.update(cx, |state, cx| style_input(state, caret, cx));
+        }
+    }
+
+    /// What the input is told of its caret and current line, again whenever it is rebuilt.
+    fn caret_style(&self) -> CaretStyle {
+        CaretStyle { shape: self.cursor_shape, blink: self.cursor_blink, highlight_line: self.highlight_line }
+    }
+
+    /// Allow or block edits. Large files and virtual documents always stay
+    /// read-only.
+    pub fn set_read_only(&mut self, read_only: bool, cx: &mut Context<Self>) {
+        self.read_only = read_only || self.large_file || self.document.read(cx).is_virtual();
+        cx.notify();
+    }
+
+    pub fn toggle_read_only(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
+        self.set_read_only(!self.read_only, cx);
+    }
+
     pub fn toggle_status_bar(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
         self.show_status_bar = !self.show_status_bar;
         cx.notify();
     }
 
+    /// How long consecutive keystrokes keep extending the same undo step.
+    pub fn set_undo_group_timeout(&mut self, timeout: Duration) {
+        self.history.set_group_timeout(timeout);
+    }
+
+    /// Show or hide "Saving..." in the status bar.
+    pub fn set_saving(&mut self, saving: bool, cx: &mut Context<Self>) {
+        if self.saving != saving {
+            self.saving = saving;
+            cx.notify();
+        }
+    }
+
+    /// Files at least this many bytes open in large-file mode (0 disables it).
+    pub fn set_large_file_threshold(&mut self, bytes: u64) {
+        self.large_file_threshold = bytes;
+    }
+
+    pub fn set_font(&mut self, family: impl Into<SharedString>, size: f32, cx: &mut Context<Self>) {
+        self.font = Some((family.into(), px(size)));
+        cx.notify();
+    }
+
+    pub fn set_status_segments(&mut self, segments: StatusBarSegments, cx: &mut Context<Self>) {
+        if self.status_segments != segments {
+            self.status_segments = segments;
+            self.frame_task = None;
+            cx.notify();
+        }
+    }
+
+    pub fn set_frame_pacing(&mut self, pacing: FramePacing, cx: &mut Context<Self>) {
+        self.frame_pacing = pacing;
+        self.frame_task = None;
+        cx.notify();
+    }
+
+    /// Note user input, waking the FPS counter if it was idle.
+    fn mark_activity(&mut self, cx: &mut Context<Self>) {
+        if self.last_activity.elapsed() >= fps::IDLE_AFTER {
+            cx.notify();
+        }
+        self.last_activity = Instant::now();
+    }
+
+    /// Redraw after `delay`, unless a redraw is already pending.
+    fn schedule_frame(&mut self, delay: Duration, window: &mut Window, cx: &mut Context<Self>) {
+        if self.frame_task.is_some() {
+            return;
+        }
+        self.frame_task = Some(cx.spawn_in(window, move |this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                cx.background_executor().timer(delay).await;
+                let _ = this.update(&mut cx, |this, cx| {
+                    this.frame_task = None;
+                    cx.notify();
+                });
+            }
+        }));
+    }
+
+    /// Cap the memory used by undo/redo history.
+    pub fn set_undo_memory_limit(&mut self, bytes: usize) {
+        self.history.set_memory_limit(bytes);
+    }
+
+    /// Alignment of the paragraph under the cursor.
+    pub fn paragraph_align(&self) -> ParagraphAlign {
+        self.caret_align
+    }
+
+    fn refresh_caret_align(&mut self, cx: &App) {
+        let state = self.input_state.read(cx);
+        let line = state.cursor_position().line as usize;
+        self.caret_align = self.paragraph_alignments.get(&state.value(), line);
+    }
+
+    /// Align the paragraph under the cursor.
+    pub fn set_paragraph_align(&mut self, align: ParagraphAlign, cx: &mut Context<Self>) {
+        let state = self.input_state.read(cx);
+        let line = state.cursor_position().line as usize;
+        let text = state.value().to_string();
+        self.paragraph_alignments.set(&text, line, align);
+        self.caret_align = align;
+        cx.notify();
+    }
+
     pub fn undo(&mut self, _: &UndoAction, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
         if let Some(snapshot) = self.history.undo() {
-            let text = snapshot.text.clone();
-            // Ignore input events while restoring state
-            self.ignore_input_events = true;
-            self.input_state.update(cx, |state, cx| {
-                state.set_value(&text, window, cx);
-                let pos = Self::offset_to_position(&text, snapshot.cursor_head);
-                state.set_cursor_position(pos, window, cx);
-            });
-            cx.on_next_frame(window, |this: &mut Self, _window, _cx| {
-                this.ignore_input_events = false;
-            });
-            self.update_dirty_state(cx);
+            self.restore_snapshot(snapshot, window, cx);
         }
     }
 
+    /// Show a state from the undo history.
+    fn restore_snapshot(&mut self, snapshot: Snapshot, window: &mut Window, cx: &mut Context<Self>) {
+        let text = snapshot.text;
+        // Ignore input events while restoring state
+        self.ignore_input_events = true;
+        self.input_state.update(cx, |state, cx| {
+            state.set_value(&text, window, cx);
+            let pos = Self::offset_to_position(&text, snapshot.cursor_head);
+            state.set_cursor_position(pos, window, cx);
+        });
+        let (anchor, head) = (snapshot.cursor_anchor, snapshot.cursor_head);
+        if anchor != head {
+            // InputState can't be given a backward selection, so one made
+            // leftwards comes back with the caret at its end
+            self.select_range(anchor.min(head)..anchor.max(head), window, cx);
+        }
+        cx.on_next_frame(window, |this: &mut Self, _window, _cx| {
+            this.ignore_input_events = false;
+        });
+        // Undoing a line ending conversion takes the old breaks back; text
+        // without any keeps the ending new lines get
+        if text.contains(['\n', '\r']) {
+            self.document.update(cx, |doc, cx| doc.set_line_ending(LineEnding::detect(&text), cx));
+        }
+        self.update_dirty_state(cx);
+    }
+
+    /// Byte offsets of the selection's anchor and head (the caret end).
+    fn selection_anchor_head(&self, window: &mut Window, cx: &mut Context<Self>) -> (usize, usize) {
+        self.input_state.update(cx, |state, cx| {
+            let head = state.cursor();
+            let Some(selection) = state.selected_text_range(true, window, cx) else {
+                return (head, head);
+            };
+            let text = state.text();
+            let start = text.offset_utf16_to_offset(selection.range.start);
+            let end = text.offset_utf16_to_offset(selection.range.end);
+            (if head == start { end } else { start }, head)
+        })
+    }
+
     fn offset_to_position(text: &str, offset: usize) -> Position {
         let mut line = 0;
         let mut character = 0;
@@ -260,38 +1115,40 @@ impl TextEditor {
     }
 
     pub fn redo(&mut self, _: &RedoAction, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
         if let Some(snapshot) = self.history.redo() {
-            let text = snapshot.text.clone();
-            self.ignore_input_events = true;
-            self.input_state.update(cx, |state, cx| {
-                state.set_value(&text, window, cx);
-                let pos = Self::offset_to_position(&text, snapshot.cursor_head);
-                state.set_cursor_position(pos, window, cx);
-            });
-            cx.on_next_frame(window, |this: &mut Self, _window, _cx| {
-                this.ignore_input_events = false;
-            });
-            self.update_dirty_state(cx);
+            self.restore_snapshot(snapshot, window, cx);
         }
     }
 
     fn update_dirty_state(&mut self, cx: &mut Context<Self>) {
-        let dirty = self.history.is_dirty();
-        if self.is_dirty != dirty {
-            self.is_dirty = dirty;
+        let was_dirty = self.is_dirty(cx);
+        let changed = self.history.is_dirty();
+        self.document.update(cx, |doc, cx| doc.update_dirty(changed, cx));
+        if self.is_dirty(cx) != was_dirty {
             cx.notify();
         }
     }
 
     /// Export to PDF via save dialog.
-    pub fn export_pdf(&mut self, _: &ExportPdfAction, window: &mut Window, cx: &mut Context<Self>) {
-        let content = self.input_state.read(cx).value().to_string();
-        let filename = self.current_file
-            .as_ref()
-            .and_then(|p| p.file_name())
-            .and_then(|n| n.to_str())
-            .unwrap_or("Untitled")
-            .to_string();
+    pub fn export_pdf(&mut self, options: &PdfExportSettings, window: &mut Window, cx: &mut Context<Self>) {
+        let text = self.input_state.read(cx).value().to_string();
+        let mut line_alignment = self.paragraph_alignments.per_line(&text);
+        let selection = options.selection_only.then(|| self.selection_range(window, cx)).flatten().filter(|r| !r.is_empty());
+        // Parsed after the dialog, off the UI thread
+        let syntax = options.syntax_colors
+            .then(|| (Theme::global(cx).highlight_theme.clone(), self.current_file(cx).map(Path::to_path_buf)));
+        let first_line = match &selection {
+            Some(range) => {
+                let first_line = text[..range.start].matches('\n').count();
+                line_alignment.drain(..first_line);
+                first_line + 1
+            }
+            None => 1,
+        };
+        let filename = self.document.read(cx).display_name();
         
         // Get theme colors for PDF
         let theme = Theme::global(cx);
@@ -303,11 +1160,23 @@ impl TextEditor {
         let fg_rgb = hsla_to_rgb_u8(fg);
         
         let config = pdf::PdfConfig {
-            font_size: 12.0,
-            margin: 72.0, // 1 inch in points
-            header: Some(format!("{} - {}", filename, current_date())),
+            font_size: options.font_size,
+            margin: options.margin,
+            page_size: options.page_size,
+            orientation: options.orientation,
+            line_numbers: options.line_numbers,
+            line_alignment,
+            header: options.include_header.then(|| options.header_template.clone()),
+            footer: Some(options.footer_template.clone()).filter(|f| !f.trim().is_empty()),
+            file_name: filename.clone(),
+            file_path: self.current_file(cx).map(|p| p.display().to_string()).unwrap_or_default(),
+            date: current_date(),
+            first_line,
             background_rgb: bg_rgb,
             text_rgb: fg_rgb,
+            token_colors: Vec::new(),
+            font_family: options.font_family.clone(),
+            render_markdown: options.render_markdown && is_markdown_file(self.current_file(cx)),
         };
         
         // Spawn async task to show save dialog and export
@@ -327,7 +1196,30 @@ impl TextEditor {
                 
                 if let Some(path) = dialog_task.await {
                     info!(path = ?path, "Exporting to PDF");
-                    match pdf::export_to_pdf(&content, &path, &config) {
+                    let mut config = config;
+                    let export = cx.background_spawn(async move {
+                        // Highlight the whole document so a selection is parsed in context
+                        let token_colors = match syntax {
+                            Some((theme, file)) => syntax_colors(&text, file.as_deref(), &theme),
+                            None => Vec::new(),
+                        };
+                        let content = match selection {
+                            Some(range) => {
+                                config.token_colors = token_colors
+                                    .into_iter()
+                                    .filter(|(span, _)| span.end > range.start && span.start < range.end)
+                                    .map(|(span, color)| (span.start.max(range.start) - range.start..span.end.min(range.end) - range.start, color))
+                                    .collect();
+                                text[range].to_string()
+                            }
+                            None => {
+                                config.token_colors = token_colors;
+                                text
+                            }
+                        };
+                        pdf::export_to_pdf(&content, &path, &config)
+                    });
+                    match export.await {
                         Ok(_) => info!("PDF export completed"),
                         Err(e) => warn!(error = %e, "PDF export failed"),
                     }
@@ -339,6 +1231,24 @@ impl TextEditor {
     }
 }
 
+/// Token colors for `text` from the editor's highlighter, when it has a
+/// grammar for the file's language.
+fn syntax_colors(text: &str, path: Option<&Path>, theme: &HighlightTheme) -> Vec<(Range<usize>, Rgb)> {
+    let Some(grammar) = language::grammar_name(path) else { return Vec::new() };
+    // The registry answers unknown names with another grammar, so ask first
+    if !LanguageRegistry::singleton().languages().iter().any(|name| name.as_ref() == grammar) {
+        return Vec::new();
+    }
+    let mut highlighter = SyntaxHighlighter::new(grammar);
+    highlighter.update(None, &Rope::from(text));
+    highlighter
+        .styles(&(0..text.len()), theme)
+        .into_iter()
+        .filter(|(range, _)| !range.is_empty())
+        .filter_map(|(range, style)| Some((range, hsla_to_rgb_u8(style.color?))))
+        .collect()
+}
+
 /// HSLA to RGB (0-255).
 fn hsla_to_rgb_u8(hsla: Hsla) -> (u8, u8, u8) {
     let h = hsla.h;
@@ -383,20 +1293,44 @@ impl Focusable for TextEditor {
 
 impl Render for TextEditor {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        // Only request continuous animation frames when status bar with FPS is visible
-        if self.show_status_bar {
-            window.request_animation_frame();
+        // Only redraw continuously while the FPS segment is on screen
+        let segments = self.status_segments;
+        let show_fps = self.show_status_bar && segments.fps;
+        let next_frame = if show_fps {
+            let idle = self.last_activity.elapsed() >= fps::IDLE_AFTER;
+            self.frame_pacing.next_frame(window.is_window_active(), idle)
+        } else {
+            NextFrame::Paused
+        };
+        match next_frame {
+            NextFrame::Immediate => window.request_animation_frame(),
+            NextFrame::After(delay) => self.schedule_frame(delay, window, cx),
+            NextFrame::Paused => {}
         }
 
         // Calculate FPS using the tracker
-        let fps = self.fps_tracker.tick().round() as u32;
+        let fps_label = if !show_fps {
+            self.fps_tracker.pause();
+            None
+        } else if next_frame == NextFrame::Paused {
+            self.fps_tracker.pause();
+            Some("FPS paused".to_string())
+        } else {
+            let fps = self.fps_tracker.tick().round() as u32;
+            Some(match self.frame_pacing.max_fps {
+                0 => format!("{} FPS", fps),
+                cap => format!("{} / {} FPS", fps, cap),
+            })
+        };
 
         let theme = Theme::global_mut(cx);
         let colors = theme.colors;
         let cursor = self.input_state.read(cx).cursor_position();
         let line = cursor.line.saturating_add(1);
         let column = cursor.character.saturating_add(1);
-        let char_count = self.input_state.read(cx).value().chars().count();
+        let char_count = self.stats.characters;
+        let word_count = (segments.words && !self.large_file).then_some(self.stats.words);
+        let line_count = self.input_state.read(cx).text().lines_len();
         let char_count_display = Self::format_with_commas(char_count);
         let selected_text_range = self.input_state.update(cx, |state, cx| {
             state.selected_text_range(true, window, cx)
@@ -417,39 +1351,136 @@ impl Render for TextEditor {
         } else {
             format!("{} characters", char_count_display)
         };
+        let visual_line = if self.soft_wrap && self.show_status_bar {
+            self.visual_line_info(window, cx).filter(|(_, rows)| *rows > 1)
+        } else {
+            None
+        };
+        let align = self.caret_align;
+        let annotated = self.show_status_bar && self.caret_annotated;
+        let access_label = if self.large_file {
+            Some("Large file (read-only)")
+        } else if self.read_only {
+            Some("Read Only")
+        } else {
+            None
+        };
+        let conflict_bar = self.render_conflict_bar(cx);
+        let find_bar = self.render_find_bar(cx);
+        let todo_panel = self.render_todo_panel(cx);
+        let find_results = self.render_find_results(cx);
+        let history_panel = self.render_history_panel(cx);
+        let ruler = self.render_ruler(window, cx);
+        let frozen_line = self.render_frozen_line(window, cx);
+        let mono_font = Theme::global(cx).mono_font_family.clone();
         let show_status_bar = self.show_status_bar;
-        let encoding = self.encoding.to_string();
-        let line_ending = self.line_ending.to_string();
+        let tab_label = self.tab_label();
+        let block_label = self.block_label();
+        let chrome = ChromeStyle::global(cx);
+        let budget = self.budget.map(|budget| {
+            let stats = self.stats;
+            let color = match budget.level(&stats) {
+                BudgetLevel::Under => colors.muted_foreground,
+                BudgetLevel::Near => colors.warning,
+                BudgetLevel::Over => colors.danger,
+            };
+            (budget.label(&stats), color)
+        });
+        let chord_hint = window.pending_input_keystrokes().map(keymap::chord_hint);
+        let auto_scroll_label = self.auto_scroll_label();
+        let extension_items: Vec<_> = cx.try_global::<Extensions>()
+            .map(|extensions| extensions.status_items())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|provider| provider(self, cx))
+            .map(|item| (item.text, item.tone.color(cx)))
+            .collect();
+        let position = self.render_position_segment(format!("Ln {}, Col {}", line, column), cx);
+        let line_ending = self.render_line_ending_segment(cx);
+        let encoding = self.render_encoding_segment(cx);
 
         div()
             .flex()
             .flex_col()
             .size_full()
             .bg(colors.background)
-            .on_action(cx.listener(Self::export_pdf))
             .on_action(cx.listener(Self::undo))
             .on_action(cx.listener(Self::redo))
             .on_action(cx.listener(Self::paste))
+            .on_action(cx.listener(Self::insert_tab))
+            .on_action(cx.listener(Self::previous_cell))
+            .on_action(cx.listener(Self::find_next))
+            .on_action(cx.listener(Self::find_previous))
+            .on_action(cx.listener(Self::promote_heading))
+            .on_action(cx.listener(Self::demote_heading))
+            .on_action(cx.listener(Self::duplicate_line))
+            .on_action(cx.listener(Self::move_line_up))
+            .on_action(cx.listener(Self::move_line_down))
+            .on_action(cx.listener(Self::delete_line))
+            .on_action(cx.listener(Self::toggle_comment))
+            .on_action(cx.listener(Self::upper_case))
+            .on_action(cx.listener(Self::lower_case))
+            .on_action(cx.listener(Self::title_case))
+            .on_action(cx.listener(Self::sentence_case))
+            .on_action(cx.listener(Self::copy_to_slot_1))
+            .on_action(cx.listener(Self::copy_to_slot_2))
+            .on_action(cx.listener(Self::copy_to_slot_3))
+            .on_action(cx.listener(Self::paste_from_slot_1))
+            .on_action(cx.listener(Self::paste_from_slot_2))
+            .on_action(cx.listener(Self::paste_from_slot_3))
+            .on_action(cx.listener(Self::go_to_line))
+            .on_action(cx.listener(Self::block_select_up))
+            .on_action(cx.listener(Self::block_select_down))
+            .on_action(cx.listener(Self::block_select_left))
+            .on_action(cx.listener(Self::block_select_right))
+            .capture_action(cx.listener(Self::block_copy))
+            .capture_action(cx.listener(Self::block_cut))
+            .capture_key_down(cx.listener(Self::block_key_down))
+            .capture_key_down(cx.listener(Self::bracket_key_down))
+            .capture_key_down(cx.listener(Self::auto_scroll_key_down))
+            .capture_key_down(cx.listener(|this, _: &KeyDownEvent, _, cx| this.mark_activity(cx)))
+            .on_mouse_move(cx.listener(|this, _: &MouseMoveEvent, _, cx| this.mark_activity(cx)))
+            .key_context("TextEditor")
+            .children(conflict_bar)
+            .children(find_bar)
             .child(
-                // Main editor area
                 div()
+                    .flex()
                     .flex_grow()
-                    .p_2()
-                // .text_color(gpui::black())  // Set text color to black
-                .child(
-                    Input::new(&self.input_state)
-                        // No borders
-                        .bordered(false)
-                            .text_color(colors.accent_foreground)
-                            .border_color(colors.border)
-                            .h_full()
+                    .child(
+                        // Main editor area
+                        div()
+                            .flex()
+                            .flex_col()
+                            .flex_grow()
+                            .p(chrome.gutter_width)
+                            .when_some(self.font.clone(), |this, (family, size)| this.font_family(family).text_size(size))
+                            .when(self.monospace, |this| this.font_family(mono_font))
+                            .capture_any_mouse_down(cx.listener(Self::block_mouse_down))
+                            .on_mouse_move(cx.listener(Self::block_mouse_move))
+                        // .text_color(gpui::black())  // Set text color to black
+                        .children(ruler)
+                        .children(frozen_line)
+                        .child(
+                            div().flex_1().min_h(px(0.)).child(
+                            Input::new(&self.input_state)
+                                // No borders
+                                .bordered(false)
+                                    .disabled(self.read_only)
+                                    .text_color(colors.accent_foreground)
+                                    .border_color(colors.border)
+                                    .h_full()
+                            ))
                     )
+                    .children(history_panel)
             )
+            .children(find_results)
+            .children(todo_panel)
             .children(if show_status_bar {
                 Some(
                     // Status bar
                     div()
-                        .h(px(24.0))
+                        .h(chrome.status_bar_height)
                         .bg(colors.muted)
                         .border_t_1()
                         .border_color(colors.border)
@@ -458,15 +1489,104 @@ impl Render for TextEditor {
                         .gap(px(8.0))
                         .px_2()
                         .text_color(colors.muted_foreground)
-                        .child(format!("Ln {}, Col {}", line, column))
-                        .child(Self::separator(colors.border))
-                        .child(count_display)
-                        .child(Self::separator(colors.border))
-                        .child(line_ending)
-                        .child(Self::separator(colors.border))
-                        .child(encoding)
-                        .child(Self::separator(colors.border))
-                        .child(format!("{} FPS", fps)),
+                        .child(position)
+                        .children(chord_hint.map(|hint| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child(hint)
+                        }))
+                        .children(auto_scroll_label.map(|label| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child(label)
+                        }))
+                        .children(block_label.map(|label| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child(label)
+                        }))
+                        .children(self.saving.then(|| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child("Saving...")
+                        }))
+                        .children(annotated.then(|| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child("Note")
+                        }))
+                        .children(visual_line.map(|(row, rows)| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child(format!("Visual line {} of {}", row, rows))
+                        }))
+                        .children((align != ParagraphAlign::Left).then(|| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child(format!("Align: {}", align))
+                        }))
+                        .children(word_count.map(|words| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child(format!("{} words", Self::format_with_commas(words)))
+                        }))
+                        .children(budget.map(|(label, color)| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child(div().text_color(color).child(label))
+                        }))
+                        .children(extension_items.into_iter().map(|(label, color)| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child(div().text_color(color).child(label))
+                        }))
+                        .when(segments.lines, |this| {
+                            this.child(Self::separator(colors.border))
+                                .child(format!("{} lines", Self::format_with_commas(line_count)))
+                        })
+                        .when(segments.characters, |this| this.child(Self::separator(colors.border)).child(count_display))
+                        .when(segments.line_ending, |this| this.child(Self::separator(colors.border)).child(line_ending))
+                        .when(segments.encoding, |this| this.child(Self::separator(colors.border)).child(encoding))
+                        .when(segments.indentation, |this| this.child(Self::separator(colors.border)).child(tab_label))
+                        .when_some(fps_label, |this, label| this.child(Self::separator(colors.border)).child(label))
+                        .children(access_label.map(|label| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .child(Self::separator(colors.border))
+                                .child(label)
+                        })),
                 )
             } else {
                 None
@@ -475,6 +1595,35 @@ impl Render for TextEditor {
 }
 
 impl TextEditor {
+    /// Wrapped row of the cursor within its line as (row, rows), both 1-based.
+    ///
+    /// Uses the input's last layout, so it is only meaningful once the
+    /// cursor line has been laid out on screen.
+    fn visual_line_info(&self, window: &mut Window, cx: &mut Context<Self>) -> Option<(usize, usize)> {
+        self.input_state.update(cx, |state, cx| {
+            let text = state.text().clone();
+            let cursor = state.cursor();
+            let row = text.offset_to_position(cursor).line as usize;
+            let start = text.line_start_offset(row);
+            let end = text.line_end_offset(row);
+
+            let mut bounds_at = |offset: usize| {
+                let offset = text.offset_to_offset_utf16(offset);
+                state.bounds_for_range(offset..offset, Bounds::default(), window, cx)
+            };
+            let start_bounds = bounds_at(start)?;
+            let cursor_y = bounds_at(cursor)?.origin.y;
+            let end_y = bounds_at(end)?.origin.y;
+
+            let line_height = start_bounds.size.height;
+            if line_height <= px(0.0) {
+                return None;
+            }
+            let row_of = |y: Pixels| ((y - start_bounds.origin.y) / line_height).round().max(0.0) as usize;
+            Some((row_of(cursor_y) + 1, row_of(end_y) + 1))
+        })
+    }
+
     fn separator(color: Hsla) -> impl IntoElement {
         div()
             .h(px(14.0))
@@ -483,7 +1632,7 @@ impl TextEditor {
             .bg(color)
     }
 
-    fn format_with_commas(value: usize) -> String {
+    pub(crate) fn format_with_commas(value: usize) -> String {
         let s = value.to_string();
         let mut out = String::new();
         for (i, ch) in s.chars().rev().enumerate() {
@@ -496,19 +1645,131 @@ impl TextEditor {
     }
 }
 
-/// Normalize tabs to two spaces.
-fn normalize_tabs(content: &str) -> String {
-    content.replace('\t', "  ")
+/// Replace each tab with spaces up to the next stop every `width` columns,
+/// counting columns from the start of its line.
+fn normalize_tabs(content: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut out = String::with_capacity(content.len());
+    let mut column = 0;
+    for ch in content.chars() {
+        match ch {
+            '\t' => {
+                let spaces = width - column % width;
+                out.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\n' => {
+                out.push(ch);
+                column = 0;
+            }
+            _ => {
+                out.push(ch);
+                column += 1;
+            }
+        }
+    }
+    out
 }
 
 #[cfg(test)]
 mod tests {
-    use super::normalize_tabs;
+    use super::{normalize_tabs, syntax_colors, LineEnding, RedoAction, TextEditor, UndoAction};
+    use gpui::{AppContext, TestAppContext, VisualTestContext};
+    use gpui_component::highlighter::HighlightTheme;
+    use gpui_component::Root;
+    use std::path::Path;
 
     #[test]
     fn test_normalize_tabs() {
-        assert_eq!(normalize_tabs("hello\tworld"), "hello  world");
-        assert_eq!(normalize_tabs("\t\t"), "    ");
-        assert_eq!(normalize_tabs("no tabs"), "no tabs");
+        assert_eq!(normalize_tabs("hello\tworld", 2), "hello world");
+        assert_eq!(normalize_tabs("hell\tworld", 4), "hell    world");
+        assert_eq!(normalize_tabs("a\tb\n\tc", 4), "a   b\n    c");
+        assert_eq!(normalize_tabs("\t\t", 2), "    ");
+        assert_eq!(normalize_tabs("no tabs", 2), "no tabs");
+        assert_eq!(normalize_tabs("\tx", 4), "    x");
+    }
+
+    #[test]
+    fn test_syntax_colors_beyond_json() {
+        let theme = HighlightTheme::default_light();
+        assert!(!syntax_colors("fn main() {}\n", Some(Path::new("main.rs")), &theme).is_empty());
+        assert!(!syntax_colors("def f():\n    pass\n", Some(Path::new("f.py")), &theme).is_empty());
+        assert!(!syntax_colors("@@ -1 +1 @@\n-old\n+new\n", Some(Path::new("fix.patch")), &theme).is_empty());
+        assert!(syntax_colors("fn main() {}\n", Some(Path::new("notes.txt")), &theme).is_empty());
+    }
+
+    #[gpui::test]
+    fn test_input_is_plain_text_until_needed(cx: &mut TestAppContext) {
+        cx.update(gpui_component::init);
+        let window = cx.add_window(|window, cx| {
+            let editor = cx.new(|cx| TextEditor::new(window, cx, "one\ntwo".to_string()));
+            Root::new(editor, window, cx)
+        });
+        let editor = window.update(cx, |root, _, _| root.view().clone().downcast::<TextEditor>().unwrap()).unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        let is_code_editor = |cx: &mut VisualTestContext| editor.read_with(cx, |ed, cx| ed.input_state.read(cx).diagnostics().is_some());
+        assert!(!is_code_editor(cx));
+
+        // The current line band is drawn in plain-text mode too
+        editor.update(cx, |ed, cx| ed.set_highlight_current_line(true, cx));
+        assert!(!is_code_editor(cx));
+
+        editor.update_in(cx, |ed, window, cx| {
+            ed.select_range(4..7, window, cx);
+            ed.set_line_numbers(true, window, cx);
+        });
+        assert!(is_code_editor(cx));
+        // The rebuilt input keeps the text and selection
+        editor.update_in(cx, |ed, window, cx| {
+            assert_eq!(ed.input_state.read(cx).value(), "one\ntwo");
+            assert_eq!(ed.selection_range(window, cx), Some(4..7));
+            ed.set_line_numbers(false, window, cx);
+        });
+        assert!(!is_code_editor(cx));
+    }
+
+    #[gpui::test]
+    fn test_undo_brings_back_the_selection(cx: &mut TestAppContext) {
+        cx.update(gpui_component::init);
+        let window = cx.add_window(|window, cx| {
+            let editor = cx.new(|cx| TextEditor::new(window, cx, "one\ntwo".to_string()));
+            Root::new(editor, window, cx)
+        });
+        let editor = window.update(cx, |root, _, _| root.view().clone().downcast::<TextEditor>().unwrap()).unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+
+        editor.update(cx, |ed, _| ed.history.clear("one\ntwo".to_string()));
+        // Selecting alone raises no input event
+        editor.update_in(cx, |ed, window, cx| ed.select_range(4..7, window, cx));
+        editor.update_in(cx, |ed, window, cx| ed.insert_text("2", window, cx));
+        editor.update_in(cx, |ed, window, cx| {
+            assert_eq!(ed.input_state.read(cx).value(), "one\n2");
+            ed.undo(&UndoAction, window, cx);
+        });
+        editor.update_in(cx, |ed, window, cx| {
+            assert_eq!(ed.input_state.read(cx).value(), "one\ntwo");
+            assert_eq!(ed.selection_range(window, cx), Some(4..7));
+        });
+    }
+
+    #[gpui::test]
+    fn test_undo_restores_the_line_ending(cx: &mut TestAppContext) {
+        cx.update(gpui_component::init);
+        let window = cx.add_window(|window, cx| {
+            let editor = cx.new(|cx| TextEditor::new(window, cx, "one\ntwo".to_string()));
+            Root::new(editor, window, cx)
+        });
+        let editor = window.update(cx, |root, _, _| root.view().clone().downcast::<TextEditor>().unwrap()).unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        let line_ending = |cx: &mut VisualTestContext| editor.read_with(cx, |ed, cx| ed.document.read(cx).line_ending());
+
+        editor.update(cx, |ed, _| ed.history.clear("one\ntwo".to_string()));
+        editor.update_in(cx, |ed, window, cx| ed.set_line_ending(LineEnding::Crlf, window, cx));
+        assert_eq!(line_ending(cx), LineEnding::Crlf);
+        editor.update_in(cx, |ed, window, cx| ed.undo(&UndoAction, window, cx));
+        editor.update_in(cx, |ed, _, cx| assert_eq!(ed.input_state.read(cx).value(), "one\ntwo"));
+        assert_eq!(line_ending(cx), LineEnding::Lf);
+        editor.update_in(cx, |ed, window, cx| ed.redo(&RedoAction, window, cx));
+        assert_eq!(line_ending(cx), LineEnding::Crlf);
     }
 }
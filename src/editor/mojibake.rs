@@ -0,0 +1,190 @@
+//! Repairing UTF-8 text that was decoded as Windows-1252 (e.g. "Ã©" for "é").
+
+use gpui::*;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::{v_flex, Theme, WindowExt};
+use std::ops::Range;
+use std::rc::Rc;
+use tracing::info;
+
+use super::TextEditor;
+
+/// Preview rows shown before the list is cut off.
+const PREVIEW_LIMIT: usize = 200;
+
+/// One run of garbled characters and the text it should have been.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    /// Byte range in the original text.
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+/// Characters Windows-1252 puts at bytes 0x80-0x9F. The five bytes it
+/// leaves undefined keep their Latin-1 meaning.
+const CP1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// The Windows-1252 byte for `c`, if it has one.
+pub(super) fn cp1252_byte(c: char) -> Option<u8> {
+    match CP1252_HIGH.iter().position(|&high| high == c) {
+        Some(index) => Some(0x80 + index as u8),
+        // Latin-1 covers the rest
+        None => ((c as u32) < 0x100).then_some(c as u8),
+    }
+}
+
+/// Decode `bytes` as Windows-1252.
+pub fn decode_windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => CP1252_HIGH[(b - 0x80) as usize],
+            _ => b as char,
+        })
+        .collect()
+}
+
+/// Re-decode a run of non-ASCII characters as UTF-8, if that is what it was.
+fn repair_run(run: &str) -> Option<String> {
+    let bytes = run.chars().map(cp1252_byte).collect::<Option<Vec<u8>>>()?;
+    let fixed = String::from_utf8(bytes).ok()?;
+    (fixed != run).then_some(fixed)
+}
+
+/// Every run of non-ASCII characters in `text` that decodes as UTF-8 mojibake.
+pub fn find_mojibake(text: &str) -> Vec<Repair> {
+    let mut repairs = Vec::new();
+    let mut run_start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), '\0'))) {
+        match (c.is_ascii(), run_start) {
+            (false, None) => run_start = Some(i),
+            (true, Some(start)) => {
+                if let Some(replacement) = repair_run(&text[start..i]) {
+                    repairs.push(Repair { range: start..i, replacement });
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    repairs
+}
+
+/// `text` with all `repairs` applied.
+pub fn apply_repairs(text: &str, repairs: &[Repair]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for repair in repairs {
+        out.push_str(&text[last..repair.range.start]);
+        out.push_str(&repair.replacement);
+        last = repair.range.end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+impl TextEditor {
+    /// Look for double-encoded text and offer to repair it, previewing each change.
+    pub fn fix_mojibake(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let text = self.input_state.read(cx).value().to_string();
+        let repairs = find_mojibake(&text);
+        let read_only = self.read_only;
+        let preview: Rc<Vec<(usize, String, String)>> = Rc::new(
+            repairs
+                .iter()
+                .take(PREVIEW_LIMIT)
+                .map(|r| {
+                    let line = text[..r.range.start].matches('\n').count() + 1;
+                    (line, text[r.range.clone()].to_string(), r.replacement.clone())
+                })
+                .collect(),
+        );
+        let status = match repairs.len() {
+            0 => "No double-encoded text found.".to_string(),
+            n if read_only => format!("{} garbled sequence(s) found. The document is read-only.", n),
+            n => format!("{} garbled sequence(s) will be repaired.", n),
+        };
+        let can_repair = !repairs.is_empty() && !read_only;
+        let editor = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, cx| {
+            let colors = Theme::global(cx).colors;
+            let rows = preview.iter().map(|(line, before, after)| {
+                div()
+                    .child(format!("{}: ", line))
+                    .child(div().text_color(colors.danger).child(before.clone()))
+                    .child(" → ")
+                    .child(div().text_color(colors.success).child(after.clone()))
+                    .flex()
+            });
+            let dialog = dialog
+                .title("Fix Mojibake")
+                .w(px(520.0))
+                .child(
+                    v_flex()
+                        .gap_2()
+                        .child(status.clone())
+                        .child(
+                            div()
+                                .id("mojibake-preview")
+                                .max_h(px(360.0))
+                                .overflow_y_scroll()
+                                .font_family("monospace")
+                                .text_sm()
+                                .children(rows),
+                        ),
+                );
+
+            if !can_repair {
+                return dialog.alert();
+            }
+            let editor = editor.clone();
+            dialog
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Repair"))
+                .on_ok(move |_, window, cx| {
+                    // Repair the text as it is now; it may have changed under the dialog
+                    let _ = editor.update(cx, |ed, cx| {
+                        let len = ed.input_state.read(cx).text().len();
+                        ed.transform_range(0..len, |text| apply_repairs(text, &find_mojibake(text)), window, cx);
+                    });
+                    info!("Repaired double-encoded text");
+                    true
+                })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_repairs, decode_windows_1252, find_mojibake};
+
+    #[test]
+    fn test_repairs_latin1_mojibake() {
+        let text = "cafÃ© and naÃ¯ve";
+        let repairs = find_mojibake(text);
+        assert_eq!(repairs.len(), 2);
+        assert_eq!(apply_repairs(text, &repairs), "café and naïve");
+    }
+
+    #[test]
+    fn test_repairs_windows_1252_punctuation() {
+        let text = "itâ€™s â€œquotedâ€\u{9d}";
+        assert_eq!(apply_repairs(text, &find_mojibake(text)), "it’s “quoted”");
+    }
+
+    #[test]
+    fn test_decode_windows_1252() {
+        assert_eq!(decode_windows_1252(b"caf\xe9 \x93hi\x94 \x80\x81"), "café “hi” €\u{81}");
+    }
+
+    #[test]
+    fn test_leaves_correct_text_alone() {
+        assert!(find_mojibake("café, naïve, 日本語, plain ascii").is_empty());
+    }
+}
@@ -1,14 +1,67 @@
 //! PDF export functionality using krilla.
 
 use krilla::color::rgb;
-use krilla::geom::{Point, PathBuilder};
+use krilla::geom::{PathBuilder, Point, Rect, Transform};
 use krilla::num::NormalizedF32;
 use krilla::page::PageSettings;
-use krilla::paint::Fill;
+use krilla::paint::{Fill, Stroke};
+use krilla::surface::Surface;
 use krilla::text::{Font, TextDirection};
 use krilla::Document;
+use serde::{Deserialize, Serialize};
+use skrifa::instance::{LocationRef, Size};
+use skrifa::{FontRef, MetadataProvider};
+use std::collections::HashMap;
+use std::ops::Range;
 use std::path::Path;
-use tracing::info;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use super::alignment::ParagraphAlign;
+use super::markdown::{parse_blocks, parse_inline, Emphasis, MarkdownBlock};
+
+/// Paper size for exported pages.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+    Legal,
+}
+
+impl PageSize {
+    pub const ALL: [PageSize; 3] = [PageSize::A4, PageSize::Letter, PageSize::Legal];
+
+    /// Portrait width and height in points.
+    pub fn dimensions(self) -> (f32, f32) {
+        match self {
+            Self::A4 => (595.0, 842.0),
+            Self::Letter => (612.0, 792.0),
+            Self::Legal => (612.0, 1008.0),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::A4 => "A4",
+            Self::Letter => "Letter",
+            Self::Legal => "Legal",
+        }
+    }
+}
+
+/// Page orientation for exported pages.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+/// A color as RGB (0-255).
+pub type Rgb = (u8, u8, u8);
 
 /// PDF export configuration.
 pub struct PdfConfig {
@@ -16,12 +69,47 @@ pub struct PdfConfig {
     pub font_size: f32,
     /// Page margins in points.
     pub margin: f32,
-    /// Header text (filename + date).
+    /// Paper size.
+    pub page_size: PageSize,
+    /// Page orientation.
+    pub orientation: Orientation,
+    /// Whether to prefix each source line with its line number.
+    pub line_numbers: bool,
+    /// Alignment of each source line; missing entries are left-aligned.
+    pub line_alignment: Vec<ParagraphAlign>,
+    /// Header template, drawn at the top of each page.
     pub header: Option<String>,
+    /// Footer template, drawn at the bottom of each page.
+    pub footer: Option<String>,
+    /// File name, full path and date filled into the templates.
+    pub file_name: String,
+    pub file_path: String,
+    pub date: String,
+    /// 1-based number of the first exported line, for line numbers.
+    pub first_line: usize,
     /// Background color as RGB (0-255).
     pub background_rgb: (u8, u8, u8),
     /// Text color as RGB (0-255).
     pub text_rgb: (u8, u8, u8),
+    /// Colors for byte ranges of the content, sorted and non-overlapping;
+    /// everything else uses `text_rgb`.
+    pub token_colors: Vec<(Range<usize>, Rgb)>,
+    /// System font family to print with; `None` uses the built-in font.
+    pub font_family: Option<String>,
+    /// Lay the content out as Markdown (headings, emphasis, lists, code
+    /// blocks) instead of printing the markup.
+    pub render_markdown: bool,
+}
+
+impl PdfConfig {
+    /// Page width and height in points, honoring orientation.
+    pub fn page_dimensions(&self) -> (f32, f32) {
+        let (w, h) = self.page_size.dimensions();
+        match self.orientation {
+            Orientation::Portrait => (w, h),
+            Orientation::Landscape => (h, w),
+        }
+    }
 }
 
 impl Default for PdfConfig {
@@ -29,9 +117,21 @@ impl Default for PdfConfig {
         Self {
             font_size: 12.0,
             margin: 72.0, // 1 inch in points
+            page_size: PageSize::default(),
+            orientation: Orientation::default(),
+            line_numbers: false,
+            line_alignment: Vec::new(),
             header: None,
+            footer: None,
+            file_name: String::new(),
+            file_path: String::new(),
+            date: String::new(),
+            first_line: 1,
             background_rgb: (255, 255, 255), // white
             text_rgb: (0, 0, 0),             // black
+            token_colors: Vec::new(),
+            font_family: None,
+            render_markdown: false,
         }
     }
 }
@@ -40,30 +140,44 @@ impl Default for PdfConfig {
 // In production, you'd embed a TTF file
 const FONT_DATA: &[u8] = include_bytes!("../../assets/fonts/NotoSans-Regular.ttf");
 
+const LINE_HEIGHT_FACTOR: f32 = 1.4;
+const RESERVED_FOOTER_SPACE: f32 = 30.0;
+
 /// Exports text content to a PDF file.
 pub fn export_to_pdf(content: &str, path: &Path, config: &PdfConfig) -> anyhow::Result<()> {
-    // A4 dimensions in points (1 point = 1/72 inch)
-    const A4_WIDTH: f32 = 595.0;
-    const A4_HEIGHT: f32 = 842.0;
-    const LINE_HEIGHT_FACTOR: f32 = 1.4;
-    const RESERVED_FOOTER_SPACE: f32 = 30.0;
     const AVG_CHAR_WIDTH_FACTOR: f32 = 0.5;
     
     let mut document = Document::new();
     
-    // Load font
-    let font = Font::new(FONT_DATA.to_vec().into(), 0)
-        .ok_or_else(|| anyhow::anyhow!("Failed to load font"))?;
+    // Load fonts, with fallbacks for whatever the first one can't show
+    let covered = [content, config.header.as_deref().unwrap_or_default(), config.footer.as_deref().unwrap_or_default(), &config.file_name, &config.file_path].concat();
+    let fonts = FontChain::new(config.font_family.as_deref(), &covered)?;
     
-    let usable_width = A4_WIDTH - (2.0 * config.margin);
+    if config.render_markdown {
+        draw_markdown(&mut document, content, config, &fonts)?;
+        return write_document(document, path);
+    }
+    
+    // Page dimensions in points (1 point = 1/72 inch)
+    let (page_width, page_height) = config.page_dimensions();
+    let char_width = config.font_size * AVG_CHAR_WIDTH_FACTOR;
     let line_height = config.font_size * LINE_HEIGHT_FACTOR;
-    let lines_per_page = ((A4_HEIGHT - 2.0 * config.margin - RESERVED_FOOTER_SPACE) / line_height) as usize;
+    let lines_per_page = ((page_height - 2.0 * config.margin - RESERVED_FOOTER_SPACE) / line_height) as usize;
+    
+    // Reserve a gutter for line numbers (digits plus two characters of padding)
+    let number_digits = (content.lines().count().max(1) + config.first_line - 1).to_string().len();
+    let gutter_width = if config.line_numbers {
+        (number_digits + 2) as f32 * char_width
+    } else {
+        0.0
+    };
+    let usable_width = page_width - (2.0 * config.margin) - gutter_width;
     
     // Approximate characters per line
-    let chars_per_line = (usable_width / (config.font_size * AVG_CHAR_WIDTH_FACTOR)) as usize;
+    let chars_per_line = (usable_width / char_width) as usize;
     
     // Wrap text into lines
-    let wrapped_lines = wrap_text(content, chars_per_line);
+    let wrapped_lines = wrap_numbered(content, chars_per_line);
     // Calculate pages needed, ensuring at least 1 page even for empty content
     let total_pages = ((wrapped_lines.len() + lines_per_page - 1) / lines_per_page.max(1)).max(1);
     
@@ -75,61 +189,15 @@ pub fn export_to_pdf(content: &str, path: &Path, config: &PdfConfig) -> anyhow::
     );
     
     let mut line_idx = 0;
+    let mut source_line = 0;
     
     for page_num in 1..=total_pages {
         let mut page = document.start_page_with(
-            PageSettings::from_wh(A4_WIDTH, A4_HEIGHT)
+            PageSettings::from_wh(page_width, page_height)
                 .ok_or_else(|| anyhow::anyhow!("Invalid page dimensions"))?
         );
         let mut surface = page.surface();
-        
-        // Draw background if not white
-        if config.background_rgb != (255, 255, 255) {
-            let mut pb = PathBuilder::new();
-            pb.move_to(0.0, 0.0);
-            pb.line_to(A4_WIDTH, 0.0);
-            pb.line_to(A4_WIDTH, A4_HEIGHT);
-            pb.line_to(0.0, A4_HEIGHT);
-            pb.close();
-            let rect = pb.finish().unwrap();
-            
-            surface.set_fill(Some(Fill {
-                paint: rgb::Color::new(
-                    config.background_rgb.0,
-                    config.background_rgb.1,
-                    config.background_rgb.2,
-                ).into(),
-                opacity: NormalizedF32::ONE,
-                rule: Default::default(),
-            }));
-            surface.draw_path(&rect);
-        }
-        
-        // Set text color
-        surface.set_fill(Some(Fill {
-            paint: rgb::Color::new(
-                config.text_rgb.0,
-                config.text_rgb.1,
-                config.text_rgb.2,
-            ).into(),
-            opacity: NormalizedF32::ONE,
-            rule: Default::default(),
-        }));
-        
-        let mut y_pos = config.margin;
-        
-        // Draw header
-        if let Some(ref header) = config.header {
-            surface.draw_text(
-                Point::from_xy(config.margin, y_pos),
-                font.clone(),
-                config.font_size * 0.9,
-                &format!("{} - Page {} of {}", header, page_num, total_pages),
-                false,
-                TextDirection::Auto,
-            );
-            y_pos += line_height * 1.5;
-        }
+        let mut y_pos = decorate_page(&mut surface, config, &fonts, page_num, total_pages);
         
         // Draw content lines
         let start_line = (page_num - 1) * lines_per_page;
@@ -140,14 +208,40 @@ pub fn export_to_pdf(content: &str, path: &Path, config: &PdfConfig) -> anyhow::
                 break;
             }
             
-            surface.draw_text(
-                Point::from_xy(config.margin, y_pos),
-                font.clone(),
-                config.font_size,
-                &wrapped_lines[line_idx],
-                false,
-                TextDirection::Auto,
-            );
+            let line = &wrapped_lines[line_idx];
+            let (number, text) = (&line.number, &line.text);
+            if let Some(number) = number {
+                source_line = number - 1;
+            }
+            if let (true, Some(number)) = (config.line_numbers, number) {
+                let label = format!("{:>width$}", number + config.first_line - 1, width = number_digits);
+                fonts.draw(&mut surface, Point::from_xy(config.margin, y_pos), config.font_size, &label);
+            }
+            
+            let align = config.line_alignment.get(source_line).copied().unwrap_or_default();
+            let (shown, x_offset) = if align == ParagraphAlign::Left {
+                (0..text.len(), 0.0)
+            } else {
+                let trimmed = text.trim();
+                let start = text.len() - text.trim_start().len();
+                (start..start + trimmed.len(), align.offset(trimmed.chars().count() as f32 * char_width, usable_width))
+            };
+            let x = config.margin + gutter_width + x_offset;
+            if config.token_colors.is_empty() {
+                fonts.draw(&mut surface, Point::from_xy(x, y_pos), config.font_size, &text[shown]);
+            } else {
+                // Each run is drawn where the previous one ended
+                let mut x = x;
+                for (range, color) in color_runs(line, &config.token_colors, config.text_rgb) {
+                    let range = range.start.max(shown.start)..range.end.min(shown.end);
+                    if range.is_empty() {
+                        continue;
+                    }
+                    surface.set_fill(Some(solid_fill(color)));
+                    x += fonts.draw(&mut surface, Point::from_xy(x, y_pos), config.font_size, &text[range]);
+                }
+                surface.set_fill(Some(solid_fill(config.text_rgb)));
+            }
             
             y_pos += line_height;
             line_idx += 1;
@@ -157,7 +251,11 @@ pub fn export_to_pdf(content: &str, path: &Path, config: &PdfConfig) -> anyhow::
         page.finish();
     }
     
-    // Save to file
+    write_document(document, path)
+}
+
+/// Save the finished `document` to `path`.
+fn write_document(document: Document, path: &Path) -> anyhow::Result<()> {
     let pdf_data = document.finish()
         .map_err(|e| anyhow::anyhow!("Failed to generate PDF: {:?}", e))?;
     std::fs::write(path, &pdf_data)?;
@@ -166,61 +264,613 @@ pub fn export_to_pdf(content: &str, path: &Path, config: &PdfConfig) -> anyhow::
     Ok(())
 }
 
-/// Wraps text into lines of approximately the given width.
-/// Preserves leading whitespace (indentation) from the original lines.
-fn wrap_text(content: &str, max_chars: usize) -> Vec<String> {
-    let mut lines = Vec::new();
+/// Paint a page's background, header and footer, leaving the fill set to
+/// the text color. Returns where the content starts.
+fn decorate_page(surface: &mut Surface, config: &PdfConfig, fonts: &FontChain, page: usize, pages: usize) -> f32 {
+    let (page_width, page_height) = config.page_dimensions();
+    
+    // Draw background if not white
+    if config.background_rgb != (255, 255, 255) {
+        fill_rect(surface, (0.0, 0.0, page_width, page_height), config.background_rgb);
+    }
+    
+    // Set text color
+    surface.set_fill(Some(solid_fill(config.text_rgb)));
     
-    for paragraph in content.lines() {
-        if paragraph.is_empty() {
-            lines.push(String::new());
-            continue;
+    let fill = |template: &str| expand_template(template, config, page, pages);
+
+    // Draw header
+    if let Some(ref header) = config.header {
+        fonts.draw(surface, Point::from_xy(config.margin, config.margin), config.font_size * 0.9, &fill(header));
+    }
+
+    // Draw footer in the space reserved below the text
+    if let Some(ref footer) = config.footer {
+        fonts.draw(surface, Point::from_xy(config.margin, page_height - config.margin), config.font_size * 0.9, &fill(footer));
+    }
+    content_top(config)
+}
+
+/// Where content starts on each page, below the header if there is one.
+fn content_top(config: &PdfConfig) -> f32 {
+    match config.header {
+        Some(_) => config.margin + config.font_size * LINE_HEIGHT_FACTOR * 1.5,
+        None => config.margin,
+    }
+}
+
+/// Fill the rectangle `(x, y, width, height)` with `color`, leaving the
+/// fill set to it.
+fn fill_rect(surface: &mut Surface, (x, y, width, height): (f32, f32, f32, f32), color: Rgb) {
+    let Some(rect) = Rect::from_xywh(x, y, width, height) else { return };
+    let mut pb = PathBuilder::new();
+    pb.push_rect(rect);
+    let Some(path) = pb.finish() else { return };
+    surface.set_fill(Some(solid_fill(color)));
+    surface.draw_path(&path);
+}
+
+/// Font size of each heading level, relative to body text.
+const HEADING_SCALE: [f32; 6] = [2.0, 1.6, 1.35, 1.15, 1.0, 0.9];
+/// How far italic text leans, as a fraction of its height.
+const ITALIC_SLANT: f32 = 0.2;
+/// Outline thickening bold text, as a fraction of its font size.
+const BOLD_STROKE: f32 = 0.04;
+
+/// A piece of laid-out Markdown in one style.
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    text: String,
+    emphasis: Emphasis,
+    size: f32,
+}
+
+/// What is drawn around a line of laid-out Markdown.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum LineKind {
+    #[default]
+    Text,
+    /// Shaded, in the monospace font.
+    Code,
+    /// With a bar in the left margin.
+    Quote,
+    /// A horizontal line and nothing else.
+    Rule,
+}
+
+/// One printed line of a Markdown document.
+#[derive(Debug, Default, PartialEq)]
+struct MarkdownLine {
+    kind: LineKind,
+    /// Space above the line, separating it from the previous block.
+    gap: f32,
+    /// Distance of the text from the left margin.
+    indent: f32,
+    /// List marker hanging to the left of the text.
+    marker: Option<String>,
+    runs: Vec<Run>,
+    height: f32,
+}
+
+/// Break `runs` into lines no wider than `width`, at spaces where possible.
+/// `measure` gives the width of text in a run's style.
+fn wrap_runs(runs: &[Run], width: f32, measure: &impl Fn(&str, &Run) -> f32) -> Vec<Vec<Run>> {
+    let mut lines: Vec<Vec<Run>> = vec![Vec::new()];
+    let mut used = 0.0;
+    let place = |lines: &mut Vec<Vec<Run>>, text: &str, style: &Run, used: &mut f32| {
+        let line = lines.last_mut().expect("at least one line");
+        match line.last_mut() {
+            Some(last) if last.emphasis == style.emphasis && last.size == style.size => last.text.push_str(text),
+            _ => line.push(Run { text: text.to_string(), ..style.clone() }),
         }
-        
-        // Preserve leading whitespace (indentation)
-        let trimmed = paragraph.trim_start();
-        let indent = &paragraph[..paragraph.len() - trimmed.len()];
-        
-        let words: Vec<&str> = trimmed.split_whitespace().collect();
-        if words.is_empty() {
-            // Line with only whitespace - preserve as empty
-            lines.push(String::new());
-            continue;
+        *used += measure(text, style);
+    };
+    for run in runs {
+        for word in run.text.split_inclusive(' ') {
+            let visible = measure(word.trim_end(), run);
+            if used + visible > width && used > 0.0 {
+                lines.push(Vec::new());
+                used = 0.0;
+            }
+            if visible <= width {
+                place(&mut lines, word, run, &mut used);
+                continue;
+            }
+            // A word wider than a whole line is broken between characters
+            for (i, c) in word.char_indices() {
+                let piece = &word[i..i + c.len_utf8()];
+                if used + measure(piece, run) > width && used > 0.0 {
+                    lines.push(Vec::new());
+                    used = 0.0;
+                }
+                place(&mut lines, piece, run, &mut used);
+            }
         }
-        
-        let mut current_line = String::new();
-        let mut is_first_line = true;
-        
-        for word in words {
-            if current_line.is_empty() {
-                // Start new line with indent (only first line of paragraph gets original indent)
-                if is_first_line {
-                    current_line = format!("{}{}", indent, word);
-                } else {
-                    // Continuation lines get same indent for visual consistency
-                    current_line = format!("{}{}", indent, word);
+    }
+    lines
+}
+
+/// Lay Markdown `blocks` out into lines `width` points wide, with body text
+/// at `font_size`.
+fn layout_markdown(blocks: &[MarkdownBlock], font_size: f32, width: f32, measure: &impl Fn(&str, &Run) -> f32) -> Vec<MarkdownLine> {
+    let runs = |text: &str, size: f32, bold: bool| -> Vec<Run> {
+        parse_inline(text)
+            .into_iter()
+            .map(|(text, emphasis)| Run { text, emphasis: Emphasis { bold: bold || emphasis.bold, ..emphasis }, size })
+            .collect()
+    };
+    let mut lines = Vec::new();
+    let mut previous: Option<&MarkdownBlock> = None;
+    for block in blocks {
+        let gap = match (previous, block) {
+            (None, _) => 0.0,
+            (Some(MarkdownBlock::ListItem(..)), MarkdownBlock::ListItem(..)) => font_size * 0.2,
+            (_, MarkdownBlock::Heading(..)) => font_size,
+            _ => font_size * 0.6,
+        };
+        previous = Some(block);
+        let (kind, indent, marker, pieces) = match block {
+            MarkdownBlock::Heading(level, text) => {
+                let size = font_size * HEADING_SCALE[(*level).clamp(1, HEADING_SCALE.len()) - 1];
+                (LineKind::Text, 0.0, None, vec![runs(text, size, true)])
+            }
+            MarkdownBlock::Paragraph(text) => (LineKind::Text, 0.0, None, vec![runs(text, font_size, false)]),
+            MarkdownBlock::ListItem(depth, marker, text) => {
+                let indent = font_size * 1.5 * (depth + 1) as f32;
+                (LineKind::Text, indent, Some(marker.clone()), vec![runs(text, font_size, false)])
+            }
+            MarkdownBlock::Quote(text) => (LineKind::Quote, font_size * 1.2, None, vec![runs(text, font_size, false)]),
+            MarkdownBlock::Code(code) => {
+                let code = code
+                    .iter()
+                    .map(|line| {
+                        let emphasis = Emphasis { code: true, ..Emphasis::default() };
+                        vec![Run { text: line.replace('\t', "    "), emphasis, size: font_size }]
+                    })
+                    .collect();
+                (LineKind::Code, font_size * 0.6, None, code)
+            }
+            MarkdownBlock::Rule => (LineKind::Rule, 0.0, None, vec![Vec::new()]),
+        };
+        // Code is shaded edge to edge, so it's padded on both sides
+        let text_width = if kind == LineKind::Code { width - indent * 2.0 } else { width - indent };
+        let mut first = true;
+        for piece in pieces {
+            for runs in wrap_runs(&piece, text_width, measure) {
+                let size = runs.iter().map(|r| r.size).fold(font_size, f32::max);
+                lines.push(MarkdownLine {
+                    kind,
+                    gap: if first { gap } else { 0.0 },
+                    indent,
+                    marker: marker.clone().filter(|_| first),
+                    runs,
+                    height: size * LINE_HEIGHT_FACTOR,
+                });
+                first = false;
+            }
+        }
+    }
+    lines
+}
+
+/// Split laid-out lines into pages whose content runs from `top` to
+/// `bottom`; a block gap at the top of a page is dropped.
+fn paginate(lines: &[MarkdownLine], top: f32, bottom: f32) -> Vec<Range<usize>> {
+    let mut pages = Vec::new();
+    let mut start = 0;
+    let mut y = top;
+    for (i, line) in lines.iter().enumerate() {
+        let needed = if i == start { line.height } else { line.gap + line.height };
+        if y + needed > bottom && i > start {
+            pages.push(start..i);
+            start = i;
+            y = top + line.height;
+        } else {
+            y += needed;
+        }
+    }
+    pages.push(start..lines.len());
+    pages
+}
+
+/// `a` moved `amount` (0-1) of the way towards `b`.
+fn mix(a: Rgb, b: Rgb, amount: f32) -> Rgb {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+    (channel(a.0, b.0), channel(a.1, b.1), channel(a.2, b.2))
+}
+
+/// Draw `run` with its baseline starting at `start`, faking bold with an
+/// outline and italic with a slant. Returns its width.
+fn draw_run(surface: &mut Surface, fonts: &FontChain, start: Point, run: &Run, color: Rgb) -> f32 {
+    if run.emphasis.bold {
+        let paint = rgb::Color::new(color.0, color.1, color.2).into();
+        surface.set_stroke(Some(Stroke { paint, width: run.size * BOLD_STROKE, ..Default::default() }));
+    }
+    if run.emphasis.italic {
+        // Lean around the baseline so the text stays in place
+        surface.push_transform(&Transform::from_row(1.0, 0.0, -ITALIC_SLANT, 1.0, ITALIC_SLANT * start.y, 0.0));
+    }
+    let width = fonts.draw(surface, start, run.size, &run.text);
+    if run.emphasis.italic {
+        surface.pop();
+    }
+    if run.emphasis.bold {
+        surface.set_stroke(None);
+    }
+    width
+}
+
+/// Add `content` to `document` laid out as Markdown.
+fn draw_markdown(document: &mut Document, content: &str, config: &PdfConfig, fonts: &FontChain) -> anyhow::Result<()> {
+    let blocks = parse_blocks(content);
+    let has_code = content.contains('`') || blocks.iter().any(|b| matches!(b, MarkdownBlock::Code(_)));
+    let monospace = if has_code { Some(FontChain::monospace(content)?) } else { None };
+    let font_for = |run: &Run| monospace.as_ref().filter(|_| run.emphasis.code).unwrap_or(fonts);
+
+    let (page_width, page_height) = config.page_dimensions();
+    let usable_width = page_width - 2.0 * config.margin;
+    let measure = |text: &str, run: &Run| font_for(run).width(text, run.size);
+    let lines = layout_markdown(&blocks, config.font_size, usable_width, &measure);
+    let top = content_top(config);
+    let pages = paginate(&lines, top, page_height - config.margin - RESERVED_FOOTER_SPACE);
+    info!(blocks = blocks.len(), lines = lines.len(), pages = pages.len(), "Exporting Markdown to PDF");
+
+    let shade = mix(config.background_rgb, config.text_rgb, 0.08);
+    let accent = mix(config.background_rgb, config.text_rgb, 0.35);
+    for (page_num, range) in pages.iter().enumerate() {
+        let mut page = document.start_page_with(
+            PageSettings::from_wh(page_width, page_height)
+                .ok_or_else(|| anyhow::anyhow!("Invalid page dimensions"))?
+        );
+        let mut surface = page.surface();
+        let mut y = decorate_page(&mut surface, config, fonts, page_num + 1, pages.len());
+        for (i, line) in lines[range.clone()].iter().enumerate() {
+            if i > 0 {
+                y += line.gap;
+            }
+            let bar = match line.kind {
+                LineKind::Text => None,
+                LineKind::Code => Some(((config.margin, y, usable_width, line.height), shade)),
+                LineKind::Quote => Some(((config.margin + config.font_size * 0.3, y, 2.0, line.height), accent)),
+                LineKind::Rule => Some(((config.margin, y + line.height / 2.0, usable_width, 0.8), accent)),
+            };
+            if let Some((rect, color)) = bar {
+                fill_rect(&mut surface, rect, color);
+                surface.set_fill(Some(solid_fill(config.text_rgb)));
+            }
+            let baseline = y + line.height / LINE_HEIGHT_FACTOR;
+            if let Some(marker) = &line.marker {
+                let x = config.margin + line.indent - config.font_size * 1.2;
+                fonts.draw(&mut surface, Point::from_xy(x, baseline), config.font_size, marker);
+            }
+            let mut x = config.margin + line.indent;
+            for run in &line.runs {
+                x += draw_run(&mut surface, font_for(run), Point::from_xy(x, baseline), run, config.text_rgb);
+            }
+            y += line.height;
+        }
+        surface.finish();
+        page.finish();
+    }
+    Ok(())
+}
+
+/// Fill a header or footer template: `{filename}` (or `{file}`), `{path}`,
+/// `{date}`, `{page}` and `{pages}` are replaced; anything else is kept as
+/// written.
+pub fn expand_template(template: &str, config: &PdfConfig, page: usize, pages: usize) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open..];
+        let close = after.find('}').map(|i| i + 1).unwrap_or(after.len());
+        match &after[..close] {
+            "{filename}" | "{file}" => out.push_str(&config.file_name),
+            "{path}" => out.push_str(&config.file_path),
+            "{date}" => out.push_str(&config.date),
+            "{page}" => out.push_str(&page.to_string()),
+            "{pages}" => out.push_str(&pages.to_string()),
+            other => out.push_str(other),
+        }
+        rest = &after[close..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Solid paint in `color`.
+fn solid_fill(color: Rgb) -> Fill {
+    Fill {
+        paint: rgb::Color::new(color.0, color.1, color.2).into(),
+        opacity: NormalizedF32::ONE,
+        rule: Default::default(),
+    }
+}
+
+/// Families tried, in order, for characters the chosen font lacks.
+const FALLBACK_FAMILIES: &[&str] = &[
+    "Noto Sans CJK SC",
+    "Noto Sans CJK JP",
+    "Noto Sans CJK KR",
+    "Microsoft YaHei",
+    "Yu Gothic",
+    "Malgun Gothic",
+    "PingFang SC",
+    "Hiragino Sans",
+    "Apple SD Gothic Neo",
+    "Noto Color Emoji",
+    "Segoe UI Emoji",
+    "Apple Color Emoji",
+    "Segoe UI Symbol",
+    "DejaVu Sans",
+];
+
+/// One font of a [`FontChain`].
+struct ChainFont {
+    font: Font,
+    data: Arc<Vec<u8>>,
+    index: u32,
+}
+
+impl ChainFont {
+    fn new(data: Arc<Vec<u8>>, index: u32) -> Option<Self> {
+        FontRef::from_index(&data, index).ok()?;
+        let font = Font::new(data.clone().into(), index)?;
+        Some(Self { font, data, index })
+    }
+
+    fn metrics(&self) -> FontRef<'_> {
+        // Checked to parse in `new`
+        FontRef::from_index(&self.data, self.index).expect("font parsed before")
+    }
+
+    /// Width of `text` in points, from the font's glyph advances.
+    fn width(&self, text: &str, font_size: f32) -> f32 {
+        let metrics = self.metrics();
+        let charmap = metrics.charmap();
+        let advances = metrics.glyph_metrics(Size::new(font_size), LocationRef::default());
+        text.chars().filter_map(|c| advances.advance_width(charmap.map(c)?)).sum()
+    }
+}
+
+/// The fonts text is drawn with: the chosen font, then the built-in one,
+/// then system fonts for characters neither has (CJK, emoji and so on).
+struct FontChain {
+    fonts: Vec<ChainFont>,
+    /// Index into `fonts` of the first font with each character; absent
+    /// characters use the previous character's font.
+    chosen: HashMap<char, usize>,
+}
+
+impl FontChain {
+    /// Load the chain for `family`, finding fonts for every character of
+    /// `text`. System fonts are only read when something needs them.
+    fn new(family: Option<&str>, text: &str) -> anyhow::Result<Self> {
+        Self::load(family.map(fontdb::Family::Name), text)
+    }
+
+    /// The chain led by the system's monospace font, for code.
+    fn monospace(text: &str) -> anyhow::Result<Self> {
+        Self::load(Some(fontdb::Family::Monospace), text)
+    }
+
+    fn load(family: Option<fontdb::Family<'_>>, text: &str) -> anyhow::Result<Self> {
+        let built_in = ChainFont::new(Arc::new(FONT_DATA.to_vec()), 0)
+            .ok_or_else(|| anyhow::anyhow!("Failed to load font"))?;
+        let mut chain = Self { fonts: vec![built_in], chosen: HashMap::new() };
+        let mut missing = chain.resolve(text);
+        if family.is_none() && missing.is_empty() {
+            return Ok(chain);
+        }
+
+        let mut db = fontdb::Database::new();
+        db.load_system_fonts();
+        let load = |id: fontdb::ID| db.with_face_data(id, |data, index| ChainFont::new(Arc::new(data.to_vec()), index)).flatten();
+        let query = |family: fontdb::Family<'_>| db.query(&fontdb::Query { families: &[family], ..Default::default() });
+
+        if let Some(family) = family {
+            match query(family).and_then(load) {
+                Some(font) => {
+                    chain.fonts.insert(0, font);
+                    chain.chosen.clear();
+                    missing = chain.resolve(text);
                 }
-            } else if current_line.len() + 1 + word.len() <= max_chars {
-                current_line.push(' ');
-                current_line.push_str(word);
-            } else {
-                lines.push(current_line);
-                is_first_line = false;
-                current_line = format!("{}{}", indent, word);
+                None => warn!(?family, "Font not found; using the built-in font"),
             }
         }
-        
-        if !current_line.is_empty() {
-            lines.push(current_line);
+        // Well-known fallbacks first, then any installed font with the glyph
+        let named = FALLBACK_FAMILIES.iter().filter_map(|name| query(fontdb::Family::Name(name)));
+        let mut tried = Vec::new();
+        for id in named.chain(db.faces().map(|face| face.id)) {
+            if missing.is_empty() {
+                break;
+            }
+            if tried.contains(&id) {
+                continue;
+            }
+            tried.push(id);
+            let Some(font) = load(id) else { continue };
+            let charmap = font.metrics().charmap();
+            if missing.iter().any(|&c| charmap.map(c).is_some()) {
+                debug!(family = ?db.face(id).and_then(|f| f.families.first()).map(|f| &f.0), "Adding fallback font");
+                chain.fonts.push(font);
+                missing = chain.resolve(text);
+            }
+        }
+        if !missing.is_empty() {
+            warn!(count = missing.len(), "No installed font has some characters");
         }
+        Ok(chain)
+    }
+
+    /// Assign each character of `text` its first font with a glyph,
+    /// returning the characters no font has.
+    fn resolve(&mut self, text: &str) -> Vec<char> {
+        let mut missing = Vec::new();
+        for c in text.chars() {
+            if c.is_whitespace() || c.is_control() || self.chosen.contains_key(&c) || missing.contains(&c) {
+                continue;
+            }
+            match self.fonts.iter().position(|font| font.metrics().charmap().map(c).is_some()) {
+                Some(index) => {
+                    self.chosen.insert(c, index);
+                }
+                None => missing.push(c),
+            }
+        }
+        missing
+    }
+
+    /// Split `text` into byte ranges drawn with one font each.
+    fn segments(&self, text: &str) -> Vec<(Range<usize>, usize)> {
+        let mut segments: Vec<(Range<usize>, usize)> = Vec::new();
+        for (i, c) in text.char_indices() {
+            let end = i + c.len_utf8();
+            let font = self.chosen.get(&c).copied();
+            match segments.last_mut() {
+                Some((range, last)) if font.is_none_or(|f| f == *last) => range.end = end,
+                _ => segments.push((i..end, font.unwrap_or(0))),
+            }
+        }
+        segments
+    }
+
+    /// Width of `text` in points.
+    fn width(&self, text: &str, font_size: f32) -> f32 {
+        self.segments(text).into_iter().map(|(range, index)| self.fonts[index].width(&text[range], font_size)).sum()
+    }
+
+    /// Draw `text` starting at `start`, returning its width.
+    fn draw(&self, surface: &mut Surface, start: Point, font_size: f32, text: &str) -> f32 {
+        let mut x = start.x;
+        for (range, index) in self.segments(text) {
+            let font = &self.fonts[index];
+            let piece = &text[range];
+            surface.draw_text(Point::from_xy(x, start.y), font.font.clone(), font_size, piece, false, TextDirection::Auto);
+            x += font.width(piece, font_size);
+        }
+        x - start.x
+    }
+}
+
+/// One printed line after wrapping.
+#[derive(Debug, Default, PartialEq)]
+struct WrappedLine {
+    /// 1-based source line number, on the first wrapped line of each.
+    number: Option<usize>,
+    text: String,
+    /// Where each copied piece of `text` starts, and the byte range of the
+    /// content it came from. Bytes outside every piece are added spaces.
+    pieces: Vec<(usize, Range<usize>)>,
+}
+
+impl WrappedLine {
+    /// Append `source` (the content at `offset`).
+    fn push(&mut self, source: &str, offset: usize) {
+        self.pieces.push((self.text.len(), offset..offset + source.len()));
+        self.text.push_str(source);
+    }
+}
+
+/// Wraps text like [`wrap_text`], tagging the first wrapped line of each
+/// source line with its 1-based line number.
+fn wrap_numbered(content: &str, max_chars: usize) -> Vec<WrappedLine> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    // Same lines as `str::lines`, keeping track of where each starts
+    for (i, raw) in content.split_inclusive('\n').enumerate() {
+        let line = raw.strip_suffix('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).unwrap_or(raw);
+        for (j, mut wrapped) in wrap_paragraph(line, offset, max_chars).into_iter().enumerate() {
+            wrapped.number = (j == 0).then_some(i + 1);
+            lines.push(wrapped);
+        }
+        offset += raw.len();
     }
-    
     lines
 }
 
+/// Wraps text into lines of approximately the given width.
+/// Preserves leading whitespace (indentation) from the original lines.
+#[cfg(test)]
+fn wrap_text(content: &str, max_chars: usize) -> Vec<String> {
+    content.lines().flat_map(|paragraph| wrap_paragraph(paragraph, 0, max_chars)).map(|line| line.text).collect()
+}
+
+/// Wrap one line found at `offset` in the content. Words are joined by
+/// single spaces and every wrapped line repeats the indentation.
+fn wrap_paragraph(paragraph: &str, offset: usize, max_chars: usize) -> Vec<WrappedLine> {
+    let trimmed = paragraph.trim_start();
+    let indent = &paragraph[..paragraph.len() - trimmed.len()];
+    // Empty or whitespace-only lines print as empty
+    if trimmed.is_empty() {
+        return vec![WrappedLine::default()];
+    }
+
+    let start_line = |word: &str, word_offset: usize| {
+        let mut line = WrappedLine::default();
+        if !indent.is_empty() {
+            line.push(indent, offset);
+        }
+        line.push(word, word_offset);
+        line
+    };
+
+    let mut lines = Vec::new();
+    let mut current: Option<WrappedLine> = None;
+    for word in trimmed.split_whitespace() {
+        let word_offset = offset + (word.as_ptr() as usize - paragraph.as_ptr() as usize);
+        match current.as_mut() {
+            Some(line) if line.text.len() + 1 + word.len() <= max_chars => {
+                line.text.push(' ');
+                line.push(word, word_offset);
+            }
+            _ => lines.extend(current.replace(start_line(word, word_offset))),
+        }
+    }
+    lines.extend(current);
+    lines
+}
+
+/// Split `line` into runs of one color. Copied bytes take their color from
+/// `token_colors`; added spaces and uncolored bytes use `default`.
+fn color_runs(line: &WrappedLine, token_colors: &[(Range<usize>, Rgb)], default: Rgb) -> Vec<(Range<usize>, Rgb)> {
+    let mut colors = vec![default; line.text.len()];
+    for (at, source) in &line.pieces {
+        for (i, offset) in source.clone().enumerate() {
+            let index = token_colors.partition_point(|(range, _)| range.end <= offset);
+            if let Some((_, color)) = token_colors.get(index).filter(|(range, _)| range.contains(&offset)) {
+                colors[at + i] = *color;
+            }
+        }
+    }
+    let mut runs: Vec<(Range<usize>, Rgb)> = Vec::new();
+    for (i, color) in colors.into_iter().enumerate() {
+        match runs.last_mut() {
+            // Never split a character
+            Some((range, last)) if *last == color || !line.text.is_char_boundary(i) => range.end = i + 1,
+            _ => runs.push((i..i + 1, color)),
+        }
+    }
+    runs
+}
+
 #[cfg(test)]
 mod tests {
-    use super::wrap_text;
+    use super::{
+        color_runs, expand_template, export_to_pdf, layout_markdown, paginate, wrap_numbered, wrap_runs, wrap_text, Emphasis,
+        FontChain, LineKind, MarkdownLine, Orientation, PageSize, PdfConfig, Run,
+    };
+    use crate::editor::markdown::parse_blocks;
+
+    /// Half an em per character, like a monospace font.
+    fn measure(text: &str, run: &Run) -> f32 {
+        text.chars().count() as f32 * run.size * 0.5
+    }
+
+    fn plain(text: &str) -> Run {
+        Run { text: text.to_string(), emphasis: Emphasis::default(), size: 10.0 }
+    }
 
     #[test]
     fn test_wrap_preserves_indentation() {
@@ -252,4 +902,105 @@ mod tests {
         let result = wrap_text(input, 80);
         assert_eq!(result, vec!["line1", "", "line2"]);
     }
+
+    #[test]
+    fn test_wrap_numbered_tags_first_wrapped_line() {
+        let input = "a b c d\n\nshort";
+        let result = wrap_numbered(input, 3);
+        let numbered: Vec<_> = result.iter().map(|line| (line.number, line.text.as_str())).collect();
+        assert_eq!(numbered, [(Some(1), "a b"), (None, "c d"), (Some(2), ""), (Some(3), "short")]);
+        // Pieces point back into the content
+        assert_eq!(result[1].pieces, [(0, 4..5), (2, 6..7)]);
+        assert_eq!(result[3].pieces, [(0, 9..14)]);
+    }
+
+    #[test]
+    fn test_color_runs_follow_source_ranges() {
+        let lines = wrap_numbered("let  x = 1;", 80);
+        let red = (255, 0, 0);
+        let runs = color_runs(&lines[0], &[(0..3, red), (10..11, red)], (0, 0, 0));
+        // Collapsed whitespace shifts the colors with the words
+        assert_eq!(lines[0].text, "let x = 1;");
+        assert_eq!(runs, [(0..3, red), (3..9, (0, 0, 0)), (9..10, red)]);
+    }
+
+    #[test]
+    fn test_font_chain_reports_missing_characters() {
+        // Plain Latin text needs nothing beyond the built-in font
+        let mut chain = FontChain::new(None, "Hello, world").unwrap();
+        assert_eq!(chain.fonts.len(), 1);
+        assert_eq!(chain.resolve("a\u{4e2d}b \u{4e2d}"), ['\u{4e2d}']);
+        assert_eq!(chain.segments("ab cd"), [(0..5, 0)]);
+    }
+
+    #[test]
+    fn test_expand_template() {
+        let config = PdfConfig {
+            file_name: "notes.txt".to_string(),
+            file_path: "/home/me/notes.txt".to_string(),
+            date: "2024-05-01".to_string(),
+            ..Default::default()
+        };
+        let expand = |template| expand_template(template, &config, 2, 7);
+        assert_eq!(expand("{filename} - {date} - Page {page} of {pages}"), "notes.txt - 2024-05-01 - Page 2 of 7");
+        assert_eq!(expand("{file} ({path})"), "notes.txt (/home/me/notes.txt)");
+        assert_eq!(expand("{unknown} {page"), "{unknown} {page");
+        assert_eq!(expand(""), "");
+    }
+
+    #[test]
+    fn test_landscape_swaps_dimensions() {
+        let config = PdfConfig {
+            page_size: PageSize::Letter,
+            orientation: Orientation::Landscape,
+            ..Default::default()
+        };
+        assert_eq!(config.page_dimensions(), (792.0, 612.0));
+    }
+
+    #[test]
+    fn test_wrap_runs_breaks_at_spaces_and_inside_long_words() {
+        let bold = Run { emphasis: Emphasis { bold: true, ..Emphasis::default() }, ..plain("bold ") };
+        let lines = wrap_runs(&[plain("one two "), bold, plain("abcdefghijkl")], 50.0, &measure);
+        let texts: Vec<Vec<&str>> = lines.iter().map(|line| line.iter().map(|r| r.text.as_str()).collect()).collect();
+        assert_eq!(texts, [vec!["one two "], vec!["bold "], vec!["abcdefghij"], vec!["kl"]]);
+        assert!(lines[1][0].emphasis.bold);
+    }
+
+    #[test]
+    fn test_layout_markdown_sizes_headings_and_hangs_markers() {
+        let blocks = parse_blocks("# Title\n\n- first item that wraps\n- second\n\n```\ncode\n```");
+        let lines = layout_markdown(&blocks, 10.0, 100.0, &measure);
+        let title = &lines[0];
+        assert_eq!((title.gap, title.runs[0].size), (0.0, 20.0));
+        assert!(title.runs[0].emphasis.bold);
+        // The item wraps under its own text with the marker on the first line only
+        let items: Vec<&MarkdownLine> = lines.iter().filter(|l| l.indent == 15.0).collect();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].marker.as_deref(), Some("\u{2022}"));
+        assert_eq!(items[1].marker, None);
+        assert_eq!(items[2].gap, 2.0);
+        let code = lines.last().unwrap();
+        assert_eq!(code.kind, LineKind::Code);
+        assert!(code.runs[0].emphasis.code);
+    }
+
+    #[test]
+    fn test_paginate_fills_pages_and_drops_leading_gaps() {
+        let line = |gap: f32| MarkdownLine { gap, height: 10.0, ..MarkdownLine::default() };
+        let lines = [line(0.0), line(5.0), line(5.0), line(5.0)];
+        assert_eq!(paginate(&lines, 0.0, 30.0), [0..2, 2..4]);
+        // An empty document still gets its one, blank page
+        let empty = paginate(&[], 0.0, 30.0);
+        assert!(empty.len() == 1 && empty[0].is_empty());
+    }
+
+    #[test]
+    fn test_export_renders_markdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.pdf");
+        let config = PdfConfig { render_markdown: true, ..PdfConfig::default() };
+        export_to_pdf("# Notes\n\nSome **bold** and *italic* text.\n\n1. one\n2. two\n\n> quoted\n\n---\n", &path, &config).unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(b"%PDF"));
+    }
 }
@@ -0,0 +1,66 @@
+//! Adding and removing "> " quote prefixes.
+
+use gpui::*;
+
+use super::TextEditor;
+
+/// Quote every line one level deeper.
+pub fn add_quote(text: &str) -> String {
+    text.split('\n')
+        .map(|line| match line {
+            "" => ">".to_string(),
+            // Nested quotes stack as ">>" rather than "> >"
+            _ if line.starts_with('>') => format!(">{}", line),
+            _ => format!("> {}", line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Remove one level of quoting from every quoted line.
+pub fn remove_quote(text: &str) -> String {
+    text.split('\n')
+        .map(|line| match line.strip_prefix('>') {
+            Some(rest) => rest.strip_prefix(' ').unwrap_or(rest),
+            None => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl TextEditor {
+    /// Quote the selected lines (or the whole document).
+    pub fn add_quote_prefix(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let range = self.selected_lines_range(window, cx);
+        self.transform_range(range, add_quote, window, cx);
+    }
+
+    /// Unquote the selected lines (or the whole document) by one level.
+    pub fn remove_quote_prefix(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let range = self.selected_lines_range(window, cx);
+        self.transform_range(range, remove_quote, window, cx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_quote, remove_quote};
+
+    #[test]
+    fn test_add_quote() {
+        assert_eq!(add_quote("hello\n\nworld"), "> hello\n>\n> world");
+        assert_eq!(add_quote("> already quoted"), ">> already quoted");
+    }
+
+    #[test]
+    fn test_remove_quote() {
+        assert_eq!(remove_quote("> hello\n>\nplain"), "hello\n\nplain");
+        assert_eq!(remove_quote(">> nested\n> > spaced"), "> nested\n> spaced");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "one\n> two\n\nthree";
+        assert_eq!(remove_quote(&add_quote(text)), text);
+    }
+}
@@ -0,0 +1,233 @@
+//! Markdown reference-style links and footnotes: inserting numbered links
+//! and renumbering or dropping their definitions.
+
+use gpui::*;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::input::{Input, InputState};
+use gpui_component::{h_flex, RopeExt, WindowExt};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use super::TextEditor;
+
+/// `[label]: destination` (or `[^label]: note` for footnotes).
+static DEFINITION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^ {0,3}\[(\^?)([^\]]+)\]:(.*)$").unwrap());
+/// Any bracketed label; a definition counts as used if its label appears.
+static BRACKETED: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[([^\[\]]+)\]").unwrap());
+/// Numbered uses: `][3]` for links, `[^3]` for footnotes.
+static NUMBERED_USE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\]\[|\[\^)(\d+)\]").unwrap());
+
+struct Definition<'a> {
+    footnote: bool,
+    label: &'a str,
+    rest: &'a str,
+}
+
+fn definition(line: &str) -> Option<Definition<'_>> {
+    let captures = DEFINITION.captures(line)?;
+    Some(Definition {
+        footnote: !captures[1].is_empty(),
+        label: captures.get(2)?.as_str(),
+        rest: captures.get(3)?.as_str(),
+    })
+}
+
+/// One past the highest numbered link definition.
+fn next_link_number(text: &str) -> usize {
+    text.lines()
+        .filter_map(definition)
+        .filter(|d| !d.footnote)
+        .filter_map(|d| d.label.parse::<usize>().ok())
+        .max()
+        .map_or(1, |n| n + 1)
+}
+
+/// Turn `selection` of `text` into `[selection][n]` and define `[n]: url`
+/// at the end. Returns the new text and the offset just past the link.
+pub fn add_reference_link(text: &str, selection: std::ops::Range<usize>, url: &str) -> (String, usize) {
+    let number = next_link_number(text);
+    let label = match &text[selection.clone()] {
+        "" => "link",
+        selected => selected,
+    };
+    let link = format!("[{}][{}]", label, number);
+    let mut out = format!("{}{}{}", &text[..selection.start], link, &text[selection.end..]);
+    let caret = selection.start + link.len();
+
+    // Join an existing block of numbered definitions, else start one
+    let trimmed = out.trim_end().len();
+    out.truncate(trimmed);
+    let last_is_definition = out.lines().last().and_then(definition).is_some_and(|d| d.label.parse::<usize>().is_ok());
+    let separator = match (out.is_empty(), last_is_definition) {
+        (true, _) => "",
+        (false, true) => "\n",
+        (false, false) => "\n\n",
+    };
+    out.push_str(&format!("{}[{}]: {}\n", separator, number, url.trim()));
+    (out, caret)
+}
+
+/// Drop unused definitions and renumber numbered links and footnotes in
+/// order of first use, moving their definitions to the end.
+pub fn renumber_references(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let body = lines.iter().filter(|l| definition(l).is_none());
+
+    let used: HashSet<String> = body.clone()
+        .flat_map(|line| BRACKETED.captures_iter(line).map(|c| c[1].to_lowercase()))
+        .collect();
+    let mut links: HashMap<String, usize> = HashMap::new();
+    let mut footnotes: HashMap<String, usize> = HashMap::new();
+    for captures in body.flat_map(|line| NUMBERED_USE.captures_iter(line)) {
+        let numbers = if &captures[1] == "[^" { &mut footnotes } else { &mut links };
+        let next = numbers.len() + 1;
+        numbers.entry(captures[2].to_string()).or_insert(next);
+    }
+
+    let mut out: Vec<String> = Vec::new();
+    let mut moved: Vec<(bool, usize, String)> = Vec::new();
+    for line in &lines {
+        let Some(def) = definition(line) else {
+            out.push(NUMBERED_USE.replace_all(line, |c: &regex::Captures| {
+                let numbers = if &c[1] == "[^" { &footnotes } else { &links };
+                let number = numbers.get(&c[2]).map_or(c[2].to_string(), usize::to_string);
+                format!("{}{}]", &c[1], number)
+            }).into_owned());
+            continue;
+        };
+        let key = if def.footnote { format!("^{}", def.label) } else { def.label.to_string() };
+        if !used.contains(&key.to_lowercase()) {
+            continue;
+        }
+        let numbers = if def.footnote { &footnotes } else { &links };
+        match numbers.get(def.label) {
+            Some(&number) => {
+                let caret = if def.footnote { "^" } else { "" };
+                moved.push((def.footnote, number, format!("[{}{}]:{}", caret, number, def.rest)));
+            }
+            None => out.push(line.to_string()),
+        }
+    }
+
+    while out.last().is_some_and(|l| l.trim().is_empty()) {
+        out.pop();
+    }
+    moved.sort();
+    for footnote in [false, true] {
+        let block: Vec<String> = moved.iter().filter(|m| m.0 == footnote).map(|m| m.2.clone()).collect();
+        if !block.is_empty() {
+            if !out.is_empty() {
+                out.push(String::new());
+            }
+            out.extend(block);
+        }
+    }
+    let mut result = out.join("\n");
+    if text.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+impl TextEditor {
+    /// Ask for a URL, then link the selection to it as a numbered reference.
+    pub fn insert_reference_link_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only || !self.markdown_assist {
+            return;
+        }
+        let selection = self.selection_range(window, cx).unwrap_or_else(|| {
+            let cursor = self.input_state.read(cx).cursor();
+            cursor..cursor
+        });
+        let url_input = cx.new(|cx| InputState::new(window, cx).placeholder("https://"));
+        let editor = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let url_input_for_ok = url_input.clone();
+            let editor = editor.clone();
+            let selection = selection.clone();
+            dialog
+                .title("Insert Reference Link")
+                .w(px(420.0))
+                .child(
+                    h_flex()
+                        .gap_3()
+                        .items_center()
+                        .child("URL")
+                        .child(div().flex_grow().child(Input::new(&url_input))),
+                )
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Insert"))
+                .on_ok(move |_, window, cx| {
+                    let url = url_input_for_ok.read(cx).value().to_string();
+                    if url.trim().is_empty() {
+                        return false;
+                    }
+                    let selection = selection.clone();
+                    let _ = editor.update(cx, |ed, cx| ed.insert_reference_link(selection, &url, window, cx));
+                    true
+                })
+        });
+    }
+
+    fn insert_reference_link(&mut self, selection: std::ops::Range<usize>, url: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let text = self.input_state.read(cx).value().to_string();
+        let (replaced, caret) = add_reference_link(&text, selection, url);
+        self.transform_range(0..text.len(), |_| replaced, window, cx);
+        self.input_state.update(cx, |state, cx| {
+            let position = state.text().offset_to_position(caret);
+            state.set_cursor_position(position, window, cx);
+        });
+    }
+
+    /// Renumber references in order of use and drop unused definitions.
+    pub fn renumber_references(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.markdown_assist {
+            return;
+        }
+        let len = self.input_state.read(cx).text().len();
+        self.transform_range(0..len, renumber_references, window, cx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_reference_link, renumber_references};
+
+    #[test]
+    fn test_add_reference_link() {
+        let (text, caret) = add_reference_link("See docs here.\n", 4..8, "https://a.example");
+        assert_eq!(text, "See [docs][1] here.\n\n[1]: https://a.example\n");
+        assert_eq!(caret, 13);
+
+        let (text, _) = add_reference_link(&text, 0..0, "https://b.example");
+        assert_eq!(text, "[link][2]See [docs][1] here.\n\n[1]: https://a.example\n[2]: https://b.example\n");
+    }
+
+    #[test]
+    fn test_renumber_references() {
+        let text = "\
+Read [b][7] then [a][3] and [b][7] again.[^4]
+Named [site] stays.
+
+[3]: https://a
+[site]: https://site
+[7]: https://b
+[9]: https://unused
+[unused]: https://gone
+[^4]: A note.
+";
+        assert_eq!(renumber_references(text), "\
+Read [b][1] then [a][2] and [b][1] again.[^1]
+Named [site] stays.
+
+[site]: https://site
+
+[1]: https://b
+[2]: https://a
+
+[^1]: A note.
+");
+    }
+}
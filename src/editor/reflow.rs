@@ -0,0 +1,228 @@
+//! Joining hard-wrapped paragraphs and hard-wrapping long lines.
+
+use gpui::*;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::input::{Input, InputState};
+use gpui_component::notification::Notification;
+use gpui_component::{h_flex, WindowExt};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+use super::{LineEnding, TextEditor};
+use crate::workspace::write_atomic;
+
+const DEFAULT_WRAP_COLUMN: usize = 72;
+
+/// Merge the lines of each blank-line-separated paragraph into one line,
+/// with `line_ending` between the lines that are left.
+pub fn join_paragraphs(text: &str, line_ending: LineEnding) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut paragraph: Option<String> = None;
+    // On plain LF a CR isn't taken for trailing whitespace
+    for line in LineEnding::Lf.normalize(text).split('\n') {
+        if line.trim().is_empty() {
+            out.extend(paragraph.take());
+            out.push(line.to_string());
+            continue;
+        }
+        paragraph = Some(match paragraph {
+            Some(joined) => format!("{} {}", joined.trim_end(), line.trim()),
+            None => line.trim_end().to_string(),
+        });
+    }
+    out.extend(paragraph);
+    line_ending.normalize(&out.join("\n"))
+}
+
+/// Break lines longer than `column` at spaces, keeping each line's
+/// indentation, with `line_ending` between lines.
+pub fn hard_wrap(text: &str, column: usize, line_ending: LineEnding) -> String {
+    let mut out: Vec<String> = Vec::new();
+    for line in LineEnding::Lf.normalize(text).split('\n') {
+        if line.chars().count() <= column {
+            out.push(line.to_string());
+            continue;
+        }
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let mut current = indent.to_string();
+        for word in line.split_whitespace() {
+            let has_words = current.len() > indent.len();
+            if has_words && current.chars().count() + 1 + word.chars().count() > column {
+                out.push(std::mem::replace(&mut current, indent.to_string()));
+            } else if has_words {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        out.push(current);
+    }
+    line_ending.normalize(&out.join("\n"))
+}
+
+impl TextEditor {
+    /// Join the lines of each paragraph in the selection (or document).
+    pub fn join_paragraphs(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let range = self.selected_lines_range(window, cx);
+        let line_ending = self.document.read(cx).line_ending();
+        self.transform_range(range, |text| join_paragraphs(text, line_ending), window, cx);
+    }
+
+    /// Ask for a column, then hard-wrap the selection (or document) at it.
+    pub fn hard_wrap_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let range = self.selected_lines_range(window, cx);
+        let column_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(DEFAULT_WRAP_COLUMN.to_string())
+        });
+        let editor = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let column_input_for_ok = column_input.clone();
+            let editor = editor.clone();
+            let range = range.clone();
+            dialog
+                .title("Hard Wrap at Column")
+                .w(px(320.0))
+                .child(
+                    h_flex()
+                        .gap_3()
+                        .items_center()
+                        .child("Column")
+                        .child(div().w(px(100.0)).child(Input::new(&column_input))),
+                )
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Wrap"))
+                .on_ok(move |_, window, cx| {
+                    let Some(column) = column_input_for_ok.read(cx).value().trim().parse::<usize>().ok().filter(|c| *c > 0) else {
+                        return false;
+                    };
+                    let range = range.clone();
+                    let _ = editor.update(cx, |ed, cx| {
+                        let line_ending = ed.document.read(cx).line_ending();
+                        ed.transform_range(range, |text| hard_wrap(text, column, line_ending), window, cx);
+                    });
+                    true
+                })
+        });
+    }
+
+    /// Ask for a column, defaulting to the soft-wrap width, then write the
+    /// document hard-wrapped at it to a file of the user's choosing.
+    pub fn export_wrapped_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let column = self.soft_wrap_column(window, cx).unwrap_or(DEFAULT_WRAP_COLUMN);
+        let column_input = cx.new(|cx| InputState::new(window, cx).default_value(column.to_string()));
+        let editor = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let column_input_for_ok = column_input.clone();
+            let editor = editor.clone();
+            dialog
+                .title("Export Wrapped Text")
+                .w(px(320.0))
+                .child(
+                    h_flex()
+                        .gap_3()
+                        .items_center()
+                        .child("Column")
+                        .child(div().w(px(100.0)).child(Input::new(&column_input))),
+                )
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Export..."))
+                .on_ok(move |_, window, cx| {
+                    let Some(column) = column_input_for_ok.read(cx).value().trim().parse::<usize>().ok().filter(|c| *c > 0) else {
+                        return false;
+                    };
+                    let _ = editor.update(cx, |ed, cx| ed.export_wrapped(column, window, cx));
+                    true
+                })
+        });
+    }
+
+    fn export_wrapped(&mut self, column: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let text = self.input_state.read(cx).value().to_string();
+        let document = self.document.read(cx);
+        let wrapped = hard_wrap(&text, column, document.line_ending());
+        let encoding = document.encoding();
+        let stem = document.path()
+            .and_then(|p| p.file_stem())
+            .and_then(|n| n.to_str())
+            .unwrap_or("Untitled")
+            .to_string();
+        if !encoding.can_encode(&wrapped) {
+            let message = format!("Not exported: the document has characters {} can't represent.", encoding);
+            window.push_notification(Notification::error(message), cx);
+            return;
+        }
+        let bytes = encoding.encode(&wrapped);
+
+        cx.spawn_in(window, move |this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let dialog_task = cx.background_spawn(async move {
+                    rfd::AsyncFileDialog::new()
+                        .add_filter("Text", &["txt"])
+                        .set_file_name(format!("{}-wrapped.txt", stem))
+                        .save_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                });
+                let Some(path): Option<PathBuf> = dialog_task.await else { return };
+                let written = cx.background_spawn({
+                    let path = path.clone();
+                    async move { write_atomic(&path, &bytes, false) }
+                });
+                match written.await {
+                    Ok(()) => info!(path = ?path, column, "Exported wrapped text"),
+                    Err(err) => {
+                        warn!(path = ?path, error = %err, "Failed to export wrapped text");
+                        let _ = this.update_in(&mut cx, |_, window, cx| {
+                            window.push_notification(Notification::error(format!("Couldn't write {}: {}", path.display(), err)), cx);
+                        });
+                    }
+                }
+            }
+        })
+        .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hard_wrap, join_paragraphs, LineEnding};
+
+    #[test]
+    fn test_join_paragraphs() {
+        let text = "first line\nsecond line\n\nnext  \n  paragraph\n";
+        assert_eq!(join_paragraphs(text, LineEnding::Lf), "first line second line\n\nnext paragraph\n");
+    }
+
+    #[test]
+    fn test_hard_wrap() {
+        assert_eq!(hard_wrap("one two three four", 9, LineEnding::Lf), "one two\nthree\nfour");
+        assert_eq!(hard_wrap("short\n", 20, LineEnding::Lf), "short\n");
+    }
+
+    #[test]
+    fn test_join_paragraphs_keeps_crlf() {
+        let text = "first line\r\nsecond line\r\n\r\nnext\r\n";
+        assert_eq!(join_paragraphs(text, LineEnding::Crlf), "first line second line\r\n\r\nnext\r\n");
+    }
+
+    #[test]
+    fn test_hard_wrap_keeps_crlf() {
+        assert_eq!(hard_wrap("one two three\r\nfour\r\n", 9, LineEnding::Crlf), "one two\r\nthree\r\nfour\r\n");
+        // A selection without line breaks still wraps with the document's
+        assert_eq!(hard_wrap("one two three", 9, LineEnding::Crlf), "one two\r\nthree");
+    }
+
+    #[test]
+    fn test_hard_wrap_keeps_indent_and_long_words() {
+        assert_eq!(hard_wrap("  alpha beta gamma", 12, LineEnding::Lf), "  alpha beta\n  gamma");
+        assert_eq!(hard_wrap("supercalifragilistic word", 5, LineEnding::Lf), "supercalifragilistic\nword");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let joined = "a b c d e f";
+        assert_eq!(join_paragraphs(&hard_wrap(joined, 4, LineEnding::Lf), LineEnding::Lf), joined);
+    }
+}
@@ -0,0 +1,98 @@
+//! Monospace alignment mode: the editor switches to the theme's monospace
+//! font and shows a column ruler above the text.
+
+use gpui::*;
+use gpui_component::{v_flex, Theme};
+
+use super::TextEditor;
+
+/// Columns the ruler covers; anything past the window is clipped.
+const RULER_COLUMNS: usize = 500;
+
+/// The ruler's two rows: column numbers (1, 10, 20, …) each starting at
+/// its column, and tick marks every 5 and 10 columns.
+pub fn ruler_rows(columns: usize) -> (String, String) {
+    let mut numbers = String::with_capacity(columns + 4);
+    let mut column = 1;
+    while column <= columns {
+        if column == 1 || column % 10 == 0 {
+            let label = column.to_string();
+            column += label.len();
+            numbers.push_str(&label);
+        } else {
+            numbers.push(' ');
+            column += 1;
+        }
+    }
+    let ticks = (1..=columns)
+        .map(|column| match column {
+            _ if column % 10 == 0 => '|',
+            _ if column % 5 == 0 => '+',
+            _ => '.',
+        })
+        .collect();
+    (numbers, ticks)
+}
+
+impl TextEditor {
+    pub fn toggle_monospace(&mut self, cx: &mut Context<Self>) {
+        self.monospace = !self.monospace;
+        cx.notify();
+    }
+
+    pub fn monospace_enabled(&self) -> bool {
+        self.monospace
+    }
+
+    /// Distance from the input's left edge to the first column of text.
+    pub(super) fn text_indent(&self, window: &mut Window, cx: &mut Context<Self>) -> Pixels {
+        // The line number gutter, when shown, sits before the first column
+        let gutter = match self.line_numbers {
+            true => self.input_state.update(cx, |state, cx| {
+                state.bounds_for_range(0..0, Bounds::default(), window, cx).map(|b| b.origin.x)
+            }),
+            false => None,
+        };
+        gpui_component::Size::Medium.input_px() + gutter.unwrap_or_default()
+    }
+
+    /// The column ruler, in monospace mode. It is drawn in the same font as
+    /// the text and indented like it, so the columns line up; it doesn't
+    /// follow horizontal scrolling when word wrap is off.
+    pub(super) fn render_ruler(&self, window: &mut Window, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if !self.monospace {
+            return None;
+        }
+        let indent = self.text_indent(window, cx);
+        let colors = Theme::global(cx).colors;
+        let (numbers, ticks) = ruler_rows(RULER_COLUMNS);
+        Some(
+            v_flex()
+                .flex_none()
+                .pl(indent)
+                .pb_1()
+                .border_b_1()
+                .border_color(colors.border)
+                .text_color(colors.muted_foreground)
+                .overflow_hidden()
+                .whitespace_nowrap()
+                .child(numbers)
+                .child(ticks),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ruler_rows;
+
+    #[test]
+    fn test_ruler_rows() {
+        let (numbers, ticks) = ruler_rows(25);
+        assert_eq!(numbers, "1        10        20    ");
+        assert_eq!(ticks, "....+....|....+....|....+");
+        // Labels start on their own column
+        assert_eq!(numbers.find("10"), Some(9));
+        assert_eq!(numbers.len(), ticks.len());
+    }
+}
@@ -0,0 +1,90 @@
+//! Numbered clipboard slots kept inside the app, separate from the system
+//! clipboard and shared by all windows.
+
+use gpui::*;
+
+use super::{normalize_tabs, CopyToSlot1, CopyToSlot2, CopyToSlot3, PasteFromSlot1, PasteFromSlot2, PasteFromSlot3, TextEditor};
+
+/// Number of slots.
+pub const SLOT_COUNT: usize = 3;
+
+/// Characters of a slot's text shown in the menu.
+const PREVIEW_CHARS: usize = 24;
+
+/// The slots' contents.
+#[derive(Default)]
+pub struct ClipboardSlots([Option<String>; SLOT_COUNT]);
+
+impl Global for ClipboardSlots {}
+
+impl ClipboardSlots {
+    /// Text in `slot` (0-based), if any.
+    pub fn get(cx: &App, slot: usize) -> Option<&str> {
+        cx.try_global::<Self>()?.0.get(slot)?.as_deref()
+    }
+
+    /// A one-line excerpt of `slot` for menus.
+    pub fn preview(cx: &App, slot: usize) -> Option<String> {
+        let text = Self::get(cx, slot)?;
+        let line = text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or_default();
+        let mut preview: String = line.chars().take(PREVIEW_CHARS).collect();
+        if preview.len() < line.len() || line.len() < text.trim().len() {
+            preview.push('…');
+        }
+        Some(preview)
+    }
+
+    /// Actions for copying to and pasting from `slot`, for key bindings.
+    pub fn actions(slot: usize) -> (Box<dyn Action>, Box<dyn Action>) {
+        match slot {
+            0 => (Box::new(CopyToSlot1), Box::new(PasteFromSlot1)),
+            1 => (Box::new(CopyToSlot2), Box::new(PasteFromSlot2)),
+            _ => (Box::new(CopyToSlot3), Box::new(PasteFromSlot3)),
+        }
+    }
+}
+
+impl TextEditor {
+    /// Put the selection in `slot` (0-based).
+    pub fn copy_to_slot(&mut self, slot: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(text) = self.selected_text(window, cx).filter(|t| !t.is_empty()) else {
+            return;
+        };
+        if slot < SLOT_COUNT {
+            cx.default_global::<ClipboardSlots>().0[slot] = Some(text);
+        }
+    }
+
+    /// Insert the text in `slot` (0-based) at the caret, replacing the selection.
+    pub fn paste_from_slot(&mut self, slot: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(text) = ClipboardSlots::get(cx, slot).map(str::to_string) else {
+            return;
+        };
+        let text = if self.tab_handling.converts() { normalize_tabs(&text, self.tab_width) } else { text };
+        self.insert_text(&text, window, cx);
+    }
+
+    pub fn copy_to_slot_1(&mut self, _: &CopyToSlot1, window: &mut Window, cx: &mut Context<Self>) {
+        self.copy_to_slot(0, window, cx);
+    }
+
+    pub fn copy_to_slot_2(&mut self, _: &CopyToSlot2, window: &mut Window, cx: &mut Context<Self>) {
+        self.copy_to_slot(1, window, cx);
+    }
+
+    pub fn copy_to_slot_3(&mut self, _: &CopyToSlot3, window: &mut Window, cx: &mut Context<Self>) {
+        self.copy_to_slot(2, window, cx);
+    }
+
+    pub fn paste_from_slot_1(&mut self, _: &PasteFromSlot1, window: &mut Window, cx: &mut Context<Self>) {
+        self.paste_from_slot(0, window, cx);
+    }
+
+    pub fn paste_from_slot_2(&mut self, _: &PasteFromSlot2, window: &mut Window, cx: &mut Context<Self>) {
+        self.paste_from_slot(1, window, cx);
+    }
+
+    pub fn paste_from_slot_3(&mut self, _: &PasteFromSlot3, window: &mut Window, cx: &mut Context<Self>) {
+        self.paste_from_slot(2, window, cx);
+    }
+}
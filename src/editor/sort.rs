@@ -0,0 +1,126 @@
+//! Sorting, reversing and deduplicating lines.
+
+use gpui::*;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+
+use super::TextEditor;
+
+/// A command that reorders or filters whole lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineOperation {
+    SortAscending,
+    SortDescending,
+    SortCaseInsensitive,
+    SortNumeric,
+    Reverse,
+    RemoveDuplicates,
+}
+
+impl LineOperation {
+    pub const ALL: [LineOperation; 6] = [
+        LineOperation::SortAscending,
+        LineOperation::SortDescending,
+        LineOperation::SortCaseInsensitive,
+        LineOperation::SortNumeric,
+        LineOperation::Reverse,
+        LineOperation::RemoveDuplicates,
+    ];
+
+    /// `lines` after the operation. A final line break stays last, and CRLF
+    /// text stays CRLF.
+    pub fn apply(self, text: &str) -> String {
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        let body = text.strip_suffix('\n').map(|t| t.strip_suffix('\r').unwrap_or(t));
+        let trailing = body.is_some();
+        let body = body.unwrap_or(text);
+        let mut lines: Vec<&str> = body.split('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).collect();
+
+        match self {
+            LineOperation::SortAscending => lines.sort(),
+            LineOperation::SortDescending => lines.sort_by(|a, b| b.cmp(a)),
+            LineOperation::SortCaseInsensitive => lines.sort_by_cached_key(|l| l.to_lowercase()),
+            LineOperation::SortNumeric => lines.sort_by(|a, b| compare_numeric(a, b)),
+            LineOperation::Reverse => lines.reverse(),
+            LineOperation::RemoveDuplicates => {
+                let mut seen = HashSet::new();
+                lines.retain(|line| seen.insert(*line));
+            }
+        }
+
+        let mut result = lines.join(newline);
+        if trailing {
+            result.push_str(newline);
+        }
+        result
+    }
+}
+
+impl fmt::Display for LineOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LineOperation::SortAscending => "Sort Ascending",
+            LineOperation::SortDescending => "Sort Descending",
+            LineOperation::SortCaseInsensitive => "Sort Ignoring Case",
+            LineOperation::SortNumeric => "Sort Numerically",
+            LineOperation::Reverse => "Reverse Lines",
+            LineOperation::RemoveDuplicates => "Remove Duplicate Lines",
+        })
+    }
+}
+
+/// The number a line starts with, ignoring leading whitespace.
+fn leading_number(line: &str) -> Option<f64> {
+    let line = line.trim_start();
+    let end = line
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && (c == '-' || c == '+'))))
+        .map_or(line.len(), |(i, _)| i);
+    line[..end].parse().ok()
+}
+
+/// Lines starting with numbers in numeric order, then the rest as text.
+fn compare_numeric(a: &str, b: &str) -> Ordering {
+    match (leading_number(a), leading_number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y).then_with(|| a.cmp(b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+impl TextEditor {
+    /// Apply `operation` to the selected lines, or to the whole document, as
+    /// one undo step.
+    pub fn apply_line_operation(&mut self, operation: LineOperation, window: &mut Window, cx: &mut Context<Self>) {
+        let range = self.selected_lines_range(window, cx);
+        self.transform_range(range, |text| operation.apply(text), window, cx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineOperation;
+
+    #[test]
+    fn test_sort_lines() {
+        let text = "banana\nApple\ncherry\napple\n";
+        assert_eq!(LineOperation::SortAscending.apply(text), "Apple\napple\nbanana\ncherry\n");
+        assert_eq!(LineOperation::SortDescending.apply(text), "cherry\nbanana\napple\nApple\n");
+        assert_eq!(LineOperation::SortCaseInsensitive.apply(text), "Apple\napple\nbanana\ncherry\n");
+        assert_eq!(LineOperation::SortAscending.apply("b\r\na"), "a\r\nb");
+    }
+
+    #[test]
+    fn test_sort_numeric() {
+        let text = "10 ten\n2 two\nnone\n-1.5 minus\n 3 three";
+        assert_eq!(LineOperation::SortNumeric.apply(text), "-1.5 minus\n2 two\n 3 three\n10 ten\nnone");
+    }
+
+    #[test]
+    fn test_reverse_and_dedupe() {
+        assert_eq!(LineOperation::Reverse.apply("a\nb\nc\n"), "c\nb\na\n");
+        assert_eq!(LineOperation::RemoveDuplicates.apply("a\nb\na\n\nb\n"), "a\nb\n\n");
+    }
+}
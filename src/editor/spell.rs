@@ -0,0 +1,670 @@
+//! Spell checking: hunspell dictionaries, squiggles under unknown words, and
+//! suggestions offered as code actions (right-click > Show Code Actions, or
+//! ctrl-.).
+
+use anyhow::anyhow;
+use gpui::*;
+use gpui_component::highlighter::DiagnosticSeverity;
+use gpui_component::input::{CodeActionProvider, InputState};
+use gpui_component::RopeExt;
+use lsp_types::CodeAction;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use super::{decode_windows_1252, TextEditor, TextMark};
+use crate::settings::{get_config_dir, store};
+
+/// Suggestions offered for a misspelled word.
+const SUGGESTION_LIMIT: usize = 5;
+
+/// Longest word (in characters) for which two-edit suggestions are tried.
+const DEEP_SUGGESTION_CHARS: usize = 12;
+
+/// Where the personal dictionary is kept in the settings store.
+const PERSONAL_WORDS_KEY: &str = "spelling.json";
+
+/// Words a hunspell dictionary accepts, with its affixes expanded.
+pub struct Dictionary {
+    words: HashSet<String>,
+    /// Lowercase characters tried when building suggestions.
+    alphabet: Vec<char>,
+}
+
+impl Dictionary {
+    /// Parse a hunspell `.dic` word list and its `.aff` affix rules.
+    pub fn parse(dic: &str, aff: &str) -> Self {
+        let affixes = Affixes::parse(aff);
+        let mut words = HashSet::new();
+        for (i, line) in dic.lines().enumerate() {
+            // The first line holds the entry count
+            if i == 0 && line.trim().parse::<usize>().is_ok() {
+                continue;
+            }
+            let entry = line.split(['\t', ' ']).next().unwrap_or_default();
+            let (stem, flags) = match entry.split_once('/') {
+                Some((stem, flags)) => (stem, affixes.flags(flags)),
+                None => (entry, Vec::new()),
+            };
+            if !stem.is_empty() {
+                affixes.expand(stem, &flags, &mut words);
+            }
+        }
+
+        let mut alphabet = Vec::new();
+        for c in affixes.try_chars.chars().chain('a'..='z').flat_map(char::to_lowercase) {
+            if !alphabet.contains(&c) {
+                alphabet.push(c);
+            }
+        }
+        Self { words, alphabet }
+    }
+
+    fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+}
+
+/// How an `.aff` file writes flags.
+#[derive(Default, Clone, Copy)]
+enum FlagMode {
+    #[default]
+    Char,
+    Long,
+    Num,
+}
+
+/// One character of an affix condition.
+enum CharClass {
+    Any,
+    One(char),
+    Set { chars: Vec<char>, negated: bool },
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Any => true,
+            CharClass::One(one) => *one == c,
+            CharClass::Set { chars, negated } => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Vec<CharClass>,
+}
+
+impl AffixRule {
+    fn apply_suffix(&self, stem: &str) -> Option<String> {
+        let chars: Vec<char> = stem.chars().collect();
+        let n = self.condition.len();
+        if chars.len() < n || !self.condition.iter().zip(&chars[chars.len() - n..]).all(|(class, &c)| class.matches(c)) {
+            return None;
+        }
+        let base = stem.strip_suffix(self.strip.as_str())?;
+        Some(format!("{}{}", base, self.add))
+    }
+
+    fn apply_prefix(&self, stem: &str) -> Option<String> {
+        let chars: Vec<char> = stem.chars().collect();
+        if chars.len() < self.condition.len() || !self.condition.iter().zip(&chars).all(|(class, &c)| class.matches(c)) {
+            return None;
+        }
+        let base = stem.strip_prefix(self.strip.as_str())?;
+        Some(format!("{}{}", self.add, base))
+    }
+}
+
+struct AffixClass {
+    /// Whether the class combines with affixes of the other kind.
+    cross: bool,
+    rules: Vec<AffixRule>,
+}
+
+/// The parts of an `.aff` file needed to expand dictionary entries.
+#[derive(Default)]
+struct Affixes {
+    flag_mode: FlagMode,
+    /// Flag sets named by number (`AF` lines), 1-based in the `.dic` file.
+    aliases: Vec<String>,
+    prefixes: HashMap<String, AffixClass>,
+    suffixes: HashMap<String, AffixClass>,
+    try_chars: String,
+    forbidden: Option<String>,
+    need_affix: Option<String>,
+}
+
+impl Affixes {
+    fn parse(aff: &str) -> Self {
+        let mut affixes = Self::default();
+        for line in aff.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", "long", ..] => affixes.flag_mode = FlagMode::Long,
+                ["FLAG", "num", ..] => affixes.flag_mode = FlagMode::Num,
+                ["TRY", chars, ..] => affixes.try_chars = chars.to_string(),
+                ["FORBIDDENWORD", flag, ..] => affixes.forbidden = Some(flag.to_string()),
+                ["NEEDAFFIX", flag, ..] => affixes.need_affix = Some(flag.to_string()),
+                ["AF", flags, ..] if flags.parse::<usize>().is_err() => affixes.aliases.push(flags.to_string()),
+                [kind @ ("PFX" | "SFX"), flag, strip, add, rest @ ..] => {
+                    let classes = if *kind == "PFX" { &mut affixes.prefixes } else { &mut affixes.suffixes };
+                    match classes.get_mut(*flag) {
+                        // The header: flag, cross product, rule count
+                        None => {
+                            classes.insert(flag.to_string(), AffixClass { cross: *strip == "Y", rules: Vec::new() });
+                        }
+                        Some(class) => {
+                            let add = add.split('/').next().unwrap_or_default();
+                            class.rules.push(AffixRule {
+                                strip: if *strip == "0" { String::new() } else { strip.to_string() },
+                                add: if add == "0" { String::new() } else { add.to_string() },
+                                condition: parse_condition(rest.first().copied().unwrap_or(".")),
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        affixes
+    }
+
+    /// Split a `.dic` entry's flags.
+    fn flags(&self, flags: &str) -> Vec<String> {
+        if !self.aliases.is_empty() {
+            if let Some(alias) = flags.parse::<usize>().ok().and_then(|n| self.aliases.get(n.wrapping_sub(1))) {
+                return self.split_flags(alias);
+            }
+        }
+        self.split_flags(flags)
+    }
+
+    fn split_flags(&self, flags: &str) -> Vec<String> {
+        match self.flag_mode {
+            FlagMode::Char => flags.chars().map(String::from).collect(),
+            FlagMode::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars.chunks(2).map(|pair| pair.iter().collect()).collect()
+            }
+            FlagMode::Num => flags.split(',').map(|f| f.trim().to_string()).collect(),
+        }
+    }
+
+    /// Add `stem` and every form its flags allow to `words`.
+    fn expand(&self, stem: &str, flags: &[String], words: &mut HashSet<String>) {
+        let has = |flag: &Option<String>| flag.as_ref().is_some_and(|flag| flags.contains(flag));
+        if has(&self.forbidden) {
+            return;
+        }
+        if !has(&self.need_affix) {
+            words.insert(stem.to_string());
+        }
+        let mut suffixed = Vec::new();
+        for class in flags.iter().filter_map(|flag| self.suffixes.get(flag)) {
+            suffixed.extend(class.rules.iter().filter_map(|rule| rule.apply_suffix(stem)).map(|word| (word, class.cross)));
+        }
+        for class in flags.iter().filter_map(|flag| self.prefixes.get(flag)) {
+            for rule in &class.rules {
+                words.extend(rule.apply_prefix(stem));
+                if class.cross {
+                    words.extend(suffixed.iter().filter(|(_, cross)| *cross).filter_map(|(word, _)| rule.apply_prefix(word)));
+                }
+            }
+        }
+        words.extend(suffixed.into_iter().map(|(word, _)| word));
+    }
+}
+
+/// Parse an affix condition such as `[^aeiou]y` or `.`.
+fn parse_condition(condition: &str) -> Vec<CharClass> {
+    if condition == "." {
+        return Vec::new();
+    }
+    let mut classes = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        classes.push(match c {
+            '.' => CharClass::Any,
+            '[' => {
+                let mut set: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let negated = set.starts_with('^');
+                if negated {
+                    set.remove(0);
+                }
+                CharClass::Set { chars: set.chars().collect(), negated }
+            }
+            c => CharClass::One(c),
+        });
+    }
+    classes
+}
+
+/// Folders searched for `<language>.dic` and `<language>.aff`: the config
+/// directory's `dictionaries` folder first, then the system's. These are
+/// files the user drops in by hand, so they're looked up on disk even when
+/// the settings store is elsewhere.
+fn dictionary_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![get_config_dir().join("dictionaries")];
+    if cfg!(target_os = "macos") {
+        dirs.extend(directories::BaseDirs::new().map(|home| home.home_dir().join("Library/Spelling")));
+        dirs.push(PathBuf::from("/Library/Spelling"));
+    } else {
+        dirs.extend(["/usr/share/hunspell", "/usr/share/myspell", "/usr/share/myspell/dicts"].into_iter().map(PathBuf::from));
+    }
+    dirs
+}
+
+/// Read and parse the dictionary for `language` (e.g. `en_US`).
+fn load_dictionary(language: &str) -> anyhow::Result<Dictionary> {
+    let dic_path = dictionary_dirs()
+        .into_iter()
+        .map(|dir| dir.join(format!("{}.dic", language)))
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            anyhow!(
+                "No {} dictionary found. Put {}.dic and {}.aff in {}.",
+                language,
+                language,
+                language,
+                get_config_dir().join("dictionaries").display()
+            )
+        })?;
+    let aff = fs::read(dic_path.with_extension("aff")).unwrap_or_default();
+    let dic = fs::read(&dic_path)?;
+    // Dictionaries not declared UTF-8 are nearly always Latin-1
+    let utf8 = String::from_utf8_lossy(&aff).lines().any(|l| l.trim().eq_ignore_ascii_case("SET UTF-8"));
+    let decode = |bytes: &[u8]| if utf8 { String::from_utf8_lossy(bytes).into_owned() } else { decode_windows_1252(bytes) };
+    let dictionary = Dictionary::parse(&decode(&dic), &decode(&aff));
+    info!(path = ?dic_path, words = dictionary.len(), "Loaded dictionary");
+    Ok(dictionary)
+}
+
+/// Words the user added or chose to ignore, saved to `spelling.json` in the
+/// settings store.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PersonalWords {
+    #[serde(default)]
+    pub added: BTreeSet<String>,
+    #[serde(default)]
+    pub ignored: BTreeSet<String>,
+}
+
+impl PersonalWords {
+    pub fn load() -> Self {
+        if let Some(contents) = store().read(PERSONAL_WORDS_KEY) {
+            if let Ok(words) = serde_json::from_str(&contents) {
+                return words;
+            }
+        }
+        Self::default()
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(err) = store().write(PERSONAL_WORDS_KEY, &json) {
+                    warn!(error = %err, "Failed to save personal dictionary");
+                }
+            }
+            Err(err) => warn!(error = %err, "Failed to serialize personal dictionary"),
+        }
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.added.contains(word) || self.ignored.contains(word)
+    }
+}
+
+/// The loaded dictionary and the user's own words, shared by all windows.
+/// Editors redraw their squiggles whenever it changes; they check text
+/// against a copy, off the UI thread.
+#[derive(Clone, Default)]
+pub struct SpellChecker {
+    /// Language of the dictionary, or of the one being loaded.
+    language: Option<String>,
+    dictionary: Option<Arc<Dictionary>>,
+    personal: Option<PersonalWords>,
+}
+
+impl Global for SpellChecker {}
+
+impl SpellChecker {
+    /// Whether `language`'s dictionary is loaded or being loaded.
+    pub fn has_language(cx: &App, language: &str) -> bool {
+        cx.try_global::<Self>().and_then(|c| c.language.as_deref()) == Some(language)
+    }
+
+    /// Load `language`'s dictionary in the background and start using it.
+    pub fn load(language: String, cx: &mut App) -> Task<anyhow::Result<()>> {
+        let checker = cx.default_global::<Self>();
+        checker.language = Some(language.clone());
+        checker.dictionary = None;
+        checker.personal.get_or_insert_with(PersonalWords::load);
+        cx.spawn(async move |cx: &mut AsyncApp| {
+            let loaded = cx
+                .background_spawn({
+                    let language = language.clone();
+                    async move { load_dictionary(&language) }
+                })
+                .await;
+            cx.update(|cx| {
+                let checker = cx.default_global::<Self>();
+                // Another language may have been picked meanwhile
+                if checker.language.as_deref() != Some(language.as_str()) {
+                    return Ok(());
+                }
+                match loaded {
+                    Ok(dictionary) => {
+                        checker.dictionary = Some(Arc::new(dictionary));
+                        Ok(())
+                    }
+                    Err(err) => {
+                        checker.language = None;
+                        Err(err)
+                    }
+                }
+            })?
+        })
+    }
+
+    /// Whether `word` is spelled right. A capitalized word may also match
+    /// the dictionary in lower case.
+    pub fn is_correct(&self, word: &str) -> bool {
+        let Some(dictionary) = &self.dictionary else {
+            return true;
+        };
+        let word = word.replace('’', "'");
+        let known = |w: &str| dictionary.contains(w) || self.personal.as_ref().is_some_and(|p| p.contains(w));
+        if known(&word) {
+            return true;
+        }
+        let lower = word.to_lowercase();
+        lower != word && word.chars().next().is_some_and(char::is_uppercase) && known(&lower)
+    }
+
+    /// Dictionary words close to `word`, best first, in its capitalization.
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let Some(dictionary) = &self.dictionary else {
+            return Vec::new();
+        };
+        let lower = word.replace('’', "'").to_lowercase();
+        let mut found = Vec::new();
+        // A proper noun typed in lower case
+        let capitalized = capitalize(&lower);
+        if capitalized != word && dictionary.contains(&capitalized) {
+            found.push(capitalized);
+        }
+        let near = edits(&lower, &dictionary.alphabet);
+        found.extend(near.iter().filter(|c| dictionary.contains(c)).map(|c| match_case(word, c)));
+        if found.is_empty() && lower.chars().count() <= DEEP_SUGGESTION_CHARS {
+            let farther = near.iter().flat_map(|c| edits(c, &dictionary.alphabet));
+            found.extend(farther.filter(|c| dictionary.contains(c)).map(|c| match_case(word, &c)));
+        }
+        let mut seen = HashSet::new();
+        found.retain(|w| seen.insert(w.clone()));
+        found.truncate(SUGGESTION_LIMIT);
+        found
+    }
+
+    /// Accept `word` from now on: add it to the personal dictionary, or
+    /// just ignore it.
+    pub fn accept(word: &str, add: bool, cx: &mut App) {
+        let checker = cx.default_global::<Self>();
+        let personal = checker.personal.get_or_insert_with(PersonalWords::load);
+        let list = if add { &mut personal.added } else { &mut personal.ignored };
+        list.insert(word.replace('’', "'"));
+        personal.save();
+        debug!(word, add, "Accepted word");
+    }
+}
+
+/// Every string one deletion, transposition, replacement or insertion away
+/// from `word`, roughly most likely typo first.
+fn edits(word: &str, alphabet: &[char]) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let join = |parts: &[&[char]]| parts.iter().flat_map(|p| p.iter()).collect::<String>();
+    let mut out = Vec::new();
+    for i in 0..chars.len() {
+        out.push(join(&[&chars[..i], &chars[i + 1..]]));
+    }
+    for i in 0..chars.len().saturating_sub(1) {
+        out.push(join(&[&chars[..i], &[chars[i + 1], chars[i]], &chars[i + 2..]]));
+    }
+    for i in 0..chars.len() {
+        for &c in alphabet.iter().filter(|&&c| c != chars[i]) {
+            out.push(join(&[&chars[..i], &[c], &chars[i + 1..]]));
+        }
+    }
+    for i in 0..=chars.len() {
+        for &c in alphabet {
+            out.push(join(&[&chars[..i], &[c], &chars[i..]]));
+        }
+    }
+    out
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+}
+
+/// `suggestion` capitalized like `original`.
+fn match_case(original: &str, suggestion: &str) -> String {
+    let letters = || original.chars().filter(|c| c.is_alphabetic());
+    if letters().count() > 1 && letters().all(char::is_uppercase) {
+        suggestion.to_uppercase()
+    } else if original.chars().next().is_some_and(char::is_uppercase) {
+        capitalize(suggestion)
+    } else {
+        suggestion.to_string()
+    }
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+/// Byte ranges of the words worth checking: runs of letters with inner
+/// apostrophes, skipping single letters, all-caps acronyms, camelCase
+/// names, words joined to digits or underscores, and URLs and e-mail
+/// addresses.
+fn checkable_words(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut offset = 0;
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let chunk_start = offset;
+        offset += piece.len();
+        let chunk = piece.trim_end_matches(char::is_whitespace);
+        if chunk.contains("://") || chunk.contains('@') || chunk.starts_with("www.") {
+            continue;
+        }
+        let chars: Vec<(usize, char)> = chunk.char_indices().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            if !chars[i].1.is_alphabetic() {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < chars.len()
+                && (chars[i].1.is_alphabetic()
+                    || (is_apostrophe(chars[i].1) && chars.get(i + 1).is_some_and(|(_, c)| c.is_alphabetic())))
+            {
+                i += 1;
+            }
+            let joined = |c: Option<&(usize, char)>| c.is_some_and(|&(_, c)| c.is_numeric() || c == '_');
+            if joined(start.checked_sub(1).map(|j| &chars[j])) || joined(chars.get(i)) {
+                continue;
+            }
+            let range = chars[start].0..chars.get(i).map_or(chunk.len(), |&(o, _)| o);
+            if worth_checking(&chunk[range.clone()]) {
+                words.push(chunk_start + range.start..chunk_start + range.end);
+            }
+        }
+    }
+    words
+}
+
+fn worth_checking(word: &str) -> bool {
+    let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    let acronym = letters.iter().all(|c| c.is_uppercase());
+    let camel_case = letters.windows(2).any(|pair| pair[0].is_lowercase() && pair[1].is_uppercase());
+    letters.len() > 1 && !acronym && !camel_case
+}
+
+/// Byte ranges of the words in `text` that `checker` doesn't know.
+fn misspellings(text: &str, checker: &SpellChecker) -> Vec<Range<usize>> {
+    checkable_words(text).into_iter().filter(|range| !checker.is_correct(&text[range.clone()])).collect()
+}
+
+/// What a spelling code action does, kept in the action's `data`.
+#[derive(Serialize, Deserialize)]
+enum SpellingFix {
+    Replace { range: Range<usize>, word: String, with: String },
+    Ignore(String),
+    AddToDictionary(String),
+}
+
+/// Offers replacements, Ignore and Add to Dictionary for the misspelled
+/// word under the caret.
+pub struct SpellingActions;
+
+impl CodeActionProvider for SpellingActions {
+    fn id(&self) -> SharedString {
+        "spelling".into()
+    }
+
+    fn code_actions(&self, state: Entity<InputState>, range: Range<usize>, _window: &mut Window, cx: &mut App) -> Task<anyhow::Result<Vec<CodeAction>>> {
+        let Some(checker) = cx.try_global::<SpellChecker>() else {
+            return Task::ready(Ok(Vec::new()));
+        };
+        let text = state.read(cx).value();
+        let Some(word_range) = checkable_words(&text).into_iter().find(|w| w.start <= range.start && range.start <= w.end) else {
+            return Task::ready(Ok(Vec::new()));
+        };
+        let word = text[word_range.clone()].to_string();
+        if checker.is_correct(&word) {
+            return Task::ready(Ok(Vec::new()));
+        }
+        let mut fixes: Vec<(String, SpellingFix)> = checker
+            .suggest(&word)
+            .into_iter()
+            .map(|with| (format!("Change to \"{}\"", with), SpellingFix::Replace { range: word_range.clone(), word: word.clone(), with }))
+            .collect();
+        fixes.push((format!("Ignore \"{}\"", word), SpellingFix::Ignore(word.clone())));
+        fixes.push((format!("Add \"{}\" to Dictionary", word), SpellingFix::AddToDictionary(word)));
+        let actions = fixes
+            .into_iter()
+            .map(|(title, fix)| CodeAction { title, data: serde_json::to_value(fix).ok(), ..Default::default() })
+            .collect();
+        Task::ready(Ok(actions))
+    }
+
+    fn perform_code_action(&self, state: Entity<InputState>, action: CodeAction, _push_to_history: bool, window: &mut Window, cx: &mut App) -> Task<anyhow::Result<()>> {
+        let Some(fix) = action.data.and_then(|data| serde_json::from_value::<SpellingFix>(data).ok()) else {
+            return Task::ready(Ok(()));
+        };
+        match fix {
+            SpellingFix::Replace { range, word, with } => state.update(cx, |state, cx| {
+                let text = state.text().clone();
+                // The text may have changed since the menu opened
+                if range.end > text.len() || text.slice(range.clone()) != word.as_str() {
+                    return;
+                }
+                let range = text.offset_to_offset_utf16(range.start)..text.offset_to_offset_utf16(range.end);
+                state.replace_text_in_range(Some(range), &with, window, cx);
+            }),
+            SpellingFix::Ignore(word) => SpellChecker::accept(&word, false, cx),
+            SpellingFix::AddToDictionary(word) => SpellChecker::accept(&word, true, cx),
+        }
+        Task::ready(Ok(()))
+    }
+}
+
+impl TextEditor {
+    /// Turn the squiggles under unknown words on or off.
+    pub fn set_spell_check(&mut self, enabled: bool, window: &mut Window, cx: &mut Context<Self>) {
+        if self.spell_check != enabled {
+            self.spell_check = enabled;
+            self.sync_input_mode(window, cx);
+            self.refresh_diagnostics(cx);
+        }
+    }
+
+    /// A copy of the loaded checker, if this editor checks spelling. Large
+    /// files aren't checked.
+    pub(super) fn spell_checker(&self, cx: &App) -> Option<SpellChecker> {
+        let checker = cx.try_global::<SpellChecker>().filter(|c| c.dictionary.is_some());
+        checker.filter(|_| self.spell_check && !self.large_file).cloned()
+    }
+}
+
+/// Squiggles for the unknown words in `text`.
+pub(super) fn spelling_marks(text: &str, checker: &SpellChecker) -> Vec<TextMark> {
+    misspellings(text, checker)
+        .into_iter()
+        .map(|range| TextMark {
+            message: format!("\"{}\" isn't in the dictionary", &text[range.clone()]),
+            range,
+            severity: DiagnosticSeverity::Error,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checkable_words, Dictionary, SpellChecker};
+    use std::sync::Arc;
+
+    const AFF: &str = "SET UTF-8\nTRY esiarntolcdugmphbyfvkwz\n\
+        SFX S Y 2\nSFX S y ies [^aeiou]y\nSFX S 0 s [^y]\n\
+        PFX U Y 1\nPFX U 0 un .\n";
+    const DIC: &str = "3\ncity/S\nhappy/U\nkind/SU\n";
+
+    fn checker() -> SpellChecker {
+        SpellChecker {
+            language: Some("test".into()),
+            dictionary: Some(Arc::new(Dictionary::parse(DIC, AFF))),
+            personal: None,
+        }
+    }
+
+    #[test]
+    fn test_affix_expansion() {
+        let dictionary = Dictionary::parse(DIC, AFF);
+        for word in ["city", "cities", "happy", "unhappy", "kinds", "unkind", "unkinds"] {
+            assert!(dictionary.contains(word), "{}", word);
+        }
+        assert!(!dictionary.contains("citys"));
+        assert!(!dictionary.contains("happys"));
+        assert_eq!(dictionary.len(), 8);
+    }
+
+    #[test]
+    fn test_checkable_words() {
+        let text = "Don't check NASA, x, camelCase, v2 or snake_case at https://example.com but here";
+        let words: Vec<&str> = checkable_words(text).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(words, ["Don't", "check", "or", "at", "but", "here"]);
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let checker = checker();
+        assert!(checker.is_correct("Cities"));
+        assert!(!checker.is_correct("citties"));
+        assert_eq!(checker.suggest("citties"), ["cities"]);
+        assert_eq!(checker.suggest("Unhapy"), ["Unhappy"]);
+        assert_eq!(checker.suggest("KINDD"), ["KIND", "KINDS"]);
+    }
+}
@@ -0,0 +1,254 @@
+//! Word, line and paragraph counts, and the length budget.
+
+use gpui::*;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::input::{Input, InputState};
+use gpui_component::{h_flex, v_flex, Theme, WindowExt};
+
+use super::TextEditor;
+
+/// Counts for a piece of text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStats {
+    pub characters: usize,
+    pub characters_no_spaces: usize,
+    pub words: usize,
+    pub lines: usize,
+    /// Runs of non-blank lines.
+    pub paragraphs: usize,
+}
+
+impl TextStats {
+    pub fn of(text: &str) -> Self {
+        let mut stats = TextStats {
+            characters: text.chars().count(),
+            characters_no_spaces: text.chars().filter(|c| !c.is_whitespace()).count(),
+            words: text.split_whitespace().count(),
+            // Matches the editor: an empty buffer still has one line
+            lines: text.split('\n').count(),
+            paragraphs: 0,
+        };
+        let mut in_paragraph = false;
+        for line in text.lines() {
+            let blank = line.trim().is_empty();
+            if !blank && !in_paragraph {
+                stats.paragraphs += 1;
+            }
+            in_paragraph = !blank;
+        }
+        stats
+    }
+}
+
+/// Target length for the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub limit: usize,
+    pub words: bool,
+}
+
+/// How close the document is to its budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetLevel {
+    Under,
+    /// At least 90% used.
+    Near,
+    Over,
+}
+
+impl Budget {
+    /// Parse "280", "280 chars" or "500 words".
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split_whitespace();
+        let limit = parts.next()?.parse::<usize>().ok().filter(|n| *n > 0)?;
+        let words = match parts.next() {
+            None => false,
+            Some(unit) if "characters".starts_with(unit) || unit == "chars" => false,
+            Some(unit) if "words".starts_with(unit) => true,
+            Some(_) => return None,
+        };
+        parts.next().is_none().then_some(Self { limit, words })
+    }
+
+    fn used(&self, stats: &TextStats) -> usize {
+        if self.words { stats.words } else { stats.characters }
+    }
+
+    pub fn level(&self, stats: &TextStats) -> BudgetLevel {
+        let used = self.used(stats);
+        if used > self.limit {
+            BudgetLevel::Over
+        } else if used * 10 >= self.limit * 9 {
+            BudgetLevel::Near
+        } else {
+            BudgetLevel::Under
+        }
+    }
+
+    /// Status bar text, e.g. "42 characters left" or "1 word over".
+    pub fn label(&self, stats: &TextStats) -> String {
+        let used = self.used(stats);
+        let (count, side) = if used > self.limit { (used - self.limit, "over") } else { (self.limit - used, "left") };
+        let unit = match (self.words, count == 1) {
+            (true, true) => "word",
+            (true, false) => "words",
+            (false, true) => "character",
+            (false, false) => "characters",
+        };
+        format!("{} {} {}", TextEditor::format_with_commas(count), unit, side)
+    }
+}
+
+impl std::fmt::Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.limit, if self.words { "words" } else { "chars" })
+    }
+}
+
+impl TextEditor {
+    /// Counts for the current text.
+    pub fn stats(&self) -> TextStats {
+        self.stats
+    }
+
+    pub(super) fn refresh_stats(&mut self, cx: &App) {
+        let text = self.input_state.read(cx).value();
+        // Counting words is too slow for large files
+        self.stats = if self.large_file {
+            TextStats { characters: text.chars().count(), ..TextStats::default() }
+        } else {
+            TextStats::of(&text)
+        };
+    }
+
+    /// Ask for a target length; an empty answer removes the budget.
+    pub fn length_budget_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let current = self.budget.map(|b| b.to_string()).unwrap_or_default();
+        let budget_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder("e.g. 280 chars or 500 words").default_value(current)
+        });
+        let editor = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let budget_input_for_ok = budget_input.clone();
+            let editor = editor.clone();
+            dialog
+                .title("Length Budget")
+                .w(px(360.0))
+                .child(
+                    h_flex()
+                        .gap_3()
+                        .items_center()
+                        .child("Target")
+                        .child(div().flex_grow().child(Input::new(&budget_input))),
+                )
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Set"))
+                .on_ok(move |_, _window, cx| {
+                    let text = budget_input_for_ok.read(cx).value();
+                    let budget = match text.trim() {
+                        "" => None,
+                        text => match Budget::parse(text) {
+                            Some(budget) => Some(budget),
+                            None => return false,
+                        },
+                    };
+                    let _ = editor.update(cx, |ed, cx| {
+                        ed.budget = budget;
+                        cx.notify();
+                    });
+                    true
+                })
+        });
+    }
+
+    /// Show counts for the whole document and the current selection.
+    pub fn show_statistics(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let document = TextStats::of(&self.input_state.read(cx).value());
+        let selection = self.selected_text(window, cx)
+            .filter(|text| !text.is_empty())
+            .map(|text| TextStats::of(&text));
+
+        window.open_dialog(cx, move |dialog, _window, cx| {
+            let colors = Theme::global(cx).colors;
+            let cell = |value: String| div().w(px(96.0)).text_right().child(value);
+            let row = |label: &'static str, value: fn(&TextStats) -> usize| {
+                h_flex()
+                    .child(div().flex_1().child(label))
+                    .child(cell(Self::format_with_commas(value(&document))))
+                    .child(cell(selection.as_ref().map_or("-".to_string(), |s| Self::format_with_commas(value(s)))))
+            };
+
+            dialog
+                .title("Document Statistics")
+                .w(px(420.0))
+                .child(
+                    v_flex()
+                        .gap_1()
+                        .child(
+                            h_flex()
+                                .text_color(colors.muted_foreground)
+                                .child(div().flex_1())
+                                .child(cell("Document".to_string()))
+                                .child(cell("Selection".to_string())),
+                        )
+                        .child(row("Characters", |s| s.characters))
+                        .child(row("Characters (no spaces)", |s| s.characters_no_spaces))
+                        .child(row("Words", |s| s.words))
+                        .child(row("Lines", |s| s.lines))
+                        .child(row("Paragraphs", |s| s.paragraphs)),
+                )
+                .alert()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Budget, BudgetLevel, TextStats};
+
+    #[test]
+    fn test_parse_budget() {
+        assert_eq!(Budget::parse("280"), Some(Budget { limit: 280, words: false }));
+        assert_eq!(Budget::parse(" 500 words "), Some(Budget { limit: 500, words: true }));
+        assert_eq!(Budget::parse("140 chars"), Some(Budget { limit: 140, words: false }));
+        assert_eq!(Budget::parse("0"), None);
+        assert_eq!(Budget::parse("10 pages"), None);
+    }
+
+    #[test]
+    fn test_budget_levels() {
+        let budget = Budget { limit: 10, words: false };
+        assert_eq!(budget.level(&TextStats::of("12345678")), BudgetLevel::Under);
+        assert_eq!(budget.level(&TextStats::of("123456789")), BudgetLevel::Near);
+        assert_eq!(budget.level(&TextStats::of("1234567890")), BudgetLevel::Near);
+        assert_eq!(budget.level(&TextStats::of("12345678901")), BudgetLevel::Over);
+        assert_eq!(budget.label(&TextStats::of("12345678901")), "1 character over");
+        assert_eq!(budget.label(&TextStats::of("123456789012")), "2 characters over");
+        assert_eq!(Budget { limit: 3, words: true }.label(&TextStats::of("one two")), "1 word left");
+        assert_eq!(Budget { limit: 3, words: true }.label(&TextStats::of("one")), "2 words left");
+    }
+
+    #[test]
+    fn test_counts() {
+        let stats = TextStats::of("Hello world\nsecond line\n\nnew paragraph");
+        assert_eq!(stats.characters, 38);
+        assert_eq!(stats.characters_no_spaces, 32);
+        assert_eq!(stats.words, 6);
+        assert_eq!(stats.lines, 4);
+        assert_eq!(stats.paragraphs, 2);
+    }
+
+    #[test]
+    fn test_empty_text() {
+        let stats = TextStats::of("");
+        assert_eq!(stats.words, 0);
+        assert_eq!(stats.lines, 1);
+        assert_eq!(stats.paragraphs, 0);
+    }
+
+    #[test]
+    fn test_blank_lines_with_spaces_separate_paragraphs() {
+        assert_eq!(TextStats::of("one\n   \ntwo\nthree\n").paragraphs, 2);
+    }
+}
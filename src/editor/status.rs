@@ -0,0 +1,156 @@
+//! Status bar segments that act when clicked: the caret position opens
+//! Go To Line, and the line ending and encoding open menus to change them.
+
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::input::{Input, InputState};
+use gpui_component::menu::{DropdownMenu, PopupMenuItem};
+use gpui_component::notification::Notification;
+use gpui_component::{h_flex, Disableable, RopeExt, Sizable, WindowExt};
+use tracing::debug;
+
+use super::{Encoding, GoToLineAction, LineEnding, TextEditor};
+
+/// Parse `line` or `line:column` (1-based) into 0-based line and column.
+pub fn parse_position(input: &str) -> Option<(usize, usize)> {
+    let mut parts = input.trim().splitn(2, [':', ',']);
+    let line: usize = parts.next()?.trim().parse().ok()?;
+    let column: usize = match parts.next() {
+        Some(column) => column.trim().parse().ok()?,
+        None => 1,
+    };
+    Some((line.checked_sub(1)?, column.saturating_sub(1)))
+}
+
+impl TextEditor {
+    pub fn encoding(&self, cx: &App) -> Encoding {
+        self.document.read(cx).encoding()
+    }
+
+    pub fn go_to_line(&mut self, _: &GoToLineAction, window: &mut Window, cx: &mut Context<Self>) {
+        self.go_to_line_dialog(window, cx);
+    }
+
+    /// Ask for a line (and optionally a column) and move the caret there.
+    pub fn go_to_line_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let (line, column) = self.caret_line_column(cx);
+        let line_count = self.input_state.read(cx).text().lines_len();
+        let position_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Line or line:column")
+                .default_value(format!("{}:{}", line + 1, column + 1))
+        });
+        position_input.update(cx, |state, cx| state.focus(window, cx));
+        let editor = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let position_input_for_ok = position_input.clone();
+            let editor = editor.clone();
+            dialog
+                .title(format!("Go to Line (1-{})", line_count))
+                .w(px(320.0))
+                .child(h_flex().gap_3().items_center().child("Line").child(div().flex_grow().child(Input::new(&position_input))))
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Go"))
+                .on_ok(move |_, window, cx| {
+                    let Some((line, column)) = parse_position(&position_input_for_ok.read(cx).value()) else {
+                        window.push_notification(Notification::warning("Enter a line number, optionally followed by :column."), cx);
+                        return false;
+                    };
+                    let _ = editor.update(cx, |ed, cx| {
+                        ed.go_to_line_column(line, column, window, cx);
+                        ed.input_state.update(cx, |state, cx| state.focus(window, cx));
+                    });
+                    true
+                })
+        });
+    }
+
+    /// Convert every line break in the buffer to `ending`, as one undo step.
+    pub fn set_line_ending(&mut self, ending: LineEnding, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
+        let len = self.input_state.read(cx).text().len();
+        self.transform_range(0..len, |text| ending.normalize(text), window, cx);
+        debug!(%ending, "Converted line endings");
+        self.document.update(cx, |doc, cx| doc.set_line_ending(ending, cx));
+    }
+
+    /// Save the document in `encoding` from now on, unless it has
+    /// characters the encoding can't represent.
+    pub fn set_encoding(&mut self, encoding: Encoding, window: &mut Window, cx: &mut Context<Self>) {
+        if !encoding.can_encode(&self.input_state.read(cx).value()) {
+            let message = format!("The document has characters {} can't represent.", encoding);
+            window.push_notification(Notification::warning(message), cx);
+            return;
+        }
+        debug!(%encoding, "Changed encoding");
+        self.document.update(cx, |doc, cx| doc.set_encoding(encoding, cx));
+        self.update_dirty_state(cx);
+    }
+
+    /// The caret position, opening Go To Line when clicked.
+    pub(super) fn render_position_segment(&self, label: String, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .id("status-position")
+            .cursor_pointer()
+            .on_click(cx.listener(|this, _, window, cx| this.go_to_line_dialog(window, cx)))
+            .child(label)
+    }
+
+    /// The line ending, with a menu converting the document to another.
+    pub(super) fn render_line_ending_segment(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let current = self.document.read(cx).line_ending();
+        let editor = cx.entity().downgrade();
+        Button::new("status-line-ending")
+            .label(current.to_string())
+            .ghost()
+            .xsmall()
+            .disabled(self.read_only)
+            .dropdown_menu_with_anchor(Corner::BottomLeft, move |menu, _window, _cx| {
+                LineEnding::ALL.into_iter().fold(menu, |menu, ending| {
+                    let editor = editor.clone();
+                    menu.item(PopupMenuItem::new(ending.to_string()).checked(ending == current).on_click(move |_, window, app| {
+                        let _ = editor.update(app, |ed, cx| ed.set_line_ending(ending, window, cx));
+                    }))
+                })
+            })
+    }
+
+    /// The encoding, with a menu choosing the one used on save.
+    pub(super) fn render_encoding_segment(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let current = self.encoding(cx);
+        let editor = cx.entity().downgrade();
+        Button::new("status-encoding")
+            .label(current.to_string())
+            .ghost()
+            .xsmall()
+            // Read-only and virtual documents are never saved
+            .disabled(self.read_only)
+            .dropdown_menu_with_anchor(Corner::BottomLeft, move |menu, _window, _cx| {
+                Encoding::ALL.into_iter().fold(menu, |menu, encoding| {
+                    let editor = editor.clone();
+                    menu.item(PopupMenuItem::new(encoding.to_string()).checked(encoding == current).on_click(move |_, window, app| {
+                        let _ = editor.update(app, |ed, cx| ed.set_encoding(encoding, window, cx));
+                    }))
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_position;
+
+    #[test]
+    fn test_parse_position() {
+        assert_eq!(parse_position("12"), Some((11, 0)));
+        assert_eq!(parse_position(" 3:7 "), Some((2, 6)));
+        assert_eq!(parse_position("3, 7"), Some((2, 6)));
+        assert_eq!(parse_position("0"), None);
+        assert_eq!(parse_position("x"), None);
+        assert_eq!(parse_position("4:"), None);
+    }
+}
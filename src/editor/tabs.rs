@@ -0,0 +1,129 @@
+//! Tab key handling and tab conversion modes.
+
+use gpui::*;
+use gpui_component::RopeExt;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::{InsertTabAction, TextEditor};
+
+/// What happens to tab characters in opened/pasted text and on the Tab key.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TabHandling {
+    /// Keep tabs in files and insert a tab on the Tab key.
+    Preserve,
+    /// Replace tabs with spaces on open and paste, and insert spaces on the Tab key.
+    Convert,
+    /// Keep tabs in files, but insert spaces on the Tab key.
+    #[default]
+    SpacesOnTabKey,
+}
+
+impl TabHandling {
+    pub const ALL: [TabHandling; 3] = [TabHandling::Preserve, TabHandling::Convert, TabHandling::SpacesOnTabKey];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TabHandling::Preserve => "Keep tabs",
+            TabHandling::Convert => "Convert to spaces",
+            TabHandling::SpacesOnTabKey => "Spaces on Tab key",
+        }
+    }
+
+    /// Whether tabs in opened or pasted text become spaces.
+    pub fn converts(self) -> bool {
+        self == TabHandling::Convert
+    }
+}
+
+impl fmt::Display for TabHandling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Text the Tab key inserts at `column`: a tab, or spaces up to the next tab stop.
+fn tab_text(handling: TabHandling, width: usize, column: usize) -> String {
+    if handling == TabHandling::Preserve {
+        return "\t".to_string();
+    }
+    let width = width.max(1);
+    " ".repeat(width - column % width)
+}
+
+/// `lines` with one level of indentation added to each non-empty line.
+fn indent_lines(lines: &str, unit: &str) -> String {
+    lines
+        .split('\n')
+        .map(|line| if line.is_empty() { line.to_string() } else { format!("{}{}", unit, line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl TextEditor {
+    /// Tab key: insert a tab or spaces, or indent every selected line.
+    pub fn insert_tab(&mut self, _: &InsertTabAction, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
+        let selection = self.selection_range(window, cx);
+        if selection.as_ref().is_none_or(|r| r.is_empty()) && self.step_table_cell(true, window, cx) {
+            return;
+        }
+        let handling = self.tab_handling;
+        let width = self.tab_width;
+        self.input_state.update(cx, |state, cx| {
+            let text = state.text().clone();
+            match selection.filter(|r| text.offset_to_position(r.start).line != text.offset_to_position(r.end).line) {
+                Some(range) => {
+                    let first = text.offset_to_position(range.start).line as usize;
+                    let last = text.offset_to_position(range.end).line as usize;
+                    let start = text.line_start_offset(first);
+                    let end = text.line_end_offset(last);
+                    let unit = tab_text(handling, width, 0);
+                    let indented = indent_lines(&text.slice(start..end).to_string(), &unit);
+                    let range = text.offset_to_offset_utf16(start)..text.offset_to_offset_utf16(end);
+                    state.replace_text_in_range(Some(range), &indented, window, cx);
+                }
+                None => {
+                    let column = state.cursor_position().character as usize;
+                    state.replace_text_in_range(None, &tab_text(handling, width, column), window, cx);
+                }
+            }
+        });
+    }
+
+    pub fn set_tab_handling(&mut self, handling: TabHandling, width: usize, cx: &mut Context<Self>) {
+        self.tab_handling = handling;
+        self.tab_width = width.max(1);
+        cx.notify();
+    }
+
+    /// Status bar label for the tab mode.
+    pub(super) fn tab_label(&self) -> String {
+        match self.tab_handling {
+            TabHandling::Preserve => "Tabs".to_string(),
+            TabHandling::Convert => format!("Spaces: {}", self.tab_width),
+            TabHandling::SpacesOnTabKey => format!("Soft tabs: {}", self.tab_width),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{indent_lines, tab_text, TabHandling};
+
+    #[test]
+    fn test_tab_text() {
+        assert_eq!(tab_text(TabHandling::Preserve, 4, 3), "\t");
+        assert_eq!(tab_text(TabHandling::Convert, 4, 0), "    ");
+        assert_eq!(tab_text(TabHandling::SpacesOnTabKey, 4, 3), " ");
+        assert_eq!(tab_text(TabHandling::Convert, 0, 5), " ");
+    }
+
+    #[test]
+    fn test_indent_lines_skips_empty_lines() {
+        assert_eq!(indent_lines("a\n\nb", "\t"), "\ta\n\n\tb");
+    }
+}
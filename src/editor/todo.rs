@@ -0,0 +1,156 @@
+//! Panel listing TODO/FIXME-style markers in the buffer, refreshed once
+//! typing pauses.
+
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::{h_flex, v_flex, Sizable, Theme};
+use std::time::Duration;
+
+use super::TextEditor;
+
+/// Pause in typing after which the list is rebuilt.
+const REFRESH_DELAY: Duration = Duration::from_millis(500);
+
+/// A marker found in the buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoItem {
+    /// 0-based line.
+    pub line: usize,
+    pub marker: String,
+    /// What follows the marker on its line.
+    pub text: String,
+}
+
+/// The first marker on each line of `text`. Markers match case-sensitively
+/// and only as whole words, as in `// TODO: tidy up` or `FIXME(ann)`.
+pub fn find_todos(text: &str, markers: &[String]) -> Vec<TodoItem> {
+    let mut items = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        let found = markers
+            .iter()
+            .filter(|m| !m.is_empty())
+            .flat_map(|marker| line.match_indices(marker.as_str()).map(move |(at, _)| (at, marker)))
+            .filter(|&(at, marker)| {
+                let before = line[..at].chars().next_back();
+                let after = line[at + marker.len()..].chars().next();
+                !before.is_some_and(|c| c.is_alphanumeric() || c == '_') && !after.is_some_and(|c| c.is_alphanumeric() || c == '_')
+            })
+            .min_by_key(|&(at, _)| at);
+        if let Some((at, marker)) = found {
+            let rest = line[at + marker.len()..].trim_start_matches([':', ' ', '\t']).trim_end();
+            items.push(TodoItem { line: line_index, marker: marker.clone(), text: rest.to_string() });
+        }
+    }
+    items
+}
+
+/// State of the open TODO panel.
+pub(super) struct TodoPanel {
+    items: Vec<TodoItem>,
+    /// Pending rebuild after an edit.
+    refresh_task: Option<Task<()>>,
+}
+
+impl TextEditor {
+    pub fn toggle_todo_panel(&mut self, cx: &mut Context<Self>) {
+        self.todos = match self.todos {
+            Some(_) => None,
+            None => Some(TodoPanel { items: find_todos(&self.input_state.read(cx).value(), &self.todo_markers), refresh_task: None }),
+        };
+        cx.notify();
+    }
+
+    pub fn todo_panel_open(&self) -> bool {
+        self.todos.is_some()
+    }
+
+    pub fn set_todo_markers(&mut self, markers: Vec<String>, cx: &mut Context<Self>) {
+        if self.todo_markers != markers {
+            self.todo_markers = markers;
+            self.refresh_todos(cx);
+        }
+    }
+
+    /// Rebuild the list now.
+    pub(super) fn refresh_todos(&mut self, cx: &mut Context<Self>) {
+        let items = find_todos(&self.input_state.read(cx).value(), &self.todo_markers);
+        if let Some(panel) = &mut self.todos {
+            panel.items = items;
+            panel.refresh_task = None;
+            cx.notify();
+        }
+    }
+
+    /// Rebuild the list once typing pauses.
+    pub(super) fn schedule_todo_refresh(&mut self, cx: &mut Context<Self>) {
+        let Some(panel) = &mut self.todos else { return };
+        // Replacing the task cancels the previous wait
+        panel.refresh_task = Some(cx.spawn(async move |this: WeakEntity<Self>, cx: &mut AsyncApp| {
+            cx.background_executor().timer(REFRESH_DELAY).await;
+            let _ = this.update(cx, |this, cx| this.refresh_todos(cx));
+        }));
+    }
+
+    fn jump_to_todo(&mut self, line: usize, window: &mut Window, cx: &mut Context<Self>) {
+        self.go_to_line_column(line, 0, window, cx);
+        self.input_state.update(cx, |state, cx| state.focus(window, cx));
+    }
+
+    pub(super) fn render_todo_panel(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let panel = self.todos.as_ref()?;
+        let colors = Theme::global(cx).colors;
+        let rows = panel.items.iter().enumerate().map(|(index, item)| {
+            let line = item.line;
+            h_flex()
+                .id(("todo-item", index))
+                .gap_2()
+                .px_2()
+                .cursor_pointer()
+                .hover(|this| this.bg(colors.accent))
+                .on_click(cx.listener(move |this, _, window, cx| this.jump_to_todo(line, window, cx)))
+                .child(div().w(px(60.0)).text_color(colors.warning).child(item.marker.clone()))
+                .child(div().w(px(70.0)).text_color(colors.muted_foreground).child(format!("Ln {}", line + 1)))
+                .child(div().flex_1().overflow_hidden().whitespace_nowrap().child(item.text.clone()))
+        });
+
+        Some(
+            v_flex()
+                .border_t_1()
+                .border_color(colors.border)
+                .child(
+                    h_flex()
+                        .px_2()
+                        .py_1()
+                        .bg(colors.muted)
+                        .child(div().flex_1().child(format!("TODO ({})", panel.items.len())))
+                        .child(Button::new("todo-close").label("Close").small().ghost()
+                            .on_click(cx.listener(|this, _, _, cx| this.toggle_todo_panel(cx)))),
+                )
+                .child(div().id("todo-items").max_h(px(160.0)).overflow_y_scroll().children(rows)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_todos, TodoItem};
+
+    fn markers() -> Vec<String> {
+        ["TODO", "FIXME", "NOTE"].map(String::from).to_vec()
+    }
+
+    #[test]
+    fn test_find_todos() {
+        let text = "fn main() {\n    // TODO: tidy up\n    // FIXME(ann) leaks\n}\n// TODOS and NOTEworthy don't count\n/* NOTE */";
+        let items = find_todos(text, &markers());
+        let item = |line, marker: &str, text: &str| TodoItem { line, marker: marker.into(), text: text.into() };
+        assert_eq!(items, [item(1, "TODO", "tidy up"), item(2, "FIXME", "(ann) leaks"), item(5, "NOTE", "*/")]);
+    }
+
+    #[test]
+    fn test_first_marker_on_a_line_wins() {
+        let items = find_todos("x // NOTE: see TODO below", &markers());
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].marker, "NOTE");
+    }
+}
@@ -0,0 +1,197 @@
+//! Encoding and decoding the selection: Base64, URL encoding and JSON
+//! string escapes.
+
+use gpui::*;
+use std::fmt;
+use tracing::warn;
+
+use super::TextEditor;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A reversible text encoding applied to the selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    Base64Encode,
+    Base64Decode,
+    UrlEncode,
+    UrlDecode,
+    JsonEscape,
+    JsonUnescape,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 6] = [
+        Transform::Base64Encode,
+        Transform::Base64Decode,
+        Transform::UrlEncode,
+        Transform::UrlDecode,
+        Transform::JsonEscape,
+        Transform::JsonUnescape,
+    ];
+
+    /// `text` transformed, or `None` if it can't be decoded.
+    pub fn apply(self, text: &str) -> Option<String> {
+        match self {
+            Transform::Base64Encode => Some(base64_encode(text.as_bytes())),
+            Transform::Base64Decode => String::from_utf8(base64_decode(text)?).ok(),
+            Transform::UrlEncode => Some(url_encode(text)),
+            Transform::UrlDecode => url_decode(text),
+            Transform::JsonEscape => {
+                let quoted = serde_json::to_string(text).ok()?;
+                Some(quoted[1..quoted.len() - 1].to_string())
+            }
+            Transform::JsonUnescape => {
+                let quoted = if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+                    text.to_string()
+                } else {
+                    format!("\"{}\"", text)
+                };
+                serde_json::from_str(&quoted).ok()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Transform::Base64Encode => "Base64 Encode",
+            Transform::Base64Decode => "Base64 Decode",
+            Transform::UrlEncode => "URL Encode",
+            Transform::UrlDecode => "URL Decode",
+            Transform::JsonEscape => "JSON Escape",
+            Transform::JsonUnescape => "JSON Unescape",
+        })
+    }
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode standard or URL-safe Base64, ignoring whitespace and padding.
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let value = match c {
+            b'-' => 62,
+            b'_' => 63,
+            _ => BASE64_ALPHABET.iter().position(|&a| a == c)? as u32,
+        };
+        buffer = buffer << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+fn url_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// Decode `%XX` escapes and `+` for space; `None` for a bad escape or
+/// bytes that aren't UTF-8.
+fn url_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+impl TextEditor {
+    /// Apply `transform` to the selection, or the whole document, as one undo
+    /// step, leaving the result selected.
+    pub fn apply_transform(&mut self, transform: Transform, window: &mut Window, cx: &mut Context<Self>) {
+        if self.read_only {
+            return;
+        }
+        let range = self
+            .selection_range(window, cx)
+            .filter(|r| !r.is_empty())
+            .unwrap_or_else(|| 0..self.input_state.read(cx).text().len());
+        let original = self.input_state.read(cx).value()[range.clone()].to_string();
+        let Some(result) = transform.apply(&original) else {
+            warn!(%transform, "Selection could not be decoded");
+            return;
+        };
+        let len = result.len();
+        self.transform_range(range.clone(), |_| result, window, cx);
+        self.select_range(range.start..range.start + len, window, cx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transform;
+
+    #[test]
+    fn test_base64() {
+        for (plain, encoded) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("héllo", "aMOpbGxv")] {
+            assert_eq!(Transform::Base64Encode.apply(plain).as_deref(), Some(encoded));
+            assert_eq!(Transform::Base64Decode.apply(encoded).as_deref(), Some(plain));
+        }
+        assert_eq!(Transform::Base64Decode.apply("Zm9v\nYmFy").as_deref(), Some("foobar"));
+        assert_eq!(Transform::Base64Decode.apply("not*base64"), None);
+    }
+
+    #[test]
+    fn test_url_encoding() {
+        assert_eq!(Transform::UrlEncode.apply("a b&c=é~").as_deref(), Some("a%20b%26c%3D%C3%A9~"));
+        assert_eq!(Transform::UrlDecode.apply("a%20b+c%C3%A9").as_deref(), Some("a b cé"));
+        assert_eq!(Transform::UrlDecode.apply("100%"), None);
+    }
+
+    #[test]
+    fn test_json_escapes() {
+        let text = "say \"hi\"\n\tbye\\";
+        let escaped = Transform::JsonEscape.apply(text).unwrap();
+        assert_eq!(escaped, "say \\\"hi\\\"\\n\\tbye\\\\");
+        assert_eq!(Transform::JsonUnescape.apply(&escaped).as_deref(), Some(text));
+        assert_eq!(Transform::JsonUnescape.apply("\"\\u00e9\"").as_deref(), Some("é"));
+        assert_eq!(Transform::JsonUnescape.apply("bad \\q"), None);
+    }
+}
@@ -2,6 +2,11 @@
 
 use std::fmt;
 
+use super::mojibake::cp1252_byte;
+
+/// Byte order mark at the start of UTF-8 files that carry one.
+pub(super) const BOM: char = '\u{FEFF}';
+
 /// Line ending style detected in a file.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum LineEnding {
@@ -17,6 +22,19 @@ pub enum LineEnding {
 }
 
 impl LineEnding {
+    /// The styles a document can be converted to.
+    pub const ALL: [LineEnding; 3] = [LineEnding::Lf, LineEnding::Crlf, LineEnding::Cr];
+
+    /// Every line break in `text` as this style. `Mixed` unifies to LF.
+    pub fn normalize(self, text: &str) -> String {
+        let unified = text.replace("\r\n", "\n").replace('\r', "\n");
+        match self {
+            Self::Crlf => unified.replace('\n', "\r\n"),
+            Self::Cr => unified.replace('\n', "\r"),
+            Self::Lf | Self::Mixed => unified,
+        }
+    }
+
     /// Detects the predominant line ending style in the given content.
     pub fn detect(content: &str) -> Self {
         let bytes = content.as_bytes();
@@ -69,12 +87,41 @@ pub enum Encoding {
     /// UTF-8 encoding (the default)
     #[default]
     Utf8,
+    /// UTF-8 starting with a byte order mark
+    Utf8Bom,
+    /// Windows-1252 (Western European)
+    Windows1252,
+}
+
+impl Encoding {
+    /// The encodings a document can be saved in.
+    pub const ALL: [Encoding; 3] = [Encoding::Utf8, Encoding::Utf8Bom, Encoding::Windows1252];
+
+    /// Whether every character of `text` can be written in this encoding.
+    pub fn can_encode(self, text: &str) -> bool {
+        match self {
+            Self::Utf8 | Self::Utf8Bom => true,
+            Self::Windows1252 => text.chars().all(|c| cp1252_byte(c).is_some()),
+        }
+    }
+
+    /// `text` as bytes in this encoding. Characters Windows-1252 lacks
+    /// become `?`.
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            Self::Utf8 => text.as_bytes().to_vec(),
+            Self::Utf8Bom => format!("{}{}", BOM, text).into_bytes(),
+            Self::Windows1252 => text.chars().map(|c| cp1252_byte(c).unwrap_or(b'?')).collect(),
+        }
+    }
 }
 
 impl fmt::Display for Encoding {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Utf8 => write!(f, "UTF-8"),
+            Self::Utf8Bom => write!(f, "UTF-8 with BOM"),
+            Self::Windows1252 => write!(f, "Windows-1252"),
         }
     }
 }
@@ -107,4 +154,20 @@ mod tests {
     fn test_detect_no_newlines() {
         assert_eq!(LineEnding::detect("hello world"), LineEnding::Lf);
     }
+
+    #[test]
+    fn test_normalize() {
+        let text = "a\r\nb\nc\rd";
+        assert_eq!(LineEnding::Lf.normalize(text), "a\nb\nc\nd");
+        assert_eq!(LineEnding::Crlf.normalize(text), "a\r\nb\r\nc\r\nd");
+        assert_eq!(LineEnding::Cr.normalize(text), "a\rb\rc\rd");
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(Encoding::Utf8Bom.encode("é"), b"\xef\xbb\xbf\xc3\xa9");
+        assert_eq!(Encoding::Windows1252.encode("é€✓"), b"\xe9\x80?");
+        assert!(Encoding::Windows1252.can_encode("café “quoted”"));
+        assert!(!Encoding::Windows1252.can_encode("✓"));
+    }
 }
@@ -0,0 +1,173 @@
+//! Keeping the caret, selection and first visible line in place across
+//! theme and font changes.
+
+use gpui::*;
+use gpui_component::input::Position;
+use gpui_component::RopeExt;
+use std::ops::Range;
+
+use super::TextEditor;
+
+/// Where the view was before a relayout.
+pub struct ViewAnchor {
+    selection: Range<usize>,
+    /// First visible line, when the caret was on screen. An off-screen
+    /// caret can't be restored without scrolling to it, so the view is
+    /// left alone then.
+    first_line: Option<usize>,
+}
+
+/// Lines on screen, as far as the last layout tells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Viewport {
+    pub first: usize,
+    /// Last fully visible line.
+    pub last: usize,
+}
+
+/// Line to put the caret on so that `first` becomes the top line again:
+/// scrolling up stops with the caret line at the top, scrolling down with
+/// it at the bottom.
+fn scroll_line(first: usize, now: Viewport) -> Option<usize> {
+    if now.first > first {
+        Some(first)
+    } else if now.first < first {
+        Some(first + (now.last - now.first))
+    } else {
+        None
+    }
+}
+
+impl TextEditor {
+    /// Remember the selection and first visible line.
+    pub fn view_anchor(&self, window: &mut Window, cx: &mut Context<Self>) -> ViewAnchor {
+        let cursor = self.input_state.read(cx).cursor();
+        let selection = self.selection_range(window, cx).unwrap_or(cursor..cursor);
+        let cursor_line = self.input_state.read(cx).cursor_position().line as usize;
+        let first_line = self
+            .viewport(window, cx)
+            .filter(|v| (v.first..=v.last).contains(&cursor_line))
+            .map(|v| v.first);
+        ViewAnchor { selection, first_line }
+    }
+
+    /// Once the input has laid out again, scroll `anchor`'s first line back
+    /// to the top and restore its selection.
+    pub fn restore_view(&mut self, anchor: ViewAnchor, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(first) = anchor.first_line else { return };
+        // One frame to lay out with the new metrics, then measure
+        cx.on_next_frame(window, move |_, window, cx| {
+            cx.on_next_frame(window, move |this, window, cx| {
+                let Some(line) = this.viewport(window, cx).and_then(|now| scroll_line(first, now)) else {
+                    return;
+                };
+                this.input_state.update(cx, |state, cx| {
+                    let line = line.min(state.text().lines_len().saturating_sub(1));
+                    state.set_cursor_position(Position { line: line as u32, character: 0 }, window, cx);
+                });
+                cx.on_next_frame(window, move |this, window, cx| {
+                    this.select_range(anchor.selection, window, cx);
+                });
+            });
+        });
+    }
+
+    /// Characters per row at which soft wrap breaks lines, judged from the
+    /// longest wrapped row on screen. `None` without soft wrap or when no
+    /// visible line wraps.
+    pub(super) fn soft_wrap_column(&self, window: &mut Window, cx: &mut Context<Self>) -> Option<usize> {
+        if !self.soft_wrap {
+            return None;
+        }
+        let viewport = self.viewport(window, cx)?;
+        self.input_state.update(cx, |state, cx| {
+            let text = state.text().clone();
+            let mut widest = None;
+            for line in viewport.first..=viewport.last {
+                let start = text.line_start_offset(line);
+                let line_text = text.slice(start..text.line_end_offset(line)).to_string();
+                let mut row: Option<(Pixels, usize)> = None;
+                for (i, _) in line_text.char_indices() {
+                    let offset = text.offset_to_offset_utf16(start + i);
+                    let y = state.bounds_for_range(offset..offset, Bounds::default(), window, cx)?.origin.y;
+                    row = match row {
+                        Some((row_y, chars)) if row_y == y => Some((row_y, chars + 1)),
+                        // Every row but the last ended where the line wrapped
+                        Some((_, chars)) => {
+                            widest = widest.max(Some(chars));
+                            Some((y, 1))
+                        }
+                        None => Some((y, 1)),
+                    };
+                }
+            }
+            widest
+        })
+    }
+
+    /// The lines the last layout put on screen.
+    ///
+    /// The input keeps its scroll offset private, but `bounds_for_range`
+    /// places every offset before the laid-out lines at the first of them
+    /// and every offset after them at the origin, which is enough to find
+    /// both ends.
+    pub(super) fn viewport(&self, window: &mut Window, cx: &mut Context<Self>) -> Option<Viewport> {
+        self.input_state.update(cx, |state, cx| {
+            let text = state.text().clone();
+            let lines = text.lines_len();
+            let mut top = |line: usize| {
+                let offset = text.offset_to_offset_utf16(text.line_start_offset(line));
+                state.bounds_for_range(offset..offset, Bounds::default(), window, cx).map(|b| b.origin.y)
+            };
+            let first_top = top(0)?;
+            // Lines above the layout share its first line's position
+            let start = if lines < 2 || top(1)? != first_top {
+                0
+            } else {
+                last_where(1..lines, |line| top(line) == Some(first_top))
+            };
+            // Lines below the layout sit at the origin
+            let end = last_where(start + 1..lines, |line| top(line).is_some_and(|y| y != px(0.)));
+            // The layout starts with the last line hidden above the viewport
+            // and ends with one partly below it
+            let first = if start == 0 { 0 } else { start + 1 };
+            let last = end.saturating_sub(1).max(first);
+            Some(Viewport { first, last })
+        })
+    }
+}
+
+/// Last line in `range` for which `pred` holds, where it holds for a prefix
+/// of the range; the line before the range if it never does.
+fn last_where(range: Range<usize>, mut pred: impl FnMut(usize) -> bool) -> usize {
+    let (mut low, mut high) = (range.start, range.end);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low.saturating_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{last_where, scroll_line, Viewport};
+
+    #[test]
+    fn test_scroll_line() {
+        let now = Viewport { first: 10, last: 30 };
+        assert_eq!(scroll_line(10, now), None);
+        assert_eq!(scroll_line(4, now), Some(4));
+        assert_eq!(scroll_line(15, now), Some(35));
+    }
+
+    #[test]
+    fn test_last_where() {
+        assert_eq!(last_where(1..10, |line| line <= 6), 6);
+        assert_eq!(last_where(1..10, |_| false), 0);
+        assert_eq!(last_where(1..10, |_| true), 9);
+    }
+}
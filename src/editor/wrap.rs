@@ -0,0 +1,90 @@
+//! Indentation of soft wrapped lines after the first.
+
+use gpui::*;
+use gpui_component::input::WrapIndentFn;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::rc::Rc;
+
+use super::markdown::list_item;
+use super::TextEditor;
+
+/// Where soft wrapped lines start, after a line's first.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapIndent {
+    /// At the left edge.
+    #[default]
+    None,
+    /// Under the line's own text: past its indentation and any list marker.
+    MatchLine,
+    /// Past the line's indentation and a few columns more.
+    Hanging,
+}
+
+impl WrapIndent {
+    pub const ALL: [WrapIndent; 3] = [WrapIndent::None, WrapIndent::MatchLine, WrapIndent::Hanging];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WrapIndent::None => "None",
+            WrapIndent::MatchLine => "Match line",
+            WrapIndent::Hanging => "Hanging",
+        }
+    }
+}
+
+impl fmt::Display for WrapIndent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Whitespace the wrapped lines of `line` start after: its leading spaces
+/// and tabs as they are, then spaces for a list marker or `hanging` columns.
+fn continuation_indent(line: &str, indent: WrapIndent, hanging: usize) -> String {
+    let text = line.trim_start_matches([' ', '\t']);
+    let leading = &line[..line.len() - text.len()];
+    let extra = match indent {
+        WrapIndent::None => return String::new(),
+        WrapIndent::MatchLine => list_item(text).map_or(0, |(_, item)| text.len() - item.len()),
+        WrapIndent::Hanging => hanging,
+    };
+    format!("{}{}", leading, " ".repeat(extra))
+}
+
+impl TextEditor {
+    /// Indent soft wrapped lines as `indent` says, by `hanging` columns
+    /// for a hanging indent.
+    pub fn set_wrap_indent(&mut self, indent: WrapIndent, hanging: usize, cx: &mut Context<Self>) {
+        if self.wrap_indent != (indent, hanging) {
+            self.wrap_indent = (indent, hanging);
+            let wrap_indent = self.wrap_indent_fn();
+            self.input_state.update(cx, |state, cx| state.set_wrap_indent(wrap_indent, cx));
+        }
+    }
+
+    /// What the input is told of the wrap indent, again whenever it is rebuilt.
+    pub(super) fn wrap_indent_fn(&self) -> Option<WrapIndentFn> {
+        let (indent, hanging) = self.wrap_indent;
+        (indent != WrapIndent::None).then(|| Rc::new(move |line: &str| continuation_indent(line, indent, hanging)) as WrapIndentFn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{continuation_indent, WrapIndent};
+
+    #[test]
+    fn test_continuation_indent() {
+        assert_eq!(continuation_indent("    code()", WrapIndent::None, 4), "");
+        assert_eq!(continuation_indent("    code()", WrapIndent::MatchLine, 4), "    ");
+        assert_eq!(continuation_indent("\tcode()", WrapIndent::MatchLine, 4), "\t");
+        assert_eq!(continuation_indent("plain", WrapIndent::MatchLine, 4), "");
+        // List items wrap under their text, not their marker
+        assert_eq!(continuation_indent("  - item", WrapIndent::MatchLine, 4), "    ");
+        assert_eq!(continuation_indent("12. item", WrapIndent::MatchLine, 4), "    ");
+        assert_eq!(continuation_indent("  key: value", WrapIndent::Hanging, 4), "      ");
+        assert_eq!(continuation_indent("- item", WrapIndent::Hanging, 2), "  ");
+    }
+}
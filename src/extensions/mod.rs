@@ -0,0 +1,308 @@
+//! Extension points for features that live outside the editor core. An
+//! extension contributes status bar segments, Tools menu entries and
+//! palette commands when activated, and they all go away when it is
+//! switched off.
+
+mod word_goal;
+
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::input::{Input, InputState};
+use gpui_component::{v_flex, Theme, WindowExt};
+use std::rc::Rc;
+use tracing::{debug, warn};
+
+use crate::editor::TextEditor;
+
+/// How a status segment is colored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Muted,
+    Success,
+}
+
+impl Tone {
+    pub fn color(self, cx: &App) -> Hsla {
+        let colors = Theme::global(cx).colors;
+        match self {
+            Tone::Muted => colors.muted_foreground,
+            Tone::Success => colors.success,
+        }
+    }
+}
+
+/// A status bar segment's content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusItem {
+    pub text: SharedString,
+    pub tone: Tone,
+}
+
+/// Computes a status segment for an editor, or `None` to hide it.
+pub type StatusProvider = Rc<dyn Fn(&TextEditor, &Context<TextEditor>) -> Option<StatusItem>>;
+/// Runs a menu entry or command against the active editor.
+pub type Handler = Rc<dyn Fn(&mut TextEditor, &mut Window, &mut Context<TextEditor>)>;
+
+/// A labelled action: a Tools menu entry or a palette command.
+#[derive(Clone)]
+pub struct Entry {
+    pub label: SharedString,
+    pub handler: Handler,
+}
+
+/// What one extension added while active.
+#[derive(Default)]
+pub struct Contributions {
+    status_items: Vec<StatusProvider>,
+    menu_entries: Vec<Entry>,
+    commands: Vec<Entry>,
+}
+
+impl Contributions {
+    pub fn status_item(&mut self, provider: impl Fn(&TextEditor, &Context<TextEditor>) -> Option<StatusItem> + 'static) -> &mut Self {
+        self.status_items.push(Rc::new(provider));
+        self
+    }
+
+    pub fn menu_entry(
+        &mut self,
+        label: impl Into<SharedString>,
+        handler: impl Fn(&mut TextEditor, &mut Window, &mut Context<TextEditor>) + 'static,
+    ) -> &mut Self {
+        self.menu_entries.push(Entry { label: label.into(), handler: Rc::new(handler) });
+        self
+    }
+
+    pub fn command(
+        &mut self,
+        label: impl Into<SharedString>,
+        handler: impl Fn(&mut TextEditor, &mut Window, &mut Context<TextEditor>) + 'static,
+    ) -> &mut Self {
+        self.commands.push(Entry { label: label.into(), handler: Rc::new(handler) });
+        self
+    }
+}
+
+/// A feature built on the extension points.
+pub trait Extension {
+    /// Stable identifier, as stored in settings.
+    fn id(&self) -> &'static str;
+    /// Name shown in the Extensions menu.
+    fn name(&self) -> &'static str;
+    /// Add this extension's status items, menu entries and commands.
+    fn activate(self: Rc<Self>, contributions: &mut Contributions);
+    /// Drop any state kept since activation.
+    fn deactivate(&self) {}
+}
+
+struct Loaded {
+    extension: Rc<dyn Extension>,
+    /// Contributions while active.
+    active: Option<Contributions>,
+}
+
+/// Every registered extension, active or not.
+#[derive(Default)]
+pub struct Extensions {
+    loaded: Vec<Loaded>,
+}
+
+impl Global for Extensions {}
+
+impl Extensions {
+    /// Add `extension`, activating it if `enabled`. A second extension with
+    /// the same id is ignored.
+    pub fn register(&mut self, extension: Rc<dyn Extension>, enabled: bool) {
+        if self.loaded.iter().any(|l| l.extension.id() == extension.id()) {
+            warn!(id = extension.id(), "Extension registered twice");
+            return;
+        }
+        self.loaded.push(Loaded { extension, active: None });
+        if enabled {
+            let id = self.loaded[self.loaded.len() - 1].extension.id();
+            self.set_enabled(id, true);
+        }
+    }
+
+    /// Activate or deactivate the extension `id`.
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) {
+        let Some(loaded) = self.loaded.iter_mut().find(|l| l.extension.id() == id) else {
+            return;
+        };
+        match (enabled, loaded.active.is_some()) {
+            (true, false) => {
+                let mut contributions = Contributions::default();
+                loaded.extension.clone().activate(&mut contributions);
+                loaded.active = Some(contributions);
+                debug!(id, "Activated extension");
+            }
+            (false, true) => {
+                loaded.active = None;
+                loaded.extension.deactivate();
+                debug!(id, "Deactivated extension");
+            }
+            _ => {}
+        }
+    }
+
+    /// Id, name and whether active, for each extension.
+    pub fn list(&self) -> impl Iterator<Item = (&'static str, &'static str, bool)> + '_ {
+        self.loaded.iter().map(|l| (l.extension.id(), l.extension.name(), l.active.is_some()))
+    }
+
+    fn active(&self) -> impl Iterator<Item = &Contributions> {
+        self.loaded.iter().filter_map(|l| l.active.as_ref())
+    }
+
+    pub fn status_items(&self) -> Vec<StatusProvider> {
+        self.active().flat_map(|c| c.status_items.iter().cloned()).collect()
+    }
+
+    pub fn menu_entries(&self) -> Vec<Entry> {
+        self.active().flat_map(|c| c.menu_entries.iter().cloned()).collect()
+    }
+
+    pub fn commands(&self) -> Vec<Entry> {
+        self.active().flat_map(|c| c.commands.iter().cloned()).collect()
+    }
+}
+
+/// Register the built-in extensions, leaving those in `disabled` inactive.
+pub fn init(disabled: &[String], cx: &mut App) {
+    let mut extensions = Extensions::default();
+    let builtin: [Rc<dyn Extension>; 1] = [Rc::new(word_goal::WordGoal::default())];
+    for extension in builtin {
+        let enabled = !disabled.iter().any(|id| id == extension.id());
+        extensions.register(extension, enabled);
+    }
+    cx.set_global(extensions);
+}
+
+/// Commands whose label contains every word of `query`, ignoring case.
+fn matching<'a>(commands: &'a [Entry], query: &str) -> Vec<&'a Entry> {
+    let query = query.to_lowercase();
+    commands
+        .iter()
+        .filter(|c| {
+            let label = c.label.to_lowercase();
+            query.split_whitespace().all(|word| label.contains(word))
+        })
+        .collect()
+}
+
+/// Ask which extension command to run on `editor`, narrowing the list as
+/// the user types; Run takes the first match.
+pub fn command_palette(editor: WeakEntity<TextEditor>, window: &mut Window, cx: &mut App) {
+    let commands: Rc<[Entry]> = cx.try_global::<Extensions>().map(|e| e.commands()).unwrap_or_default().into();
+    let query_input = cx.new(|cx| InputState::new(window, cx).placeholder("Type to filter commands"));
+    query_input.update(cx, |state, cx| state.focus(window, cx));
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let query = query_input.read(cx).value();
+        let muted = Theme::global(cx).colors.muted_foreground;
+        let accent = Theme::global(cx).colors.accent;
+        let rows = matching(&commands, &query).into_iter().enumerate().map(|(i, command)| {
+            let editor = editor.clone();
+            let handler = command.handler.clone();
+            div()
+                .id(("palette-command", i))
+                .px_2()
+                .py_1()
+                .cursor_pointer()
+                .hover(move |this| this.bg(accent))
+                .on_click(move |_, window, cx| {
+                    window.close_dialog(cx);
+                    let _ = editor.update(cx, |ed, cx| handler(ed, window, cx));
+                })
+                .child(command.label.clone())
+        });
+        let first = matching(&commands, &query).first().map(|c| c.handler.clone());
+        let editor = editor.clone();
+        dialog
+            .title("Run Command")
+            .w(px(420.0))
+            .child(
+                v_flex()
+                    .gap_2()
+                    .child(Input::new(&query_input))
+                    .child(div().id("palette-commands").max_h(px(280.0)).overflow_y_scroll().children(rows))
+                    .when(commands.is_empty(), |this| this.child(div().text_color(muted).child("No extension commands are active."))),
+            )
+            .confirm()
+            .button_props(DialogButtonProps::default().ok_text("Run"))
+            .on_ok(move |_, window, cx| {
+                let Some(handler) = first.clone() else { return false };
+                let _ = editor.update(cx, |ed, cx| handler(ed, window, cx));
+                true
+            })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{matching, Contributions, Extension, Extensions};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[derive(Default)]
+    struct Probe {
+        deactivated: Cell<usize>,
+    }
+
+    impl Extension for Probe {
+        fn id(&self) -> &'static str {
+            "probe"
+        }
+
+        fn name(&self) -> &'static str {
+            "Probe"
+        }
+
+        fn activate(self: Rc<Self>, contributions: &mut Contributions) {
+            contributions
+                .menu_entry("Probe Entry", |_, _, _| {})
+                .command("Probe: Run", |_, _, _| {})
+                .command("Probe: Reset", |_, _, _| {});
+        }
+
+        fn deactivate(&self) {
+            self.deactivated.set(self.deactivated.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_contributions_follow_activation() {
+        let probe = Rc::new(Probe::default());
+        let mut extensions = Extensions::default();
+        extensions.register(probe.clone(), false);
+        assert!(extensions.commands().is_empty());
+
+        extensions.set_enabled("probe", true);
+        assert_eq!(extensions.commands().len(), 2);
+        assert_eq!(extensions.menu_entries()[0].label, "Probe Entry");
+        // Enabling twice doesn't duplicate anything
+        extensions.set_enabled("probe", true);
+        assert_eq!(extensions.commands().len(), 2);
+
+        extensions.set_enabled("probe", false);
+        assert!(extensions.commands().is_empty());
+        assert_eq!(probe.deactivated.get(), 1);
+        assert_eq!(extensions.list().collect::<Vec<_>>(), vec![("probe", "Probe", false)]);
+
+        extensions.register(Rc::new(Probe::default()), true);
+        assert_eq!(extensions.list().count(), 1);
+    }
+
+    #[test]
+    fn test_matching_commands() {
+        let mut contributions = Contributions::default();
+        contributions.command("Word Goal: Set...", |_, _, _| {}).command("Word Goal: Clear", |_, _, _| {});
+        let commands = contributions.commands;
+        let labels = |query| matching(&commands, query).iter().map(|c| c.label.to_string()).collect::<Vec<_>>();
+        assert_eq!(labels("goal clear"), vec!["Word Goal: Clear"]);
+        assert_eq!(labels("GOAL").len(), 2);
+        assert_eq!(labels(""), labels("goal"));
+        assert!(labels("zoom").is_empty());
+    }
+}
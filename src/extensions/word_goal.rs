@@ -0,0 +1,129 @@
+//! Word goal: a minimum word count per document, with progress in the
+//! status bar. Built on the extension points rather than the editor.
+
+use gpui::*;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::input::{Input, InputState};
+use gpui_component::{h_flex, WindowExt};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{Contributions, Extension, StatusItem, Tone};
+use crate::editor::TextEditor;
+
+/// Goals by editor.
+#[derive(Default)]
+pub struct WordGoal {
+    goals: RefCell<HashMap<EntityId, usize>>,
+}
+
+/// Status bar text and tone for `words` written towards `goal`.
+fn progress(words: usize, goal: usize) -> StatusItem {
+    if words >= goal {
+        StatusItem {
+            text: format!("Goal reached: {} words", TextEditor::format_with_commas(words)).into(),
+            tone: Tone::Success,
+        }
+    } else {
+        StatusItem {
+            text: format!(
+                "{} / {} words ({}%)",
+                TextEditor::format_with_commas(words),
+                TextEditor::format_with_commas(goal),
+                words * 100 / goal,
+            )
+            .into(),
+            tone: Tone::Muted,
+        }
+    }
+}
+
+impl WordGoal {
+    /// Ask for the goal of the editor in `cx`; an empty answer clears it.
+    fn goal_dialog(self: &Rc<Self>, window: &mut Window, cx: &mut Context<TextEditor>) {
+        let id = cx.entity_id();
+        let current = self.goals.borrow().get(&id).map(|g| g.to_string()).unwrap_or_default();
+        let goal_input = cx.new(|cx| InputState::new(window, cx).placeholder("e.g. 500").default_value(current));
+        goal_input.update(cx, |state, cx| state.focus(window, cx));
+        let this = self.clone();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let goal_input_for_ok = goal_input.clone();
+            let this = this.clone();
+            dialog
+                .title("Word Goal")
+                .w(px(320.0))
+                .child(h_flex().gap_3().items_center().child("Words").child(div().flex_grow().child(Input::new(&goal_input))))
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Set"))
+                .on_ok(move |_, window, cx| {
+                    let text = goal_input_for_ok.read(cx).value();
+                    match text.trim() {
+                        "" => {
+                            this.goals.borrow_mut().remove(&id);
+                        }
+                        text => match text.parse::<usize>() {
+                            Ok(goal) if goal > 0 => {
+                                this.goals.borrow_mut().insert(id, goal);
+                            }
+                            _ => return false,
+                        },
+                    }
+                    window.refresh();
+                    true
+                })
+        });
+    }
+}
+
+impl Extension for WordGoal {
+    fn id(&self) -> &'static str {
+        "word_goal"
+    }
+
+    fn name(&self) -> &'static str {
+        "Word Goal"
+    }
+
+    fn activate(self: Rc<Self>, contributions: &mut Contributions) {
+        let status = self.clone();
+        let menu = self.clone();
+        let set = self.clone();
+        let clear = self;
+        contributions
+            .status_item(move |ed, cx| {
+                let goal = *status.goals.borrow().get(&cx.entity_id())?;
+                // Large files don't get their words counted
+                if ed.large_file {
+                    return None;
+                }
+                Some(progress(ed.stats().words, goal))
+            })
+            .menu_entry("Word Goal...", move |_, window, cx| menu.goal_dialog(window, cx))
+            .command("Word Goal: Set...", move |_, window, cx| set.goal_dialog(window, cx))
+            .command("Word Goal: Clear", move |_, _, cx| {
+                clear.goals.borrow_mut().remove(&cx.entity_id());
+                cx.notify();
+            });
+    }
+
+    fn deactivate(&self) {
+        self.goals.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{progress, Tone};
+
+    #[test]
+    fn test_progress() {
+        let item = progress(1250, 2000);
+        assert_eq!(item.text, "1,250 / 2,000 words (62%)");
+        assert_eq!(item.tone, Tone::Muted);
+        let item = progress(2000, 2000);
+        assert_eq!(item.text, "Goal reached: 2,000 words");
+        assert_eq!(item.tone, Tone::Success);
+    }
+}
@@ -0,0 +1,103 @@
+//! Converting files without opening a window, for scripts:
+//! `onetext --export-pdf input.txt output.pdf [--theme NAME]`.
+
+use anyhow::{anyhow, Context};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::editor::{export_to_pdf, is_markdown_file, PdfConfig, Rgb};
+use crate::settings::AppSettings;
+
+/// Export `input` to the PDF `output` with the last-used PDF options,
+/// coloured like `theme` (black on white without one).
+pub fn export_pdf(input: &Path, output: &Path, theme: Option<&str>, themes_dir: &Path) -> anyhow::Result<()> {
+    let bytes = fs::read(input).with_context(|| format!("Couldn't read {}", input.display()))?;
+    let text = String::from_utf8(bytes).map_err(|_| anyhow!("{} isn't UTF-8 text", input.display()))?;
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(&text);
+
+    let options = AppSettings::load().pdf_export;
+    let mut config = PdfConfig {
+        font_size: options.font_size,
+        margin: options.margin,
+        page_size: options.page_size,
+        orientation: options.orientation,
+        line_numbers: options.line_numbers,
+        header: options.include_header.then(|| options.header_template.clone()),
+        footer: Some(options.footer_template.clone()).filter(|f| !f.trim().is_empty()),
+        file_name: input.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+        file_path: fs::canonicalize(input).unwrap_or_else(|_| input.to_path_buf()).display().to_string(),
+        date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        font_family: options.font_family.clone(),
+        render_markdown: options.render_markdown && is_markdown_file(Some(input)),
+        ..PdfConfig::default()
+    };
+    if let Some(name) = theme {
+        let (background, foreground) = theme_colors(themes_dir, name)?;
+        config.background_rgb = background;
+        config.text_rgb = foreground;
+    }
+
+    export_to_pdf(text, output, &config)?;
+    info!(input = ?input, output = ?output, "Exported without a window");
+    Ok(())
+}
+
+/// Background and foreground of the theme called `name` in `themes_dir`.
+fn theme_colors(themes_dir: &Path, name: &str) -> anyhow::Result<(Rgb, Rgb)> {
+    let entries = fs::read_dir(themes_dir).with_context(|| format!("Couldn't list {}", themes_dir.display()))?;
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Ok(json) = fs::read_to_string(&path) else { continue };
+        let Ok(file) = serde_json::from_str::<serde_json::Value>(&json) else { continue };
+        let themes = file["themes"].as_array().into_iter().flatten();
+        for theme in themes.filter(|t| t["name"].as_str().is_some_and(|n| n.eq_ignore_ascii_case(name))) {
+            let color = |key: &str| theme["colors"][key].as_str().and_then(parse_hex_color);
+            if let (Some(background), Some(foreground)) = (color("background"), color("foreground")) {
+                return Ok((background, foreground));
+            }
+        }
+    }
+    Err(anyhow!("No theme named \"{}\" in {}", name, themes_dir.display()))
+}
+
+/// `#rgb`, `#rrggbb` or `#rrggbbaa` as RGB; alpha is ignored.
+fn parse_hex_color(text: &str) -> Option<Rgb> {
+    let hex = text.strip_prefix('#')?;
+    let channel = |i: usize, len: usize| {
+        let value = u8::from_str_radix(hex.get(i * len..(i + 1) * len)?, 16).ok()?;
+        Some(if len == 1 { value * 17 } else { value })
+    };
+    let len = match hex.len() {
+        3 => 1,
+        6 | 8 => 2,
+        _ => return None,
+    };
+    Some((channel(0, len)?, channel(1, len)?, channel(2, len)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_hex_color, theme_colors};
+    use std::fs;
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#040404"), Some((4, 4, 4)));
+        assert_eq!(parse_hex_color("#5da60222"), Some((0x5d, 0xa6, 0x02)));
+        assert_eq!(parse_hex_color("#fff"), Some((255, 255, 255)));
+        assert_eq!(parse_hex_color("fff"), None);
+        assert_eq!(parse_hex_color("#12345"), None);
+    }
+
+    #[test]
+    fn test_theme_colors() {
+        let dir = tempfile::tempdir().unwrap();
+        let theme = r##"{"themes": [{"name": "Night", "colors": {"background": "#000000", "foreground": "#ffffff"}}]}"##;
+        fs::write(dir.path().join("night.json"), theme).unwrap();
+        assert_eq!(theme_colors(dir.path(), "night").unwrap(), ((0, 0, 0), (255, 255, 255)));
+        assert!(theme_colors(dir.path(), "Day").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Single-instance support: later launches hand their file to the running
+//! instance over a unix socket (named pipe on Windows) and exit.
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+/// A file (or empty window) requested by another launch.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OpenRequest {
+    pub file: Option<PathBuf>,
+    #[serde(default)]
+    pub read_only: bool,
+    /// Open in a new window even if the current one is empty.
+    #[serde(default)]
+    pub new_window: bool,
+    /// 0-based line and column to put the caret on.
+    #[serde(default)]
+    pub position: Option<(usize, usize)>,
+}
+
+impl OpenRequest {
+    /// One line of the wire format; paths are made absolute since the
+    /// running instance has its own working directory.
+    fn to_line(&self) -> Option<String> {
+        let mut request = self.clone();
+        request.file = request.file.map(|p| std::path::absolute(&p).unwrap_or(p));
+        let mut line = serde_json::to_string(&request).ok()?;
+        line.push('\n');
+        Some(line)
+    }
+}
+
+/// Pass `request` to an already running instance. Returns false when there
+/// is none, in which case this process should become the instance.
+pub fn forward(request: &OpenRequest) -> bool {
+    let Some(line) = request.to_line() else {
+        return false;
+    };
+    let sent = platform::send(&line);
+    if sent {
+        debug!(?request, "Forwarded to running instance");
+    }
+    sent
+}
+
+/// Start accepting requests from later launches.
+pub fn listen() -> Option<UnboundedReceiver<OpenRequest>> {
+    let (tx, rx) = unbounded();
+    match platform::serve(tx) {
+        Ok(()) => Some(rx),
+        Err(err) => {
+            warn!(error = %err, "Failed to start single-instance listener");
+            None
+        }
+    }
+}
+
+/// Parse each complete line of `data` and pass it on.
+fn dispatch(data: &[u8], tx: &UnboundedSender<OpenRequest>) {
+    for line in String::from_utf8_lossy(data).lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str::<OpenRequest>(line) {
+            Ok(request) => {
+                let _ = tx.unbounded_send(request);
+            }
+            Err(err) => warn!(error = %err, "Ignoring malformed open request"),
+        }
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use super::{dispatch, OpenRequest};
+    use directories::ProjectDirs;
+    use futures::channel::mpsc::UnboundedSender;
+    use std::io::{Read, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+
+    fn socket_path() -> Option<PathBuf> {
+        let dirs = ProjectDirs::from("com", "OneText", "OneText")?;
+        let dir = dirs.runtime_dir().unwrap_or(dirs.config_dir());
+        Some(dir.join("onetext.sock"))
+    }
+
+    pub fn send(line: &str) -> bool {
+        let Some(path) = socket_path() else {
+            return false;
+        };
+        UnixStream::connect(path)
+            .and_then(|mut stream| stream.write_all(line.as_bytes()))
+            .is_ok()
+    }
+
+    pub fn serve(tx: UnboundedSender<OpenRequest>) -> anyhow::Result<()> {
+        let path = socket_path().ok_or_else(|| anyhow::anyhow!("No runtime directory"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Nobody answered on it, so any existing socket is left over from a crash
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)?;
+
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut data = Vec::new();
+                if stream.read_to_end(&mut data).is_ok() {
+                    dispatch(&data, &tx);
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{dispatch, OpenRequest};
+    use futures::channel::mpsc::UnboundedSender;
+    use std::io::Write;
+    use tracing::warn;
+
+    const PIPE_NAME: &str = r"\\.\pipe\OneText";
+
+    pub fn send(line: &str) -> bool {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(PIPE_NAME)
+            .and_then(|mut pipe| pipe.write_all(line.as_bytes()))
+            .is_ok()
+    }
+
+    pub fn serve(tx: UnboundedSender<OpenRequest>) -> anyhow::Result<()> {
+        std::thread::spawn(move || {
+            use windows::core::w;
+            use windows::Win32::Foundation::{CloseHandle, ERROR_PIPE_CONNECTED};
+            use windows::Win32::Storage::FileSystem::{ReadFile, FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_INBOUND};
+            use windows::Win32::System::Pipes::{
+                ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_READMODE_BYTE, PIPE_TYPE_BYTE, PIPE_WAIT,
+            };
+
+            unsafe {
+                let pipe = CreateNamedPipeW(
+                    w!(r"\\.\pipe\OneText"),
+                    PIPE_ACCESS_INBOUND | FILE_FLAG_FIRST_PIPE_INSTANCE,
+                    PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT,
+                    1,
+                    0,
+                    4096,
+                    0,
+                    None,
+                );
+                if pipe.is_invalid() {
+                    warn!("Failed to create single-instance pipe");
+                    return;
+                }
+
+                loop {
+                    // A client may connect between creation and this call
+                    if let Err(err) = ConnectNamedPipe(pipe, None) {
+                        if err.code() != ERROR_PIPE_CONNECTED.to_hresult() {
+                            warn!(error = %err, "Single-instance pipe failed");
+                            break;
+                        }
+                    }
+                    let mut data = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    loop {
+                        let mut read = 0u32;
+                        if ReadFile(pipe, Some(&mut buf), Some(&mut read), None).is_err() || read == 0 {
+                            break;
+                        }
+                        data.extend_from_slice(&buf[..read as usize]);
+                    }
+                    dispatch(&data, &tx);
+                    let _ = DisconnectNamedPipe(pipe);
+                }
+                let _ = CloseHandle(pipe);
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dispatch, OpenRequest};
+    use futures::channel::mpsc::unbounded;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_dispatch_parses_each_line() {
+        let (tx, mut rx) = unbounded();
+        let first = OpenRequest { file: Some(PathBuf::from("/tmp/a.txt")), read_only: true, new_window: false, position: Some((4, 2)) };
+        let line = first.to_line().unwrap();
+        dispatch(format!("{line}not json\n{{\"file\":null}}\n").as_bytes(), &tx);
+        assert_eq!(rx.try_next().unwrap(), Some(first));
+        assert_eq!(rx.try_next().unwrap(), Some(OpenRequest::default()));
+        assert!(rx.try_next().is_err());
+    }
+}
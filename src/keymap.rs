@@ -0,0 +1,418 @@
+//! Key bindings: a preset following one platform's or editor's habits,
+//! with the user's own bindings from settings layered on top.
+
+use gpui::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use tracing::{debug, warn};
+
+/// Context for bindings that must win over the text input's own.
+const INPUT: Option<&str> = Some("TextEditor > Input");
+
+/// Keystrokes, action name and context. In a preset layer an empty action
+/// removes the keys bound by an earlier layer.
+type Binding = (&'static str, &'static str, Option<&'static str>);
+
+/// Bindings every preset starts from, written with `ctrl`.
+const BASE: &[Binding] = &[
+    ("ctrl-p", "global::ExportPdfAction", None),
+    ("ctrl-f", "global::FindAction", None),
+    ("ctrl-f", "global::FindAction", INPUT),
+    ("f3", "editor::FindNextAction", None),
+    ("shift-f3", "editor::FindPreviousAction", None),
+    ("escape", "editor::CloseFindAction", Some("FindBar > Input")),
+    ("ctrl-n", "global::NewFileAction", None),
+    ("ctrl-shift-n", "global::NewWindowAction", None),
+    ("ctrl-shift-w", "global::CloseWindowAction", None),
+    ("ctrl-o", "global::OpenFileDialogAction", None),
+    ("ctrl-shift-o", "global::QuickOpenAction", None),
+    ("ctrl-s", "global::SaveFileAction", None),
+    ("ctrl-shift-s", "global::SaveFileAsAction", None),
+    ("ctrl-alt-pageup", "global::PreviousFileAction", None),
+    ("ctrl-alt-pagedown", "global::NextFileAction", None),
+    ("ctrl-,", "global::PreferencesAction", None),
+    ("alt-f4", "global::ExitAppAction", None),
+    ("f11", "global::ToggleFullScreenAction", None),
+    // editor bindings
+    ("ctrl-c", "input::Copy", None),
+    ("ctrl-v", "editor::NormalizePasteAction", None),
+    ("ctrl-x", "input::Cut", None),
+    ("ctrl-a", "input::SelectAll", None),
+    ("ctrl-z", "editor::UndoAction", None),
+    ("ctrl-shift-z", "editor::RedoAction", None),
+    ("ctrl-y", "editor::RedoAction", None), // Alternate Redo
+    ("ctrl-shift-]", "editor::PromoteHeadingAction", None),
+    ("ctrl-shift-[", "editor::DemoteHeadingAction", None),
+    ("ctrl-shift-d", "editor::DuplicateLineAction", None),
+    ("ctrl-shift-k", "editor::DeleteLineAction", None),
+    ("ctrl-g", "editor::GoToLineAction", None),
+    ("ctrl-/", "editor::ToggleCommentAction", None),
+    ("ctrl-shift-u", "editor::UpperCaseAction", None),
+    ("ctrl-shift-l", "editor::LowerCaseAction", None),
+    ("ctrl-alt-shift-1", "editor::CopyToSlot1", None),
+    ("ctrl-alt-shift-2", "editor::CopyToSlot2", None),
+    ("ctrl-alt-shift-3", "editor::CopyToSlot3", None),
+    ("ctrl-alt-1", "editor::PasteFromSlot1", None),
+    ("ctrl-alt-2", "editor::PasteFromSlot2", None),
+    ("ctrl-alt-3", "editor::PasteFromSlot3", None),
+    // Two-step chords, as in Sublime Text and VS Code
+    ("ctrl-k ctrl-c", "editor::ToggleCommentAction", None),
+    ("ctrl-k ctrl-u", "editor::UpperCaseAction", None),
+    ("ctrl-k ctrl-l", "editor::LowerCaseAction", None),
+    // Overrides the input's own indent so the tab setting applies
+    ("tab", "editor::InsertTabAction", INPUT),
+    ("shift-tab", "editor::PreviousCellAction", INPUT),
+    ("alt-up", "editor::MoveLineUpAction", INPUT),
+    ("alt-down", "editor::MoveLineDownAction", INPUT),
+    ("alt-shift-up", "editor::BlockSelectUp", INPUT),
+    ("alt-shift-down", "editor::BlockSelectDown", INPUT),
+    ("alt-shift-left", "editor::BlockSelectLeft", INPUT),
+    ("alt-shift-right", "editor::BlockSelectRight", INPUT),
+];
+
+/// Notepad++ habits.
+const WINDOWS: &[Binding] = &[
+    ("ctrl-d", "editor::DuplicateLineAction", None),
+    ("ctrl-l", "editor::DeleteLineAction", None),
+    ("ctrl-q", "editor::ToggleCommentAction", None),
+    ("ctrl-shift-up", "editor::MoveLineUpAction", INPUT),
+    ("ctrl-shift-down", "editor::MoveLineDownAction", INPUT),
+    ("ctrl-pageup", "global::PreviousFileAction", None),
+    ("ctrl-pagedown", "global::NextFileAction", None),
+];
+
+/// GNOME and KDE habits.
+const LINUX: &[Binding] = &[
+    ("ctrl-q", "global::ExitAppAction", None),
+    ("ctrl-pageup", "global::PreviousFileAction", None),
+    ("ctrl-pagedown", "global::NextFileAction", None),
+];
+
+/// macOS habits, applied after `ctrl` has become `cmd`.
+const MAC_OS: &[Binding] = &[
+    ("alt-f4", "", None),
+    ("cmd-y", "", None),
+    ("cmd-q", "global::ExitAppAction", None),
+    ("cmd-w", "global::CloseWindowAction", None),
+    ("cmd-l", "editor::GoToLineAction", None),
+    ("cmd-g", "editor::FindNextAction", None),
+    ("cmd-shift-g", "editor::FindPreviousAction", None),
+    ("cmd-alt-left", "global::PreviousFileAction", None),
+    ("cmd-alt-right", "global::NextFileAction", None),
+    ("ctrl-cmd-f", "global::ToggleFullScreenAction", None),
+];
+
+/// Vim's insert-mode keys; there is no modal editing.
+const VIM: &[Binding] = &[
+    ("ctrl-w", "input::DeleteToPreviousWordStart", INPUT),
+    ("ctrl-u", "input::DeleteToBeginningOfLine", INPUT),
+    ("ctrl-h", "input::Backspace", INPUT),
+    ("ctrl-r", "editor::RedoAction", None),
+    ("ctrl-[", "editor::CloseFindAction", Some("FindBar > Input")),
+];
+
+/// Emacs movement, kill/yank and `ctrl-x` chords.
+const EMACS: &[Binding] = &[
+    // `ctrl-x` starts chords, so it can't cut as well
+    ("ctrl-x", "", None),
+    ("ctrl-x ctrl-s", "global::SaveFileAction", None),
+    ("ctrl-x ctrl-w", "global::SaveFileAsAction", None),
+    ("ctrl-x ctrl-f", "global::OpenFileDialogAction", None),
+    ("ctrl-x ctrl-c", "global::ExitAppAction", None),
+    ("ctrl-x h", "input::SelectAll", None),
+    ("ctrl-s", "global::FindAction", None),
+    ("ctrl-s", "global::FindAction", INPUT),
+    ("ctrl-r", "editor::FindPreviousAction", None),
+    ("ctrl-g", "editor::CloseFindAction", Some("FindBar > Input")),
+    ("alt-g g", "editor::GoToLineAction", None),
+    ("ctrl-/", "editor::UndoAction", None),
+    ("ctrl-_", "editor::UndoAction", None),
+    ("alt-;", "editor::ToggleCommentAction", None),
+    ("alt-w", "input::Copy", None),
+    ("ctrl-w", "input::Cut", None),
+    ("ctrl-y", "editor::NormalizePasteAction", None),
+    ("ctrl-a", "input::MoveHome", INPUT),
+    ("ctrl-e", "input::MoveEnd", INPUT),
+    ("ctrl-f", "input::MoveRight", INPUT),
+    ("ctrl-b", "input::MoveLeft", INPUT),
+    ("ctrl-n", "input::MoveDown", INPUT),
+    ("ctrl-p", "input::MoveUp", INPUT),
+    ("alt-f", "input::MoveToNextWord", INPUT),
+    ("alt-b", "input::MoveToPreviousWord", INPUT),
+    ("ctrl-d", "input::Delete", INPUT),
+    ("ctrl-v", "input::MovePageDown", INPUT),
+    ("alt-v", "input::MovePageUp", INPUT),
+];
+
+/// A set of key bindings to start from.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeymapPreset {
+    /// Whichever of Windows, macOS and Linux this is.
+    #[default]
+    System,
+    Windows,
+    MacOs,
+    Linux,
+    Vim,
+    Emacs,
+}
+
+impl KeymapPreset {
+    pub const ALL: [KeymapPreset; 6] = [
+        KeymapPreset::System,
+        KeymapPreset::Windows,
+        KeymapPreset::MacOs,
+        KeymapPreset::Linux,
+        KeymapPreset::Vim,
+        KeymapPreset::Emacs,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::System => "System",
+            Self::Windows => "Windows",
+            Self::MacOs => "macOS",
+            Self::Linux => "Linux",
+            Self::Vim => "Vim",
+            Self::Emacs => "Emacs",
+        }
+    }
+
+    /// The platform preset `System` stands for here.
+    fn resolve(self) -> Self {
+        match self {
+            Self::System if cfg!(target_os = "macos") => Self::MacOs,
+            Self::System if cfg!(target_os = "windows") => Self::Windows,
+            Self::System => Self::Linux,
+            other => other,
+        }
+    }
+
+    /// Layers applied over `BASE`, in order.
+    fn layers(self) -> Vec<&'static [Binding]> {
+        let platform = if cfg!(target_os = "macos") { MAC_OS } else { LINUX };
+        match self.resolve() {
+            Self::Windows => vec![WINDOWS],
+            Self::MacOs => vec![MAC_OS],
+            Self::Vim => vec![platform, VIM],
+            Self::Emacs => vec![platform, EMACS],
+            _ => vec![LINUX],
+        }
+    }
+
+    /// Whether `BASE` is written with `cmd` instead of `ctrl`.
+    fn uses_cmd(self) -> bool {
+        match self.resolve() {
+            Self::MacOs => true,
+            // Their own keys stay ctrl keys, leaving cmd to the platform
+            Self::Vim | Self::Emacs => cfg!(target_os = "macos"),
+            _ => false,
+        }
+    }
+
+    /// `keys`, written with `ctrl`, as this preset binds them: with `cmd`
+    /// on macOS. For bindings made outside the tables here.
+    pub fn platform_keys(self, keys: &str) -> String {
+        if self.uses_cmd() { with_cmd(keys) } else { keys.to_string() }
+    }
+}
+
+/// A binding ready to install; an empty `action` disables the keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindingSpec {
+    pub keys: String,
+    pub action: String,
+    pub context: Option<&'static str>,
+}
+
+/// `keys` with a leading `ctrl` on each keystroke turned into `cmd`.
+fn with_cmd(keys: &str) -> String {
+    keys.split_whitespace()
+        .map(|stroke| match stroke.strip_prefix("ctrl-") {
+            Some(rest) => format!("cmd-{}", rest),
+            None => stroke.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The bindings of `preset` with `overrides` (keystrokes to action name,
+/// empty to unbind) on top. A later binding for the same keys and context
+/// replaces an earlier one.
+pub fn compose(preset: KeymapPreset, overrides: &BTreeMap<String, String>) -> Vec<BindingSpec> {
+    let mut bindings: Vec<BindingSpec> = Vec::new();
+    let base = BASE.iter().map(|&(keys, action, context)| {
+        (preset.platform_keys(keys), action, context)
+    });
+    let layers = preset.layers().into_iter().flatten().map(|&(keys, action, context)| (keys.to_string(), action, context));
+    for (keys, action, context) in base.chain(layers) {
+        bindings.retain(|b| !(b.keys == keys && b.context == context));
+        if !action.is_empty() {
+            bindings.push(BindingSpec { keys, action: action.to_string(), context });
+        }
+    }
+    // The user's keys replace the preset's in every context, and are bound
+    // for the editor's input too so they beat its built-in keys
+    for (keys, action) in overrides {
+        let keys = keys.split_whitespace().collect::<Vec<_>>().join(" ");
+        bindings.retain(|b| b.keys != keys);
+        for context in [None, INPUT] {
+            bindings.push(BindingSpec { keys: keys.clone(), action: action.trim().to_string(), context });
+        }
+    }
+    bindings
+}
+
+/// Status bar text while the first keys of a chord await the rest.
+pub fn chord_hint(pending: &[Keystroke]) -> String {
+    let keys: Vec<String> = pending.iter().map(Keystroke::unparse).collect();
+    format!("{} \u{2026} (waiting for next key)", keys.join(" "))
+}
+
+/// The bindings made before the first `install`: the widgets' own.
+struct BuiltInBindings(Vec<KeyBinding>);
+
+impl Global for BuiltInBindings {}
+
+/// Bind the keys of `preset` and `overrides`, skipping (and logging) any
+/// that name unknown actions or don't parse.
+pub fn install(preset: KeymapPreset, overrides: &BTreeMap<String, String>, cx: &mut App) {
+    if !cx.has_global::<BuiltInBindings>() {
+        let built_in = cx.key_bindings().borrow().bindings().cloned().collect();
+        cx.set_global(BuiltInBindings(built_in));
+    }
+    let specs = compose(preset, overrides);
+    let mut bindings = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let action = if spec.action.is_empty() {
+            Box::new(NoAction) as Box<dyn Action>
+        } else {
+            match cx.build_action(&spec.action, None) {
+                Ok(action) => action,
+                Err(err) => {
+                    warn!(keys = %spec.keys, action = %spec.action, error = %err, "Unknown action in key bindings");
+                    continue;
+                }
+            }
+        };
+        let context = spec.context.and_then(|c| KeyBindingContextPredicate::parse(c).ok()).map(Rc::new);
+        match KeyBinding::load(&spec.keys, action, context, false, None, cx.keyboard_mapper().as_ref()) {
+            Ok(binding) => bindings.push(binding),
+            Err(err) => warn!(keys = %spec.keys, error = %err, "Invalid keystrokes in key bindings"),
+        }
+    }
+    debug!(?preset, count = bindings.len(), "Installing key bindings");
+    cx.bind_keys(bindings);
+}
+
+/// Drop every binding made since the first `install`, so another preset
+/// can be installed without the old one's keys lingering.
+pub fn reset(cx: &mut App) {
+    let Some(built_in) = cx.try_global::<BuiltInBindings>().map(|b| b.0.clone()) else { return };
+    cx.clear_key_bindings();
+    cx.bind_keys(built_in);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chord_hint, compose, install, reset, with_cmd, BindingSpec, KeymapPreset, INPUT};
+    use gpui::{App, KeyBinding, Keystroke, NoAction, TestAppContext};
+    use std::collections::BTreeMap;
+
+    fn action<'a>(bindings: &'a [BindingSpec], keys: &str, context: Option<&str>) -> Option<&'a str> {
+        bindings.iter().find(|b| b.keys == keys && b.context == context).map(|b| b.action.as_str())
+    }
+
+    #[test]
+    fn test_with_cmd() {
+        assert_eq!(with_cmd("ctrl-alt-1"), "cmd-alt-1");
+        assert_eq!(with_cmd("ctrl-k ctrl-c"), "cmd-k cmd-c");
+        assert_eq!(with_cmd("alt-up"), "alt-up");
+    }
+
+    #[test]
+    fn test_presets_layer_over_the_base() {
+        let mac = compose(KeymapPreset::MacOs, &BTreeMap::new());
+        assert_eq!(action(&mac, "cmd-s", None), Some("global::SaveFileAction"));
+        assert_eq!(action(&mac, "ctrl-s", None), None);
+        assert_eq!(action(&mac, "alt-f4", None), None);
+        assert_eq!(action(&mac, "cmd-q", None), Some("global::ExitAppAction"));
+
+        let windows = compose(KeymapPreset::Windows, &BTreeMap::new());
+        assert_eq!(action(&windows, "ctrl-d", None), Some("editor::DuplicateLineAction"));
+        assert_eq!(action(&windows, "ctrl-s", None), Some("global::SaveFileAction"));
+
+        let emacs = compose(KeymapPreset::Emacs, &BTreeMap::new());
+        assert_eq!(action(&emacs, "ctrl-x", None), None);
+        assert_eq!(action(&emacs, "ctrl-x ctrl-s", None), Some("global::SaveFileAction"));
+        assert_eq!(action(&emacs, "ctrl-s", None), Some("global::FindAction"));
+        assert_eq!(action(&emacs, "ctrl-a", INPUT), Some("input::MoveHome"));
+        // Replaced, not added alongside
+        assert_eq!(emacs.iter().filter(|b| b.keys == "ctrl-s" && b.context.is_none()).count(), 1);
+    }
+
+    #[test]
+    fn test_overrides_replace_and_unbind() {
+        let overrides = BTreeMap::from([
+            ("ctrl-d".to_string(), "editor::DeleteLineAction".to_string()),
+            ("ctrl-f".to_string(), String::new()),
+        ]);
+        let bindings = compose(KeymapPreset::Linux, &overrides);
+        assert_eq!(action(&bindings, "ctrl-d", None), Some("editor::DeleteLineAction"));
+        assert_eq!(action(&bindings, "ctrl-d", INPUT), Some("editor::DeleteLineAction"));
+        // Unbinding covers the input context the preset bound too
+        assert_eq!(action(&bindings, "ctrl-f", None), Some(""));
+        assert_eq!(action(&bindings, "ctrl-f", INPUT), Some(""));
+        assert_eq!(bindings.iter().filter(|b| b.keys == "ctrl-f").count(), 2);
+    }
+
+    #[test]
+    fn test_chords_follow_the_preset() {
+        let linux = compose(KeymapPreset::Linux, &BTreeMap::new());
+        assert_eq!(action(&linux, "ctrl-k ctrl-c", None), Some("editor::ToggleCommentAction"));
+        let mac = compose(KeymapPreset::MacOs, &BTreeMap::new());
+        assert_eq!(action(&mac, "cmd-k cmd-c", None), Some("editor::ToggleCommentAction"));
+
+        let overrides = BTreeMap::from([("ctrl-k   ctrl-d".to_string(), "editor::DuplicateLineAction".to_string())]);
+        let bindings = compose(KeymapPreset::Linux, &overrides);
+        assert_eq!(action(&bindings, "ctrl-k ctrl-d", None), Some("editor::DuplicateLineAction"));
+    }
+
+    #[test]
+    fn test_platform_keys() {
+        assert_eq!(KeymapPreset::MacOs.platform_keys("ctrl-k a"), "cmd-k a");
+        assert_eq!(KeymapPreset::Linux.platform_keys("ctrl-k a"), "ctrl-k a");
+        assert_eq!(KeymapPreset::Windows.platform_keys("ctrl-' a"), "ctrl-' a");
+    }
+
+    #[test]
+    fn test_chord_hint() {
+        let pending = [Keystroke::parse("ctrl-k").unwrap()];
+        assert_eq!(chord_hint(&pending), "ctrl-k \u{2026} (waiting for next key)");
+    }
+
+    fn bound(keys: &str, cx: &App) -> bool {
+        let input: Vec<Keystroke> = keys.split_whitespace().map(|k| Keystroke::parse(k).unwrap()).collect();
+        !cx.key_bindings().borrow().all_bindings_for_input(&input).is_empty()
+    }
+
+    #[gpui::test]
+    fn test_reset_swaps_presets(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            cx.bind_keys([KeyBinding::new("f9", NoAction, None)]);
+            install(KeymapPreset::Emacs, &BTreeMap::new(), cx);
+            assert!(bound("ctrl-x ctrl-s", cx));
+
+            reset(cx);
+            assert!(!bound("ctrl-x ctrl-s", cx));
+            // The widgets' own keys stay
+            assert!(bound("f9", cx));
+
+            install(KeymapPreset::Windows, &BTreeMap::new(), cx);
+            assert!(bound("ctrl-d", cx));
+            assert!(!bound("ctrl-x ctrl-s", cx));
+        });
+    }
+}
@@ -3,17 +3,24 @@
 mod settings;
 mod workspace;
 mod editor;
+mod extensions;
+mod headless;
+mod instance;
+mod keymap;
+mod style;
+mod themes;
+mod watcher;
 
 use gpui::*;
-use gpui_component::{Root, Theme, ThemeRegistry};
-use gpui_component::input::{Copy, Cut, SelectAll};
+use gpui_component::Root;
 use gpui_component_assets::Assets;
 use clap::Parser;
+use futures::StreamExt;
 use std::path::PathBuf;
-use tracing::warn;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tracing::{debug, warn};
 use workspace::Workspace;
-use settings::AppSettings;
-use crate::editor::{UndoAction, RedoAction, NormalizePasteAction}; // Import editor actions
+use settings::{AppSettings, SessionOverrides};
 
 /// Returns the compilation directory or the directory containing the executable.
 pub fn get_app_root() -> PathBuf {
@@ -33,6 +40,13 @@ actions!(global, [
     SaveFileAction,
     SaveFileAsAction,
     FindAction,
+    PreferencesAction,
+    NewWindowAction,
+    CloseWindowAction,
+    PreviousFileAction,
+    NextFileAction,
+    QuickOpenAction,
+    ToggleFullScreenAction,
     ExitAppAction
 ]);
 
@@ -41,8 +55,71 @@ actions!(global, [
 #[command(version = "0.1.3")]
 #[command(about = "A text editor", long_about = None)]
 struct Cli {
-    /// Optional file to open on startup
+    /// Optional file to open on startup, optionally as `file:line[:column]`
     file: Option<PathBuf>,
+    /// Line to put the cursor on (1-based)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    line: Option<u64>,
+    /// Column to put the cursor on (1-based); needs --line
+    #[arg(long, requires = "line", value_parser = clap::value_parser!(u64).range(1..))]
+    column: Option<u64>,
+    /// Open the file read-only
+    #[arg(long, alias = "read-only")]
+    readonly: bool,
+    /// Theme for this run, without saving it
+    #[arg(long)]
+    theme: Option<String>,
+    /// Editor font size in pixels for this run, without saving it
+    #[arg(long, value_parser = parse_font_size)]
+    font_size: Option<f32>,
+    /// Open in a new window of the running instance
+    #[arg(long)]
+    new_window: bool,
+    /// Start with default settings and keep any changes only until exit
+    #[arg(long)]
+    clean: bool,
+    /// Render on the CPU instead of the GPU
+    #[arg(long)]
+    software_rendering: bool,
+    /// Convert INPUT to the PDF OUTPUT and exit, without opening a window
+    #[arg(long, num_args = 2, value_names = ["INPUT", "OUTPUT"])]
+    export_pdf: Option<Vec<PathBuf>>,
+    /// Show FILE read-only in a bare window, apart from any running
+    /// instance, for use as a quick-look or preview command
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["file", "new_window", "export_pdf"])]
+    preview: Option<PathBuf>,
+}
+
+/// Font sizes the preferences allow.
+fn parse_font_size(text: &str) -> Result<f32, String> {
+    match text.parse::<f32>() {
+        Ok(size) if (6.0..=72.0).contains(&size) => Ok(size),
+        _ => Err("expected a size from 6 to 72".to_string()),
+    }
+}
+
+/// Split a trailing `:line` or `:line:column` (1-based) off `path`, as
+/// compilers print them. A path that exists as written is left whole.
+fn split_file_position(path: PathBuf) -> (PathBuf, Option<(usize, usize)>) {
+    if path.exists() {
+        return (path, None);
+    }
+    let text = path.to_string_lossy().into_owned();
+    let number = |part: &str| part.parse::<usize>().ok().filter(|&n| n > 0);
+    let Some((head, last)) = text.rsplit_once(':').filter(|(head, _)| !head.is_empty()) else {
+        return (path, None);
+    };
+    let Some(last) = number(last) else {
+        return (path, None);
+    };
+    let with_line = head
+        .rsplit_once(':')
+        .filter(|(file, _)| !file.is_empty())
+        .and_then(|(file, line)| Some((file, number(line)?)));
+    match with_line {
+        Some((file, line)) => (PathBuf::from(file), Some((line - 1, last - 1))),
+        None => (PathBuf::from(head), Some((last - 1, 0))),
+    }
 }
 
 fn main() {
@@ -55,155 +132,275 @@ fn main() {
         .init();
 
     let args = Cli::parse();
-    let settings = AppSettings::load();
 
-    let options = WindowOptions {
-        window_bounds: Some(AppSettings::window_bounds()),
-        titlebar: Some(gpui_component::TitleBar::title_bar_options()),
-        ..Default::default()
+    if let Some([input, output]) = args.export_pdf.as_deref() {
+        let themes_dir = get_app_root().join("assets/themes");
+        if let Err(err) = headless::export_pdf(input, output, args.theme.as_deref(), &themes_dir) {
+            eprintln!("onetext: {:#}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let (file, mut position) = match args.file.clone() {
+        Some(path) => {
+            let (path, position) = split_file_position(path);
+            (Some(path), position)
+        }
+        None => (None, None),
     };
+    if let Some(line) = args.line {
+        let column = args.column.unwrap_or(1);
+        position = Some((line as usize - 1, column as usize - 1));
+    }
+
+    if args.clean {
+        settings::install_store(Box::new(settings::MemoryStore::default()));
+    }
+    if let Some(path) = args.preview.clone() {
+        SessionOverrides { theme: args.theme.clone(), font_size: args.font_size }.install();
+        run_preview(path, args.software_rendering);
+        return;
+    }
+
+    // Hand the file to an already running OneText instead of starting another
+    let request = instance::OpenRequest {
+        file: file.clone(),
+        read_only: args.readonly,
+        new_window: args.new_window,
+        position,
+    };
+    // A clean session has settings of its own, so it never hands off
+    if !args.clean && instance::forward(&request) {
+        return;
+    }
+    // and never takes requests from other launches either
+    let requests = if args.clean { None } else { instance::listen() };
+
+    SessionOverrides { theme: args.theme.clone(), font_size: args.font_size }.install();
+    let settings = AppSettings::load();
+    if args.software_rendering || settings.software_rendering {
+        workspace::force_software_rendering();
+    }
 
     Application::new().with_assets(Assets).run(move |cx: &mut App| {
         // Initialize gpui-component (required before using components)
         gpui_component::init(cx);
 
+        style::init(cx);
+        extensions::init(&settings.disabled_extensions, cx);
+        settings::watch_config_file("settings.json", cx, reload_settings);
+        settings::report_save_errors(cx);
+        cx.on_app_quit(|cx| {
+            // Catch a maximize or full screen change the last event missed
+            save_window_bounds(cx);
+            settings::flush_saves();
+            async {}
+        })
+        .detach();
+
         // Load themes and set the default theme
-        let theme_name = SharedString::from(settings.theme.clone());
-        if let Err(err) = ThemeRegistry::watch_dir(
-            get_app_root().join("assets/themes"),
-            cx,
-            move |cx| {
-                if let Some(theme) = ThemeRegistry::global(cx)
-                    .themes()
-                    .get(&theme_name)
-                    .cloned()
-                {
-                    Theme::global_mut(cx).apply_config(&theme);
+        let theme_name = SessionOverrides::get().apply(&settings).theme;
+        themes::init(get_app_root().join("assets/themes"), theme_name, cx);
+
+        // Global Keybindings: the preset, then the user's own overrides
+        workspace::install_key_bindings(&settings, cx);
+
+        // Quit once the last window is closed; otherwise forget the closed one
+        cx.on_window_closed(|cx| {
+            if cx.windows().is_empty() {
+                cx.quit();
+            } else {
+                save_window_bounds(cx);
+            }
+        })
+        .detach();
+
+        // Windows opened from now on save their bounds as they move
+        SAVE_BOUNDS.store(true, Ordering::Relaxed);
+
+        if let Some(window) = open_workspace_window(cx, settings.clone(), file.clone(), args.readonly, position) {
+            window.update(cx, |_root, _window, cx| cx.activate(true)).ok();
+        }
+
+        if let Some(mut requests) = requests {
+            cx.spawn(async move |cx: &mut AsyncApp| {
+                while let Some(request) = requests.next().await {
+                    let _ = cx.update(|cx| handle_open_request(cx, request));
                 }
+            })
+            .detach();
+        }
+    });
+}
+
+/// Run just a preview window: no single-instance handoff, no window
+/// position saving and no settings watching, so it starts fast and leaves
+/// the editor's own state alone.
+fn run_preview(path: PathBuf, software_rendering: bool) {
+    let settings = AppSettings::load();
+    if software_rendering || settings.software_rendering {
+        workspace::force_software_rendering();
+    }
+
+    Application::new().with_assets(Assets).run(move |cx: &mut App| {
+        gpui_component::init(cx);
+        style::init(cx);
+        extensions::init(&settings.disabled_extensions, cx);
+        let theme_name = SessionOverrides::get().apply(&settings).theme;
+        themes::init(get_app_root().join("assets/themes"), theme_name, cx);
+        keymap::install(settings.keymap_preset, &settings.key_bindings, cx);
+        settings::report_save_errors(cx);
+        cx.on_app_quit(|_| {
+            settings::flush_saves();
+            async {}
+        })
+        .detach();
+
+        cx.on_window_closed(|cx| {
+            if cx.windows().is_empty() {
+                cx.quit();
             }
-        ) {
-            warn!(error = %err, "Failed to watch themes directory");
+        })
+        .detach();
+
+        if let Some(window) = open_workspace_window(cx, settings.clone(), Some(path), true, None) {
+            window.update(cx, |root, _window, cx| {
+                if let Ok(workspace) = root.view().clone().downcast::<Workspace>() {
+                    workspace.update(cx, |ws, cx| ws.set_preview(cx));
+                }
+                cx.activate(true);
+            }).ok();
         }
+    });
+}
+
+/// Open a file forwarded by another launch: in the frontmost window when it
+/// is empty and unmodified, otherwise in a new window.
+fn handle_open_request(cx: &mut App, request: instance::OpenRequest) {
+    let target = cx.active_window()
+        .or_else(|| cx.windows().last().copied())
+        .and_then(|window| window.downcast::<Root>())
+        .filter(|_| !request.new_window);
 
-        // Global Keybindings
-        cx.bind_keys([
-            KeyBinding::new("ctrl-p", ExportPdfAction, None),
-            KeyBinding::new("ctrl-f", FindAction, None),
-            KeyBinding::new("ctrl-n", NewFileAction, None),
-            KeyBinding::new("ctrl-o", OpenFileDialogAction, None),
-            KeyBinding::new("ctrl-s", SaveFileAction, None),
-            KeyBinding::new("ctrl-shift-s", SaveFileAsAction, None),
-            KeyBinding::new("alt-f4", ExitAppAction, None),
-            // editor bindings
-            KeyBinding::new("ctrl-c", Copy, None),
-            KeyBinding::new("ctrl-v", NormalizePasteAction, None),
-            KeyBinding::new("ctrl-x", Cut, None),
-            KeyBinding::new("ctrl-a", SelectAll, None),
-            KeyBinding::new("ctrl-z", UndoAction, None),
-            KeyBinding::new("ctrl-shift-z", RedoAction, None),
-            KeyBinding::new("ctrl-y", RedoAction, None), // Alternate Redo
-        ]);
-
-        let file_to_open = args.file.clone();
-
-        let window = cx.open_window(options, move |window, cx| {
-            // Create the workspace view
-            let workspace = cx.new(|cx| {
-                let mut ws = Workspace::new(window, cx, settings.clone());
-                if let Some(path) = file_to_open.clone() {
-                    ws.open_file(path, window, cx);
+    let reused = target.and_then(|window| {
+        window.update(cx, |root, window, cx| {
+            let workspace = root.view().clone().downcast::<Workspace>().ok()?;
+            workspace.update(cx, |ws, cx| {
+                let is_dirty = ws.editor_entity.as_ref().is_some_and(|e| e.read(cx).is_dirty(cx));
+                if ws.current_file(cx).is_some() || is_dirty {
+                    return None;
                 }
-                ws
-            });
-
-            // Window Persistence Polling (Windows Only)
-            #[cfg(target_os = "windows")]
-            {
-                std::thread::spawn(move || {
-                    use windows::Win32::Foundation::{HWND, LPARAM, RECT};
-                    use windows::Win32::UI::WindowsAndMessaging::{
-                        GetWindowRect, EnumWindows, GetWindowThreadProcessId, IsWindowVisible,
-                    };
-                    use windows::Win32::System::Threading::GetCurrentProcessId;
-
-                    let mut consecutive_failures = 0u32;
-
-                    loop {
-                        std::thread::sleep(std::time::Duration::from_secs(2));
-                        
-                        // Find window belonging to this process
-                        let bounds_opt: Option<(f32, f32, f32, f32)> = unsafe {
-                            struct FindData {
-                                pid: u32,
-                                hwnd: HWND,
-                            }
-                            
-                            unsafe extern "system" fn enum_proc(window: HWND, param: LPARAM) -> windows::core::BOOL {
-                                let data = &mut *(param.0 as *mut FindData);
-                                let mut pid = 0u32;
-                                GetWindowThreadProcessId(window, Some(&mut pid));
-                                if pid == data.pid && IsWindowVisible(window).as_bool() {
-                                    data.hwnd = window;
-                                    return windows::core::BOOL(0); // Stop enumeration
-                                }
-                                windows::core::BOOL(1) // Continue
-                            }
-                            
-                            let pid = GetCurrentProcessId();
-                            let mut data = FindData { pid, hwnd: HWND(std::ptr::null_mut()) };
-                            let _ = EnumWindows(Some(enum_proc), LPARAM(&mut data as *mut _ as isize));
-                            
-                            if !data.hwnd.0.is_null() {
-                                let mut rect = RECT::default();
-                                if GetWindowRect(data.hwnd, &mut rect).is_ok() {
-                                    let w = (rect.right - rect.left) as f32;
-                                    let h = (rect.bottom - rect.top) as f32;
-                                    Some((rect.left as f32, rect.top as f32, w, h))
-                                } else {
-                                    None
-                                }
-                            } else {
-                                None
-                            }
-                        };
-
-                        if let Some((x, y, w, h)) = bounds_opt {
-                            consecutive_failures = 0; // Reset on success
-                            
-                            // Use separate WindowState to avoid race with main settings
-                            let state = settings::WindowState::load();
-                            let changed = state.x != Some(x) || state.y != Some(y) ||
-                                          (state.width - w).abs() > 1.0 || (state.height - h).abs() > 1.0;
-                            
-                            if changed {
-                                let new_state = settings::WindowState {
-                                    x: Some(x),
-                                    y: Some(y),
-                                    width: w,
-                                    height: h,
-                                };
-                                new_state.save();
-                            }
-                        } else {
-                            // Window not found - app may be closing
-                            consecutive_failures += 1;
-                            if consecutive_failures >= 3 {
-                                break; // Exit thread after 3 consecutive failures
-                            }
-                        }
-                    }
-                });
+                if let Some(path) = request.file.clone() {
+                    ws.open_file_with(path, request.read_only, request.position, window, cx);
+                }
+                Some(())
+            })?;
+            window.activate_window();
+            Some(())
+        }).ok().flatten()
+    });
+
+    if reused.is_none() {
+        let settings = AppSettings::load();
+        if let Some(window) = open_workspace_window(cx, settings, request.file, request.read_only, request.position) {
+            window.update(cx, |_root, window, _cx| window.activate_window()).ok();
+        }
+    }
+}
+
+/// Apply an externally edited settings.json to every window.
+fn reload_settings(cx: &mut App) {
+    for window in cx.windows() {
+        let Some(window) = window.downcast::<Root>() else { continue };
+        let _ = window.update(cx, |root, window, cx| {
+            if let Ok(workspace) = root.view().clone().downcast::<Workspace>() {
+                workspace.update(cx, |ws, cx| ws.reload_settings(window, cx));
             }
+        });
+    }
+}
 
-            // Wrap in Root - this MUST be the top-level view in the window
-            cx.new(|cx| Root::new(workspace.clone(), window, cx))
-        }).expect("Failed to create main window");
+/// Windows opened so far; each new window takes the next saved position.
+static WINDOW_COUNT: AtomicUsize = AtomicUsize::new(0);
+/// Whether windows save their bounds when moved or resized; off for `--preview`.
+static SAVE_BOUNDS: AtomicBool = AtomicBool::new(false);
 
-        // Focus the workspace/editor after window is created
-        window.update(cx, |_root, _window, cx| {
-            // Root doesn't have focus_editor, so we need to access it through the workspace
-            // For now, just activate the window
-            cx.activate(true);
-        }).ok();
+/// Open a window with its own workspace, optionally loading `file`.
+pub fn open_workspace_window(
+    cx: &mut App,
+    settings: AppSettings,
+    file: Option<PathBuf>,
+    read_only: bool,
+    position: Option<(usize, usize)>,
+) -> Option<WindowHandle<Root>> {
+    let index = WINDOW_COUNT.fetch_add(1, Ordering::Relaxed);
+    let options = WindowOptions {
+        window_bounds: Some(AppSettings::window_bounds(index)),
+        titlebar: Some(gpui_component::TitleBar::title_bar_options()),
+        ..Default::default()
+    };
+
+    let result = cx.open_window(options, move |window, cx| {
+        // Create the workspace view
+        let workspace = cx.new(|cx| {
+            if SAVE_BOUNDS.load(Ordering::Relaxed) {
+                // This window is mid-update when the bounds change, so save once it is done
+                cx.observe_window_bounds(window, |_, _, cx| cx.defer(save_window_bounds)).detach();
+            }
+            let mut ws = Workspace::new(window, cx, settings);
+            if let Some(path) = file {
+                ws.open_file_with(path, read_only, position, window, cx);
+            }
+            ws
+        });
+
+        // Wrap in Root - this MUST be the top-level view in the window
+        cx.new(|cx| Root::new(workspace, window, cx))
     });
+    match result {
+        Ok(window) => Some(window),
+        Err(err) => {
+            warn!(error = %err, "Failed to open window");
+            None
+        }
+    }
+}
+
+/// Save the bounds of every open window, first-opened first, if they
+/// moved. Maximized and full screen windows save that, with the bounds
+/// they restore to.
+fn save_window_bounds(cx: &mut App) {
+    let bounds: Vec<settings::WindowGeometry> = cx.windows()
+        .into_iter()
+        .filter_map(|handle| handle.update(cx, |_, window, _| window.window_bounds()).ok())
+        .map(settings::WindowGeometry::from_window_bounds)
+        .collect();
+    let mut bounds = bounds.into_iter();
+    // Keep what the last window left behind once it has closed
+    let Some(primary) = bounds.next() else { return };
+    let state = settings::WindowState::load();
+    let new_state = settings::WindowState {
+        primary,
+        additional: bounds.collect(),
+    };
+    if !state.approx_eq(&new_state) {
+        debug!(windows = 1 + new_state.additional.len(), "Saving window bounds");
+        new_state.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_file_position;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_split_file_position() {
+        let split = |text: &str| split_file_position(PathBuf::from(text));
+        assert_eq!(split("missing/error.log:1523"), (PathBuf::from("missing/error.log"), Some((1522, 0))));
+        assert_eq!(split("missing/main.rs:10:5"), (PathBuf::from("missing/main.rs"), Some((9, 4))));
+        assert_eq!(split("missing/notes.txt"), (PathBuf::from("missing/notes.txt"), None));
+        assert_eq!(split("missing/a:b"), (PathBuf::from("missing/a:b"), None));
+        assert_eq!(split(":12"), (PathBuf::from(":12"), None));
+    }
 }
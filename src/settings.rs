@@ -1,12 +1,24 @@
 use serde::{Deserialize, Serialize};
-use gpui::{px, WindowBounds, Bounds, Point, Size};
+use futures::StreamExt;
+use gpui::{px, App, WindowBounds, Bounds, Point, Size};
+use gpui_component::input::CursorShape;
+use gpui_component::notification::Notification;
+use gpui_component::{Root, WindowExt};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::fs;
+use std::io;
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use directories::ProjectDirs;
 use tracing::warn;
+use crate::editor::{Orientation, PageSize, TabHandling, WrapIndent};
+use crate::keymap::KeymapPreset;
+use crate::watcher::DirWatcher;
+use crate::workspace::write_atomic;
 
 /// Persisted app settings (font, theme, preferences).
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct AppSettings {
     /// Font family name for the editor.
     pub font_family: String,
@@ -20,10 +32,447 @@ pub struct AppSettings {
     /// Whether to warn about unsaved changes.
     #[serde(default = "default_true")]
     pub enable_unsaved_changes_protection: bool,
+
+    /// Seconds between autosaves of a modified file (0 disables autosave).
+    #[serde(default)]
+    pub autosave_interval_secs: u64,
+
+    /// How autosave handles a file that was changed by another program.
+    #[serde(default)]
+    pub autosave_conflict_policy: SaveConflictPolicy,
+
+    /// Milliseconds within which consecutive typed characters share one
+    /// undo step (0 makes every keystroke its own step).
+    #[serde(default = "default_undo_group_timeout_ms")]
+    pub undo_group_timeout_ms: u64,
+
+    /// Megabytes of edited text kept for undo/redo; older steps are dropped.
+    #[serde(default = "default_undo_memory_limit_mb")]
+    pub undo_memory_limit_mb: usize,
+
+    /// Files of at least this many megabytes open read-only without undo
+    /// history (0 disables large-file mode).
+    #[serde(default = "default_large_file_threshold_mb")]
+    pub large_file_threshold_mb: u64,
+
+    /// Whether tabs are kept, converted to spaces, or only typed as spaces.
+    #[serde(default)]
+    pub tab_handling: TabHandling,
+
+    /// Columns per tab stop when tabs become spaces.
+    #[serde(default = "default_tab_width")]
+    pub tab_width: usize,
+
+    /// Where soft wrapped lines start after a line's first.
+    #[serde(default)]
+    pub wrap_indent: WrapIndent,
+
+    /// Extra columns for a hanging wrap indent.
+    #[serde(default = "default_wrap_hanging_indent")]
+    pub wrap_hanging_indent: usize,
+
+    /// Strip spaces and tabs from the ends of lines when saving.
+    #[serde(default)]
+    pub trim_trailing_whitespace_on_save: bool,
+
+    /// End the file with exactly one line break when saving.
+    #[serde(default)]
+    pub final_newline_on_save: bool,
+
+    /// Keep the previous version of a file as `<name>.bak` when saving.
+    #[serde(default)]
+    pub backup_on_save: bool,
+
+    /// Extension Save As adds when the typed name has none (empty adds
+    /// nothing).
+    #[serde(default = "default_extension")]
+    pub default_extension: String,
+
+    /// Folder Save As starts in for untitled documents (`None` uses the
+    /// last-used folder).
+    #[serde(default)]
+    pub default_save_dir: Option<PathBuf>,
+
+    /// Suggest a name made from the first line when saving an untitled
+    /// document ("Meeting notes" becomes `meeting-notes.txt`).
+    #[serde(default = "default_true")]
+    pub name_untitled_from_first_line: bool,
+
+    /// Folder Tools > Watch Folder opens new .txt files from (`None` asks
+    /// the first time).
+    #[serde(default)]
+    pub watch_folder: Option<PathBuf>,
+
+    /// Files opened lately, newest first, offered by Quick Open.
+    #[serde(default)]
+    pub recent_files: Vec<PathBuf>,
+
+    /// File type filters offered by the open and save dialogs.
+    #[serde(default = "default_file_filters")]
+    pub file_filters: Vec<FileFilter>,
+
+    /// Markdown helpers: table formatting and Tab moving between table cells.
+    #[serde(default = "default_true")]
+    pub markdown_assist: bool,
+
+    /// Add the closer when typing a bracket or quote.
+    #[serde(default)]
+    pub auto_pair_brackets: bool,
+
+    /// Underline the bracket matching the one at the caret.
+    #[serde(default)]
+    pub highlight_matching_brackets: bool,
+
+    /// Highlight the line holding the caret.
+    #[serde(default)]
+    pub highlight_current_line: bool,
+
+    /// Show line numbers beside the text.
+    #[serde(default)]
+    pub show_line_numbers: bool,
+
+    /// How the caret is drawn.
+    #[serde(default)]
+    pub cursor_style: CursorStyle,
+
+    /// Milliseconds between caret blinks (0 keeps it steady).
+    #[serde(default = "default_cursor_blink_ms")]
+    pub cursor_blink_ms: u64,
+
+    /// Words listed in the TODO panel, such as `TODO` and `FIXME`.
+    #[serde(default = "default_todo_markers")]
+    pub todo_markers: Vec<String>,
+
+    /// Underline words missing from the dictionary.
+    #[serde(default)]
+    pub spell_check: bool,
+
+    /// Hunspell dictionary used for spell checking, e.g. `en_US`.
+    #[serde(default = "default_spell_check_language")]
+    pub spell_check_language: String,
+
+    /// Most frames per second the editor draws (0 for no cap).
+    #[serde(default)]
+    pub frame_rate_cap: u32,
+
+    /// Stop redrawing while the window is unfocused or idle.
+    #[serde(default)]
+    pub power_saving: bool,
+
+    /// Which optional segments the status bar shows.
+    #[serde(default)]
+    pub status_bar: StatusBarSegments,
+
+    /// Render on the CPU, for machines with broken GPU drivers. Applies
+    /// from the next start.
+    #[serde(default)]
+    pub software_rendering: bool,
+
+    /// Switching between a day and a night theme by the clock.
+    #[serde(default)]
+    pub theme_schedule: ThemeSchedule,
+
+    /// Last-used PDF export options.
+    #[serde(default)]
+    pub pdf_export: PdfExportSettings,
+
+    /// Color source code in HTML exports like the editor's highlighter.
+    #[serde(default = "default_true")]
+    pub html_syntax_colors: bool,
+
+    /// Key bindings to start from. Applies from the next start.
+    #[serde(default)]
+    pub keymap_preset: KeymapPreset,
+
+    /// The user's own bindings over the preset's: keystrokes (like
+    /// `ctrl-k ctrl-d`) to an action name (like `editor::DeleteLineAction`),
+    /// or to an empty string to unbind them.
+    #[serde(default)]
+    pub key_bindings: BTreeMap<String, String>,
+
+    /// Ids of extensions switched off.
+    #[serde(default)]
+    pub disabled_extensions: Vec<String>,
+}
+
+/// Settings given on the command line for this run only; never saved.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionOverrides {
+    pub theme: Option<String>,
+    pub font_size: Option<f32>,
+}
+
+static SESSION_OVERRIDES: OnceLock<SessionOverrides> = OnceLock::new();
+
+impl SessionOverrides {
+    /// Set the overrides for this run; only the first call counts.
+    pub fn install(self) {
+        let _ = SESSION_OVERRIDES.set(self);
+    }
+
+    pub fn get() -> &'static SessionOverrides {
+        SESSION_OVERRIDES.get_or_init(SessionOverrides::default)
+    }
+
+    /// `settings` as this run should use them.
+    pub fn apply(&self, settings: &AppSettings) -> AppSettings {
+        let mut settings = settings.clone();
+        if let Some(theme) = &self.theme {
+            settings.theme = theme.clone();
+        }
+        if let Some(size) = self.font_size {
+            settings.font_size = size;
+        }
+        settings
+    }
+}
+
+/// Day and night themes and the local times at which each takes over.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ThemeSchedule {
+    pub enabled: bool,
+    pub light_theme: String,
+    pub dark_theme: String,
+    /// "HH:MM" at which the light theme starts.
+    pub light_from: String,
+    /// "HH:MM" at which the dark theme starts.
+    pub dark_from: String,
+}
+
+impl Default for ThemeSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            light_theme: default_theme(),
+            dark_theme: "Default Dark".to_string(),
+            light_from: "07:00".to_string(),
+            dark_from: "19:00".to_string(),
+        }
+    }
+}
+
+/// A named set of extensions in a file dialog's type list; `*` matches
+/// every file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FileFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+/// An optional part of the status bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusSegment {
+    Words,
+    Lines,
+    Characters,
+    LineEnding,
+    Encoding,
+    Indentation,
+    Fps,
+}
+
+impl StatusSegment {
+    pub const ALL: [StatusSegment; 7] = [
+        StatusSegment::Words,
+        StatusSegment::Lines,
+        StatusSegment::Characters,
+        StatusSegment::LineEnding,
+        StatusSegment::Encoding,
+        StatusSegment::Indentation,
+        StatusSegment::Fps,
+    ];
+}
+
+impl std::fmt::Display for StatusSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StatusSegment::Words => "Word Count",
+            StatusSegment::Lines => "Line Count",
+            StatusSegment::Characters => "Character Count",
+            StatusSegment::LineEnding => "Line Ending",
+            StatusSegment::Encoding => "Encoding",
+            StatusSegment::Indentation => "Indentation",
+            StatusSegment::Fps => "Frame Rate",
+        })
+    }
+}
+
+/// Visibility of each optional status bar segment. The frame rate keeps the
+/// window redrawing, so release builds hide it unless asked.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct StatusBarSegments {
+    pub words: bool,
+    pub lines: bool,
+    pub characters: bool,
+    pub line_ending: bool,
+    pub encoding: bool,
+    pub indentation: bool,
+    pub fps: bool,
+}
+
+impl StatusBarSegments {
+    fn flag(&mut self, segment: StatusSegment) -> &mut bool {
+        match segment {
+            StatusSegment::Words => &mut self.words,
+            StatusSegment::Lines => &mut self.lines,
+            StatusSegment::Characters => &mut self.characters,
+            StatusSegment::LineEnding => &mut self.line_ending,
+            StatusSegment::Encoding => &mut self.encoding,
+            StatusSegment::Indentation => &mut self.indentation,
+            StatusSegment::Fps => &mut self.fps,
+        }
+    }
+
+    pub fn shows(mut self, segment: StatusSegment) -> bool {
+        *self.flag(segment)
+    }
+
+    pub fn toggle(&mut self, segment: StatusSegment) {
+        let flag = self.flag(segment);
+        *flag = !*flag;
+    }
+}
+
+impl Default for StatusBarSegments {
+    fn default() -> Self {
+        Self {
+            words: true,
+            lines: true,
+            characters: true,
+            line_ending: true,
+            encoding: true,
+            indentation: true,
+            fps: cfg!(debug_assertions),
+        }
+    }
+}
+
+/// Options offered by the PDF export dialog.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct PdfExportSettings {
+    pub page_size: PageSize,
+    pub orientation: Orientation,
+    /// Page margins in points.
+    pub margin: f32,
+    /// Font size in points.
+    pub font_size: f32,
+    pub line_numbers: bool,
+    /// Whether to print the header.
+    pub include_header: bool,
+    /// Header text; see `expand_template` for the placeholders.
+    pub header_template: String,
+    /// Footer text, with the same placeholders (empty for none).
+    pub footer_template: String,
+    /// Export only the selected text.
+    pub selection_only: bool,
+    /// Color source code like the editor's highlighter.
+    pub syntax_colors: bool,
+    /// System font to print with (`None` uses the built-in Noto Sans).
+    pub font_family: Option<String>,
+    /// Lay Markdown files out (headings, emphasis, lists) instead of
+    /// printing the markup.
+    pub render_markdown: bool,
+}
+
+impl Default for PdfExportSettings {
+    fn default() -> Self {
+        Self {
+            page_size: PageSize::default(),
+            orientation: Orientation::default(),
+            margin: 72.0, // 1 inch in points
+            font_size: 12.0,
+            line_numbers: false,
+            include_header: true,
+            header_template: "{filename} - {date} - Page {page} of {pages}".to_string(),
+            footer_template: String::new(),
+            selection_only: false,
+            syntax_colors: true,
+            font_family: None,
+            render_markdown: false,
+        }
+    }
+}
+
+/// Resolution strategy when autosave finds the file modified on disk.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SaveConflictPolicy {
+    /// Keep whichever side was modified most recently.
+    KeepNewer,
+    /// Always overwrite the file on disk with the buffer.
+    KeepBuffer,
+    /// Ask before overwriting or reloading.
+    #[default]
+    Prompt,
+}
+
+/// How the caret is drawn.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorStyle {
+    #[default]
+    Bar,
+    Block,
+    Underline,
+}
+
+impl CursorStyle {
+    pub const ALL: [CursorStyle; 3] = [CursorStyle::Bar, CursorStyle::Block, CursorStyle::Underline];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CursorStyle::Bar => "Bar",
+            CursorStyle::Block => "Block",
+            CursorStyle::Underline => "Underline",
+        }
+    }
+
+    pub fn shape(self) -> CursorShape {
+        match self {
+            CursorStyle::Bar => CursorShape::Bar,
+            CursorStyle::Block => CursorShape::Block,
+            CursorStyle::Underline => CursorShape::Underline,
+        }
+    }
 }
 
 fn default_true() -> bool { true }
 
+fn default_cursor_blink_ms() -> u64 { 500 }
+
+fn default_undo_group_timeout_ms() -> u64 { 1000 }
+
+fn default_undo_memory_limit_mb() -> usize { 64 }
+
+fn default_large_file_threshold_mb() -> u64 { 50 }
+
+fn default_tab_width() -> usize { 2 }
+
+fn default_wrap_hanging_indent() -> usize { 4 }
+
+fn default_extension() -> String { "txt".to_string() }
+
+fn default_file_filters() -> Vec<FileFilter> {
+    [
+        ("Text", &["txt", "text"][..]),
+        ("Markdown", &["md", "markdown"]),
+        ("Logs", &["log", "out"]),
+        ("All Files", &["*"]),
+    ]
+    .into_iter()
+    .map(|(name, extensions)| FileFilter { name: name.to_string(), extensions: extensions.iter().map(|e| e.to_string()).collect() })
+    .collect()
+}
+
+fn default_todo_markers() -> Vec<String> {
+    ["TODO", "FIXME", "NOTE"].map(String::from).to_vec()
+}
+
+fn default_spell_check_language() -> String { "en_US".to_string() }
+
 fn default_theme() -> String {
     "Default Light".to_string()
 }
@@ -35,15 +484,61 @@ impl Default for AppSettings {
             font_size: 14.0,
             theme: default_theme(),
             enable_unsaved_changes_protection: true,
+            autosave_interval_secs: 0,
+            autosave_conflict_policy: SaveConflictPolicy::default(),
+            undo_group_timeout_ms: default_undo_group_timeout_ms(),
+            undo_memory_limit_mb: default_undo_memory_limit_mb(),
+            large_file_threshold_mb: default_large_file_threshold_mb(),
+            tab_handling: TabHandling::default(),
+            tab_width: default_tab_width(),
+            wrap_indent: WrapIndent::default(),
+            wrap_hanging_indent: default_wrap_hanging_indent(),
+            trim_trailing_whitespace_on_save: false,
+            final_newline_on_save: false,
+            backup_on_save: false,
+            default_extension: default_extension(),
+            default_save_dir: None,
+            name_untitled_from_first_line: true,
+            watch_folder: None,
+            recent_files: Vec::new(),
+            file_filters: default_file_filters(),
+            markdown_assist: true,
+            auto_pair_brackets: false,
+            highlight_matching_brackets: false,
+            highlight_current_line: false,
+            show_line_numbers: false,
+            cursor_style: CursorStyle::default(),
+            cursor_blink_ms: default_cursor_blink_ms(),
+            todo_markers: default_todo_markers(),
+            spell_check: false,
+            spell_check_language: default_spell_check_language(),
+            frame_rate_cap: 0,
+            power_saving: false,
+            status_bar: StatusBarSegments::default(),
+            software_rendering: false,
+            theme_schedule: ThemeSchedule::default(),
+            pdf_export: PdfExportSettings::default(),
+            html_syntax_colors: true,
+            keymap_preset: KeymapPreset::default(),
+            key_bindings: BTreeMap::new(),
+            disabled_extensions: Vec::new(),
         }
     }
 }
 
+/// Environment variable naming a config directory to use instead of the
+/// platform one.
+pub const CONFIG_DIR_ENV: &str = "ONETEXT_CONFIG_DIR";
+
 /// Get the config directory, creating it if needed.
-fn get_config_dir() -> PathBuf {
-    let proj_dirs = ProjectDirs::from("com", "OneText", "OneText")
-        .expect("Could not determine config directory for this platform");
-    let config_dir = proj_dirs.config_dir().to_path_buf();
+pub(crate) fn get_config_dir() -> PathBuf {
+    let config_dir = match std::env::var_os(CONFIG_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => ProjectDirs::from("com", "OneText", "OneText")
+            .expect("Could not determine config directory for this platform")
+            .config_dir()
+            .to_path_buf(),
+    };
     if !config_dir.exists() {
         if let Err(e) = fs::create_dir_all(&config_dir) {
             warn!("Failed to create config directory: {}", e);
@@ -52,70 +547,402 @@ fn get_config_dir() -> PathBuf {
     config_dir
 }
 
-impl AppSettings {
-    fn get_config_path() -> PathBuf {
-        get_config_dir().join("settings.json")
+/// Where settings and window state are persisted, as text under a key
+/// such as `settings.json`.
+pub trait SettingsStore: Send + Sync {
+    /// The stored text, or `None` if there is none.
+    fn read(&self, key: &str) -> Option<String>;
+    fn write(&self, key: &str, contents: &str) -> io::Result<()>;
+}
+
+/// Files in a directory: the platform config directory by default (XDG on
+/// Linux, the roaming profile on Windows), or any other, like a portable
+/// install's own folder.
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl SettingsStore for FileStore {
+    fn read(&self, key: &str) -> Option<String> {
+        fs::read_to_string(self.dir.join(key)).ok()
     }
 
-    /// Load from disk, or use defaults if missing.
-    pub fn load() -> Self {
-        if let Ok(contents) = fs::read_to_string(Self::get_config_path()) {
-            if let Ok(settings) = serde_json::from_str(&contents) {
-                return settings;
+    fn write(&self, key: &str, contents: &str) -> io::Result<()> {
+        write_atomic(&self.dir.join(key), contents.as_bytes(), false)
+    }
+}
+
+/// Keeps everything in memory, for tests and throwaway sessions.
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl SettingsStore for MemoryStore {
+    fn read(&self, key: &str) -> Option<String> {
+        self.entries.lock().ok()?.get(key).cloned()
+    }
+
+    fn write(&self, key: &str, contents: &str) -> io::Result<()> {
+        let mut entries = self.entries.lock().map_err(|_| io::Error::other("settings store poisoned"))?;
+        entries.insert(key.to_string(), contents.to_string());
+        Ok(())
+    }
+}
+
+static STORE: OnceLock<Box<dyn SettingsStore>> = OnceLock::new();
+
+/// Use `store` for this run instead of the config directory; only the
+/// first call counts, and only before anything is loaded.
+pub fn install_store(store: Box<dyn SettingsStore>) {
+    let _ = STORE.set(store);
+}
+
+/// The store settings are loaded from and saved to.
+pub fn store() -> &'static dyn SettingsStore {
+    STORE.get_or_init(|| Box::new(FileStore::new(get_config_dir()))).as_ref()
+}
+
+const SETTINGS_KEY: &str = "settings.json";
+const WINDOW_STATE_KEY: &str = "window_state.json";
+
+/// How long the writer waits for saves to stop coming before it writes
+/// them, so a burst of changes is written once.
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Saves waiting for the background writer, the newest per key.
+#[derive(Default)]
+struct Pending {
+    saves: HashMap<&'static str, String>,
+    /// When the newest save was queued.
+    queued_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct SaveQueue {
+    pending: Mutex<Pending>,
+    wake: Condvar,
+    /// Held while writing, so a flush and the writer never reorder saves.
+    writing: Mutex<()>,
+}
+
+static SAVE_ERRORS: OnceLock<futures::channel::mpsc::UnboundedSender<String>> = OnceLock::new();
+
+fn save_queue() -> &'static SaveQueue {
+    static QUEUE: OnceLock<SaveQueue> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        std::thread::spawn(|| loop {
+            {
+                let queue = save_queue();
+                let mut pending = queue.pending.lock().unwrap_or_else(|e| e.into_inner());
+                loop {
+                    let quiet = pending.queued_at.map_or(Duration::ZERO, |at| at.elapsed());
+                    if !pending.saves.is_empty() && quiet >= SAVE_DEBOUNCE {
+                        break;
+                    }
+                    // Each new save starts the wait over
+                    pending = if pending.saves.is_empty() {
+                        queue.wake.wait(pending).unwrap_or_else(|e| e.into_inner())
+                    } else {
+                        queue.wake.wait_timeout(pending, SAVE_DEBOUNCE - quiet).unwrap_or_else(|e| e.into_inner()).0
+                    };
+                }
+            }
+            flush_saves();
+        });
+        SaveQueue::default()
+    })
+}
+
+/// Hand `contents` to the background writer to store under `key`.
+fn queue_save(key: &'static str, contents: String) {
+    let queue = save_queue();
+    let mut pending = queue.pending.lock().unwrap_or_else(|e| e.into_inner());
+    pending.saves.insert(key, contents);
+    pending.queued_at = Some(Instant::now());
+    queue.wake.notify_one();
+}
+
+/// Text saved under `key`, including a save the writer hasn't done yet.
+fn read_saved(key: &str) -> Option<String> {
+    let pending = save_queue().pending.lock().unwrap_or_else(|e| e.into_inner()).saves.get(key).cloned();
+    pending.or_else(|| store().read(key))
+}
+
+/// Write any queued saves now, on this thread. Called on quit.
+pub fn flush_saves() {
+    let queue = save_queue();
+    let _writing = queue.writing.lock().unwrap_or_else(|e| e.into_inner());
+    let batch = std::mem::take(&mut queue.pending.lock().unwrap_or_else(|e| e.into_inner()).saves);
+    for (key, contents) in batch {
+        if let Err(err) = store().write(key, &contents) {
+            warn!(error = %err, key, "Failed to save settings");
+            if let Some(errors) = SAVE_ERRORS.get() {
+                let _ = errors.unbounded_send(format!("Couldn't save {}: {}", key, err));
+            }
+        }
+    }
+}
+
+/// Show failed background saves as notifications in every window.
+pub fn report_save_errors(cx: &mut App) {
+    let (tx, mut rx) = futures::channel::mpsc::unbounded();
+    if SAVE_ERRORS.set(tx).is_err() {
+        return;
+    }
+    cx.spawn(async move |cx| {
+        while let Some(message) = rx.next().await {
+            let shown = cx.update(|cx| {
+                for window in cx.windows() {
+                    let Some(window) = window.downcast::<Root>() else { continue };
+                    let _ = window.update(cx, |_, window, cx| {
+                        window.push_notification(Notification::error(message.clone()), cx);
+                    });
+                }
+            });
+            if shown.is_err() {
+                break;
             }
         }
-        Self::default()
+    })
+    .detach();
+}
+
+impl AppSettings {
+    /// Load from the store, or use defaults if missing.
+    pub fn load() -> Self {
+        Self::try_load().unwrap_or_default()
     }
 
-    /// Save to disk.
+    /// Load from the store; `None` if missing or unparseable.
+    pub fn try_load() -> Option<Self> {
+        Self::parse(&read_saved(SETTINGS_KEY)?)
+    }
+
+    /// Load straight from `store`, past the save queue.
+    #[cfg(test)]
+    pub fn try_load_from(store: &dyn SettingsStore) -> Option<Self> {
+        Self::parse(&store.read(SETTINGS_KEY)?)
+    }
+
+    fn parse(contents: &str) -> Option<Self> {
+        serde_json::from_str(contents)
+            .inspect_err(|err| warn!(error = %err, "Ignoring unreadable settings file"))
+            .ok()
+    }
+
+    /// Save to the store in the background, shortly after the last change.
     pub fn save(&self) {
         if let Ok(json) = serde_json::to_string_pretty(self) {
-            let _ = fs::write(Self::get_config_path(), json);
+            queue_save(SETTINGS_KEY, json);
+        }
+    }
+
+    /// Save straight to `store`, past the save queue.
+    #[cfg(test)]
+    pub fn save_to(&self, store: &dyn SettingsStore) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            if let Err(err) = store.write(SETTINGS_KEY, &json) {
+                warn!(error = %err, "Failed to save settings");
+            }
         }
     }
 
-    pub fn window_bounds() -> WindowBounds {
+    /// Bounds for the `index`th window opened in this session.
+    pub fn window_bounds(index: usize) -> WindowBounds {
         let state = WindowState::load();
-        let width = if state.width > 0.0 { state.width } else { 800.0 };
-        let height = if state.height > 0.0 { state.height } else { 600.0 };
+        let (geometry, cascade) = match index.checked_sub(1).and_then(|i| state.additional.get(i)) {
+            Some(saved) => (saved.clone(), 0.0),
+            // No saved position for this window: offset it from the first one
+            None => (state.primary.clone(), 30.0 * index as f32),
+        };
+        let width = if geometry.width > 0.0 { geometry.width } else { 800.0 };
+        let height = if geometry.height > 0.0 { geometry.height } else { 600.0 };
         
         let size = Size { width: px(width), height: px(height) };
-        if let (Some(x), Some(y)) = (state.x, state.y) {
-            WindowBounds::Windowed(Bounds::new(Point { x: px(x), y: px(y) }, size))
+        let bounds = if let (Some(x), Some(y)) = (geometry.x, geometry.y) {
+            Bounds::new(Point { x: px(x + cascade), y: px(y + cascade) }, size)
         } else {
             // Fallback to fixed position when no saved position exists
-            WindowBounds::Windowed(Bounds::new(Point { x: px(100.0), y: px(100.0) }, size))
+            Bounds::new(Point { x: px(100.0 + cascade), y: px(100.0 + cascade) }, size)
+        };
+        // Windows borrowing the first one's position open windowed
+        match (geometry.fullscreen, geometry.maximized) {
+            _ if cascade > 0.0 => WindowBounds::Windowed(bounds),
+            (true, _) => WindowBounds::Fullscreen(bounds),
+            (false, true) => WindowBounds::Maximized(bounds),
+            (false, false) => WindowBounds::Windowed(bounds),
         }
     }
 }
 
-/// Separate window state to avoid race condition with main settings.
-/// Saved to a different file and only updated by the persistence thread.
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
-pub struct WindowState {
+/// Quiet period after a config file changes, so one save is handled once.
+const CONFIG_SETTLE: Duration = Duration::from_millis(100);
+
+/// Call `on_change` whenever `file_name` in the config directory is
+/// created, modified or removed.
+pub fn watch_config_file(file_name: &'static str, cx: &mut App, on_change: impl Fn(&mut App) + 'static) {
+    let watcher = DirWatcher::new(&get_config_dir(), move |event| {
+        let changed = event.paths.iter().any(|p| p.file_name().is_some_and(|n| n == file_name));
+        if changed { vec![()] } else { Vec::new() }
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            warn!(error = %err, file = file_name, "Failed to watch config file");
+            return;
+        }
+    };
+
+    cx.spawn(async move |cx| {
+        while watcher.next_batch(CONFIG_SETTLE, cx.background_executor()).await.is_some() {
+            if cx.update(|cx| on_change(cx)).is_err() {
+                break;
+            }
+        }
+    })
+    .detach();
+}
+
+/// Position and size of one window.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct WindowGeometry {
     pub x: Option<f32>,
     pub y: Option<f32>,
     pub width: f32,
     pub height: f32,
+    /// Whether the window was maximized; the size above is what it restores to.
+    #[serde(default)]
+    pub maximized: bool,
+    /// Whether the window was full screen; the size above is what it restores to.
+    #[serde(default)]
+    pub fullscreen: bool,
 }
 
-impl WindowState {
-    fn get_path() -> PathBuf {
-        get_config_dir().join("window_state.json")
+impl WindowGeometry {
+    pub fn from_window_bounds(window_bounds: WindowBounds) -> Self {
+        let bounds = window_bounds.get_bounds();
+        Self {
+            x: Some(f32::from(bounds.origin.x)),
+            y: Some(f32::from(bounds.origin.y)),
+            width: f32::from(bounds.size.width),
+            height: f32::from(bounds.size.height),
+            maximized: matches!(window_bounds, WindowBounds::Maximized(_)),
+            fullscreen: matches!(window_bounds, WindowBounds::Fullscreen(_)),
+        }
     }
 
+    /// Same state, and bounds equal to within a pixel.
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.maximized == other.maximized && self.fullscreen == other.fullscreen
+            && self.x == other.x && self.y == other.y
+            && (self.width - other.width).abs() <= 1.0
+            && (self.height - other.height).abs() <= 1.0
+    }
+}
+
+/// Separate window state to avoid race condition with main settings.
+/// Saved to a different file and only updated as windows move, resize or close.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct WindowState {
+    /// The first window; stored at the top level for older state files.
+    #[serde(flatten)]
+    pub primary: WindowGeometry,
+    /// Any further windows, in the order they were open.
+    #[serde(default)]
+    pub additional: Vec<WindowGeometry>,
+}
+
+impl WindowState {
     pub fn load() -> Self {
-        if let Ok(contents) = fs::read_to_string(Self::get_path()) {
-            if let Ok(state) = serde_json::from_str(&contents) {
-                return state;
-            }
-        }
-        Self::default()
+        read_saved(WINDOW_STATE_KEY)
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    #[cfg(test)]
+    pub fn load_from(store: &dyn SettingsStore) -> Self {
+        store.read(WINDOW_STATE_KEY)
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Same windows, each equal to within a pixel.
+    pub fn approx_eq(&self, other: &Self) -> bool {
+        self.primary.approx_eq(&other.primary)
+            && self.additional.len() == other.additional.len()
+            && self.additional.iter().zip(&other.additional).all(|(a, b)| a.approx_eq(b))
     }
 
     pub fn save(&self) {
         if let Ok(json) = serde_json::to_string_pretty(self) {
-            let _ = fs::write(Self::get_path(), json);
+            queue_save(WINDOW_STATE_KEY, json);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn save_to(&self, store: &dyn SettingsStore) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = store.write(WINDOW_STATE_KEY, &json);
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::{AppSettings, MemoryStore, SessionOverrides, SettingsStore, StatusBarSegments, StatusSegment, WindowGeometry, WindowState};
+
+    #[test]
+    fn test_session_overrides() {
+        let settings = AppSettings::default();
+        let overrides = SessionOverrides { theme: Some("Dark".to_string()), font_size: None };
+        let effective = overrides.apply(&settings);
+        assert_eq!(effective.theme, "Dark");
+        assert_eq!(effective.font_size, settings.font_size);
+        assert_eq!(SessionOverrides::default().apply(&settings), settings);
+    }
+
+    #[test]
+    fn test_window_state_reads_single_window_format() {
+        let state: WindowState = serde_json::from_str(r#"{"x": 10.0, "y": 20.0, "width": 800.0, "height": 600.0}"#).unwrap();
+        assert_eq!(state.primary.x, Some(10.0));
+        assert_eq!(state.primary.width, 800.0);
+        assert!(!state.primary.maximized && !state.primary.fullscreen);
+        assert!(state.additional.is_empty());
+    }
+
+    #[test]
+    fn test_status_bar_segments() {
+        // Segments missing from the file keep their defaults
+        let mut segments: StatusBarSegments = serde_json::from_str(r#"{"words": false}"#).unwrap();
+        assert!(!segments.shows(StatusSegment::Words));
+        assert!(segments.shows(StatusSegment::Encoding));
+        segments.toggle(StatusSegment::Words);
+        assert!(segments.shows(StatusSegment::Words));
+    }
+
+    #[test]
+    fn test_memory_store_round_trip() {
+        let store = MemoryStore::default();
+        assert!(AppSettings::try_load_from(&store).is_none());
+
+        let settings = AppSettings { font_size: 21.0, ..AppSettings::default() };
+        settings.save_to(&store);
+        assert_eq!(AppSettings::try_load_from(&store), Some(settings));
+
+        let state = WindowState {
+            primary: WindowGeometry { x: Some(5.0), y: None, width: 640.0, height: 480.0, maximized: true, fullscreen: false },
+            additional: Vec::new(),
+        };
+        state.save_to(&store);
+        assert!(WindowState::load_from(&store).approx_eq(&state));
+
+        store.write("settings.json", "not json").unwrap();
+        assert!(AppSettings::try_load_from(&store).is_none());
+    }
+}
@@ -0,0 +1,184 @@
+//! User overrides for UI chrome beyond what themes expose, read from
+//! `style.css` in the config directory and reloaded when it changes.
+//!
+//! The file holds CSS-like declarations:
+//!
+//! ```css
+//! /* Roomier chrome */
+//! status-bar-height: 28px;
+//! menu-bar-height: 36px;
+//! menu-padding: 12px;
+//! gutter-width: 16px;
+//! accent-color: #3b82f6;
+//! ```
+
+use gpui::*;
+use gpui_component::Theme;
+use std::fs;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+use crate::settings::{get_config_dir, watch_config_file};
+
+/// Chrome metrics, with the built-in values unless overridden.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChromeStyle {
+    pub status_bar_height: Pixels,
+    pub menu_bar_height: Pixels,
+    /// Horizontal padding of the menu bar.
+    pub menu_padding: Pixels,
+    /// Space between the editor's edges and its text.
+    pub gutter_width: Pixels,
+    /// Replaces the theme's accent color.
+    pub accent_color: Option<Hsla>,
+}
+
+impl Global for ChromeStyle {}
+
+/// The active theme's own accent color, from before any override.
+struct ThemeAccent(Hsla);
+
+impl Global for ThemeAccent {}
+
+impl Default for ChromeStyle {
+    fn default() -> Self {
+        Self {
+            status_bar_height: px(24.0),
+            menu_bar_height: px(32.0),
+            menu_padding: px(8.0),
+            gutter_width: px(8.0),
+            accent_color: None,
+        }
+    }
+}
+
+/// "12px" or "12".
+fn parse_pixels(value: &str) -> Option<Pixels> {
+    let number = value.strip_suffix("px").unwrap_or(value).trim();
+    number.parse::<f32>().ok().filter(|n| (0.0..=200.0).contains(n)).map(px)
+}
+
+impl ChromeStyle {
+    /// Parse declarations over the defaults, skipping (and logging) any
+    /// that aren't understood.
+    pub fn parse(text: &str) -> Self {
+        let mut style = Self::default();
+        let mut rest = text;
+        let mut uncommented = String::new();
+        while let Some(start) = rest.find("/*") {
+            uncommented.push_str(&rest[..start]);
+            rest = rest[start..].find("*/").map_or("", |end| &rest[start + end + 2..]);
+        }
+        uncommented.push_str(rest);
+
+        for declaration in uncommented.split([';', '\n']).map(str::trim).filter(|d| !d.is_empty()) {
+            let Some((property, value)) = declaration.split_once(':') else {
+                warn!("Ignoring style declaration without a value: {}", declaration);
+                continue;
+            };
+            let value = value.trim();
+            let applied = match property.trim() {
+                "status-bar-height" => parse_pixels(value).map(|v| style.status_bar_height = v),
+                "menu-bar-height" => parse_pixels(value).map(|v| style.menu_bar_height = v),
+                "menu-padding" => parse_pixels(value).map(|v| style.menu_padding = v),
+                "gutter-width" => parse_pixels(value).map(|v| style.gutter_width = v),
+                "accent-color" => Rgba::try_from(value).ok().map(|c| style.accent_color = Some(c.into())),
+                _ => None,
+            };
+            if applied.is_none() {
+                warn!("Ignoring style declaration: {}", declaration);
+            }
+        }
+        style
+    }
+
+    /// Always the file in the config directory, whatever the settings store:
+    /// it's written by hand and watched for edits, not saved by the app.
+    fn path() -> PathBuf {
+        get_config_dir().join("style.css")
+    }
+
+    /// Read the overrides file; a missing file means no overrides.
+    pub fn load() -> Self {
+        match fs::read_to_string(Self::path()) {
+            Ok(text) => Self::parse(&text),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// The current overrides.
+    pub fn global(cx: &App) -> Self {
+        cx.try_global::<Self>().cloned().unwrap_or_default()
+    }
+
+    /// Put the accent color over the theme's; call right after applying
+    /// a theme, whose accent is kept for when the override goes away.
+    pub fn apply_accent(cx: &mut App) {
+        let accent = Theme::global(cx).colors.accent;
+        cx.set_global(ThemeAccent(accent));
+        Self::reapply_accent(cx);
+    }
+
+    /// Show the overriding accent color, or the theme's own without one.
+    fn reapply_accent(cx: &mut App) {
+        let accent = Self::global(cx).accent_color.or_else(|| cx.try_global::<ThemeAccent>().map(|theme| theme.0));
+        if let Some(accent) = accent {
+            Theme::global_mut(cx).colors.accent = accent;
+        }
+    }
+}
+
+/// Load the overrides and reload them whenever `style.css` changes.
+pub fn init(cx: &mut App) {
+    cx.set_global(ChromeStyle::load());
+    ChromeStyle::apply_accent(cx);
+
+    watch_config_file("style.css", cx, |cx| {
+        let style = ChromeStyle::load();
+        if *cx.global::<ChromeStyle>() == style {
+            return;
+        }
+        info!("Reloading style overrides");
+        cx.set_global(style);
+        ChromeStyle::reapply_accent(cx);
+        cx.refresh_windows();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChromeStyle;
+    use gpui::{px, rgb, Hsla, TestAppContext};
+    use gpui_component::Theme;
+
+    #[gpui::test]
+    fn test_theme_accent_returns_without_override(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            gpui_component::init(cx);
+            let theme_accent = Theme::global(cx).colors.accent;
+            let red = Hsla::from(rgb(0xff0000));
+            cx.set_global(ChromeStyle { accent_color: Some(red), ..ChromeStyle::default() });
+            ChromeStyle::apply_accent(cx);
+            assert_eq!(Theme::global(cx).colors.accent, red);
+
+            cx.set_global(ChromeStyle::default());
+            ChromeStyle::reapply_accent(cx);
+            assert_eq!(Theme::global(cx).colors.accent, theme_accent);
+        });
+    }
+
+    #[test]
+    fn test_parse_style() {
+        let style = ChromeStyle::parse("/* big */ status-bar-height: 30px;\nmenu-padding: 4\naccent-color: #ff0000;\nbogus: 1;");
+        assert_eq!(style.status_bar_height, px(30.0));
+        assert_eq!(style.menu_padding, px(4.0));
+        assert_eq!(style.accent_color, Some(Hsla::from(rgb(0xff0000))));
+        assert_eq!(style.gutter_width, ChromeStyle::default().gutter_width);
+    }
+
+    #[test]
+    fn test_parse_style_rejects_bad_values() {
+        let style = ChromeStyle::parse("gutter-width: wide; status-bar-height: -3px; accent-color: blue");
+        assert_eq!(style, ChromeStyle::default());
+    }
+}
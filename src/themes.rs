@@ -0,0 +1,229 @@
+//! User themes in `assets/themes`. Problems in the files are shown to the
+//! user as well as logged, and a theme that can't be read in full gives way
+//! to the default one instead of being applied in part.
+
+use gpui::*;
+use gpui_component::notification::Notification;
+use gpui_component::{Root, Theme, ThemeConfig, ThemeRegistry, ThemeSet, WindowExt};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::settings::{AppSettings, SessionOverrides};
+use crate::watcher::DirWatcher;
+
+/// Quiet period after a change, so one save is handled once.
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// A problem with one theme file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeProblem {
+    pub file: String,
+    /// 1-based; 0 when the problem has no position.
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ThemeProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            0 => write!(f, "{}: {}", self.file, self.message),
+            line => write!(f, "{}, line {}: {}", self.file, line, self.message),
+        }
+    }
+}
+
+/// 1-based line of the first `"needle"` string in `text`, or 0.
+fn line_of(text: &str, needle: &str) -> usize {
+    text.find(&format!("\"{}\"", needle))
+        .map(|at| text[..at].matches('\n').count() + 1)
+        .unwrap_or(0)
+}
+
+/// The themes in the theme file `file` holding `text`, leaving out any
+/// with a color that doesn't parse, and the problems found.
+pub fn check_file(file: &str, text: &str) -> (Vec<ThemeConfig>, Vec<ThemeProblem>) {
+    let set = match serde_json::from_str::<ThemeSet>(text) {
+        Ok(set) => set,
+        Err(err) => {
+            // serde_json puts the position at the end of the message
+            let message = err.to_string();
+            let message = message.split(" at line ").next().unwrap_or_default().to_string();
+            return (Vec::new(), vec![ThemeProblem { file: file.to_string(), line: err.line(), message }]);
+        }
+    };
+    // Colors are strings to the schema; parse them as the theme would
+    let raw: serde_json::Value = serde_json::from_str(text).unwrap_or_default();
+    let mut themes = Vec::new();
+    let mut problems = Vec::new();
+    for (i, theme) in set.themes.into_iter().enumerate() {
+        let colors = raw["themes"][i]["colors"].as_object().cloned().unwrap_or_default();
+        let bad = colors.iter().find_map(|(key, value)| {
+            let value = value.as_str()?;
+            Rgba::try_from(value).is_err().then(|| (key.clone(), value.to_string()))
+        });
+        match bad {
+            Some((key, value)) => problems.push(ThemeProblem {
+                file: file.to_string(),
+                line: line_of(text, &value),
+                message: format!("\"{}\" in theme \"{}\" isn't a color: {}", key, theme.name, value),
+            }),
+            None => themes.push(theme),
+        }
+    }
+    (themes, problems)
+}
+
+/// Every readable theme in `dir` and the problems with the rest.
+pub fn scan(dir: &Path) -> (Vec<ThemeConfig>, Vec<ThemeProblem>) {
+    let mut themes = Vec::new();
+    let mut problems = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (themes, problems);
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths.iter().filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "json")) {
+        let file = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let (found, found_problems) = check_file(&file, &text);
+                themes.extend(found);
+                problems.extend(found_problems);
+            }
+            Err(err) => problems.push(ThemeProblem { file, line: 0, message: err.to_string() }),
+        }
+    }
+    (themes, problems)
+}
+
+/// Apply the theme named `name` from `dir` or the built-in ones. When it
+/// is missing or broken, apply the default theme for the current mode.
+/// Returns the problems to show.
+fn apply_named(dir: &Path, name: &str, cx: &mut App) -> Vec<String> {
+    let (themes, problems) = scan(dir);
+    let mut messages: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+    for problem in &problems {
+        warn!(%problem, "Invalid theme file");
+    }
+
+    let registry = ThemeRegistry::global(cx);
+    let config = themes.into_iter().find(|t| t.name == name).map(Rc::new)
+        .or_else(|| registry.default_themes().values().find(|t| t.name == name).cloned());
+    let config = match config {
+        Some(config) => config,
+        None => {
+            let fallback = if Theme::global(cx).mode.is_dark() {
+                registry.default_dark_theme().clone()
+            } else {
+                registry.default_light_theme().clone()
+            };
+            messages.push(format!("Theme \"{}\" couldn't be loaded; using \"{}\" instead.", name, fallback.name));
+            fallback
+        }
+    };
+    Theme::global_mut(cx).apply_config(&config);
+    crate::style::ChromeStyle::apply_accent(cx);
+    messages
+}
+
+/// Show `messages` as a warning toast in every window.
+fn report(messages: Vec<String>, cx: &mut App) {
+    if messages.is_empty() {
+        return;
+    }
+    let text = messages.join("\n");
+    for window in cx.windows() {
+        let Some(window) = window.downcast::<Root>() else { continue };
+        let _ = window.update(cx, |_, window, cx| {
+            window.push_notification(Notification::warning(text.clone()).title("Theme problem"), cx);
+        });
+    }
+}
+
+/// The theme this run should use.
+fn wanted_theme() -> String {
+    SessionOverrides::get().apply(&AppSettings::load()).theme
+}
+
+/// Load the themes in `dir`, apply `theme`, and re-check and re-apply
+/// whenever a file there changes.
+pub fn init(dir: PathBuf, theme: String, cx: &mut App) {
+    let load_dir = dir.clone();
+    // The registry lists the themes in menus; problems are reported from our own pass
+    let watched = ThemeRegistry::watch_dir(dir.clone(), cx, move |cx| {
+        let messages = apply_named(&load_dir, &theme, cx);
+        // Windows open after the registry starts loading
+        cx.defer(move |cx| report(messages, cx));
+    });
+    if let Err(err) = watched {
+        warn!(error = %err, "Failed to watch themes directory");
+        cx.defer(move |cx| report(vec![format!("Themes won't reload on change: {}", err)], cx));
+    }
+
+    // Nothing to watch until the folder exists
+    if let Err(err) = fs::create_dir_all(&dir) {
+        warn!(error = %err, "Failed to create themes directory");
+    }
+    let watcher = DirWatcher::new(&dir, |event| {
+        let is_theme = event.paths.iter().any(|p| p.extension().is_some_and(|e| e == "json"));
+        if is_theme { vec![()] } else { Vec::new() }
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            warn!(error = %err, "Failed to watch themes directory");
+            cx.defer(move |cx| report(vec![format!("Themes won't reload on change: {}", err)], cx));
+            return;
+        }
+    };
+
+    cx.spawn(async move |cx| {
+        while watcher.next_batch(SETTLE, cx.background_executor()).await.is_some() {
+            info!("Theme files changed; reapplying");
+            let applied = cx.update(|cx| {
+                let messages = apply_named(&dir, &wanted_theme(), cx);
+                report(messages, cx);
+                cx.refresh_windows();
+            });
+            if applied.is_err() {
+                break;
+            }
+        }
+    })
+    .detach();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_file, ThemeProblem};
+
+    #[test]
+    fn test_check_file() {
+        let good = r##"{"name": "Set", "themes": [{"name": "Paper", "mode": "light", "colors": {"background": "#ffffff"}}]}"##;
+        let (themes, problems) = check_file("paper.json", good);
+        assert_eq!(themes.len(), 1);
+        assert!(problems.is_empty());
+
+        let (themes, problems) = check_file("broken.json", "{\n  \"name\": \"Set\",\n  \"themes\": [\n}");
+        assert!(themes.is_empty());
+        assert_eq!(problems[0].file, "broken.json");
+        assert_eq!(problems[0].line, 4);
+        assert!(!problems[0].message.contains("at line"));
+    }
+
+    #[test]
+    fn test_bad_color_drops_the_theme() {
+        let text = "{\"themes\": [\n  {\"name\": \"Ok\", \"colors\": {}},\n  {\"name\": \"Bad\", \"colors\": {\n    \"background\": \"#zzzzzz\"\n  }}\n]}";
+        let (themes, problems) = check_file("mixed.json", text);
+        assert_eq!(themes.iter().map(|t| t.name.to_string()).collect::<Vec<_>>(), vec!["Ok"]);
+        assert_eq!(problems, vec![ThemeProblem {
+            file: "mixed.json".into(),
+            line: 4,
+            message: "\"background\" in theme \"Bad\" isn't a color: #zzzzzz".into(),
+        }]);
+        assert_eq!(problems[0].to_string(), "mixed.json, line 4: \"background\" in theme \"Bad\" isn't a color: #zzzzzz");
+    }
+}
@@ -0,0 +1,51 @@
+//! Watching a folder for changes on disk, for everything that reloads or
+//! picks up files as they change: themes, config files, the watch folder
+//! and the open document.
+
+use futures::channel::mpsc::{self, UnboundedReceiver};
+use futures::StreamExt;
+use gpui::BackgroundExecutor;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::time::Duration;
+
+/// A watched folder, yielding what its `pick` function took from each
+/// change. Watching stops when this is dropped.
+pub struct DirWatcher<T> {
+    _watcher: RecommendedWatcher,
+    changes: UnboundedReceiver<T>,
+}
+
+impl<T: Send + 'static> DirWatcher<T> {
+    /// Watch `dir`, but not its subfolders, keeping what `pick` takes from
+    /// each event.
+    pub fn new(dir: &Path, pick: impl Fn(notify::Event) -> Vec<T> + Send + 'static) -> notify::Result<Self> {
+        let (tx, changes) = mpsc::unbounded();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                for item in pick(event) {
+                    let _ = tx.unbounded_send(item);
+                }
+            }
+        })?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(Self { _watcher: watcher, changes })
+    }
+
+    /// The next changes, once none has come for `settle`, so a program
+    /// saving in several steps is seen once it's done. `None` once the
+    /// watcher has stopped.
+    pub async fn next_batch(&mut self, settle: Duration, executor: &BackgroundExecutor) -> Option<Vec<T>> {
+        let mut batch = vec![self.changes.next().await?];
+        loop {
+            executor.timer(settle).await;
+            let before = batch.len();
+            while let Ok(Some(item)) = self.changes.try_next() {
+                batch.push(item);
+            }
+            if batch.len() == before {
+                return Some(batch);
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Inserting another file into the buffer, and appending the buffer to
+//! another file, for collecting notes in one place.
+
+use gpui::*;
+use gpui_component::notification::Notification;
+use gpui_component::WindowExt;
+use std::path::Path;
+use tracing::{debug, info, warn};
+
+use super::Workspace;
+use super::compression::{read_text, write_text};
+use super::file_ops::{file_dialog, remember_dialog_dir, with_workspace_async};
+
+/// `existing` followed by `addition`, on a new line if `existing` doesn't
+/// end with one. The line break matches `existing`'s style.
+pub fn appended(existing: &str, addition: &str) -> String {
+    let newline = if existing.contains("\r\n") { "\r\n" } else { "\n" };
+    let separator = if existing.is_empty() || existing.ends_with('\n') { "" } else { newline };
+    format!("{}{}{}", existing, separator, addition)
+}
+
+/// Add `text` to the end of the file at `path`.
+fn append_to(path: &Path, text: &str, backup: bool) -> std::io::Result<()> {
+    let existing = read_text(path)?;
+    write_text(path, &appended(&existing, text), backup)
+}
+
+impl Workspace {
+    /// Pick a file and insert its contents at the caret, replacing the selection.
+    pub fn insert_file_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.editor_entity.as_ref().is_none_or(|e| e.read(cx).read_only) {
+            return;
+        }
+        let dialog = file_dialog(self.current_file(cx).as_deref(), None, &self.settings.file_filters).set_title("Insert File");
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let picked = cx.background_spawn(async move { dialog.pick_file().await.map(|f| f.path().to_path_buf()) });
+                let Some(path) = picked.await else {
+                    debug!("Insert file canceled");
+                    return;
+                };
+                remember_dialog_dir(&path);
+                let read = cx.background_spawn({
+                    let path = path.clone();
+                    async move { read_text(&path) }
+                });
+                let read = read.await;
+                with_workspace_async(&mut cx, |this, window, cx| match read {
+                    Ok(text) => {
+                        this.with_editor(cx, |ed, cx| ed.insert_text(&text, window, cx));
+                        info!(path = ?path, "Inserted file");
+                    }
+                    Err(err) => {
+                        warn!(path = ?path, error = %err, "Failed to read file to insert");
+                        window.push_notification(Notification::error(format!("Couldn't read {}: {}", path.display(), err)), cx);
+                    }
+                });
+            }
+        })
+        .detach();
+    }
+
+    /// Pick a file and add the selection (or the whole buffer) to its end.
+    pub fn append_to_file_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(selected) = self.with_editor(cx, |ed, cx| ed.selected_text(window, cx).filter(|t| !t.is_empty())) else {
+            return;
+        };
+        let text = selected.unwrap_or_else(|| self.get_editor_text(cx));
+        let current = self.current_file(cx);
+        let backup = self.settings.backup_on_save;
+        let dialog = file_dialog(current.as_deref(), None, &self.settings.file_filters).set_title("Append to File");
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let picked = cx.background_spawn(async move { dialog.pick_file().await.map(|f| f.path().to_path_buf()) });
+                let Some(path) = picked.await else {
+                    debug!("Append to file canceled");
+                    return;
+                };
+                remember_dialog_dir(&path);
+                // The buffer would no longer match the file it came from
+                if current.as_deref() == Some(path.as_path()) {
+                    warn!(path = ?path, "Not appending a document to itself");
+                    return;
+                }
+                let result = cx.background_spawn({
+                    let path = path.clone();
+                    async move { append_to(&path, &text, backup) }
+                });
+                match result.await {
+                    Ok(()) => info!(path = ?path, "Appended to file"),
+                    Err(err) => {
+                        warn!(path = ?path, error = %err, "Failed to append to file");
+                        with_workspace_async(&mut cx, |_, window, cx| {
+                            window.push_notification(Notification::error(format!("Couldn't append to {}: {}", path.display(), err)), cx);
+                        });
+                    }
+                }
+            }
+        })
+        .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append_to, appended};
+    use std::fs;
+
+    #[test]
+    fn test_appended() {
+        assert_eq!(appended("", "new"), "new");
+        assert_eq!(appended("old\n", "new"), "old\nnew");
+        assert_eq!(appended("old", "new"), "old\nnew");
+        assert_eq!(appended("a\r\nold", "new"), "a\r\nold\r\nnew");
+    }
+
+    #[test]
+    fn test_append_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("inbox.txt");
+        fs::write(&path, "first").unwrap();
+        append_to(&path, "second\n", false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+    }
+}
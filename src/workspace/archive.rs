@@ -0,0 +1,334 @@
+//! Browsing .zip archives and opening their text entries.
+
+use anyhow::{anyhow, bail};
+use flate2::read::DeflateDecoder;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::{h_flex, v_flex, Sizable, Theme};
+use rfd::AsyncFileDialog;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use super::Workspace;
+use super::file_ops::with_workspace_async;
+
+const END_OF_CENTRAL_DIR: u32 = 0x0605_4b50;
+const CENTRAL_DIR_ENTRY: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// A file inside a zip archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipEntry {
+    pub name: String,
+    /// Uncompressed size in bytes.
+    pub size: u64,
+    method: u16,
+    compressed_size: u64,
+    header_offset: usize,
+}
+
+pub fn is_zip_file(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> anyhow::Result<u16> {
+    let b = bytes.get(at..at + 2).ok_or_else(|| anyhow!("Archive is truncated"))?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> anyhow::Result<u32> {
+    let b = bytes.get(at..at + 4).ok_or_else(|| anyhow!("Archive is truncated"))?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// The file entries of a zip archive, from its central directory. Directories are skipped.
+pub fn list_entries(bytes: &[u8]) -> anyhow::Result<Vec<ZipEntry>> {
+    // The end record sits at the very end, followed by a comment of up to 64 KB
+    let end = (0..=bytes.len().saturating_sub(22))
+        .rev()
+        .take(0x10000 + 22)
+        .find(|&at| u32_at(bytes, at).ok() == Some(END_OF_CENTRAL_DIR))
+        .ok_or_else(|| anyhow!("Not a zip archive"))?;
+    let count = u16_at(bytes, end + 10)? as usize;
+    let mut at = u32_at(bytes, end + 16)? as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if u32_at(bytes, at)? != CENTRAL_DIR_ENTRY {
+            bail!("Corrupt central directory");
+        }
+        let name_len = u16_at(bytes, at + 28)? as usize;
+        let extra_len = u16_at(bytes, at + 30)? as usize;
+        let comment_len = u16_at(bytes, at + 32)? as usize;
+        let name = bytes.get(at + 46..at + 46 + name_len).ok_or_else(|| anyhow!("Archive is truncated"))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        if !name.ends_with('/') {
+            entries.push(ZipEntry {
+                name,
+                size: u32_at(bytes, at + 24)? as u64,
+                method: u16_at(bytes, at + 10)?,
+                compressed_size: u32_at(bytes, at + 20)? as u64,
+                header_offset: u32_at(bytes, at + 42)? as usize,
+            });
+        }
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// Decompressed contents of `entry`.
+pub fn read_entry(bytes: &[u8], entry: &ZipEntry) -> anyhow::Result<Vec<u8>> {
+    let at = entry.header_offset;
+    if u32_at(bytes, at)? != LOCAL_HEADER {
+        bail!("Corrupt entry header for {}", entry.name);
+    }
+    let start = at + 30 + u16_at(bytes, at + 26)? as usize + u16_at(bytes, at + 28)? as usize;
+    let data = bytes
+        .get(start..start + entry.compressed_size as usize)
+        .ok_or_else(|| anyhow!("Archive is truncated"))?;
+    match entry.method {
+        STORED => Ok(data.to_vec()),
+        DEFLATED => {
+            let mut out = Vec::with_capacity(entry.size as usize);
+            DeflateDecoder::new(data).read_to_end(&mut out)?;
+            Ok(out)
+        }
+        method => bail!("Unsupported compression method {} for {}", method, entry.name),
+    }
+}
+
+/// Listing of a zip archive, shown in place of the editor.
+pub struct ArchiveView {
+    pub path: PathBuf,
+    bytes: Arc<Vec<u8>>,
+    entries: Vec<ZipEntry>,
+    workspace: WeakEntity<Workspace>,
+}
+
+impl ArchiveView {
+    fn entry_text(&self, index: usize) -> anyhow::Result<String> {
+        let entry = &self.entries[index];
+        String::from_utf8(read_entry(&self.bytes, entry)?)
+            .map_err(|_| anyhow!("{} is not a UTF-8 text file", entry.name))
+    }
+}
+
+impl Render for ArchiveView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let colors = Theme::global(cx).colors;
+        let name = self.path.file_name().and_then(|n| n.to_str()).unwrap_or("Archive").to_string();
+
+        let rows = self.entries.iter().enumerate().map(|(index, entry)| {
+            h_flex()
+                .gap_2()
+                .px_2()
+                .border_b_1()
+                .border_color(colors.border)
+                .child(div().flex_1().overflow_hidden().child(entry.name.clone()))
+                .child(div().text_color(colors.muted_foreground).child(format!("{} bytes", entry.size)))
+                .child(Button::new(("archive-open", index)).label("Open").small().ghost()
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        let _ = this.workspace.update(cx, |ws, cx| ws.open_archive_entry(index, window, cx));
+                    })))
+                .child(Button::new(("archive-extract", index)).label("Extract and Edit...").small().ghost()
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        let _ = this.workspace.update(cx, |ws, cx| ws.extract_archive_entry(index, window, cx));
+                    })))
+        });
+
+        v_flex()
+            .size_full()
+            .bg(colors.background)
+            .child(
+                div()
+                    .px_2()
+                    .py_1()
+                    .bg(colors.muted)
+                    .border_b_1()
+                    .border_color(colors.border)
+                    .child(format!("{} ({} files)", name, self.entries.len())),
+            )
+            .child(div().id("archive-entries").flex_1().overflow_y_scroll().children(rows))
+    }
+}
+
+impl Workspace {
+    /// Read a zip archive and show its listing instead of the editor.
+    pub(crate) fn open_archive(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        let listing = fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok((list_entries(&bytes)?, bytes)));
+        let (entries, bytes) = match listing {
+            Ok(listing) => listing,
+            Err(err) => {
+                warn!(path = ?path, error = %err, "Failed to open archive");
+                return;
+            }
+        };
+        debug!(path = ?path, entries = entries.len(), "Opened archive");
+
+        let workspace = cx.entity().downgrade();
+        let view = cx.new(|_| ArchiveView {
+            path,
+            bytes: Arc::new(bytes),
+            entries,
+            workspace,
+        });
+        self.archive = Some(view);
+        self.show_archive(window, cx);
+    }
+
+    /// Switch back to the listing of the open archive.
+    pub fn show_archive(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(archive) = &self.archive else { return };
+        let name = archive.read(cx).path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        self.active_view = archive.clone().into();
+        window.set_window_title(&name);
+        self.cached_title = name;
+        cx.notify();
+    }
+
+    /// Show an archive entry in the editor, read-only.
+    fn open_archive_entry(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(archive) = &self.archive else { return };
+        let (path, text) = {
+            let archive = archive.read(cx);
+            (archive.path.join(&archive.entries[index].name), archive.entry_text(index))
+        };
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let text = match text {
+            Ok(text) => text,
+            Err(err) => {
+                warn!(path = ?path, error = %err, "Failed to open archive entry");
+                return;
+            }
+        };
+        self.handle_unsaved_changes(window, cx, move |this, window, cx| {
+            let Some(editor) = this.editor_entity.clone() else { return };
+            // Not a real file: saving goes through Save As
+            editor.update(cx, |ed, cx| ed.open_virtual(name, text, window, cx));
+            this.record_disk_mtime(cx);
+            this.active_view = editor.into();
+            this.update_title(window, cx);
+            cx.notify();
+        });
+    }
+
+    /// Write an archive entry next to wherever the user picks, then edit the copy.
+    fn extract_archive_entry(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(archive) = &self.archive else { return };
+        let (entry, bytes) = {
+            let archive = archive.read(cx);
+            (archive.entries[index].clone(), archive.bytes.clone())
+        };
+        let file_name = Path::new(&entry.name).file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let extracted = cx.background_spawn(async move {
+                    let dest = AsyncFileDialog::new().set_file_name(&file_name).save_file().await?.path().to_path_buf();
+                    let written = read_entry(&bytes, &entry).and_then(|data| Ok(fs::write(&dest, data)?));
+                    match written {
+                        Ok(()) => {
+                            info!(path = ?dest, entry = entry.name, "Extracted archive entry");
+                            Some(dest)
+                        }
+                        Err(err) => {
+                            warn!(path = ?dest, error = %err, "Failed to extract archive entry");
+                            None
+                        }
+                    }
+                }).await;
+
+                let Some(dest) = extracted else {
+                    let _ = cx.update(|_, _| {});
+                    return;
+                };
+                with_workspace_async(&mut cx, |this, window, cx_ws| {
+                    this.handle_unsaved_changes(window, cx_ws, move |this, window, cx| this.load_file(dest, false, window, cx));
+                });
+            }
+        })
+        .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{list_entries, read_entry};
+    use flate2::write::DeflateEncoder;
+    use std::io::Write;
+
+    /// Build a zip with the given (name, data, deflate) entries.
+    fn build_zip(files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data, deflate) in files {
+            let (method, stored): (u16, Vec<u8>) = if *deflate {
+                let mut enc = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                enc.write_all(data).unwrap();
+                (8, enc.finish().unwrap())
+            } else {
+                (0, data.to_vec())
+            };
+            let offset = out.len() as u32;
+            out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            out.extend_from_slice(&[0; 4]);
+            out.extend_from_slice(&method.to_le_bytes());
+            out.extend_from_slice(&[0; 8]);
+            out.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&stored);
+
+            central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            central.extend_from_slice(&[0; 6]);
+            central.extend_from_slice(&method.to_le_bytes());
+            central.extend_from_slice(&[0; 8]);
+            central.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 12]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let central_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&central_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_list_and_read_entries() {
+        let zip = build_zip(&[
+            ("docs/", b"", false),
+            ("docs/readme.txt", b"plain text\n", false),
+            ("notes.md", b"# compressed notes\n", true),
+        ]);
+        let entries = list_entries(&zip).unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["docs/readme.txt", "notes.md"]);
+        assert_eq!(read_entry(&zip, &entries[0]).unwrap(), b"plain text\n");
+        assert_eq!(read_entry(&zip, &entries[1]).unwrap(), b"# compressed notes\n");
+    }
+
+    #[test]
+    fn test_rejects_non_zip() {
+        assert!(list_entries(b"just some text").is_err());
+        assert!(list_entries(b"").is_err());
+    }
+}
@@ -0,0 +1,362 @@
+//! Periodic autosave, and noticing and resolving external edits to the
+//! open file as they happen.
+
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::notification::Notification;
+use gpui_component::{h_flex, Sizable, Theme, WindowExt};
+use notify::EventKind;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+
+use super::Workspace;
+use super::compression;
+use super::file_ops::with_workspace_async;
+use crate::editor::Encoding;
+use crate::settings::SaveConflictPolicy;
+use crate::watcher::DirWatcher;
+
+/// How long the file is left alone so the program writing it can finish.
+const SETTLE: Duration = Duration::from_millis(300);
+
+/// The open file, watched for changes by other programs.
+pub(super) struct FileWatch {
+    path: PathBuf,
+    _task: Task<()>,
+}
+
+/// What autosave should do with a file that changed on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Write the buffer over the file on disk.
+    Overwrite,
+    /// Replace the buffer with the file on disk.
+    Reload,
+    /// Ask the user.
+    Ask,
+}
+
+/// Decide how to resolve a conflict between the buffer and a newer file on disk.
+pub fn resolve_conflict(
+    policy: SaveConflictPolicy,
+    disk_modified: SystemTime,
+    buffer_modified: Option<SystemTime>,
+) -> ConflictResolution {
+    match policy {
+        SaveConflictPolicy::KeepBuffer => ConflictResolution::Overwrite,
+        SaveConflictPolicy::Prompt => ConflictResolution::Ask,
+        SaveConflictPolicy::KeepNewer => match buffer_modified {
+            Some(edited) if edited > disk_modified => ConflictResolution::Overwrite,
+            _ => ConflictResolution::Reload,
+        },
+    }
+}
+
+/// Last modification time of a file, if it exists.
+pub fn disk_mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Whether `event` writes or replaces the file called `name`.
+fn touches_file(event: &notify::Event, name: &OsStr) -> bool {
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+        && event.paths.iter().any(|p| p.file_name() == Some(name))
+}
+
+impl Workspace {
+    /// (Re)start the autosave timer from the current settings.
+    pub(crate) fn start_autosave(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let interval = self.settings.autosave_interval_secs;
+        if interval == 0 {
+            self.autosave_task = None;
+            return;
+        }
+
+        self.autosave_task = Some(cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                loop {
+                    cx.background_executor().timer(Duration::from_secs(interval)).await;
+                    if with_workspace_async(&mut cx, |this, window, cx_ws| this.autosave(window, cx_ws)).is_none() {
+                        break;
+                    }
+                }
+            }
+        }));
+    }
+
+    /// Watch the current file for changes by other programs, if it isn't
+    /// watched already.
+    pub(super) fn watch_current_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let path = self.current_file(cx);
+        if self.file_watch.as_ref().map(|watch| &watch.path) == path.as_ref() {
+            return;
+        }
+        self.file_watch = None;
+        let Some(path) = path else { return };
+        let Some(name) = path.file_name().map(OsStr::to_os_string) else { return };
+        let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        // Editors often save by renaming a new file over the old, so watch the folder
+        let mut watcher = match DirWatcher::new(dir, move |event| if touches_file(&event, &name) { vec![()] } else { Vec::new() }) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                warn!(path = ?path, error = %err, "Failed to watch file for changes");
+                return;
+            }
+        };
+
+        let task = cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                while watcher.next_batch(SETTLE, cx.background_executor()).await.is_some() {
+                    if with_workspace_async(&mut cx, |this, window, cx_ws| this.check_disk_change(window, cx_ws)).is_none() {
+                        return;
+                    }
+                }
+            }
+        });
+        self.file_watch = Some(FileWatch { path, _task: task });
+    }
+
+    /// The current file may have changed on disk: reload it if nothing is
+    /// unsaved, or else show the conflict banner.
+    pub(super) fn check_disk_change(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        // Our own saves record the new timestamp once written
+        if self.saves.is_busy() {
+            return;
+        }
+        let Some(path) = self.current_file(cx) else { return };
+        let Some(disk) = disk_mtime(&path) else { return };
+        if self.disk_mtime.is_none_or(|known| disk <= known) || self.pending_conflict == Some(disk) {
+            return;
+        }
+        if self.document.read(cx).is_dirty() {
+            info!(path = ?path, "File changed on disk while edited");
+            self.pending_conflict = Some(disk);
+            cx.notify();
+        } else {
+            info!(path = ?path, "Reloading file changed on disk");
+            self.reload_from_disk(path, window, cx);
+        }
+    }
+
+    /// Remember the on-disk timestamp of the current file.
+    pub(crate) fn record_disk_mtime(&mut self, cx: &App) {
+        self.disk_mtime = self.current_file(cx).as_deref().and_then(disk_mtime);
+        self.pending_conflict = None;
+    }
+
+    fn autosave(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(path) = self.current_file(cx) else { return };
+        let Some((is_dirty, last_edited)) = self.editor_entity.as_ref().map(|e| {
+            let ed = e.read(cx);
+            (ed.is_dirty(cx), ed.last_edited)
+        }) else {
+            return;
+        };
+        if !is_dirty {
+            return;
+        }
+
+        let on_disk = disk_mtime(&path);
+        let changed_externally = match (on_disk, self.disk_mtime) {
+            (Some(disk), Some(known)) => disk > known,
+            _ => false,
+        };
+
+        if !changed_externally {
+            debug!(path = ?path, "Autosaving");
+            self.autosave_file(window, cx);
+            return;
+        }
+
+        let Some(disk) = on_disk else { return };
+        match resolve_conflict(self.settings.autosave_conflict_policy, disk, last_edited) {
+            ConflictResolution::Overwrite => {
+                info!(path = ?path, "Autosave overwriting externally modified file");
+                self.autosave_file(window, cx);
+            }
+            ConflictResolution::Reload => {
+                info!(path = ?path, "Autosave reloading externally modified file");
+                self.reload_from_disk(path, window, cx);
+            }
+            ConflictResolution::Ask => {
+                // The banner asks, until the user picks or the file changes again
+                if self.pending_conflict != Some(disk) {
+                    self.pending_conflict = Some(disk);
+                    cx.notify();
+                }
+            }
+        }
+    }
+
+    /// Replace the buffer with the file on disk, read in the background.
+    fn reload_from_disk(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let read = cx.background_spawn({
+                    let path = path.clone();
+                    async move { compression::read_decoded(&path) }
+                });
+                let decoded = read.await;
+                with_workspace_async(&mut cx, |this, window, cx_ws| match decoded {
+                    Ok((contents, encoding)) => this.show_reloaded(path, contents, encoding, window, cx_ws),
+                    Err(err) => {
+                        warn!(path = ?path, error = %err, "Failed to reload file");
+                        window.push_notification(Notification::error(format!("Couldn't reload {}: {}", path.display(), err)), cx_ws);
+                    }
+                });
+            }
+        })
+        .detach();
+    }
+
+    fn show_reloaded(&mut self, path: PathBuf, contents: String, encoding: Encoding, window: &mut Window, cx: &mut Context<Self>) {
+        // Another file was opened while this one was read
+        if self.current_file(cx).as_ref() != Some(&path) {
+            return;
+        }
+        if let Some(editor) = &self.editor_entity {
+            editor.update(cx, |ed, cx| {
+                ed.open_decoded(path, contents, encoding, window, cx);
+            });
+        }
+        self.record_disk_mtime(cx);
+        self.update_title(window, cx);
+        cx.notify();
+    }
+
+    /// The choices for a file changed on disk while it has unsaved edits.
+    pub(super) fn render_disk_conflict(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        self.pending_conflict?;
+        let colors = Theme::global(cx).colors;
+        let name = self.document.read(cx).display_name();
+        Some(
+            h_flex()
+                .gap_2()
+                .px_2()
+                .py_1()
+                .text_sm()
+                .bg(colors.muted)
+                .border_b_1()
+                .border_color(colors.border)
+                .child(div().flex_1().child(format!("{} was changed by another program.", name)))
+                .child(Button::new("disk-conflict-overwrite").label("Overwrite").small().ghost()
+                    .on_click(cx.listener(|this, _, window, cx| this.autosave_file(window, cx))))
+                .child(Button::new("disk-conflict-reload").label("Reload").small().ghost()
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        if let Some(path) = this.current_file(cx) {
+                            this.reload_from_disk(path, window, cx);
+                        }
+                    })))
+                .child(Button::new("disk-conflict-compare").label("Compare").small().ghost()
+                    .on_click(cx.listener(|this, _, window, cx| this.compare_with_saved(window, cx)))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve_conflict, touches_file, ConflictResolution};
+    use crate::settings::{self, AppSettings, MemoryStore, SaveConflictPolicy};
+    use crate::workspace::Workspace;
+    use gpui::{AppContext, TestAppContext, VisualTestContext};
+    use gpui_component::Root;
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+    use notify::{Event, EventKind};
+    use std::ffi::OsStr;
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_keep_buffer_always_overwrites() {
+        let res = resolve_conflict(SaveConflictPolicy::KeepBuffer, at(200), Some(at(100)));
+        assert_eq!(res, ConflictResolution::Overwrite);
+    }
+
+    #[test]
+    fn test_prompt_always_asks() {
+        let res = resolve_conflict(SaveConflictPolicy::Prompt, at(100), Some(at(200)));
+        assert_eq!(res, ConflictResolution::Ask);
+    }
+
+    #[test]
+    fn test_keep_newer_prefers_recent_buffer() {
+        let res = resolve_conflict(SaveConflictPolicy::KeepNewer, at(100), Some(at(200)));
+        assert_eq!(res, ConflictResolution::Overwrite);
+    }
+
+    #[test]
+    fn test_keep_newer_prefers_recent_disk() {
+        let res = resolve_conflict(SaveConflictPolicy::KeepNewer, at(200), Some(at(100)));
+        assert_eq!(res, ConflictResolution::Reload);
+    }
+
+    #[test]
+    fn test_keep_newer_without_edit_time_reloads() {
+        let res = resolve_conflict(SaveConflictPolicy::KeepNewer, at(200), None);
+        assert_eq!(res, ConflictResolution::Reload);
+    }
+
+    #[test]
+    fn test_touches_file() {
+        let name = OsStr::new("notes.txt");
+        let modified = Event::new(EventKind::Modify(ModifyKind::Any)).add_path(PathBuf::from("/docs/notes.txt"));
+        assert!(touches_file(&modified, name));
+        let replaced = Event::new(EventKind::Create(CreateKind::File)).add_path(PathBuf::from("/docs/notes.txt"));
+        assert!(touches_file(&replaced, name));
+        let other = Event::new(EventKind::Modify(ModifyKind::Any)).add_path(PathBuf::from("/docs/todo.txt"));
+        assert!(!touches_file(&other, name));
+        let read = Event::new(EventKind::Access(AccessKind::Any)).add_path(PathBuf::from("/docs/notes.txt"));
+        assert!(!touches_file(&read, name));
+    }
+
+    /// Write `text` to `path` and date it `secs` after the epoch.
+    fn write_at(path: &Path, text: &str, secs: u64) {
+        fs::write(path, text).unwrap();
+        File::options().write(true).open(path).unwrap().set_modified(at(secs)).unwrap();
+    }
+
+    #[gpui::test]
+    async fn test_external_changes_reload_or_raise_the_banner(cx: &mut TestAppContext) {
+        settings::install_store(Box::new(MemoryStore::default()));
+        cx.update(gpui_component::init);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        write_at(&path, "first", 100);
+
+        let settings = AppSettings { autosave_interval_secs: 0, ..AppSettings::default() };
+        let window = cx.add_window(|window, cx| {
+            let workspace = cx.new(|cx| Workspace::new(window, cx, settings));
+            Root::new(workspace, window, cx)
+        });
+        let workspace = window.update(cx, |root, _, _| root.view().clone().downcast::<Workspace>().unwrap()).unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        workspace.update_in(cx, |ws, window, cx| ws.open_file(path.clone(), window, cx));
+        cx.run_until_parked();
+
+        // Nothing unsaved: the buffer follows the file
+        write_at(&path, "second", 200);
+        workspace.update_in(cx, |ws, window, cx| ws.check_disk_change(window, cx));
+        cx.run_until_parked();
+        assert_eq!(workspace.update_in(cx, |ws, _, cx| ws.get_editor_text(cx)), "second");
+        assert_eq!(workspace.read_with(cx, |ws, _| ws.pending_conflict), None);
+
+        // Unsaved edits are kept and the banner asks what to do
+        workspace.update_in(cx, |ws, _, cx| ws.document.update(cx, |doc, cx| doc.update_dirty(true, cx)));
+        write_at(&path, "third", 300);
+        workspace.update_in(cx, |ws, window, cx| ws.check_disk_change(window, cx));
+        cx.run_until_parked();
+        assert_eq!(workspace.update_in(cx, |ws, _, cx| ws.get_editor_text(cx)), "second");
+        assert_eq!(workspace.read_with(cx, |ws, _| ws.pending_conflict), Some(at(300)));
+    }
+}
@@ -0,0 +1,491 @@
+//! Converting every text file in a folder at once: encoding, line endings
+//! and trailing whitespace, with a dry-run preview first.
+
+use gpui::*;
+use gpui_component::button::{Button, ButtonGroup};
+use gpui_component::checkbox::Checkbox;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::{h_flex, v_flex, Selectable, Sizable, Theme, WindowExt};
+use rfd::AsyncFileDialog;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use tracing::{debug, info, warn};
+
+use super::Workspace;
+use super::file_ops::with_workspace_async;
+use super::safe_write::write_atomic;
+use crate::editor::{decode_windows_1252, LineEnding};
+
+/// Preview rows shown before the list is cut off.
+const PREVIEW_LIMIT: usize = 500;
+
+const BOM: &str = "\u{FEFF}";
+
+/// Encoding files should end up in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TargetEncoding {
+    /// Leave the encoding alone; files that aren't UTF-8 are skipped.
+    #[default]
+    Keep,
+    /// UTF-8 without a byte order mark. Files that aren't UTF-8 are read as
+    /// Windows-1252.
+    Utf8,
+    /// UTF-8 with a byte order mark.
+    Utf8Bom,
+}
+
+impl TargetEncoding {
+    const ALL: [(TargetEncoding, &'static str); 3] = [
+        (TargetEncoding::Keep, "Keep"),
+        (TargetEncoding::Utf8, "UTF-8"),
+        (TargetEncoding::Utf8Bom, "UTF-8 with BOM"),
+    ];
+}
+
+/// Line endings offered for normalization.
+const LINE_ENDINGS: [(Option<LineEnding>, &str); 3] = [
+    (None, "Keep"),
+    (Some(LineEnding::Lf), "LF"),
+    (Some(LineEnding::Crlf), "CRLF"),
+];
+
+/// What to do to each file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatchOptions {
+    pub encoding: TargetEncoding,
+    /// Line ending to normalize to, or `None` to keep them.
+    pub line_ending: Option<LineEnding>,
+    pub trim_trailing_whitespace: bool,
+    /// Include files in subfolders.
+    pub recursive: bool,
+}
+
+/// Result of planning one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilePlan {
+    /// The file would change; the list says how.
+    Convert(Vec<&'static str>),
+    /// Nothing to do.
+    Unchanged,
+    /// The file was left out, and why.
+    Skipped(&'static str),
+}
+
+/// `bytes` converted per `options`, with the changes made, or why the file
+/// was skipped.
+pub fn convert(bytes: &[u8], options: &BatchOptions) -> Result<(Vec<u8>, Vec<&'static str>), &'static str> {
+    if bytes.contains(&0) {
+        return Err("binary");
+    }
+    let mut changes = Vec::new();
+    let decoded = match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) if options.encoding == TargetEncoding::Keep => return Err("not UTF-8"),
+        Err(_) => {
+            changes.push("Windows-1252 to UTF-8");
+            decode_windows_1252(bytes)
+        }
+    };
+    let had_bom = decoded.starts_with(BOM);
+    let mut text = decoded.strip_prefix(BOM).unwrap_or(&decoded).to_string();
+
+    if let Some(ending) = options.line_ending {
+        let normalized = ending.normalize(&text);
+        if normalized != text {
+            changes.push(if ending == LineEnding::Crlf { "line endings to CRLF" } else { "line endings to LF" });
+            text = normalized;
+        }
+    }
+    if options.trim_trailing_whitespace {
+        let trimmed = trim_trailing_whitespace(&text);
+        if trimmed != text {
+            changes.push("trailing whitespace trimmed");
+            text = trimmed;
+        }
+    }
+    let bom = match options.encoding {
+        TargetEncoding::Keep => had_bom,
+        TargetEncoding::Utf8 => false,
+        TargetEncoding::Utf8Bom => true,
+    };
+    match (had_bom, bom) {
+        (true, false) => changes.push("byte order mark removed"),
+        (false, true) => changes.push("byte order mark added"),
+        _ => {}
+    }
+    if bom {
+        text.insert_str(0, BOM);
+    }
+    Ok((text.into_bytes(), changes))
+}
+
+/// `text` without spaces and tabs at the ends of lines.
+fn trim_trailing_whitespace(text: &str) -> String {
+    text.split('\n')
+        .map(|line| match line.strip_suffix('\r') {
+            Some(line) => format!("{}\r", line.trim_end_matches([' ', '\t'])),
+            None => line.trim_end_matches([' ', '\t']).to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Files in `dir` (and below it when `recursive`), skipping hidden entries,
+/// in alphabetical order.
+pub fn list_files(dir: &Path, recursive: bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            warn!(dir = ?dir, "Could not list folder");
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            // Don't follow links to folders, which may loop
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            if is_dir && recursive {
+                pending.push(path);
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// What converting `path` would do.
+fn plan_file(path: &Path, options: &BatchOptions) -> FilePlan {
+    let Ok(bytes) = fs::read(path) else { return FilePlan::Skipped("unreadable") };
+    match convert(&bytes, options) {
+        Ok((_, changes)) if changes.is_empty() => FilePlan::Unchanged,
+        Ok((_, changes)) => FilePlan::Convert(changes),
+        Err(reason) => FilePlan::Skipped(reason),
+    }
+}
+
+/// Convert `path` in place. The file is read again so that changes made
+/// since the preview aren't lost.
+fn convert_file(path: &Path, options: &BatchOptions, backup: bool) -> anyhow::Result<bool> {
+    let bytes = fs::read(path)?;
+    let (converted, changes) = convert(&bytes, options).map_err(anyhow::Error::msg)?;
+    if changes.is_empty() {
+        return Ok(false);
+    }
+    write_atomic(path, &converted, backup)?;
+    Ok(true)
+}
+
+/// Dialog body holding the options being edited.
+struct BatchPanel {
+    dir: PathBuf,
+    options: BatchOptions,
+}
+
+impl BatchPanel {
+    fn row(label: &'static str, control: impl IntoElement) -> impl IntoElement {
+        h_flex()
+            .gap_3()
+            .items_center()
+            .child(div().w(px(110.0)).child(label))
+            .child(control)
+    }
+}
+
+impl Render for BatchPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let options = self.options;
+        v_flex()
+            .gap_3()
+            .child(format!("Folder: {}", self.dir.display()))
+            .child(Self::row(
+                "Encoding",
+                ButtonGroup::new("batch-encoding")
+                    .small()
+                    .children(TargetEncoding::ALL.iter().map(|(encoding, label)| {
+                        Button::new(*label).label(*label).selected(*encoding == options.encoding)
+                    }))
+                    .on_click(cx.listener(|this, clicks: &Vec<usize>, _, cx| {
+                        if let Some((encoding, _)) = clicks.first().and_then(|&i| TargetEncoding::ALL.get(i)) {
+                            this.options.encoding = *encoding;
+                            cx.notify();
+                        }
+                    })),
+            ))
+            .child(Self::row(
+                "Line endings",
+                ButtonGroup::new("batch-line-endings")
+                    .small()
+                    .children(LINE_ENDINGS.iter().map(|(ending, label)| {
+                        Button::new(*label).label(*label).selected(*ending == options.line_ending)
+                    }))
+                    .on_click(cx.listener(|this, clicks: &Vec<usize>, _, cx| {
+                        if let Some((ending, _)) = clicks.first().and_then(|&i| LINE_ENDINGS.get(i)) {
+                            this.options.line_ending = *ending;
+                            cx.notify();
+                        }
+                    })),
+            ))
+            .child(
+                Checkbox::new("batch-trim")
+                    .label("Trim trailing whitespace")
+                    .checked(options.trim_trailing_whitespace)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.options.trim_trailing_whitespace = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Checkbox::new("batch-recursive")
+                    .label("Include subfolders")
+                    .checked(options.recursive)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.options.recursive = *checked;
+                        cx.notify();
+                    })),
+            )
+    }
+}
+
+impl Workspace {
+    /// Pick a folder, choose the conversions, preview them, then run them in
+    /// the background.
+    pub fn batch_convert(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let mut dialog = AsyncFileDialog::new().set_title("Batch Convert Folder");
+        if let Some(dir) = self.current_file(cx).as_deref().and_then(Path::parent) {
+            dialog = dialog.set_directory(dir);
+        }
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let picked = cx.background_spawn(async move { dialog.pick_folder().await.map(|d| d.path().to_path_buf()) });
+                let Some(dir) = picked.await else {
+                    debug!("Batch convert canceled");
+                    return;
+                };
+                with_workspace_async(&mut cx, |this, window, cx| this.batch_options_dialog(dir, window, cx));
+            }
+        })
+        .detach();
+    }
+
+    fn batch_options_dialog(&mut self, dir: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        let panel = cx.new(|_| BatchPanel { dir, options: BatchOptions::default() });
+        let workspace = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let panel_for_ok = panel.clone();
+            let workspace = workspace.clone();
+            dialog
+                .title("Batch Convert")
+                .w(px(520.0))
+                .child(panel.clone())
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Preview"))
+                .on_ok(move |_, window, cx| {
+                    let panel = panel_for_ok.read(cx);
+                    let (dir, options) = (panel.dir.clone(), panel.options);
+                    let _ = workspace.update(cx, |this, cx| this.batch_preview(dir, options, window, cx));
+                    true
+                })
+        });
+    }
+
+    /// Plan every file in the background, then show what would change.
+    fn batch_preview(&mut self, dir: PathBuf, options: BatchOptions, window: &mut Window, cx: &mut Context<Self>) {
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let plans = cx
+                    .background_spawn({
+                        let dir = dir.clone();
+                        async move {
+                            list_files(&dir, options.recursive)
+                                .into_iter()
+                                .map(|path| {
+                                    let plan = plan_file(&path, &options);
+                                    (path, plan)
+                                })
+                                .collect::<Vec<_>>()
+                        }
+                    })
+                    .await;
+                with_workspace_async(&mut cx, |this, window, cx| this.show_batch_preview(dir, options, plans, window, cx));
+            }
+        })
+        .detach();
+    }
+
+    fn show_batch_preview(
+        &mut self,
+        dir: PathBuf,
+        options: BatchOptions,
+        plans: Vec<(PathBuf, FilePlan)>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let to_convert: Vec<PathBuf> = plans
+            .iter()
+            .filter(|(_, plan)| matches!(plan, FilePlan::Convert(_)))
+            .map(|(path, _)| path.clone())
+            .collect();
+        let skipped = plans.iter().filter(|(_, plan)| matches!(plan, FilePlan::Skipped(_))).count();
+        let status = format!(
+            "{} of {} files will change; {} skipped.",
+            to_convert.len(),
+            plans.len(),
+            skipped
+        );
+        let rows: Rc<Vec<(String, String, bool)>> = Rc::new(
+            plans
+                .iter()
+                .filter_map(|(path, plan)| {
+                    let name = path.strip_prefix(&dir).unwrap_or(path).display().to_string();
+                    match plan {
+                        FilePlan::Convert(changes) => Some((name, changes.join(", "), false)),
+                        FilePlan::Skipped(reason) => Some((name, format!("skipped: {}", reason), true)),
+                        FilePlan::Unchanged => None,
+                    }
+                })
+                .take(PREVIEW_LIMIT)
+                .collect(),
+        );
+        let backup = self.settings.backup_on_save;
+        let workspace = cx.entity().downgrade();
+        let to_convert = Rc::new(to_convert);
+
+        window.open_dialog(cx, move |dialog, _window, cx| {
+            let colors = Theme::global(cx).colors;
+            let table = rows.iter().map(|(name, changes, skipped)| {
+                h_flex()
+                    .gap_3()
+                    .child(div().w(px(240.0)).overflow_x_hidden().child(name.clone()))
+                    .child(div().text_color(if *skipped { colors.muted_foreground } else { colors.foreground }).child(changes.clone()))
+            });
+            let dialog = dialog
+                .title("Batch Convert Preview")
+                .w(px(640.0))
+                .child(
+                    v_flex()
+                        .gap_2()
+                        .child(status.clone())
+                        .child(
+                            div()
+                                .id("batch-preview")
+                                .max_h(px(360.0))
+                                .overflow_y_scroll()
+                                .text_sm()
+                                .children(table),
+                        ),
+                );
+            if to_convert.is_empty() {
+                return dialog.alert();
+            }
+            let files = to_convert.clone();
+            let workspace = workspace.clone();
+            dialog
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text(format!("Convert {} Files", files.len())))
+                .on_ok(move |_, window, cx| {
+                    let files = files.to_vec();
+                    let _ = workspace.update(cx, |this, cx| this.run_batch(files, options, backup, window, cx));
+                    true
+                })
+        });
+    }
+
+    /// Convert `files` in the background and report how it went.
+    fn run_batch(&mut self, files: Vec<PathBuf>, options: BatchOptions, backup: bool, window: &mut Window, cx: &mut Context<Self>) {
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let (converted, failed) = cx
+                    .background_spawn(async move {
+                        let mut converted = Vec::new();
+                        let mut failed = 0;
+                        for path in files {
+                            match convert_file(&path, &options, backup) {
+                                Ok(true) => converted.push(path),
+                                Ok(false) => {}
+                                Err(err) => {
+                                    warn!(path = ?path, error = %err, "Batch conversion failed");
+                                    failed += 1;
+                                }
+                            }
+                        }
+                        (converted, failed)
+                    })
+                    .await;
+                info!(converted = converted.len(), failed, "Batch conversion finished");
+
+                with_workspace_async(&mut cx, |this, window, cx| {
+                    // Show the new contents of the open file unless it has edits
+                    let is_dirty = this.editor_entity.as_ref().is_some_and(|e| e.read(cx).is_dirty(cx));
+                    if let Some(current) = this.current_file(cx).filter(|p| converted.contains(p) && !is_dirty) {
+                        this.load_file(current, false, window, cx);
+                    }
+                    let message = match failed {
+                        0 => format!("Converted {} files.", converted.len()),
+                        n => format!("Converted {} files; {} could not be converted (see the log).", converted.len(), n),
+                    };
+                    window.open_dialog(cx, move |dialog, _window, _cx| {
+                        dialog.title("Batch Convert").child(message.clone()).alert()
+                    });
+                });
+            }
+        })
+        .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert, list_files, BatchOptions, TargetEncoding};
+    use crate::editor::LineEnding;
+    use std::fs;
+
+    #[test]
+    fn test_convert() {
+        let options = BatchOptions {
+            encoding: TargetEncoding::Utf8,
+            line_ending: Some(LineEnding::Lf),
+            trim_trailing_whitespace: true,
+            recursive: false,
+        };
+        let (bytes, changes) = convert(b"\xef\xbb\xbfa \r\nb\r\n", &options).unwrap();
+        assert_eq!(bytes, b"a\nb\n");
+        assert_eq!(changes, ["line endings to LF", "trailing whitespace trimmed", "byte order mark removed"]);
+
+        let (bytes, changes) = convert(b"caf\xe9", &options).unwrap();
+        assert_eq!(bytes, "café".as_bytes());
+        assert_eq!(changes, ["Windows-1252 to UTF-8"]);
+
+        let crlf = BatchOptions { line_ending: Some(LineEnding::Crlf), ..BatchOptions::default() };
+        assert_eq!(convert(b"a\nb\rc\r\n", &crlf).unwrap().0, b"a\r\nb\r\nc\r\n");
+        assert_eq!(convert(b"ok\n", &crlf).unwrap().1, ["line endings to CRLF"]);
+        assert!(convert(b"ok\r\n", &crlf).unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn test_convert_skips() {
+        assert_eq!(convert(b"caf\xe9", &BatchOptions::default()), Err("not UTF-8"));
+        assert_eq!(convert(b"a\0b", &BatchOptions::default()), Err("binary"));
+    }
+
+    #[test]
+    fn test_list_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("b.txt"), "").unwrap();
+        fs::write(dir.path().join(".hidden"), "").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("a.txt"), "").unwrap();
+
+        assert_eq!(list_files(dir.path(), false), [dir.path().join("b.txt")]);
+        assert_eq!(list_files(dir.path(), true), [dir.path().join("b.txt"), dir.path().join("sub").join("a.txt")]);
+    }
+}
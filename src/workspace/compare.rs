@@ -0,0 +1,190 @@
+//! Side-by-side comparison of the buffer with another file or with its own
+//! saved version on disk.
+
+use gpui::*;
+use gpui::prelude::FluentBuilder;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::notification::Notification;
+use gpui_component::{h_flex, v_flex, Sizable, Theme, WindowExt};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use super::Workspace;
+use super::compression::read_text;
+use super::file_ops::{file_dialog, remember_dialog_dir, with_workspace_async};
+use crate::editor::{side_by_side, DiffRow, RowKind};
+
+/// Diff of the buffer (left) against another text (right), shown in place of the editor.
+pub struct CompareView {
+    left_title: String,
+    right_title: String,
+    rows: Vec<DiffRow>,
+    /// Changed, removed and added row counts.
+    counts: (usize, usize, usize),
+    workspace: WeakEntity<Workspace>,
+}
+
+impl CompareView {
+    fn new(left_title: String, right_title: String, left: &str, right: &str, workspace: WeakEntity<Workspace>) -> Self {
+        let rows = side_by_side(left, right);
+        let count = |kind| rows.iter().filter(|r| r.kind == kind).count();
+        let counts = (count(RowKind::Changed), count(RowKind::Removed), count(RowKind::Added));
+        Self { left_title, right_title, rows, counts, workspace }
+    }
+
+    fn render_row(&self, index: usize, cx: &App) -> Div {
+        let theme = Theme::global(cx);
+        let colors = theme.colors;
+        let row = &self.rows[index];
+        let (left_bg, right_bg) = match row.kind {
+            RowKind::Same => (None, None),
+            RowKind::Changed => (Some(colors.warning.opacity(0.2)), Some(colors.warning.opacity(0.2))),
+            RowKind::Removed => (Some(colors.danger.opacity(0.2)), Some(colors.muted)),
+            RowKind::Added => (Some(colors.muted), Some(colors.success.opacity(0.2))),
+        };
+        let half = |side: &Option<(usize, String)>, bg: Option<Hsla>| {
+            let (number, text) = match side {
+                Some((line, text)) => ((line + 1).to_string(), text.clone()),
+                None => (String::new(), String::new()),
+            };
+            h_flex()
+                .flex_1()
+                .min_w(px(0.))
+                .overflow_hidden()
+                .when_some(bg, |this, bg| this.bg(bg))
+                .child(div().w(px(48.)).flex_none().pr_2().text_right().text_color(colors.muted_foreground).child(number))
+                .child(div().flex_1().whitespace_nowrap().overflow_hidden().child(text))
+        };
+        h_flex()
+            .w_full()
+            .font_family(theme.mono_font_family.clone())
+            .child(half(&row.left, left_bg))
+            .child(div().w(px(1.)).h_full().bg(colors.border))
+            .child(half(&row.right, right_bg))
+    }
+}
+
+impl Render for CompareView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let colors = Theme::global(cx).colors;
+        let (changed, removed, added) = self.counts;
+        let summary = if changed + removed + added == 0 {
+            "No differences".to_string()
+        } else {
+            format!("{} changed, {} removed, {} added", changed, removed, added)
+        };
+
+        v_flex()
+            .size_full()
+            .bg(colors.background)
+            .child(
+                h_flex()
+                    .gap_2()
+                    .px_2()
+                    .py_1()
+                    .bg(colors.muted)
+                    .border_b_1()
+                    .border_color(colors.border)
+                    .child(div().flex_1().overflow_hidden().child(self.left_title.clone()))
+                    .child(div().flex_1().overflow_hidden().child(self.right_title.clone()))
+                    .child(div().text_color(colors.muted_foreground).child(summary))
+                    .child(Button::new("compare-close").label("Close").small().ghost()
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            let _ = this.workspace.update(cx, |ws, cx| ws.close_compare(window, cx));
+                        }))),
+            )
+            .child(
+                uniform_list("compare-rows", self.rows.len(), cx.processor(|this, range: std::ops::Range<usize>, _window, cx| {
+                    range.map(|index| this.render_row(index, cx)).collect()
+                }))
+                .flex_1(),
+            )
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string()
+}
+
+impl Workspace {
+    /// Show the buffer side by side with `other`.
+    fn show_compare(&mut self, right_title: String, other: &str, cx: &mut Context<Self>) {
+        let left_title = match &self.current_file(cx) {
+            Some(path) => format!("{} (buffer)", display_name(path)),
+            None => format!("{} (buffer)", self.document.read(cx).display_name()),
+        };
+        let text = self.get_editor_text(cx);
+        let workspace = cx.entity().downgrade();
+        let view = cx.new(|_| CompareView::new(left_title, right_title, &text, other, workspace));
+        debug!(rows = view.read(cx).rows.len(), "Showing comparison");
+        self.active_view = view.into();
+        cx.notify();
+    }
+
+    /// Leave the comparison and go back to the editor.
+    pub fn close_compare(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(editor) = self.editor_entity.clone() else { return };
+        let input = editor.read(cx).input_state.clone();
+        self.active_view = editor.into();
+        input.update(cx, |state, cx| state.focus(window, cx));
+        cx.notify();
+    }
+
+    /// Compare the buffer with the saved version of the current file.
+    pub fn compare_with_saved(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(path) = self.current_file(cx) else { return };
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let read = cx.background_spawn({
+                    let path = path.clone();
+                    async move { read_text(&path) }
+                });
+                let saved = read.await;
+                with_workspace_async(&mut cx, |this, window, cx| match saved {
+                    Ok(saved) => {
+                        info!(path = ?path, "Comparing buffer with saved version");
+                        this.show_compare(format!("{} (on disk)", display_name(&path)), &saved, cx);
+                    }
+                    Err(err) => {
+                        warn!(path = ?path, error = %err, "Failed to read saved version to compare");
+                        window.push_notification(Notification::error(format!("Couldn't read {}: {}", path.display(), err)), cx);
+                    }
+                });
+            }
+        })
+        .detach();
+    }
+
+    /// Pick a file and compare the buffer with it.
+    pub fn compare_with_file_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let dialog = file_dialog(self.current_file(cx).as_deref(), None, &self.settings.file_filters).set_title("Compare With");
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let picked = cx.background_spawn(async move { dialog.pick_file().await.map(|f| f.path().to_path_buf()) });
+                let Some(path) = picked.await else {
+                    debug!("Compare canceled");
+                    return;
+                };
+                remember_dialog_dir(&path);
+                let read = cx.background_spawn({
+                    let path: PathBuf = path.clone();
+                    async move { read_text(&path) }
+                });
+                let text = read.await;
+                with_workspace_async(&mut cx, |this, window, cx| match text {
+                    Ok(text) => {
+                        info!(path = ?path, "Comparing buffer with file");
+                        this.show_compare(display_name(&path), &text, cx);
+                    }
+                    Err(err) => {
+                        warn!(path = ?path, error = %err, "Failed to read file to compare");
+                        window.push_notification(Notification::error(format!("Couldn't read {}: {}", path.display(), err)), cx);
+                    }
+                });
+            }
+        })
+        .detach();
+    }
+}
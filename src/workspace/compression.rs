@@ -0,0 +1,159 @@
+//! Transparent decompression of compressed text files.
+//!
+//! Files are recognized by extension. Gzip, zstd and bzip2 files are
+//! decompressed on open and recompressed on save.
+
+use bzip2::read::MultiBzDecoder;
+use bzip2::write::BzEncoder;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use super::safe_write;
+use crate::editor::{decode_windows_1252, Encoding};
+
+/// Container format of a file on disk, judged by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            Some("bz2") => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+
+    /// Turn the raw file bytes into text, with the encoding it was read in.
+    pub fn decode(self, bytes: Vec<u8>) -> io::Result<(String, Encoding)> {
+        let bytes = match self {
+            Compression::None => bytes,
+            Compression::Gzip => {
+                let mut out = Vec::new();
+                GzDecoder::new(bytes.as_slice()).read_to_end(&mut out)?;
+                out
+            }
+            Compression::Zstd => zstd::decode_all(bytes.as_slice())?,
+            Compression::Bzip2 => {
+                // Parallel compressors write several streams back to back
+                let mut out = Vec::new();
+                MultiBzDecoder::new(bytes.as_slice()).read_to_end(&mut out)?;
+                out
+            }
+        };
+        decode_text(bytes)
+    }
+
+    /// Turn encoded text into the bytes to write to disk.
+    pub fn encode(self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(bytes.to_vec()),
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+            // Level 0 is zstd's default
+            Compression::Zstd => zstd::encode_all(bytes, 0),
+            Compression::Bzip2 => {
+                let mut encoder = BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// UTF-8 if `bytes` are valid UTF-8, otherwise Windows-1252. Bytes with a
+/// NUL in them are taken for binary rather than text.
+fn decode_text(bytes: Vec<u8>) -> io::Result<(String, Encoding)> {
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok((text, Encoding::Utf8)),
+        Err(err) if !err.as_bytes().contains(&0) => Ok((decode_windows_1252(err.as_bytes()), Encoding::Windows1252)),
+        Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+    }
+}
+
+/// Read a text file, decompressing it if needed.
+pub fn read_text(path: &Path) -> io::Result<String> {
+    read_decoded(path).map(|(text, _)| text)
+}
+
+/// `read_text`, also giving the encoding the file was read in.
+pub fn read_decoded(path: &Path) -> io::Result<(String, Encoding)> {
+    Compression::from_path(path).decode(fs::read(path)?)
+}
+
+/// Write a text file, compressing it to match its extension. The file is
+/// replaced atomically, after copying the old one to `.bak` if `backup`.
+pub fn write_text(path: &Path, text: &str, backup: bool) -> io::Result<()> {
+    write_bytes(path, text.as_bytes(), backup)
+}
+
+/// `write_text` for text already encoded to bytes.
+pub fn write_bytes(path: &Path, bytes: &[u8], backup: bool) -> io::Result<()> {
+    safe_write::write_atomic(path, &Compression::from_path(path).encode(bytes)?, backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_decoded, read_text, write_bytes, write_text, Compression};
+    use crate::editor::Encoding;
+    use std::path::Path;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Compression::from_path(Path::new("notes.txt")), Compression::None);
+        assert_eq!(Compression::from_path(Path::new("log.txt.GZ")), Compression::Gzip);
+        assert_eq!(Compression::from_path(Path::new("a.zst")), Compression::Zstd);
+        assert_eq!(Compression::from_path(Path::new("a.bz2")), Compression::Bzip2);
+    }
+
+    #[test]
+    fn test_compressed_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["notes.txt.gz", "notes.txt.zst", "notes.txt.bz2"] {
+            let path = dir.path().join(name);
+            write_text(&path, "hello\nworld\n", false).unwrap();
+            // Written compressed, not as plain text
+            assert_ne!(std::fs::read(&path).unwrap(), b"hello\nworld\n", "{}", name);
+            assert_eq!(read_text(&path).unwrap(), "hello\nworld\n", "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_concatenated_bzip2_streams() {
+        let mut bytes = Compression::Bzip2.encode(b"one\n").unwrap();
+        bytes.extend(Compression::Bzip2.encode(b"two\n").unwrap());
+        assert_eq!(Compression::Bzip2.decode(bytes).unwrap().0, "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_read_falls_back_to_windows_1252() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("latin.txt");
+        write_bytes(&path, b"caf\xe9 \x93quoted\x94\n", false).unwrap();
+        assert_eq!(read_decoded(&path).unwrap(), ("café “quoted”\n".to_string(), Encoding::Windows1252));
+        write_text(&path, "café\n", false).unwrap();
+        assert_eq!(read_decoded(&path).unwrap(), ("café\n".to_string(), Encoding::Utf8));
+        // NUL bytes mean binary, not text
+        write_bytes(&path, b"\xff\x00", false).unwrap();
+        assert!(read_decoded(&path).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_data_errors() {
+        assert!(Compression::Zstd.decode(vec![1, 2, 3]).is_err());
+        assert!(Compression::Bzip2.decode(vec![1, 2, 3]).is_err());
+    }
+}
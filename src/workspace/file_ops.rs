@@ -1,25 +1,158 @@
 //! File operations for the workspace (open, save, save-as dialogs).
 
+use futures::future::{FutureExt, Shared};
 use gpui::*;
-use gpui_component::Root;
-use std::fs;
-use std::path::PathBuf;
+use gpui_component::notification::Notification;
+use gpui_component::{Root, WindowExt};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tracing::{debug, info, warn};
 use rfd::{AsyncFileDialog, AsyncMessageDialog, MessageButtons, MessageDialogResult};
 
 use super::Workspace;
+use super::compression;
+use crate::editor::Encoding;
+use crate::settings::FileFilter;
+
+/// Where the last file picked in a dialog this session was.
+#[derive(Default)]
+struct LastDialogPick {
+    dir: Option<PathBuf>,
+    /// Its extension, lowercased, so the next dialog selects its filter.
+    extension: Option<String>,
+}
+
+static LAST_DIALOG_PICK: Mutex<LastDialogPick> = Mutex::new(LastDialogPick { dir: None, extension: None });
+
+/// `filters` as dialog filters, with the one matching `extension` first so
+/// dialogs select it; an unknown extension gets a filter of its own. An
+/// "All Files" filter is added if none matches everything.
+fn dialog_filters(filters: &[FileFilter], extension: Option<&str>) -> Vec<(String, Vec<String>)> {
+    let mut filters: Vec<(String, Vec<String>)> = filters
+        .iter()
+        .filter(|f| !f.extensions.is_empty())
+        .map(|f| (f.name.clone(), f.extensions.iter().map(|e| e.to_ascii_lowercase()).collect()))
+        .collect();
+    if !filters.iter().any(|(_, exts)| exts.iter().any(|e| e == "*")) {
+        filters.push(("All Files".to_string(), vec!["*".to_string()]));
+    }
+    let Some(extension) = extension.map(str::to_ascii_lowercase) else { return filters };
+    match filters.iter().position(|(_, exts)| exts.contains(&extension)) {
+        Some(index) => {
+            let filter = filters.remove(index);
+            filters.insert(0, filter);
+        }
+        None => filters.insert(0, (format!("{} files", extension.to_uppercase()), vec![extension])),
+    }
+    filters
+}
+
+/// File name Save As suggests for the document at `current`. Untitled
+/// documents are named from `first_line` when given.
+fn default_save_name(current: Option<&Path>, first_line: Option<&str>, extension: &str) -> String {
+    let extension = extension.trim().trim_start_matches('.');
+    current
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| {
+            let stem = first_line.map(slugify).filter(|s| !s.is_empty()).unwrap_or_else(|| "Untitled".to_string());
+            if extension.is_empty() { stem } else { format!("{}.{}", stem, extension) }
+        })
+}
+
+/// Longest name, in characters, taken from a first line.
+const SLUG_LIMIT: usize = 48;
+
+/// `line` as a lowercase file name: letters and digits kept, everything
+/// else collapsed to single dashes.
+fn slugify(line: &str) -> String {
+    let mut slug = String::new();
+    for word in line.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        if slug.chars().count() + word.chars().count() + 1 > SLUG_LIMIT && !slug.is_empty() {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.extend(word.chars().flat_map(char::to_lowercase).take(SLUG_LIMIT));
+    }
+    slug
+}
+
+/// `path` with `extension` added if the name has none. Dotfiles are left
+/// alone, as is a name whose extended form already exists, since the
+/// dialog only confirmed overwriting the name as typed.
+fn with_default_extension(path: PathBuf, extension: &str) -> PathBuf {
+    let extension = extension.trim().trim_start_matches('.');
+    let is_dotfile = path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.'));
+    if extension.is_empty() || path.extension().is_some() || is_dotfile {
+        return path;
+    }
+    let extended = path.with_extension(extension);
+    if extended.exists() {
+        debug!(path = ?extended, "Not adding the default extension over an existing file");
+        return path;
+    }
+    extended
+}
+
+/// A file dialog starting in the last-used directory (or beside
+/// `current`), with `filters`. `extension`'s filter comes first, or else
+/// the one the last picked file matched.
+pub(super) fn file_dialog(current: Option<&Path>, extension: Option<&str>, filters: &[FileFilter]) -> AsyncFileDialog {
+    let (last_dir, last_extension) = match LAST_DIALOG_PICK.lock() {
+        Ok(last) => (last.dir.clone(), last.extension.clone()),
+        Err(_) => (None, None),
+    };
+    let start_dir = last_dir.or_else(|| current.and_then(|p| p.parent()).map(Path::to_path_buf));
+    // Only a configured filter is worth reselecting
+    let last_extension = last_extension.filter(|ext| filters.iter().any(|f| f.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext))));
+    let mut dialog = AsyncFileDialog::new();
+    if let Some(dir) = start_dir.filter(|d| d.is_dir()) {
+        dialog = dialog.set_directory(dir);
+    }
+    for (name, exts) in dialog_filters(filters, extension.or(last_extension.as_deref())) {
+        dialog = dialog.add_filter(name, &exts);
+    }
+    dialog
+}
+
+/// Remember where `path` was picked, and its type, for the next dialog.
+pub(super) fn remember_dialog_dir(path: &Path) {
+    if let Ok(mut last) = LAST_DIALOG_PICK.lock() {
+        last.dir = path.parent().map(Path::to_path_buf);
+        last.extension = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    }
+}
+
+/// Saves of the document, so writes never overlap. A save asked for while
+/// another runs waits for it; a request for the same target as the last
+/// waiting save joins it, and that save writes the text as it is when its
+/// turn comes.
+#[derive(Default)]
+pub(super) struct SaveQueue {
+    /// In writing order: the first is writing, each other one waits for
+    /// the one before it. A target of `None` is the document's own file.
+    saves: VecDeque<(Option<PathBuf>, Shared<Task<bool>>)>,
+}
+
+impl SaveQueue {
+    /// Whether a save is writing or waiting to.
+    pub(super) fn is_busy(&self) -> bool {
+        !self.saves.is_empty()
+    }
+}
 
 /// Access workspace from async context. Returns None if downcast fails.
-fn with_workspace_async<R>(
+pub(super) fn with_workspace_async<R>(
     cx: &mut AsyncWindowContext,
     f: impl FnOnce(&mut Workspace, &mut Window, &mut Context<Workspace>) -> R,
 ) -> Option<R> {
     cx.update(|window, app| {
-        Root::update(window, app, |root, window, cx_root| {
-            root.view().clone().downcast::<Workspace>().ok().map(|workspace| {
-                workspace.update(cx_root, |this, cx_ws| f(this, window, cx_ws))
-            })
-        })
+        // Not inside Root::update, so `f` can push notifications to the root
+        let workspace = Root::update(window, app, |root, _, _| root.view().clone()).downcast::<Workspace>().ok()?;
+        Some(workspace.update(app, |this, cx_ws| f(this, window, cx_ws)))
     })
     .ok()
     .flatten()
@@ -33,43 +166,23 @@ impl Workspace {
         });
     }
 
-    pub fn open_dialog_internal(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+    fn open_dialog_internal(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let dialog = file_dialog(self.current_file(cx).as_deref(), None, &self.settings.file_filters);
         cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
             let mut cx = cx.clone();
             async move {
                 debug!("Opening file dialog");
                 let dialog_task = cx.background_spawn(async move {
-                    if let Some(file) = AsyncFileDialog::new().pick_file().await {
-                        let path = file.path().to_path_buf();
-                        match fs::read_to_string(&path) {
-                            Ok(contents) => Some((path, contents)),
-                            Err(err) => {
-                                warn!(path = ?path, error = %err, "Failed to read file");
-                                None
-                            }
-                        }
-                    } else {
-                        None
-                    }
+                    dialog
+                        .pick_file()
+                        .await
+                        .map(|file| file.path().to_path_buf())
                 });
 
-                if let Some((path, contents)) = dialog_task.await {
-                    debug!(path = ?path, bytes = contents.len(), "File selected from dialog");
-                    with_workspace_async(&mut cx, |this, window, cx_ws| {
-                        debug!(has_editor = this.editor_entity.is_some(), "Updating workspace with file");
-                        this.current_file = Some(path.clone());
-                        
-                        // Make sure to reset editor state completely
-                        if let Some(editor) = &this.editor_entity {
-                            let contents = contents.clone();
-                            editor.update(cx_ws, |ed, cx_ed| {
-                                let _ = ed.open_file(path.clone(), window, cx_ed, Some(contents));
-                            });
-                        } else {
-                            warn!("Editor entity missing when opening file");
-                        }
-                        this.update_title(window, cx_ws);
-                    });
+                if let Some(path) = dialog_task.await {
+                    debug!(path = ?path, "File selected from dialog");
+                    remember_dialog_dir(&path);
+                    with_workspace_async(&mut cx, |this, window, cx_ws| this.load_file(path, false, window, cx_ws));
                 } else {
                     debug!("Open dialog canceled");
                     let _ = cx.update(|_, _| {});
@@ -86,20 +199,79 @@ impl Workspace {
         }
     }
 
+    /// Save the file, after the on-save transforms, or show Save As if
+    /// untitled.
     pub fn save_file_task(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Option<Task<bool>> {
-        if self.current_file.is_none() {
+        if self.current_file(cx).is_none() {
             return Some(self.save_as_dialog_task(window, cx));
         }
+        self.apply_save_transforms(window, cx);
+        self.queue_save(None, window, cx)
+    }
 
-        let path = self.current_file.clone()?;
+    /// Save the text as it is, for autosave: trimming whitespace or adding
+    /// a newline while the user types would move text under the caret.
+    pub(super) fn autosave_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(task) = self.queue_save(None, window, cx) {
+            task.detach();
+        }
+    }
 
-        Some(cx.spawn_in(window, move |_this: WeakEntity<Self>, cx_async: &mut AsyncWindowContext| {
+    /// Write the editor's text once earlier saves finish: to `save_as`,
+    /// which becomes the document's file if the write succeeds, or else to
+    /// the document's file as it is by then.
+    fn queue_save(&mut self, save_as: Option<PathBuf>, window: &mut Window, cx: &mut Context<Self>) -> Option<Task<bool>> {
+        if save_as.is_none() && self.current_file(cx).is_none() {
+            return None;
+        }
+        let waiting = self.saves.saves.len() > 1;
+        if let Some((_, next)) = self.saves.saves.back().filter(|(target, _)| waiting && *target == save_as) {
+            debug!(path = ?save_as, "Joining the queued save");
+            return Some(cx.foreground_executor().spawn(next.clone()));
+        }
+
+        let previous = self.saves.saves.back().map(|(_, save)| save.clone());
+        let queued = previous.is_some();
+        let target = save_as.clone();
+        let save = cx.spawn_in(window, move |_this: WeakEntity<Self>, cx_async: &mut AsyncWindowContext| {
             let mut cx = cx_async.clone();
             async move {
-                let contents = Self::get_editor_text_async(&mut cx);
-                Self::write_file_and_update(&mut cx, path, contents).await
+                if let Some(previous) = previous {
+                    previous.await;
+                }
+                // Our turn
+                let turn = with_workspace_async(&mut cx, |this, _window, cx_ws| {
+                    this.with_editor(cx_ws, |ed, cx| ed.set_saving(true, cx));
+                    let path = target.clone().or_else(|| this.current_file(cx_ws))?;
+                    let is_dirty = this.editor_entity.as_ref().is_some_and(|e| e.read(cx_ws).is_dirty(cx_ws));
+                    Some((path, is_dirty))
+                })
+                .flatten();
+                let saved = match turn {
+                    Some((path, is_dirty)) => {
+                        // A queued save with nothing new to write is redundant
+                        if queued && target.is_none() && !is_dirty {
+                            debug!(path = ?path, "Skipping a save with nothing new");
+                            true
+                        } else {
+                            let contents = Self::get_editor_text_async(&mut cx);
+                            Self::write_file_and_update(&mut cx, path, contents, target.is_some()).await
+                        }
+                    }
+                    None => true,
+                };
+                with_workspace_async(&mut cx, |this, _window, cx_ws| {
+                    this.saves.saves.pop_front();
+                    if this.saves.saves.is_empty() {
+                        this.with_editor(cx_ws, |ed, cx| ed.set_saving(false, cx));
+                    }
+                });
+                saved
             }
-        }))
+        })
+        .shared();
+        self.saves.saves.push_back((save_as, save.clone()));
+        Some(cx.foreground_executor().spawn(save))
     }
 
     /// Show Save As dialog.
@@ -108,31 +280,43 @@ impl Workspace {
     }
 
     pub fn save_as_dialog_task(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Task<bool> {
+        let current = self.current_file(cx);
+        let default_extension = self.settings.default_extension.clone();
+        let first_line = self.editor_entity.as_ref()
+            .filter(|_| current.is_none() && self.settings.name_untitled_from_first_line)
+            .and_then(|e| e.read(cx).input_state.read(cx).value().lines().find(|l| !l.trim().is_empty()).map(str::to_string));
+        let name = default_save_name(current.as_deref(), first_line.as_deref(), &default_extension);
+        let extension = Path::new(&name).extension().and_then(|e| e.to_str()).map(str::to_string);
+        let mut dialog = file_dialog(current.as_deref(), extension.as_deref(), &self.settings.file_filters).set_file_name(name);
+        if current.is_none() {
+            if let Some(dir) = self.settings.default_save_dir.as_ref().filter(|d| d.is_dir()) {
+                dialog = dialog.set_directory(dir);
+            }
+        }
         cx.spawn_in(window, move |_this: WeakEntity<Self>, cx_async: &mut AsyncWindowContext| {
             let mut cx = cx_async.clone();
             async move {
                 debug!("Opening save-as dialog");
                 let dialog_task = cx.background_spawn(async move {
-                    AsyncFileDialog::new()
+                    dialog
                         .save_file()
                         .await
                         .map(|file| file.path().to_path_buf())
                 });
 
                 if let Some(path) = dialog_task.await {
+                    let path = with_default_extension(path, &default_extension);
                     debug!(path = ?path, "Save-as path selected");
-                    
-                    // Update editor's file path first
-                    with_workspace_async(&mut cx, |this, _window, cx_ws| {
-                        if let Some(editor) = &this.editor_entity {
-                            editor.update(cx_ws, |ed, _| {
-                                ed.current_file = Some(path.clone());
-                            });
-                        }
-                    });
-                    
-                    let contents = Self::get_editor_text_async(&mut cx);
-                    Self::write_file_and_update(&mut cx, path, contents).await
+                    remember_dialog_dir(&path);
+                    let save = with_workspace_async(&mut cx, |this, window, cx_ws| {
+                        this.apply_save_transforms(window, cx_ws);
+                        this.queue_save(Some(path), window, cx_ws)
+                    })
+                    .flatten();
+                    match save {
+                        Some(save) => save.await,
+                        None => false,
+                    }
                 } else {
                     debug!("Save-as dialog canceled");
                     let _ = cx.update(|_, _| {});
@@ -142,54 +326,77 @@ impl Workspace {
         })
     }
 
+    /// Trim trailing whitespace and add the final newline in the buffer,
+    /// as the settings ask, before an explicit save.
+    fn apply_save_transforms(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let trim = self.settings.trim_trailing_whitespace_on_save;
+        let final_newline = self.settings.final_newline_on_save;
+        self.with_editor(cx, |ed, cx| ed.apply_save_transforms(trim, final_newline, window, cx));
+    }
+
+    /// The text to save.
     fn get_editor_text_async(cx: &mut AsyncWindowContext) -> String {
-        with_workspace_async(cx, |this, _window, cx_ws| {
-            this.get_editor_text(cx_ws)
-        })
-        .unwrap_or_default()
+        with_workspace_async(cx, |this, _window, cx_ws| this.get_editor_text(cx_ws)).unwrap_or_default()
     }
 
-    async fn write_file_and_update(cx: &mut AsyncWindowContext, path: PathBuf, contents: String) -> bool {
+    /// Write `contents` to `path`, then mark the document saved, moving it
+    /// to `path` first when `save_as` is set.
+    async fn write_file_and_update(cx: &mut AsyncWindowContext, path: PathBuf, contents: String, save_as: bool) -> bool {
         let path_for_write = path.clone();
-        let success = cx.background_spawn(async move {
-            match fs::write(&path_for_write, contents) {
-                Ok(_) => {
-                    info!(path = ?path_for_write, "File saved");
-                    true
-                }
-                Err(err) => {
-                    warn!(path = ?path_for_write, error = %err, "Failed to save file");
-                    false
-                }
+        let (backup, encoding, encodable) = with_workspace_async(cx, |this, window, cx| {
+            let encoding = this.editor_entity.as_ref().map(|e| e.read(cx).encoding(cx)).unwrap_or_default();
+            // Writing would turn the characters the encoding lacks into `?`
+            let encodable = encoding.can_encode(&contents);
+            if !encodable {
+                let message = format!("Not saved: the document has characters {} can't represent. Change the encoding to UTF-8 to save it.", encoding);
+                window.push_notification(Notification::error(message), cx);
             }
+            (this.settings.backup_on_save, encoding, encodable)
+        })
+        .unwrap_or((false, Encoding::default(), true));
+        if !encodable {
+            warn!(path = ?path, %encoding, "Refused to save characters the encoding lacks");
+            return false;
+        }
+        let written = cx.background_spawn(async move {
+            compression::write_bytes(&path_for_write, &encoding.encode(&contents), backup)
         }).await;
 
-        if success {
-            with_workspace_async(cx, |this, window, cx_ws| {
-                this.current_file = Some(path.clone());
-                
-                // Mark editor clean
-                if let Some(editor) = &this.editor_entity {
-                    editor.update(cx_ws, |ed, _| ed.mark_clean());
-                }
-                
-                this.update_title(window, cx_ws);
-                cx_ws.notify();
-            });
-            true
-        } else {
-            let _ = cx.update(|_, _| {});
-            false
+        match written {
+            Ok(()) => {
+                info!(path = ?path, "File saved");
+                with_workspace_async(cx, |this, window, cx_ws| {
+                    if let Some(editor) = &this.editor_entity {
+                        editor.update(cx_ws, |ed, cx| {
+                            // Only now that the file exists does the document belong to it
+                            if save_as {
+                                ed.set_current_file(Some(path.clone()), cx);
+                            }
+                            ed.mark_clean(cx);
+                        });
+                    }
+                    this.record_disk_mtime(cx_ws);
+                    this.update_title(window, cx_ws);
+                    cx_ws.notify();
+                });
+                true
+            }
+            Err(err) => {
+                warn!(path = ?path, error = %err, "Failed to save file");
+                with_workspace_async(cx, |_, window, cx_ws| {
+                    window.push_notification(Notification::error(format!("Couldn't save {}: {}", path.display(), err)), cx_ws);
+                });
+                false
+            }
         }
     }
 
+    /// The editor's text as it is now. The document's copy only catches up
+    /// once the input's change events run, after the current update.
     pub(super) fn get_editor_text(&self, cx: &mut Context<Self>) -> String {
-        if let Some(editor) = &self.editor_entity {
-            editor.update(cx, |ed, cx_ed| {
-                ed.input_state.read(cx_ed).value().to_string()
-            })
-        } else {
-            String::new()
+        match &self.editor_entity {
+            Some(editor) => editor.read(cx).input_state.read(cx).value().to_string(),
+            None => String::new(),
         }
     }
 
@@ -210,7 +417,7 @@ impl Workspace {
 
         // Check dirty state
         let is_dirty = if let Some(editor) = &self.editor_entity {
-            editor.read(cx).is_dirty
+            editor.read(cx).is_dirty(cx)
         } else {
             false
         };
@@ -220,13 +427,15 @@ impl Workspace {
             return;
         }
 
-        // Show dialog
+        // Show dialog, naming the document whose edits are at stake
+        let name = self.document.read(cx).display_name();
+        let description = format!("{} has unsaved changes. Do you want to save them?", name);
         cx.spawn_in(window, move |_this, cx_async: &mut AsyncWindowContext| {
             let mut cx = cx_async.clone();
             async move {
                 let result = AsyncMessageDialog::new()
                     .set_title("Unsaved Changes")
-                    .set_description("You have unsaved changes. Do you want to save them?")
+                    .set_description(description)
                     .set_buttons(MessageButtons::YesNoCancel)
                     .show()
                     .await;
@@ -260,3 +469,131 @@ impl Workspace {
         }).detach();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{default_save_name, dialog_filters, slugify, with_default_extension};
+    use crate::settings::{self, AppSettings, FileFilter, MemoryStore};
+    use crate::workspace::Workspace;
+    use gpui::{AppContext, TestAppContext, VisualTestContext};
+    use gpui_component::Root;
+    use std::fs;
+    use std::path::Path;
+
+    #[gpui::test]
+    async fn test_save_writes_transformed_text(cx: &mut TestAppContext) {
+        settings::install_store(Box::new(MemoryStore::default()));
+        cx.update(gpui_component::init);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "one  \ntwo\t").unwrap();
+
+        let settings = AppSettings {
+            trim_trailing_whitespace_on_save: true,
+            final_newline_on_save: true,
+            autosave_interval_secs: 0,
+            ..AppSettings::default()
+        };
+        let window = cx.add_window(|window, cx| {
+            let workspace = cx.new(|cx| Workspace::new(window, cx, settings));
+            Root::new(workspace, window, cx)
+        });
+        let workspace = window.update(cx, |root, _, _| root.view().clone().downcast::<Workspace>().unwrap()).unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        workspace.update_in(cx, |ws, window, cx| ws.open_file(path.clone(), window, cx));
+        cx.run_until_parked();
+
+        // Autosave writes the text as it is
+        workspace.update_in(cx, |ws, window, cx| ws.autosave_file(window, cx));
+        cx.run_until_parked();
+        assert_eq!(fs::read(&path).unwrap(), b"one  \ntwo\t");
+        assert_eq!(workspace.update_in(cx, |ws, _, cx| ws.get_editor_text(cx)), "one  \ntwo\t");
+
+        let save = workspace.update_in(cx, |ws, window, cx| ws.save_file_task(window, cx)).unwrap();
+        assert!(save.await);
+        assert_eq!(fs::read(&path).unwrap(), b"one\ntwo\n");
+        cx.run_until_parked();
+        assert!(!workspace.read_with(cx, |ws, cx| ws.document.read(cx).is_dirty()));
+    }
+
+    #[gpui::test]
+    async fn test_save_as_moves_the_document_only_once_written(cx: &mut TestAppContext) {
+        settings::install_store(Box::new(MemoryStore::default()));
+        cx.update(gpui_component::init);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "notes").unwrap();
+
+        let settings = AppSettings { autosave_interval_secs: 0, ..AppSettings::default() };
+        let window = cx.add_window(|window, cx| {
+            let workspace = cx.new(|cx| Workspace::new(window, cx, settings));
+            Root::new(workspace, window, cx)
+        });
+        let workspace = window.update(cx, |root, _, _| root.view().clone().downcast::<Workspace>().unwrap()).unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        workspace.update_in(cx, |ws, window, cx| ws.open_file(path.clone(), window, cx));
+        cx.run_until_parked();
+
+        let unwritable = dir.path().join("missing").join("copy.txt");
+        let save = workspace.update_in(cx, |ws, window, cx| ws.queue_save(Some(unwritable.clone()), window, cx)).unwrap();
+        assert!(!save.await);
+        assert_eq!(workspace.read_with(cx, |ws, cx| ws.current_file(cx)), Some(path.clone()));
+
+        let copy = dir.path().join("copy.txt");
+        let save = workspace.update_in(cx, |ws, window, cx| ws.queue_save(Some(copy.clone()), window, cx)).unwrap();
+        assert!(save.await);
+        assert_eq!(fs::read(&copy).unwrap(), b"notes");
+        assert_eq!(workspace.read_with(cx, |ws, cx| ws.current_file(cx)), Some(copy));
+    }
+
+    #[test]
+    fn test_dialog_filters() {
+        let filters = AppSettings::default().file_filters;
+        let dialog_filters = |extension| dialog_filters(&filters, extension);
+        let names = |filters: Vec<(String, Vec<String>)>| filters.into_iter().map(|f| f.0).collect::<Vec<_>>();
+        assert_eq!(names(dialog_filters(None)), ["Text", "Markdown", "Logs", "All Files"]);
+        assert_eq!(names(dialog_filters(Some("MD"))), ["Markdown", "Text", "Logs", "All Files"]);
+        assert_eq!(dialog_filters(Some("rs"))[0], ("RS files".to_string(), vec!["rs".to_string()]));
+    }
+
+    #[test]
+    fn test_dialog_filters_always_offer_all_files() {
+        let filters = [FileFilter { name: "Code".to_string(), extensions: vec!["RS".to_string()] }];
+        assert_eq!(
+            dialog_filters(&filters, None),
+            [("Code".to_string(), vec!["rs".to_string()]), ("All Files".to_string(), vec!["*".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_default_save_name() {
+        assert_eq!(default_save_name(Some(Path::new("/notes/todo.md")), Some("Plan"), "txt"), "todo.md");
+        assert_eq!(default_save_name(None, None, "txt"), "Untitled.txt");
+        assert_eq!(default_save_name(None, None, ".md"), "Untitled.md");
+        assert_eq!(default_save_name(None, None, ""), "Untitled");
+        assert_eq!(default_save_name(None, Some("Meeting notes 2024"), "txt"), "meeting-notes-2024.txt");
+        assert_eq!(default_save_name(None, Some("## ---"), "txt"), "Untitled.txt");
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("  # Hello, World! "), "hello-world");
+        assert_eq!(slugify("Café über alles"), "café-über-alles");
+        let long = slugify(&"word ".repeat(30));
+        assert!(long.chars().count() <= 48 && long.ends_with("word"));
+    }
+
+    #[test]
+    fn test_with_default_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let extended = |name: &str, ext: &str| with_default_extension(dir.path().join(name), ext);
+        assert_eq!(extended("notes", "txt"), dir.path().join("notes.txt"));
+        assert_eq!(extended("notes", ".md"), dir.path().join("notes.md"));
+        assert_eq!(extended("notes.log", "txt"), dir.path().join("notes.log"));
+        assert_eq!(extended(".env", "txt"), dir.path().join(".env"));
+        assert_eq!(extended("notes", ""), dir.path().join("notes"));
+
+        std::fs::write(dir.path().join("taken.txt"), "").unwrap();
+        assert_eq!(extended("taken", "txt"), dir.path().join("taken"));
+    }
+}
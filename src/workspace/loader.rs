@@ -0,0 +1,381 @@
+//! Background file loading with a progress indicator.
+
+use gpui::*;
+use gpui_component::notification::Notification;
+use gpui_component::progress::Progress;
+use gpui_component::{h_flex, Theme, WindowExt};
+use rfd::{AsyncMessageDialog, MessageButtons, MessageDialogResult, MessageLevel};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::editor::Encoding;
+use super::{archive, Workspace};
+use super::compression::Compression;
+use super::file_ops::with_workspace_async;
+
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// Most of a file shown by "Open as Hex"; the dump is about four times
+/// the size of the bytes.
+const HEX_LIMIT: usize = 4 * 1024 * 1024;
+
+/// A file being read in the background.
+pub struct LoadProgress {
+    pub path: PathBuf,
+    /// Open the file read-only once loaded.
+    read_only: bool,
+    /// 0-based line and column to put the caret at once loaded.
+    caret: Option<(usize, usize)>,
+    /// Bytes read so far, updated by the reader.
+    read: Arc<AtomicU64>,
+    total: u64,
+}
+
+impl LoadProgress {
+    /// Percentage read, 0-100.
+    pub fn percent(&self) -> f32 {
+        percent(self.read.load(Ordering::Relaxed), self.total)
+    }
+}
+
+fn percent(read: u64, total: u64) -> f32 {
+    if total == 0 {
+        return 100.0;
+    }
+    (read as f64 / total as f64 * 100.0).min(100.0) as f32
+}
+
+/// Read a text file in chunks, adding the bytes read to `read` as it goes.
+///
+/// Compressed files are decompressed once fully read. Files that aren't
+/// UTF-8 are read as Windows-1252.
+pub fn read_chunked(path: &Path, read: &AtomicU64) -> io::Result<(String, Encoding)> {
+    let mut file = File::open(path)?;
+    let len = file.metadata().map(|m| m.len() as usize).unwrap_or(0);
+    let mut bytes = Vec::with_capacity(len);
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..n]);
+        read.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    Compression::from_path(path).decode(bytes)
+}
+
+/// A way past a file that failed to open, offered in the error prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Recovery {
+    Retry,
+    /// Replace undecodable bytes and open without editing.
+    ReadOnly,
+    Hex,
+}
+
+impl Recovery {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Retry => "Retry",
+            Self::ReadOnly => "Open Read-Only",
+            Self::Hex => "Open as Hex",
+        }
+    }
+}
+
+/// Why `name` couldn't be opened, in words, and what to offer about it.
+fn describe_open_error(name: &str, err: &io::Error) -> (String, &'static [Recovery]) {
+    match err.kind() {
+        io::ErrorKind::NotFound => (format!("{} doesn't exist. It may have been moved or deleted.", name), &[Recovery::Retry]),
+        io::ErrorKind::PermissionDenied => (format!("You don't have permission to read {}.", name), &[Recovery::Retry]),
+        io::ErrorKind::InvalidData => (
+            format!("{} looks like a binary file. It can be shown read-only with unreadable bytes replaced, or as hex.", name),
+            &[Recovery::ReadOnly, Recovery::Hex],
+        ),
+        io::ErrorKind::Unsupported => (format!("Couldn't open {}: {}.", name, err), &[Recovery::Hex]),
+        _ => (format!("Couldn't open {}: {}.", name, err), &[Recovery::Retry]),
+    }
+}
+
+/// Classic hex dump of `bytes`: offset, sixteen bytes in hex and the
+/// printable ASCII alongside.
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 5);
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        out.push_str(&format!("{:08x} ", row * 16));
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(byte) => out.push_str(&format!(" {:02x}", byte)),
+                None => out.push_str("   "),
+            }
+            if i == 7 {
+                out.push(' ');
+            }
+        }
+        let ascii: String = chunk.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }).collect();
+        out.push_str(&format!("  |{}|\n", ascii));
+    }
+    out
+}
+
+impl Workspace {
+    /// Read `path` off the UI thread, then show it in the editor.
+    pub(super) fn load_file(&mut self, path: PathBuf, read_only: bool, window: &mut Window, cx: &mut Context<Self>) {
+        if archive::is_zip_file(&path) {
+            self.open_archive(path, window, cx);
+            return;
+        }
+        let total = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let read = Arc::new(AtomicU64::new(0));
+        self.loading = Some(LoadProgress {
+            path: path.clone(),
+            read_only,
+            caret: None,
+            read: read.clone(),
+            total,
+        });
+        cx.notify();
+
+        let reader = cx.background_spawn({
+            let path = path.clone();
+            async move { read_chunked(&path, &read) }
+        });
+
+        // Repaint the progress bar while the reader runs
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                loop {
+                    cx.background_executor().timer(PROGRESS_INTERVAL).await;
+                    let loading = with_workspace_async(&mut cx, |this, _window, cx_ws| {
+                        cx_ws.notify();
+                        this.loading.is_some()
+                    });
+                    if loading != Some(true) {
+                        break;
+                    }
+                }
+            }
+        }).detach();
+
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let result = reader.await;
+                with_workspace_async(&mut cx, |this, window, cx_ws| {
+                    // A newer load replaced this one
+                    if this.loading.as_ref().map(|l| &l.path) != Some(&path) {
+                        return;
+                    }
+                    let Some(loading) = this.loading.take() else { return };
+
+                    match result {
+                        Ok((contents, encoding)) => {
+                            debug!(path = ?path, bytes = contents.len(), %encoding, "File loaded");
+                            if encoding != Encoding::Utf8 {
+                                let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                                window.push_notification(Notification::info(format!("{} isn't UTF-8; opened as {}.", name, encoding)), cx_ws);
+                            }
+                            if let Some(editor) = &this.editor_entity {
+                                editor.update(cx_ws, |ed, cx_ed| {
+                                    ed.open_decoded(path.clone(), contents, encoding, window, cx_ed);
+                                    if loading.read_only {
+                                        ed.set_read_only(true, cx_ed);
+                                    }
+                                    if let Some((line, column)) = loading.caret {
+                                        ed.go_to_line_column(line, column, window, cx_ed);
+                                    }
+                                });
+                                this.active_view = editor.clone().into();
+                            }
+                            this.add_recent_file(&path);
+                            this.record_disk_mtime(cx_ws);
+                            this.update_title(window, cx_ws);
+                        }
+                        Err(err) => {
+                            warn!(path = ?path, error = %err, "Failed to read file");
+                            this.report_open_error(path, loading.read_only, loading.caret, err, window, cx_ws);
+                        }
+                    }
+                    cx_ws.notify();
+                });
+            }
+        }).detach();
+    }
+
+    /// Tell the user why `path` didn't open and carry out whichever way
+    /// past it they pick.
+    fn report_open_error(&mut self, path: PathBuf, read_only: bool, caret: Option<(usize, usize)>, err: io::Error, window: &mut Window, cx: &mut Context<Self>) {
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| path.display().to_string());
+        let (description, recoveries) = describe_open_error(&name, &err);
+        let buttons = match *recoveries {
+            [only] => MessageButtons::OkCancelCustom(only.label().to_string(), "Cancel".to_string()),
+            [first, second, ..] => MessageButtons::YesNoCancelCustom(first.label().to_string(), second.label().to_string(), "Cancel".to_string()),
+            [] => MessageButtons::Ok,
+        };
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let result = AsyncMessageDialog::new()
+                    .set_level(MessageLevel::Error)
+                    .set_title("Couldn't Open File")
+                    .set_description(description)
+                    .set_buttons(buttons)
+                    .show()
+                    .await;
+                // Some platforms answer with the standard result for a custom button
+                let chosen = match result {
+                    MessageDialogResult::Custom(label) => recoveries.iter().find(|r| r.label() == label),
+                    MessageDialogResult::Ok | MessageDialogResult::Yes => recoveries.first(),
+                    MessageDialogResult::No => recoveries.get(1),
+                    _ => None,
+                };
+                let Some(&recovery) = chosen else { return };
+                info!(path = ?path, ?recovery, "Recovering from a failed open");
+                if recovery == Recovery::Retry {
+                    with_workspace_async(&mut cx, |this, window, cx_ws| {
+                        this.load_file(path, read_only, window, cx_ws);
+                        if let Some(loading) = &mut this.loading {
+                            loading.caret = caret;
+                        }
+                    });
+                    return;
+                }
+
+                let bytes = cx.background_spawn({
+                    let path = path.clone();
+                    async move { fs::read(&path) }
+                }).await;
+                with_workspace_async(&mut cx, |this, window, cx_ws| {
+                    let bytes = match bytes {
+                        Ok(bytes) => bytes,
+                        Err(err) => {
+                            warn!(path = ?path, error = %err, "Failed to read file");
+                            this.report_open_error(path, read_only, caret, err, window, cx_ws);
+                            return;
+                        }
+                    };
+                    let Some(editor) = this.editor_entity.clone() else { return };
+                    if recovery == Recovery::Hex {
+                        // The dump is a new untitled document, so saving can't overwrite the file
+                        let mut dump = hex_dump(&bytes[..bytes.len().min(HEX_LIMIT)]);
+                        if bytes.len() > HEX_LIMIT {
+                            dump.push_str(&format!("... {} more bytes not shown\n", bytes.len() - HEX_LIMIT));
+                        }
+                        this.close_file(window, cx_ws);
+                        editor.update(cx_ws, |ed, cx_ed| {
+                            ed.new_with_text(dump, window, cx_ed);
+                            ed.mark_clean(cx_ed);
+                            ed.set_read_only(true, cx_ed);
+                        });
+                    } else {
+                        let contents = String::from_utf8_lossy(&bytes).into_owned();
+                        editor.update(cx_ws, |ed, cx_ed| {
+                            if let Err(err) = ed.open_file(path.clone(), window, cx_ed, Some(contents)) {
+                                warn!(path = ?path, error = %err, "Failed to show file");
+                            }
+                            ed.set_read_only(true, cx_ed);
+                            if let Some((line, column)) = caret {
+                                ed.go_to_line_column(line, column, window, cx_ed);
+                            }
+                        });
+                        this.record_disk_mtime(cx_ws);
+                    }
+                    this.active_view = editor.into();
+                    this.update_title(window, cx_ws);
+                    cx_ws.notify();
+                });
+            }
+        }).detach();
+    }
+
+    /// Like `load_file`, then put the caret at 0-based `line` and `column`.
+    pub(super) fn load_file_at(&mut self, path: PathBuf, read_only: bool, line: usize, column: usize, window: &mut Window, cx: &mut Context<Self>) {
+        self.load_file(path, read_only, window, cx);
+        if let Some(loading) = &mut self.loading {
+            loading.caret = Some((line, column));
+        }
+    }
+
+    pub(super) fn render_load_progress(&self, cx: &App) -> Option<impl IntoElement> {
+        let loading = self.loading.as_ref()?;
+        let name = loading.path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("file");
+        let percent = loading.percent();
+
+        Some(
+            h_flex()
+                .gap_2()
+                .px_2()
+                .py_1()
+                .text_sm()
+                .text_color(Theme::global(cx).colors.muted_foreground)
+                .child(format!("Loading {}... {:.0}%", name, percent))
+                .child(div().flex_1().child(Progress::new().value(percent))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe_open_error, hex_dump, percent, read_chunked, Recovery};
+    use crate::editor::Encoding;
+    use std::io::{self, Write};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_read_chunked_reports_bytes_read() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"line one\nline two\n").unwrap();
+
+        let read = AtomicU64::new(0);
+        let (contents, encoding) = read_chunked(file.path(), &read).unwrap();
+        assert_eq!(contents, "line one\nline two\n");
+        assert_eq!(encoding, Encoding::Utf8);
+        assert_eq!(read.load(Ordering::Relaxed), 18);
+    }
+
+    #[test]
+    fn test_read_chunked_rejects_binary() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&[0xff, 0xfe, 0x00]).unwrap();
+
+        let read = AtomicU64::new(0);
+        assert!(read_chunked(file.path(), &read).is_err());
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent(50, 200), 25.0);
+        assert_eq!(percent(0, 0), 100.0);
+        assert_eq!(percent(300, 200), 100.0);
+    }
+
+    #[test]
+    fn test_describe_open_error_offers_recoveries_by_kind() {
+        let offered = |kind| describe_open_error("a.txt", &io::Error::from(kind)).1;
+        assert_eq!(offered(io::ErrorKind::NotFound), [Recovery::Retry]);
+        assert_eq!(offered(io::ErrorKind::PermissionDenied), [Recovery::Retry]);
+        assert_eq!(offered(io::ErrorKind::InvalidData), [Recovery::ReadOnly, Recovery::Hex]);
+        assert_eq!(offered(io::ErrorKind::Unsupported), [Recovery::Hex]);
+        assert!(describe_open_error("a.txt", &io::Error::from(io::ErrorKind::NotFound)).0.starts_with("a.txt doesn't exist"));
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let dump = hex_dump(b"Hello, world!\n\xff\x00xyz");
+        assert_eq!(
+            dump,
+            "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a ff 00  |Hello, world!...|\n\
+             00000010  78 79 7a                                          |xyz|\n"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Global marks: a letter remembers a file and caret position, across files
+//! and restarts. `ctrl-k <letter>` sets one and `ctrl-' <letter>` jumps back.
+
+use gpui::*;
+use gpui_component::notification::Notification;
+use gpui_component::WindowExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+use super::Workspace;
+use crate::keymap::KeymapPreset;
+use crate::settings::store;
+
+/// Where marks are kept in the settings store.
+const MARKS_KEY: &str = "marks.json";
+
+/// Set the global mark named by the letter.
+#[derive(Clone, PartialEq, Action)]
+#[action(namespace = workspace, no_json)]
+pub struct SetMarkAction(pub char);
+
+/// Jump to the global mark named by the letter.
+#[derive(Clone, PartialEq, Action)]
+#[action(namespace = workspace, no_json)]
+pub struct JumpToMarkAction(pub char);
+
+/// A remembered file and caret position (0-based, column in characters).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Mark {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Every mark, saved to `marks.json` in the settings store.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct GlobalMarks {
+    pub marks: BTreeMap<char, Mark>,
+}
+
+impl GlobalMarks {
+    pub fn load() -> Self {
+        if let Some(contents) = store().read(MARKS_KEY) {
+            if let Ok(marks) = serde_json::from_str(&contents) {
+                return marks;
+            }
+        }
+        Self::default()
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(err) = store().write(MARKS_KEY, &json) {
+                    warn!(error = %err, "Failed to save marks");
+                }
+            }
+            Err(err) => warn!(error = %err, "Failed to serialize marks"),
+        }
+    }
+}
+
+/// `ctrl-k <letter>` and `ctrl-' <letter>` for every letter, with `cmd`
+/// where `preset` uses it.
+pub fn key_bindings(preset: KeymapPreset) -> Vec<KeyBinding> {
+    let set = preset.platform_keys("ctrl-k");
+    let jump = preset.platform_keys("ctrl-'");
+    ('a'..='z')
+        .flat_map(|letter| {
+            let name = letter.to_ascii_uppercase();
+            [
+                KeyBinding::new(&format!("{} {}", set, letter), SetMarkAction(name), None),
+                KeyBinding::new(&format!("{} {}", jump, letter), JumpToMarkAction(name), None),
+            ]
+        })
+        .collect()
+}
+
+impl Workspace {
+    /// Remember the current file and caret as mark `name`.
+    pub fn set_mark(&mut self, name: char, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(path) = self.current_file(cx) else {
+            window.push_notification(Notification::warning("Save the document before setting a mark."), cx);
+            return;
+        };
+        let Some((line, column)) = self.editor_entity.as_ref().map(|e| e.read(cx).caret_line_column(cx)) else {
+            return;
+        };
+        let mut marks = GlobalMarks::load();
+        marks.marks.insert(name, Mark { path, line, column });
+        marks.save();
+        debug!(%name, line, column, "Set mark");
+        window.push_notification(Notification::info(format!("Mark {} set at line {}.", name, line + 1)), cx);
+    }
+
+    /// Go to mark `name`, opening its file if needed.
+    pub fn jump_to_mark(&mut self, name: char, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(mark) = GlobalMarks::load().marks.remove(&name) else {
+            window.push_notification(Notification::warning(format!("Mark {} isn't set.", name)), cx);
+            return;
+        };
+        if self.current_file(cx).as_ref() == Some(&mark.path) {
+            self.with_editor(cx, |ed, cx| ed.go_to_line_column(mark.line, mark.column, window, cx));
+            return;
+        }
+        if !mark.path.exists() {
+            window.push_notification(Notification::error(format!("{} no longer exists.", mark.path.display())), cx);
+            return;
+        }
+        self.handle_unsaved_changes(window, cx, move |this, window, cx| {
+            this.load_file_at(mark.path, false, mark.line, mark.column, window, cx);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GlobalMarks, Mark};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_marks_round_trip() {
+        let mut marks = GlobalMarks::default();
+        marks.marks.insert('A', Mark { path: PathBuf::from("/notes/todo.md"), line: 4, column: 2 });
+        let json = serde_json::to_string(&marks).unwrap();
+        assert_eq!(serde_json::from_str::<GlobalMarks>(&json).unwrap(), marks);
+    }
+}
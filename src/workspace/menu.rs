@@ -7,9 +7,14 @@ use gpui_component::menu::{DropdownMenu, PopupMenuItem};
 use gpui_component::button::{Button, ButtonVariants};
 use gpui_component::input::{Copy, Cut, SelectAll};
 
-use crate::{ExitAppAction, ExportPdfAction, FindAction, NewFileAction, OpenFileDialogAction, SaveFileAction, SaveFileAsAction};
-use crate::editor::{UndoAction, RedoAction, NormalizePasteAction};
+use crate::{CloseWindowAction, ExitAppAction, ExportPdfAction, FindAction, NewFileAction, NewWindowAction, NextFileAction, OpenFileDialogAction, PreferencesAction, PreviousFileAction, QuickOpenAction, SaveFileAction, SaveFileAsAction, ToggleFullScreenAction};
+use crate::editor::{UndoAction, RedoAction, NormalizePasteAction, DataFormat, ParagraphAlign, Resolution, PromoteHeadingAction, DemoteHeadingAction,
+    DuplicateLineAction, MoveLineUpAction, MoveLineDownAction, DeleteLineAction, ToggleCommentAction, GoToLineAction, Case, ClipboardSlots, LineOperation, Transform, SLOT_COUNT};
 use super::Workspace;
+use super::panes::PaneLayout;
+use crate::extensions::Extensions;
+use crate::settings::{CursorStyle, StatusSegment};
+use crate::style::ChromeStyle;
 
 /// Shorthand for accessing workspace from menu handlers.
 macro_rules! with_workspace {
@@ -23,23 +28,82 @@ macro_rules! with_workspace {
 }
 
 impl Workspace {
-    pub(super) fn build_file_menu(&self) -> impl IntoElement {
+    pub(super) fn build_file_menu(&self, cx: &App) -> impl IntoElement {
+        let has_file = self.current_file(cx).is_some();
         Button::new("menu:file")
             .label("File")
             .text()
             .dropdown_caret(true)
-            .dropdown_menu(|menu, _window, _cx_menu| {
+            .dropdown_menu(move |menu, window, cx_menu| {
                 menu
                     .item(PopupMenuItem::new("New").on_click(|_, window, app| {
                         with_workspace!(window, app, |this, window, cx| {
                             this.new_file(window, cx);
                         });
                     }).action(Box::new(NewFileAction)))
+                    .item(PopupMenuItem::new("New From Clipboard").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.new_from_clipboard(window, cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Duplicate").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, _window, cx| {
+                            this.duplicate_document(cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::new("New Window").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, _window, cx| {
+                            this.new_window(cx);
+                        });
+                    }).action(Box::new(NewWindowAction)))
                     .item(PopupMenuItem::new("Open...").on_click(|_, window, app| {
                         with_workspace!(window, app, |this, window, cx| {
                             this.open_dialog(window, cx);
                         });
                     }).action(Box::new(OpenFileDialogAction)))
+                    .item(PopupMenuItem::new("Quick Open...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.quick_open(window, cx);
+                        });
+                    }).action(Box::new(QuickOpenAction)))
+                    .item(PopupMenuItem::new("Open Beside...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.open_beside_dialog(window, cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Insert File at Cursor...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.insert_file_dialog(window, cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Append to File...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.append_to_file_dialog(window, cx);
+                        });
+                    }))
+                    .submenu("Compare With", window, cx_menu, move |submenu, _window, _cx_submenu| {
+                        submenu
+                            .item(PopupMenuItem::new("File...").on_click(|_, window, app| {
+                                with_workspace!(window, app, |this, window, cx| {
+                                    this.compare_with_file_dialog(window, cx);
+                                });
+                            }))
+                            .item(PopupMenuItem::new("Saved Version").disabled(!has_file).on_click(|_, window, app| {
+                                with_workspace!(window, app, |this, window, cx| {
+                                    this.compare_with_saved(window, cx);
+                                });
+                            }))
+                    })
+                    .item(PopupMenuItem::new("Previous File in Folder").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.open_sibling_file(false, window, cx);
+                        });
+                    }).action(Box::new(PreviousFileAction)))
+                    .item(PopupMenuItem::new("Next File in Folder").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.open_sibling_file(true, window, cx);
+                        });
+                    }).action(Box::new(NextFileAction)))
                     .item(PopupMenuItem::new("Save").on_click(|_, window, app| {
                         with_workspace!(window, app, |this, window, cx| {
                             this.save_file(window, cx);
@@ -51,12 +115,37 @@ impl Workspace {
                         });
                     }).action(Box::new(SaveFileAsAction)))
                     .item(PopupMenuItem::separator())
-                    .item(PopupMenuItem::new("Export to PDF...").on_click(|_, window, app| {
+                    .submenu("Export", window, cx_menu, |submenu, _window, _cx_submenu| {
+                        submenu
+                            .item(PopupMenuItem::new("PDF...").on_click(|_, window, app| {
+                                with_workspace!(window, app, |this, window, cx| {
+                                    this.export_pdf(window, cx);
+                                });
+                            }).action(Box::new(ExportPdfAction)))
+                            .item(PopupMenuItem::new("HTML...").on_click(|_, window, app| {
+                                with_workspace!(window, app, |this, window, cx| {
+                                    let syntax = this.settings.html_syntax_colors;
+                                    this.with_editor(cx, |ed, cx| ed.export_html(syntax, window, cx));
+                                });
+                            }))
+                            .item(PopupMenuItem::new("Wrapped Text...").on_click(|_, window, app| {
+                                with_workspace!(window, app, |this, window, cx| {
+                                    this.with_editor(cx, |ed, cx| ed.export_wrapped_dialog(window, cx));
+                                });
+                            }))
+                    })
+                    .item(PopupMenuItem::separator())
+                    .item(PopupMenuItem::new("Preferences...").on_click(|_, window, app| {
                         with_workspace!(window, app, |this, window, cx| {
-                            this.with_editor(cx, |ed, cx| ed.export_pdf(&ExportPdfAction, window, cx));
+                            this.open_preferences(window, cx);
                         });
-                    }).action(Box::new(ExportPdfAction)))
+                    }).action(Box::new(PreferencesAction)))
                     .item(PopupMenuItem::separator())
+                    .item(PopupMenuItem::new("Close Window").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.close_window(window, cx);
+                        });
+                    }).action(Box::new(CloseWindowAction)))
                     .item(PopupMenuItem::new("Exit").on_click(|_, window, app| {
                         with_workspace!(window, app, |this, window, cx| {
                             this.exit_app(window, cx);
@@ -66,11 +155,12 @@ impl Workspace {
     }
 
     pub(super) fn build_edit_menu(&self) -> impl IntoElement {
+        let markdown_assist = self.settings.markdown_assist;
         Button::new("menu:edit")
             .label("Edit")
             .text()
             .dropdown_caret(true)
-            .dropdown_menu(|menu, _window, _cx_menu| {
+            .dropdown_menu(move |menu, window, cx_menu| {
                 menu
                     .item(PopupMenuItem::new("Undo").on_click(|_, window, app| {
                         with_workspace!(window, app, |this, window, cx| {
@@ -93,26 +183,179 @@ impl Workspace {
                             this.with_editor(cx, |ed, cx| ed.copy(window, cx));
                         });
                     }).action(Box::new(Copy)))
+                    .item(PopupMenuItem::new("Copy Modified Lines").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, _window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.copy_modified_lines(cx));
+                        });
+                    }))
                     .item(PopupMenuItem::new("Paste").on_click(|_, window, app| {
                         with_workspace!(window, app, |this, window, cx| {
                             this.with_editor(cx, |ed, cx| ed.paste(&NormalizePasteAction, window, cx));
                         });
                     }).action(Box::new(NormalizePasteAction)))
+                    .submenu("Clipboard Slots", window, cx_menu, |submenu, _window, cx_submenu| {
+                        let submenu = (0..SLOT_COUNT).fold(submenu, |submenu, slot| {
+                            submenu.item(PopupMenuItem::new(format!("Copy to Slot {}", slot + 1)).on_click(move |_, window, app| {
+                                with_workspace!(window, app, |this, window, cx| {
+                                    this.with_editor(cx, |ed, cx| ed.copy_to_slot(slot, window, cx));
+                                });
+                            }).action(ClipboardSlots::actions(slot).0))
+                        });
+                        (0..SLOT_COUNT).fold(submenu.separator(), |submenu, slot| {
+                            let preview = ClipboardSlots::preview(cx_submenu, slot);
+                            let label = match &preview {
+                                Some(preview) => format!("Paste from Slot {}: {}", slot + 1, preview),
+                                None => format!("Paste from Slot {} (empty)", slot + 1),
+                            };
+                            submenu.item(PopupMenuItem::new(label).disabled(preview.is_none()).on_click(move |_, window, app| {
+                                with_workspace!(window, app, |this, window, cx| {
+                                    this.with_editor(cx, |ed, cx| ed.paste_from_slot(slot, window, cx));
+                                });
+                            }).action(ClipboardSlots::actions(slot).1))
+                        })
+                    })
                     .item(PopupMenuItem::separator())
                     .item(PopupMenuItem::new("Find").on_click(|_, window, app| {
                         with_workspace!(window, app, |this, window, cx| {
                             this.with_editor(cx, |ed, cx| ed.open_search(window, cx));
                         });
                     }).action(Box::new(FindAction)))
+                    .item(PopupMenuItem::new("Go to Line...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.go_to_line_dialog(window, cx));
+                        });
+                    }).action(Box::new(GoToLineAction)))
                     .item(PopupMenuItem::new("Select All").on_click(|_, window, app| {
                         with_workspace!(window, app, |this, window, cx| {
                             this.with_editor(cx, |ed, cx| ed.select_all(window, cx));
                         });
                     }).action(Box::new(SelectAll)))
+                    .item(PopupMenuItem::separator())
+                    .item(PopupMenuItem::new("Duplicate Line").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.duplicate_line(&DuplicateLineAction, window, cx));
+                        });
+                    }).action(Box::new(DuplicateLineAction)))
+                    .item(PopupMenuItem::new("Move Line Up").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.move_line_up(&MoveLineUpAction, window, cx));
+                        });
+                    }).action(Box::new(MoveLineUpAction)))
+                    .item(PopupMenuItem::new("Move Line Down").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.move_line_down(&MoveLineDownAction, window, cx));
+                        });
+                    }).action(Box::new(MoveLineDownAction)))
+                    .item(PopupMenuItem::new("Delete Line").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.delete_line(&DeleteLineAction, window, cx));
+                        });
+                    }).action(Box::new(DeleteLineAction)))
+                    .item(PopupMenuItem::new("Toggle Comment").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.toggle_comment(&ToggleCommentAction, window, cx));
+                        });
+                    }).action(Box::new(ToggleCommentAction)))
+                    .item(PopupMenuItem::new("Annotate Line...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.annotate_line_dialog(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Remove Line Annotation").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.remove_line_annotation(window, cx));
+                        });
+                    }))
+                    .submenu("Convert Case", window, cx_menu, |submenu, _window, _cx_submenu| {
+                        Case::ALL.into_iter().fold(submenu, |submenu, case| {
+                            submenu.item(
+                                PopupMenuItem::new(case.to_string())
+                                    .on_click(move |_, window, app| {
+                                        with_workspace!(window, app, |this, window, cx| {
+                                            this.with_editor(cx, |ed, cx| ed.convert_case(case, window, cx));
+                                        });
+                                    })
+                                    .action(case.action()),
+                            )
+                        })
+                    })
+                    .submenu("Line Operations", window, cx_menu, |submenu, _window, _cx_submenu| {
+                        LineOperation::ALL.into_iter().fold(submenu, |submenu, operation| {
+                            submenu.item(PopupMenuItem::new(operation.to_string()).on_click(move |_, window, app| {
+                                with_workspace!(window, app, |this, window, cx| {
+                                    this.with_editor(cx, |ed, cx| ed.apply_line_operation(operation, window, cx));
+                                });
+                            }))
+                        })
+                    })
+                    .submenu("Transform", window, cx_menu, |submenu, _window, _cx_submenu| {
+                        Transform::ALL.into_iter().fold(submenu, |submenu, transform| {
+                            submenu.item(PopupMenuItem::new(transform.to_string()).on_click(move |_, window, app| {
+                                with_workspace!(window, app, |this, window, cx| {
+                                    this.with_editor(cx, |ed, cx| ed.apply_transform(transform, window, cx));
+                                });
+                            }))
+                        })
+                    })
+                    .item(PopupMenuItem::separator())
+                    .item(PopupMenuItem::new("Add Quote Prefix").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.add_quote_prefix(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Remove Quote Prefix").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.remove_quote_prefix(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Promote Heading").disabled(!markdown_assist).on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.promote_heading(&PromoteHeadingAction, window, cx));
+                        });
+                    }).action(Box::new(PromoteHeadingAction)))
+                    .item(PopupMenuItem::new("Demote Heading").disabled(!markdown_assist).on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.demote_heading(&DemoteHeadingAction, window, cx));
+                        });
+                    }).action(Box::new(DemoteHeadingAction)))
+                    .item(PopupMenuItem::new("Clean Up Whitespace...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.whitespace_cleanup(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Document Statistics").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.show_statistics(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Length Budget...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.length_budget_dialog(window, cx));
+                        });
+                    }))
             })
     }
 
-    pub(super) fn build_view_menu(&self, soft_wrap_enabled: bool, show_status_bar: bool, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+    pub(super) fn build_view_menu(&self, soft_wrap_enabled: bool, show_status_bar: bool, paragraph_align: ParagraphAlign, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_side_pane = self.side_editor.is_some();
+        let split_layout = self.split.then_some(self.pane_layout);
+        let has_archive = self.archive.is_some();
+        let (read_only, large_file) = self.editor_entity.as_ref()
+            .map(|e| (e.read(cx).read_only, e.read(cx).large_file))
+            .unwrap_or_default();
+        let sync_scrolling = self.sync_scrolling;
+        let spell_check = self.settings.spell_check;
+        let status_segments = self.settings.status_bar;
+        let todo_panel = self.editor_entity.as_ref().is_some_and(|e| e.read(cx).todo_panel_open());
+        let history_panel = self.editor_entity.as_ref().is_some_and(|e| e.read(cx).history_panel_open());
+        let current_line = self.settings.highlight_current_line;
+        let line_numbers = self.settings.show_line_numbers;
+        let cursor_style = self.settings.cursor_style;
+        let cursor_blinks = self.settings.cursor_blink_ms > 0;
+        let freeze_top_line = self.editor_entity.as_ref().is_some_and(|e| e.read(cx).freeze_top_line_enabled());
+        let auto_scroll = self.editor_entity.as_ref().is_some_and(|e| e.read(cx).auto_scroll_enabled());
+        let monospace = self.editor_entity.as_ref().is_some_and(|e| e.read(cx).monospace_enabled());
+        let fullscreen = window.is_fullscreen();
         Button::new("menu:view")
             .label("View")
             .text()
@@ -125,11 +368,129 @@ impl Workspace {
                                 this.with_editor(cx, |ed, cx| ed.toggle_soft_wrap(window, cx));
                             });
                         }))
+                        .item(PopupMenuItem::new("Highlight Current Line").checked(current_line).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, window, cx| {
+                                this.toggle_current_line_highlight(window, cx);
+                            });
+                        }))
+                        .item(PopupMenuItem::new("Line Numbers").checked(line_numbers).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, window, cx| {
+                                this.toggle_line_numbers(window, cx);
+                            });
+                        }))
+                        .submenu("Cursor", window, cx_menu, move |submenu, _window, _cx_submenu| {
+                            CursorStyle::ALL.into_iter().fold(submenu, |submenu, style| {
+                                submenu.item(
+                                    PopupMenuItem::new(style.label())
+                                        .checked(style == cursor_style)
+                                        .on_click(move |_, window, app| {
+                                            with_workspace!(window, app, |this, window, cx| {
+                                                this.set_cursor_style(style, window, cx);
+                                            });
+                                        }),
+                                )
+                            })
+                            .separator()
+                            .item(PopupMenuItem::new("Blink").checked(cursor_blinks).on_click(|_, window, app| {
+                                with_workspace!(window, app, |this, window, cx| {
+                                    this.toggle_cursor_blink(window, cx);
+                                });
+                            }))
+                        })
+                        .item(PopupMenuItem::new("Freeze Top Line").checked(freeze_top_line).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, _window, cx| {
+                                this.with_editor(cx, |ed, cx| ed.toggle_freeze_top_line(cx));
+                            });
+                        }))
+                        .item(PopupMenuItem::new("Auto-Scroll").checked(auto_scroll).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, window, cx| {
+                                this.with_editor(cx, |ed, cx| ed.toggle_auto_scroll(window, cx));
+                            });
+                        }))
+                        .item(PopupMenuItem::new("Monospace Alignment").checked(monospace).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, _window, cx| {
+                                this.with_editor(cx, |ed, cx| ed.toggle_monospace(cx));
+                            });
+                        }))
+                        .item(PopupMenuItem::new("Read Only").checked(read_only).disabled(large_file).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, window, cx| {
+                                this.with_editor(cx, |ed, cx| ed.toggle_read_only(window, cx));
+                            });
+                        }))
                         .item(PopupMenuItem::new("Status Bar").checked(show_status_bar).on_click(|_, window, app| {
                             with_workspace!(window, app, |this, window, cx| {
                                 this.with_editor(cx, |ed, cx| ed.toggle_status_bar(window, cx));
                             });
                         }))
+                        .submenu("Status Bar Segments", window, cx_menu, move |submenu, _window, _cx_submenu| {
+                            StatusSegment::ALL.into_iter().fold(submenu, |submenu, segment| {
+                                submenu.item(
+                                    PopupMenuItem::new(segment.to_string())
+                                        .checked(status_segments.shows(segment))
+                                        .on_click(move |_, window, app| {
+                                            with_workspace!(window, app, |this, window, cx| {
+                                                this.toggle_status_segment(segment, window, cx);
+                                            });
+                                        }),
+                                )
+                            })
+                        })
+                        .item(PopupMenuItem::new("TODO List").checked(todo_panel).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, _window, cx| {
+                                this.with_editor(cx, |ed, cx| ed.toggle_todo_panel(cx));
+                            });
+                        }))
+                        .item(PopupMenuItem::new("History").checked(history_panel).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, _window, cx| {
+                                this.with_editor(cx, |ed, cx| ed.toggle_history_panel(cx));
+                            });
+                        }))
+                        .item(PopupMenuItem::new("Spell Check").checked(spell_check).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, window, cx| {
+                                this.toggle_spell_check(window, cx);
+                            });
+                        }))
+                        .submenu("Paragraph Alignment", window, cx_menu, move |submenu, _window, _cx_submenu| {
+                            ParagraphAlign::ALL.into_iter().fold(submenu, |submenu, align| {
+                                submenu.item(
+                                    PopupMenuItem::new(align.to_string())
+                                        .checked(align == paragraph_align)
+                                        .on_click(move |_, window, app| {
+                                            with_workspace!(window, app, |this, _window, cx| {
+                                                this.with_editor(cx, |ed, cx| ed.set_paragraph_align(align, cx));
+                                            });
+                                        }),
+                                )
+                            })
+                        })
+                        .item(PopupMenuItem::new("Full Screen").checked(fullscreen).on_click(|_, window, _app| {
+                            window.toggle_fullscreen();
+                        }).action(Box::new(ToggleFullScreenAction)))
+                        .item(PopupMenuItem::separator())
+                        .submenu("Split Editor", window, cx_menu, move |submenu, _window, _cx_submenu| {
+                            [("Side by Side", PaneLayout::SideBySide), ("Top and Bottom", PaneLayout::Stacked)].into_iter().fold(submenu, |submenu, (label, layout)| {
+                                submenu.item(PopupMenuItem::new(label).checked(split_layout == Some(layout)).on_click(move |_, window, app| {
+                                    with_workspace!(window, app, |this, window, cx| {
+                                        this.split_editor(layout, window, cx);
+                                    });
+                                }))
+                            })
+                        })
+                        .item(PopupMenuItem::new("Sync Scrolling").checked(sync_scrolling).disabled(!has_side_pane).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, _window, cx| {
+                                this.toggle_sync_scrolling(cx);
+                            });
+                        }))
+                        .item(PopupMenuItem::new("Close Side Pane").disabled(!has_side_pane).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, window, cx| {
+                                this.close_side_pane(window, cx);
+                            });
+                        }))
+                        .item(PopupMenuItem::new("Archive Listing").disabled(!has_archive).on_click(|_, window, app| {
+                            with_workspace!(window, app, |this, window, cx| {
+                                this.show_archive(window, cx);
+                            });
+                        }))
                         .item(PopupMenuItem::separator())
                         .submenu("Theme", window, cx_menu, |submenu, _window, cx_submenu| {
                             let mut theme_names: Vec<String> = ThemeRegistry::global(cx_submenu)
@@ -151,8 +512,8 @@ impl Workspace {
                                                 let theme_name = name.clone();
                                                 move |_, window, app| {
                                                     let name = theme_name.clone();
-                                                    with_workspace!(window, app, |this, _window, cx| {
-                                                        this.apply_theme(name, cx);
+                                                    with_workspace!(window, app, |this, window, cx| {
+                                                        this.apply_theme(name, window, cx);
                                                     });
                                                 }
                                             }),
@@ -160,13 +521,189 @@ impl Workspace {
                                 },
                             )
                         })
-                        .item(PopupMenuItem::separator())
-                        .item(PopupMenuItem::new("License").on_click(|_, window, app| {
+                }
+            })
+    }
+
+    pub(super) fn build_help_menu(&self) -> impl IntoElement {
+        Button::new("menu:help")
+            .label("Help")
+            .text()
+            .dropdown_caret(true)
+            .dropdown_menu(move |menu, _window, _cx_menu| {
+                menu
+                    .item(PopupMenuItem::new("Renderer Info").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.show_renderer_info(window, cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::new("License").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.open_license(window, cx);
+                        });
+                    }))
+            })
+    }
+
+    pub(super) fn build_format_menu(&self) -> impl IntoElement {
+        Button::new("menu:format")
+            .label("Format")
+            .text()
+            .dropdown_caret(true)
+            .dropdown_menu(move |menu, _window, _cx_menu| {
+                let menu = menu
+                    .item(PopupMenuItem::new("Pretty-Print").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.reformat(None, true, window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Minify").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.reformat(None, false, window, cx));
+                        });
+                    }));
+                [DataFormat::Json, DataFormat::Xml].into_iter().fold(menu, |menu, format| {
+                    menu.item(PopupMenuItem::separator())
+                        .item(PopupMenuItem::new(format!("Pretty-Print {}", format)).on_click(move |_, window, app| {
                             with_workspace!(window, app, |this, window, cx| {
-                                this.open_license(window, cx);
+                                this.with_editor(cx, |ed, cx| ed.reformat(Some(format), true, window, cx));
                             });
                         }))
-                }
+                        .item(PopupMenuItem::new(format!("Minify {}", format)).on_click(move |_, window, app| {
+                            with_workspace!(window, app, |this, window, cx| {
+                                this.with_editor(cx, |ed, cx| ed.reformat(Some(format), false, window, cx));
+                            });
+                        }))
+                })
+            })
+    }
+
+    pub(super) fn build_tools_menu(&self, cx: &App) -> impl IntoElement {
+        let is_patch = self.current_file(cx).as_deref().is_some_and(super::patch::is_patch_file);
+        let markdown_assist = self.settings.markdown_assist;
+        let watching = self.watch_folder_enabled();
+        Button::new("menu:tools")
+            .label("Tools")
+            .text()
+            .dropdown_caret(true)
+            .dropdown_menu(move |menu, window, cx_menu| {
+                let (extension_entries, extension_list) = cx_menu.try_global::<Extensions>()
+                    .map(|extensions| (extensions.menu_entries(), extensions.list().collect::<Vec<_>>()))
+                    .unwrap_or_default();
+                let menu = menu
+                    .item(PopupMenuItem::new("Apply Patch...").disabled(!is_patch).on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.apply_patch(window, cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Fix Mojibake...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.fix_mojibake(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Batch Convert...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.batch_convert(window, cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Split File...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.split_file_dialog(window, cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Join Files...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.join_files_dialog(window, cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Watch Folder").checked(watching).on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.toggle_watch_folder(window, cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Choose Watch Folder...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.choose_watch_folder(window, cx);
+                        });
+                    }))
+                    .item(PopupMenuItem::separator())
+                    .item(PopupMenuItem::new("Join Hard-Wrapped Paragraphs").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.join_paragraphs(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Hard Wrap at Column...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.hard_wrap_dialog(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Format Table").disabled(!markdown_assist).on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.format_table(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Insert Reference Link...").disabled(!markdown_assist).on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.insert_reference_link_dialog(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Renumber References").disabled(!markdown_assist).on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.renumber_references(window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::separator())
+                    .item(PopupMenuItem::new("Next Conflict").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.goto_conflict(true, window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Previous Conflict").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.goto_conflict(false, window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Accept Current").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.resolve_conflict(Resolution::Current, window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Accept Incoming").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.resolve_conflict(Resolution::Incoming, window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::new("Accept Both").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| ed.resolve_conflict(Resolution::Both, window, cx));
+                        });
+                    }))
+                    .item(PopupMenuItem::separator());
+                // Entries contributed by active extensions
+                let menu = extension_entries.into_iter().fold(menu, |menu, entry| {
+                    let handler = entry.handler;
+                    menu.item(PopupMenuItem::new(entry.label).on_click(move |_, window, app| {
+                        let handler = handler.clone();
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.with_editor(cx, |ed, cx| handler(ed, window, cx));
+                        });
+                    }))
+                });
+                menu
+                    .item(PopupMenuItem::new("Run Command...").on_click(|_, window, app| {
+                        with_workspace!(window, app, |this, window, cx| {
+                            this.command_palette(window, cx);
+                        });
+                    }))
+                    .submenu("Extensions", window, cx_menu, move |submenu, _window, _cx_submenu| {
+                        extension_list.iter().fold(submenu, |submenu, &(id, name, enabled)| {
+                            submenu.item(PopupMenuItem::new(name).checked(enabled).on_click(move |_, window, app| {
+                                with_workspace!(window, app, |this, _window, cx| {
+                                    this.set_extension_enabled(id, !enabled, cx);
+                                });
+                            }))
+                        })
+                    })
             })
     }
 
@@ -174,30 +711,37 @@ impl Workspace {
         let theme = Theme::global_mut(cx);
         let palette = theme.colors;
         
-        let (soft_wrap_enabled, show_status_bar) = if let Some(editor) = &self.editor_entity {
+        let (soft_wrap_enabled, show_status_bar, paragraph_align) = if let Some(editor) = &self.editor_entity {
             let ed = editor.read(cx);
-            (ed.soft_wrap, ed.show_status_bar)
+            (ed.soft_wrap, ed.show_status_bar, ed.paragraph_align())
         } else {
-            (true, true)
+            (true, true, ParagraphAlign::Left)
         };
 
-        let file_menu = self.build_file_menu();
+        let file_menu = self.build_file_menu(cx);
         let edit_menu = self.build_edit_menu();
-        let view_menu = self.build_view_menu(soft_wrap_enabled, show_status_bar, window, cx);
+        let view_menu = self.build_view_menu(soft_wrap_enabled, show_status_bar, paragraph_align, window, cx);
+        let format_menu = self.build_format_menu();
+        let tools_menu = self.build_tools_menu(cx);
+        let help_menu = self.build_help_menu();
+        let style = ChromeStyle::global(cx);
 
         div()
             .flex()
             .relative()
             .w_full()
-            .h(px(32.0))
+            .h(style.menu_bar_height)
             .border_b_1()
             .border_color(palette.border)
             .bg(palette.muted)
-            .px_2()
+            .px(style.menu_padding)
             .items_center()
             .gap(px(8.0))
             .child(file_menu)
             .child(edit_menu)
             .child(view_menu)
+            .child(format_menu)
+            .child(tools_menu)
+            .child(help_menu)
     }
 }
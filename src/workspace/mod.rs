@@ -2,70 +2,308 @@
 //!
 //! This module is split into:
 //! - `mod.rs` - Core Workspace struct and basic operations
+//! - `append.rs` - Inserting another file, and appending to another file
+//! - `batch.rs` - Converting every text file in a folder at once
 //! - `file_ops.rs` - File dialog operations (open, save, save-as)
 //! - `menu.rs` - Menu bar building
+//! - `autosave.rs` - Periodic autosave, and external changes to the open file
+//! - `pdf_dialog.rs` - PDF export options dialog
+//! - `loader.rs` - Background file loading with progress
+//! - `marks.rs` - Global marks remembering a file and caret position
+//! - `patch.rs` - Applying unified diffs to files
+//! - `panes.rs` - Side-by-side panes and synchronized scrolling
+//! - `compare.rs` - Side-by-side diff against another file or the saved version
+//! - `compression.rs` - Opening and saving compressed text files
+//! - `archive.rs` - Zip archive listing view
+//! - `preferences.rs` - Preferences dialog
+//! - `quick_open.rs` - Fuzzy file finder over the folder and recent files
+//! - `renderer.rs` - Renderer diagnostics and software fallback
+//! - `safe_write.rs` - Atomic saves with optional backups
+//! - `schedule.rs` - Day/night theme schedule
+//! - `siblings.rs` - Stepping to the previous or next file in the folder
+//! - `spelling.rs` - Spell checking and its dictionary
+//! - `split_join.rs` - Splitting a file into parts and joining them back
+//! - `watch.rs` - Opening new files from a watched folder
 
+mod append;
+mod archive;
+mod autosave;
+mod batch;
+mod compare;
+mod compression;
 mod file_ops;
+mod loader;
+mod marks;
 mod menu;
+mod panes;
+mod patch;
+mod pdf_dialog;
+mod preferences;
+mod quick_open;
+mod renderer;
+mod safe_write;
+mod schedule;
+mod siblings;
+mod spelling;
+mod split_join;
+mod watch;
 
 use gpui::*;
-use gpui_component::{Theme, ThemeRegistry};
+use gpui_component::notification::Notification;
+use gpui_component::{Root, Theme, ThemeRegistry, WindowExt};
 
 use gpui_component::TitleBar;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-use crate::{ExitAppAction, FindAction, NewFileAction, OpenFileDialogAction, SaveFileAction, SaveFileAsAction};
-use tracing::debug;
-use crate::editor::TextEditor;
-use crate::settings::AppSettings;
+use crate::{CloseWindowAction, ExitAppAction, ExportPdfAction, FindAction, NewFileAction, NewWindowAction, NextFileAction, OpenFileDialogAction, PreferencesAction, PreviousFileAction, QuickOpenAction, SaveFileAction, SaveFileAsAction, ToggleFullScreenAction};
+use tracing::{debug, warn};
+use crate::editor::{Document, DocumentEvent, FramePacing, SaveFindResultsAction, TextEditor};
+use crate::extensions::Extensions;
+use crate::keymap;
+use marks::{JumpToMarkAction, SetMarkAction};
+use crate::settings::{AppSettings, CursorStyle, SessionOverrides, StatusSegment};
+use crate::style::ChromeStyle;
+pub use renderer::force_software_rendering;
+pub use safe_write::write_atomic;
+
+/// Largest clipboard text New From Clipboard accepts.
+const CLIPBOARD_LIMIT: usize = 16 * 1024 * 1024;
 
 /// Main workspace - holds the editor and current file state.
 pub struct Workspace {
     /// The active view being displayed.
     pub active_view: AnyView,
-    /// The text editor entity.
+    /// The open document: its file, text and save state.
+    pub document: Entity<Document>,
+    /// The text editor entity, a view over `document`.
     pub editor_entity: Option<Entity<TextEditor>>,
-    /// Path to the currently open file.
-    pub current_file: Option<PathBuf>,
     /// Application settings.
     pub settings: AppSettings,
     /// Cached window title to avoid redundant updates.
     cached_title: String,
+    /// Modification time of the current file when it was last loaded or saved.
+    disk_mtime: Option<SystemTime>,
+    /// Disk version of the file whose conflict with unsaved edits the banner is asking about.
+    pending_conflict: Option<SystemTime>,
+    /// The current file, watched for changes by other programs.
+    file_watch: Option<autosave::FileWatch>,
+    autosave_task: Option<Task<()>>,
+    theme_schedule_task: Option<Task<()>>,
+    /// File currently being read in the background.
+    loading: Option<loader::LoadProgress>,
+    /// Editor shown beside the main one for side-by-side review.
+    pub side_editor: Option<Entity<TextEditor>>,
+    /// Whether the side pane is a second view of the current document.
+    pub split: bool,
+    /// How the main and side panes are arranged.
+    pub pane_layout: panes::PaneLayout,
+    /// Whether scrolling one pane scrolls the other.
+    pub sync_scrolling: bool,
+    /// Cursor line the panes were last synchronized to.
+    synced_line: Option<u32>,
+    pane_subscriptions: Vec<Subscription>,
+    /// File split or join running in the background.
+    file_job: Option<split_join::FileJob>,
+    /// Folder new text files are opened from, while watching.
+    folder_watch: Option<watch::FolderWatch>,
+    /// Zip archive being browsed, if one was opened.
+    pub archive: Option<Entity<archive::ArchiveView>>,
+    /// Saves of the current file, run one at a time.
+    saves: file_ops::SaveQueue,
+    /// A bare read-only window started with `--preview`.
+    preview: bool,
+}
+
+/// Bind the keymap preset with the user's keys on top, then the mark keys.
+pub fn install_key_bindings(settings: &AppSettings, cx: &mut App) {
+    keymap::install(settings.keymap_preset, &settings.key_bindings, cx);
+    cx.bind_keys(marks::key_bindings(settings.keymap_preset));
+}
+
+/// Ask about unsaved changes in each of `windows` in turn, bringing each to
+/// the front first, and quit after the last.
+fn exit_remaining(mut windows: Vec<AnyWindowHandle>, cx: &mut App) {
+    let Some(next) = windows.pop() else {
+        cx.quit();
+        return;
+    };
+    let workspace = next
+        .downcast::<Root>()
+        .and_then(|handle| handle.update(cx, |root, _, _| root.view().clone().downcast::<Workspace>().ok()).ok().flatten());
+    match workspace {
+        Some(workspace) => {
+            let _ = next.update(cx, |_, window, cx| {
+                window.activate_window();
+                workspace.update(cx, |ws, cx| {
+                    ws.handle_unsaved_changes(window, cx, move |_this, _window, cx| exit_remaining(windows, cx));
+                });
+            });
+        }
+        None => exit_remaining(windows, cx),
+    }
 }
 
 impl Workspace {
     pub fn new(window: &mut Window, cx: &mut Context<Self>, settings: AppSettings) -> Self {
-        let editor = cx.new(|cx| TextEditor::new(window, cx, "".into()));
+        let document = cx.new(|_| Document::untitled());
+        let editor = cx.new(|cx| TextEditor::with_document(document.clone(), window, cx, "".into()));
+        cx.subscribe_in(&document, window, |this, _, event: &DocumentEvent, window, cx| {
+            if matches!(event, DocumentEvent::PathChanged | DocumentEvent::DirtyChanged | DocumentEvent::FormatChanged) {
+                this.update_title(window, cx);
+                cx.notify();
+            }
+            if matches!(event, DocumentEvent::PathChanged) {
+                this.watch_current_file(window, cx);
+            }
+        }).detach();
 
-        Self {
+        let mut workspace = Self {
             active_view: editor.clone().into(),
+            document,
             editor_entity: Some(editor),
-            current_file: None,
             settings,
             cached_title: String::new(),
-        }
+            disk_mtime: None,
+            pending_conflict: None,
+            file_watch: None,
+            autosave_task: None,
+            theme_schedule_task: None,
+            loading: None,
+            side_editor: None,
+            split: false,
+            pane_layout: panes::PaneLayout::default(),
+            sync_scrolling: false,
+            synced_line: None,
+            pane_subscriptions: Vec::new(),
+            file_job: None,
+            folder_watch: None,
+            archive: None,
+            saves: file_ops::SaveQueue::default(),
+            preview: false,
+        };
+        workspace.apply_editor_settings(window, cx);
+        workspace.load_dictionary(window, cx);
+        workspace.start_autosave(window, cx);
+        workspace.start_theme_schedule(window, cx);
+        // Timers stall while the machine sleeps; catch up when the window is back
+        cx.observe_window_activation(window, |this, window, cx| {
+            if window.is_window_active() {
+                this.apply_scheduled_theme(window, cx);
+            }
+        }).detach();
+        workspace
     }
 
-    pub fn open_file(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
-        if let Some(editor) = &self.editor_entity {
+    /// Push editor-related settings to every open editor.
+    pub(crate) fn apply_editor_settings(&self, window: &mut Window, cx: &mut Context<Self>) {
+        let settings = &SessionOverrides::get().apply(&self.settings);
+        let undo_group_timeout = Duration::from_millis(settings.undo_group_timeout_ms);
+        let undo_memory_limit = settings.undo_memory_limit_mb.saturating_mul(1024 * 1024);
+        let large_file_threshold = settings.large_file_threshold_mb.saturating_mul(1024 * 1024);
+        for editor in self.editor_entity.iter().chain(&self.side_editor) {
             editor.update(cx, |ed, cx| {
-                let _ = ed.open_file(path.clone(), window, cx, None);
+                ed.set_undo_group_timeout(undo_group_timeout);
+                ed.set_undo_memory_limit(undo_memory_limit);
+                ed.set_large_file_threshold(large_file_threshold);
+                ed.set_font(settings.font_family.clone(), settings.font_size, cx);
+                ed.set_tab_handling(settings.tab_handling, settings.tab_width, cx);
+                ed.set_wrap_indent(settings.wrap_indent, settings.wrap_hanging_indent, cx);
+                ed.set_markdown_assist(settings.markdown_assist, cx);
+                ed.set_bracket_options(settings.auto_pair_brackets, settings.highlight_matching_brackets, window, cx);
+                ed.set_highlight_current_line(settings.highlight_current_line, cx);
+                ed.set_line_numbers(settings.show_line_numbers, window, cx);
+                let blink = (settings.cursor_blink_ms > 0).then(|| Duration::from_millis(settings.cursor_blink_ms));
+                ed.set_cursor_style(settings.cursor_style.shape(), blink, cx);
+                ed.set_spell_check(settings.spell_check, window, cx);
+                ed.set_todo_markers(settings.todo_markers.clone(), cx);
+                ed.set_frame_pacing(FramePacing { max_fps: settings.frame_rate_cap, power_saving: settings.power_saving }, cx);
+                ed.set_status_segments(settings.status_bar, cx);
             });
         }
-        self.current_file = Some(path);
-        self.update_title(window, cx);
+    }
+
+    /// Show or hide one status bar segment and remember the choice.
+    pub fn toggle_status_segment(&mut self, segment: StatusSegment, window: &mut Window, cx: &mut Context<Self>) {
+        self.settings.status_bar.toggle(segment);
+        self.settings.save();
+        self.apply_editor_settings(window, cx);
         cx.notify();
     }
 
+    /// Turn the current line highlight on or off and remember the choice.
+    pub fn toggle_current_line_highlight(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.settings.highlight_current_line = !self.settings.highlight_current_line;
+        self.settings.save();
+        self.apply_editor_settings(window, cx);
+        cx.notify();
+    }
+
+    /// Show or hide line numbers and remember the choice.
+    pub fn toggle_line_numbers(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.settings.show_line_numbers = !self.settings.show_line_numbers;
+        self.settings.save();
+        self.preserving_views(window, cx, |this, window, cx| this.apply_editor_settings(window, cx));
+        cx.notify();
+    }
+
+    /// Draw the caret as `style` and remember the choice.
+    pub fn set_cursor_style(&mut self, style: CursorStyle, window: &mut Window, cx: &mut Context<Self>) {
+        self.settings.cursor_style = style;
+        self.settings.save();
+        self.apply_editor_settings(window, cx);
+        cx.notify();
+    }
+
+    /// Make the caret blink or hold steady and remember the choice; blinking
+    /// comes back at the default rate.
+    pub fn toggle_cursor_blink(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.settings.cursor_blink_ms = match self.settings.cursor_blink_ms {
+            0 => AppSettings::default().cursor_blink_ms,
+            _ => 0,
+        };
+        self.settings.save();
+        self.apply_editor_settings(window, cx);
+        cx.notify();
+    }
+
+    /// Switch an extension on or off and remember the choice.
+    pub fn set_extension_enabled(&mut self, id: &str, enabled: bool, cx: &mut Context<Self>) {
+        cx.global_mut::<Extensions>().set_enabled(id, enabled);
+        self.settings.disabled_extensions.retain(|disabled| disabled != id);
+        if !enabled {
+            self.settings.disabled_extensions.push(id.to_string());
+        }
+        self.settings.save();
+        cx.refresh_windows();
+    }
+
+    /// Pick an extension command to run on the active editor.
+    pub fn command_palette(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(editor) = &self.editor_entity {
+            crate::extensions::command_palette(editor.downgrade(), window, cx);
+        }
+    }
+
+    pub fn open_file(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        self.open_file_with(path, false, None, window, cx);
+    }
+
+    /// Open `path` in place of the current document once unsaved changes
+    /// are dealt with, with the caret at 0-based `position` if given.
+    pub fn open_file_with(&mut self, path: PathBuf, read_only: bool, position: Option<(usize, usize)>, window: &mut Window, cx: &mut Context<Self>) {
+        self.handle_unsaved_changes(window, cx, move |this, window, cx| match position {
+            Some((line, column)) => this.load_file_at(path, read_only, line, column, window, cx),
+            None => this.load_file(path, read_only, window, cx),
+        });
+    }
+
     /// Build window title (filename + dirty marker).
     fn get_title_text(&self, cx: &Context<Self>) -> String {
-        let filename = self.current_file.as_ref()
-            .and_then(|p| p.file_name())
-            .and_then(|n| n.to_str())
-            .unwrap_or("OneText");
-            
+        let filename = self.document.read(cx).display_name();
+
         let is_dirty = self.editor_entity.as_ref()
-            .map(|e| e.read(cx).is_dirty)
+            .map(|e| e.read(cx).is_dirty(cx))
             .unwrap_or(false);
         
         if is_dirty {
@@ -88,27 +326,138 @@ impl Workspace {
     pub fn close_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         if let Some(editor) = &self.editor_entity {
             editor.update(cx, |ed, cx| ed.close_file(window, cx));
+            self.active_view = editor.clone().into();
         }
-        self.current_file = None;
+        self.record_disk_mtime(cx);
         self.update_title(window, cx);
         cx.notify();
     }
 
+    /// The open document's file, if it has one.
+    pub fn current_file(&self, cx: &App) -> Option<PathBuf> {
+        self.document.read(cx).path().map(Path::to_path_buf)
+    }
+
+    /// Show only the document, without menus, for `--preview`.
+    pub fn set_preview(&mut self, cx: &mut Context<Self>) {
+        self.preview = true;
+        self.autosave_task = None;
+        cx.notify();
+    }
+
     pub fn new_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         self.handle_unsaved_changes(window, cx, |this, window, cx| {
             this.close_file(window, cx);
         });
     }
 
-    pub fn exit_app(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        self.handle_unsaved_changes(window, cx, |_this, _window, cx| {
-            cx.quit();
+    /// Start an untitled document holding the clipboard text.
+    pub fn new_from_clipboard(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let text = cx.read_from_clipboard().and_then(|item| item.text()).unwrap_or_default();
+        if text.is_empty() {
+            window.push_notification(Notification::warning("The clipboard has no text."), cx);
+            return;
+        }
+        if text.len() > CLIPBOARD_LIMIT {
+            let message = format!("The clipboard text is over {} MB; paste it into a document instead.", CLIPBOARD_LIMIT / (1024 * 1024));
+            window.push_notification(Notification::warning(message), cx);
+            return;
+        }
+        self.handle_unsaved_changes(window, cx, move |this, window, cx| {
+            this.close_file(window, cx);
+            if let Some(editor) = &this.editor_entity {
+                editor.update(cx, |ed, cx| ed.new_with_text(text, window, cx));
+            }
+            debug!("Created a document from the clipboard");
+            this.update_title(window, cx);
+        });
+    }
+
+    /// Open another window with its own workspace.
+    pub fn new_window(&mut self, cx: &mut Context<Self>) {
+        let settings = self.settings.clone();
+        cx.defer(move |cx| {
+            crate::open_workspace_window(cx, settings, None, false, None);
         });
     }
 
+    /// Open a copy of the document in a new window as an unsaved untitled
+    /// document, with the caret where it is here.
+    pub fn duplicate_document(&mut self, cx: &mut Context<Self>) {
+        let Some(editor) = &self.editor_entity else { return };
+        let text = self.document.read(cx).text().to_string();
+        let (line, column) = editor.read(cx).caret_line_column(cx);
+        self.open_text_in_new_window(text, (line, column), cx);
+        debug!(line, column, "Duplicated document");
+    }
+
+    /// Put the Find All results in an untitled document in a new window.
+    pub fn find_results_to_new_window(&mut self, cx: &mut Context<Self>) {
+        let Some(text) = self.editor_entity.as_ref().and_then(|e| e.read(cx).find_results_text()) else { return };
+        self.open_text_in_new_window(text, (0, 0), cx);
+    }
+
+    /// Open a new window holding `text` as an unsaved untitled document,
+    /// with the caret at `(line, column)`.
+    fn open_text_in_new_window(&mut self, text: String, (line, column): (usize, usize), cx: &mut Context<Self>) {
+        let settings = self.settings.clone();
+        cx.defer(move |cx| {
+            let Some(handle) = crate::open_workspace_window(cx, settings, None, false, None) else { return };
+            let _ = handle.update(cx, |root, window, cx| {
+                let Ok(workspace) = root.view().clone().downcast::<Workspace>() else { return };
+                workspace.update(cx, |this, cx| {
+                    if let Some(editor) = &this.editor_entity {
+                        editor.update(cx, |ed, cx| {
+                            ed.new_with_text(text, window, cx);
+                            ed.go_to_line_column(line, column, window, cx);
+                        });
+                    }
+                    this.update_title(window, cx);
+                });
+            });
+        });
+    }
+
+    /// Close this window; the app quits when it was the last one.
+    pub fn close_window(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.handle_unsaved_changes(window, cx, |_this, window, _cx| {
+            window.remove_window();
+        });
+    }
+
+    /// Quit once every window's unsaved changes are saved or discarded,
+    /// asking in this window first and then in each other one; cancelling
+    /// anywhere stops the quit.
+    pub fn exit_app(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let this_window = window.window_handle();
+        let others: Vec<AnyWindowHandle> = cx.windows().into_iter().filter(|w| *w != this_window).collect();
+        self.handle_unsaved_changes(window, cx, move |_this, _window, cx| exit_remaining(others, cx));
+    }
+
+    /// Show the license as a read-only virtual document, so it can't be
+    /// edited or saved over the bundled copy.
     pub fn open_license(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let license_path = crate::get_app_root().join("assets").join("License.txt");
-        self.open_file(license_path, window, cx);
+        match std::fs::read_to_string(&license_path) {
+            Ok(text) => self.open_virtual("License".to_string(), text, window, cx),
+            Err(err) => {
+                warn!(path = ?license_path, error = %err, "Failed to read license");
+                window.push_notification(Notification::error(format!("Can't read the license: {}", err)), cx);
+            }
+        }
+    }
+
+    /// Replace the document with read-only text called `name` that has no
+    /// file behind it; Save goes to Save As.
+    pub fn open_virtual(&mut self, name: String, text: String, window: &mut Window, cx: &mut Context<Self>) {
+        self.handle_unsaved_changes(window, cx, move |this, window, cx| {
+            let Some(editor) = this.editor_entity.clone() else { return };
+            editor.update(cx, |ed, cx| ed.open_virtual(name, text, window, cx));
+            this.record_disk_mtime(cx);
+            this.active_view = editor.into();
+            this.update_title(window, cx);
+            cx.notify();
+        });
     }
 
     // --- Editor Access ---
@@ -122,14 +471,28 @@ impl Workspace {
     }
 
     /// Apply theme and save preference.
-    pub(crate) fn apply_theme(&mut self, theme_name: String, cx: &mut Context<Self>) {
+    pub(crate) fn apply_theme(&mut self, theme_name: String, window: &mut Window, cx: &mut Context<Self>) {
         let name = SharedString::from(theme_name);
         if let Some(theme) = ThemeRegistry::global(cx).themes().get(&name).cloned() {
-            Theme::global_mut(cx).apply_config(&theme);
+            self.preserving_views(window, cx, |_, _, cx| {
+                Theme::global_mut(cx).apply_config(&theme);
+                ChromeStyle::apply_accent(cx);
+            });
             self.settings.theme = name.to_string();
             AppSettings::save(&self.settings);
         }
     }
+
+    /// Run `f`, then put every editor's caret, selection and first visible
+    /// line back where they were once the text has laid out again.
+    pub(crate) fn preserving_views(&mut self, window: &mut Window, cx: &mut Context<Self>, f: impl FnOnce(&mut Self, &mut Window, &mut Context<Self>)) {
+        let editors: Vec<_> = self.editor_entity.iter().chain(&self.side_editor).cloned().collect();
+        let anchors: Vec<_> = editors.iter().map(|e| e.update(cx, |ed, cx| ed.view_anchor(window, cx))).collect();
+        f(self, window, cx);
+        for (editor, anchor) in editors.iter().zip(anchors) {
+            editor.update(cx, |ed, cx| ed.restore_view(anchor, window, cx));
+        }
+    }
 }
 
 // --- Render ---
@@ -151,9 +514,20 @@ impl Render for Workspace {
             .bg(palette.background)
             .on_action(cx.listener(|this, _: &NewFileAction, window, cx| this.new_file(window, cx)))
             .on_action(cx.listener(|this, _: &OpenFileDialogAction, window, cx| this.open_dialog(window, cx)))
+            .on_action(cx.listener(|this, _: &QuickOpenAction, window, cx| this.quick_open(window, cx)))
             .on_action(cx.listener(|this, _: &SaveFileAction, window, cx| this.save_file(window, cx)))
             .on_action(cx.listener(|this, _: &SaveFileAsAction, window, cx| this.save_as_dialog(window, cx)))
+            .on_action(cx.listener(|this, _: &ExportPdfAction, window, cx| this.export_pdf(window, cx)))
+            .on_action(cx.listener(|this, _: &PreferencesAction, window, cx| this.open_preferences(window, cx)))
             .on_action(cx.listener(|this, _: &FindAction, window, cx| { this.with_editor(cx, |ed, cx| ed.open_search(window, cx)); }))
+            .on_action(cx.listener(|this, _: &SaveFindResultsAction, _window, cx| this.find_results_to_new_window(cx)))
+            .on_action(cx.listener(|this, action: &SetMarkAction, window, cx| this.set_mark(action.0, window, cx)))
+            .on_action(cx.listener(|this, action: &JumpToMarkAction, window, cx| this.jump_to_mark(action.0, window, cx)))
+            .on_action(cx.listener(|this, _: &PreviousFileAction, window, cx| this.open_sibling_file(false, window, cx)))
+            .on_action(cx.listener(|this, _: &NextFileAction, window, cx| this.open_sibling_file(true, window, cx)))
+            .on_action(cx.listener(|this, _: &NewWindowAction, _window, cx| this.new_window(cx)))
+            .on_action(cx.listener(|_this, _: &ToggleFullScreenAction, window, _cx| window.toggle_fullscreen()))
+            .on_action(cx.listener(|this, _: &CloseWindowAction, window, cx| this.close_window(window, cx)))
             .on_action(cx.listener(|this, _: &ExitAppAction, window, cx| this.exit_app(window, cx)))
             .child(TitleBar::new().child(
                         div()
@@ -168,7 +542,12 @@ impl Render for Workspace {
                                     .child(self.get_title_text(cx))
                             )
                     ))
-            .child(menu_bar)
-            .child(self.active_view.clone())
+            .children((!self.preview).then_some(menu_bar))
+            .children(self.render_load_progress(cx))
+            .children(self.render_disk_conflict(cx))
+            .children(self.render_file_job(cx))
+            .child(self.render_panes(cx))
+            .children(Root::render_dialog_layer(window, cx))
+            .children(Root::render_notification_layer(window, cx))
     }
 }
@@ -0,0 +1,257 @@
+//! Side-by-side panes, split views of one document and synchronized
+//! scrolling.
+
+use gpui::*;
+use gpui::prelude::FluentBuilder;
+use gpui_component::input::{InputState, Position};
+use gpui_component::notification::Notification;
+use gpui_component::{RopeExt, Theme, WindowExt};
+use rfd::{AsyncFileDialog, AsyncMessageDialog, MessageButtons, MessageDialogResult};
+use tracing::{debug, warn};
+
+use super::Workspace;
+use super::compression;
+use super::file_ops::with_workspace_async;
+use crate::editor::TextEditor;
+
+/// How the two panes are arranged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaneLayout {
+    #[default]
+    SideBySide,
+    Stacked,
+}
+
+/// Line to show in a pane with `line_count` lines to match `line` in the other.
+fn synced_line(line: u32, line_count: usize) -> u32 {
+    line.min(line_count.saturating_sub(1) as u32)
+}
+
+impl Workspace {
+    /// Pick a file and show it, read-only, in a pane beside the current
+    /// editor. Save always writes the main pane's document.
+    pub fn open_beside_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let picked = cx.background_spawn(async move {
+                    let path = AsyncFileDialog::new().pick_file().await?.path().to_path_buf();
+                    match compression::read_text(&path) {
+                        Ok(contents) => Some((path, contents)),
+                        Err(err) => {
+                            warn!(path = ?path, error = %err, "Failed to read file");
+                            None
+                        }
+                    }
+                }).await;
+
+                let Some((path, contents)) = picked else {
+                    let _ = cx.update(|_, _| {});
+                    return;
+                };
+                debug!(path = ?path, "Opening file beside current editor");
+                with_workspace_async(&mut cx, |this, window, cx_ws| {
+                    let editor = cx_ws.new(|cx| {
+                        let mut editor = TextEditor::new(window, cx, String::new());
+                        let _ = editor.open_file(path, window, cx, Some(contents));
+                        // Edits here would have no way to be saved
+                        editor.set_read_only(true, cx);
+                        editor
+                    });
+                    this.set_side_editor(editor, window, cx_ws);
+                });
+            }
+        })
+        .detach();
+    }
+
+    /// Show the current document in a second pane as well, arranged by
+    /// `layout`, or rearrange the split if there is one. Both panes are
+    /// views of the same document, so edits in either appear in both;
+    /// each keeps its own caret and scroll position.
+    pub fn split_editor(&mut self, layout: PaneLayout, window: &mut Window, cx: &mut Context<Self>) {
+        self.pane_layout = layout;
+        if self.split {
+            cx.notify();
+            return;
+        }
+        let Some(main) = self.editor_entity.clone() else { return };
+        if self.side_editor.as_ref().is_some_and(|side| side.read(cx).is_dirty(cx)) {
+            window.push_notification(Notification::warning("Close the side pane first; it has unsaved edits."), cx);
+            return;
+        }
+
+        let document = main.read(cx).document.clone();
+        let read_only = main.read(cx).read_only;
+        let editor = cx.new(|cx| TextEditor::view_of(document, read_only, window, cx));
+        self.set_side_editor(editor, window, cx);
+        self.split = true;
+    }
+
+    /// Close the side pane, confirming first if it has unsaved edits. A
+    /// split pane closes at once, as its edits are in the main pane too.
+    pub fn close_side_pane(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(editor) = &self.side_editor else { return };
+        if self.split || !editor.read(cx).is_dirty(cx) {
+            self.remove_side_editor(cx);
+            return;
+        }
+
+        cx.spawn_in(window, move |_this, cx_async: &mut AsyncWindowContext| {
+            let mut cx = cx_async.clone();
+            async move {
+                let result = AsyncMessageDialog::new()
+                    .set_title("Close Side Pane")
+                    .set_description("The side pane has edits that will be discarded. Close it anyway?")
+                    .set_buttons(MessageButtons::YesNo)
+                    .show()
+                    .await;
+                if result == MessageDialogResult::Yes {
+                    with_workspace_async(&mut cx, |this, _window, cx_ws| this.remove_side_editor(cx_ws));
+                }
+            }
+        }).detach();
+    }
+
+    pub fn toggle_sync_scrolling(&mut self, cx: &mut Context<Self>) {
+        self.sync_scrolling = !self.sync_scrolling;
+        self.synced_line = None;
+        cx.notify();
+    }
+
+    fn set_side_editor(&mut self, editor: Entity<TextEditor>, window: &mut Window, cx: &mut Context<Self>) {
+        let inputs = self.editor_entity.as_ref().map(|main| {
+            (main.read(cx).input_state.clone(), editor.read(cx).input_state.clone())
+        });
+        self.pane_subscriptions.clear();
+        if let Some((main, side)) = inputs {
+            self.pane_subscriptions = vec![
+                cx.observe_in(&main, window, {
+                    let side = side.clone();
+                    move |this, main, window, cx| this.sync_panes(main, side.clone(), window, cx)
+                }),
+                cx.observe_in(&side, window, move |this, side, window, cx| {
+                    this.sync_panes(side, main.clone(), window, cx)
+                }),
+            ];
+        }
+        self.side_editor = Some(editor);
+        self.split = false;
+        self.apply_editor_settings(window, cx);
+        self.synced_line = None;
+        cx.notify();
+    }
+
+    fn remove_side_editor(&mut self, cx: &mut Context<Self>) {
+        self.side_editor = None;
+        self.split = false;
+        self.pane_subscriptions.clear();
+        cx.notify();
+    }
+
+    /// Keep the other pane scrolled to the line the cursor is on in the focused pane.
+    ///
+    /// The input does not expose its scroll offset, so the panes are locked
+    /// by cursor line: moving the cursor scrolls the other pane to match.
+    fn sync_panes(&mut self, source: Entity<InputState>, target: Entity<InputState>, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.sync_scrolling || !source.read(cx).focus_handle(cx).is_focused(window) {
+            return;
+        }
+        let line = source.read(cx).cursor_position().line;
+        if self.synced_line == Some(line) {
+            return;
+        }
+        self.synced_line = Some(line);
+
+        target.update(cx, |state, cx| {
+            let line = synced_line(line, state.text().lines_len());
+            state.set_cursor_position(Position { line, character: 0 }, window, cx);
+        });
+        // Moving the cursor focuses the other pane; hand focus back
+        source.update(cx, |state, cx| state.focus(window, cx));
+    }
+
+    /// The editor area: the active view, plus the side pane when one is open.
+    pub(super) fn render_panes(&self, cx: &App) -> AnyElement {
+        let Some(side) = &self.side_editor else {
+            return self.active_view.clone().into_any_element();
+        };
+
+        div()
+            .flex()
+            .when(self.pane_layout == PaneLayout::Stacked, |this| this.flex_col())
+            .size_full()
+            .child(self.render_pane(0, self.active_view.clone(), cx))
+            .child(self.render_pane(1, side.clone().into(), cx))
+            .into_any_element()
+    }
+
+    fn render_pane(&self, index: usize, view: AnyView, cx: &App) -> impl IntoElement {
+        let border = Theme::global(cx).colors.border;
+        div()
+            .flex_1()
+            .overflow_hidden()
+            .map(|this| match self.pane_layout {
+                PaneLayout::SideBySide => this.h_full().when(index > 0, |this| this.border_l_1().border_color(border)),
+                PaneLayout::Stacked => this.w_full().when(index > 0, |this| this.border_t_1().border_color(border)),
+            })
+            .child(view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{synced_line, PaneLayout};
+    use crate::settings::{self, AppSettings, MemoryStore};
+    use crate::workspace::Workspace;
+    use gpui::{AppContext, EntityInputHandler, TestAppContext, VisualTestContext};
+    use gpui_component::Root;
+
+    #[gpui::test]
+    async fn test_split_panes_share_the_document(cx: &mut TestAppContext) {
+        settings::install_store(Box::new(MemoryStore::default()));
+        cx.update(gpui_component::init);
+        let settings = AppSettings { autosave_interval_secs: 0, ..AppSettings::default() };
+        let window = cx.add_window(|window, cx| {
+            let workspace = cx.new(|cx| Workspace::new(window, cx, settings));
+            Root::new(workspace, window, cx)
+        });
+        let workspace = window.update(cx, |root, _, _| root.view().clone().downcast::<Workspace>().unwrap()).unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        let type_into = |side: bool, offset: usize, text: &'static str, cx: &mut VisualTestContext| {
+            workspace.update_in(cx, |ws, window, cx| {
+                let editor = if side { ws.side_editor.clone() } else { ws.editor_entity.clone() }.unwrap();
+                let input = editor.read(cx).input_state.clone();
+                input.update(cx, |state, cx| state.replace_text_in_range(Some(offset..offset), text, window, cx));
+            });
+            cx.run_until_parked();
+        };
+        let text_of = |side: bool, cx: &mut VisualTestContext| {
+            workspace.read_with(cx, |ws, cx| {
+                let editor = if side { ws.side_editor.as_ref() } else { ws.editor_entity.as_ref() }.unwrap();
+                editor.read(cx).input_state.read(cx).value().to_string()
+            })
+        };
+
+        type_into(false, 0, "hello", cx);
+        workspace.update_in(cx, |ws, window, cx| ws.split_editor(PaneLayout::SideBySide, window, cx));
+        assert_eq!(text_of(true, cx), "hello");
+
+        type_into(true, 5, " world", cx);
+        assert_eq!(text_of(false, cx), "hello world");
+        type_into(false, 0, "> ", cx);
+        assert_eq!(text_of(true, cx), "> hello world");
+        assert!(workspace.read_with(cx, |ws, cx| ws.document.read(cx).is_dirty()));
+    }
+
+    #[test]
+    fn test_synced_line_matches_source_line() {
+        assert_eq!(synced_line(10, 100), 10);
+    }
+
+    #[test]
+    fn test_synced_line_clamps_to_shorter_file() {
+        assert_eq!(synced_line(10, 4), 3);
+        assert_eq!(synced_line(10, 0), 0);
+    }
+}
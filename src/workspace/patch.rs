@@ -0,0 +1,351 @@
+//! Applying unified diffs (.patch/.diff files) to other files.
+
+use anyhow::{anyhow, bail};
+use gpui::*;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::{v_flex, Theme, WindowExt};
+use rfd::AsyncFileDialog;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use tracing::{info, warn};
+
+use super::Workspace;
+use super::file_ops::with_workspace_async;
+
+/// One line of a hunk body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+/// A `@@ -a,b +c,d @@` section of a unified diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines.iter().filter_map(|line| match line {
+            HunkLine::Context(text) | HunkLine::Removed(text) => Some(text.as_str()),
+            HunkLine::Added(_) => None,
+        }).collect()
+    }
+
+    fn new_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|line| match line {
+            HunkLine::Context(text) | HunkLine::Added(text) => Some(text.as_str()),
+            HunkLine::Removed(_) => None,
+        })
+    }
+}
+
+/// The hunks for one file in a patch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilePatch {
+    /// Path from the `+++` header, without the `b/` prefix.
+    pub path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+/// Whether a file looks like a patch by its extension.
+pub fn is_patch_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|e| e.to_str()), Some("patch" | "diff"))
+}
+
+/// Parse the `-a,b` or `+c,d` part of a hunk header.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Strip the `a/`/`b/` prefix and any timestamp from a header path.
+fn header_path(rest: &str) -> Option<String> {
+    let path = rest.split('\t').next()?.trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// Parse a unified diff into per-file hunks.
+pub fn parse_patch(text: &str) -> anyhow::Result<Vec<FilePatch>> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if let Some(rest) = line.strip_prefix("+++ ") {
+            files.push(FilePatch { path: header_path(rest), hunks: Vec::new() });
+            continue;
+        }
+        let Some(header) = line.strip_prefix("@@ -") else { continue };
+
+        let (old, rest) = header.split_once(" +").ok_or_else(|| anyhow!("Malformed hunk header: {}", line))?;
+        let new = rest.split(" @@").next().unwrap_or(rest);
+        let (old_start, old_len) = parse_range(old).ok_or_else(|| anyhow!("Malformed hunk header: {}", line))?;
+        let (new_start, new_len) = parse_range(new).ok_or_else(|| anyhow!("Malformed hunk header: {}", line))?;
+
+        let mut hunk = Hunk { old_start, old_len, new_start, new_len, lines: Vec::new() };
+        let (mut old_seen, mut new_seen) = (0, 0);
+        while old_seen < old_len || new_seen < new_len {
+            let Some(body) = lines.next() else {
+                bail!("Hunk at line {} ends early", old_start);
+            };
+            let parsed = match body.chars().next() {
+                Some('+') => HunkLine::Added(body[1..].to_string()),
+                Some('-') => HunkLine::Removed(body[1..].to_string()),
+                Some(' ') => HunkLine::Context(body[1..].to_string()),
+                // Some tools strip the space from empty context lines
+                None => HunkLine::Context(String::new()),
+                Some('\\') => continue,
+                Some(_) => bail!("Unexpected line in hunk: {}", body),
+            };
+            match parsed {
+                HunkLine::Added(_) => new_seen += 1,
+                HunkLine::Removed(_) => old_seen += 1,
+                HunkLine::Context(_) => {
+                    old_seen += 1;
+                    new_seen += 1;
+                }
+            }
+            hunk.lines.push(parsed);
+        }
+        // "\ No newline at end of file" after the last line
+        while lines.peek().is_some_and(|l| l.starts_with('\\')) {
+            lines.next();
+        }
+
+        if files.is_empty() {
+            files.push(FilePatch::default());
+        }
+        if let Some(file) = files.last_mut() {
+            file.hunks.push(hunk);
+        }
+    }
+
+    files.retain(|file| !file.hunks.is_empty());
+    Ok(files)
+}
+
+/// Apply hunks to `original`, allowing each hunk to have moved from its
+/// stated line as long as its context still matches.
+pub fn apply_hunks(original: &str, hunks: &[Hunk]) -> anyhow::Result<String> {
+    let lines: Vec<&str> = original.lines().collect();
+    let mut out: Vec<&str> = Vec::with_capacity(lines.len());
+    let mut pos = 0;
+    let mut offset: isize = 0;
+
+    for (i, hunk) in hunks.iter().enumerate() {
+        let expected = hunk.old_lines();
+        // A pure insertion's start is the line it goes after
+        let stated = if hunk.old_len == 0 { hunk.old_start } else { hunk.old_start.saturating_sub(1) };
+        let want = (stated as isize + offset).max(pos as isize) as usize;
+
+        let last = lines.len().checked_sub(expected.len())
+            .filter(|&last| last >= pos)
+            .ok_or_else(|| anyhow!("Hunk {} does not match the target file", i + 1))?;
+        let want = want.min(last);
+        let found = (0..=last - pos)
+            .flat_map(|distance| [want.checked_add(distance), want.checked_sub(distance)])
+            .flatten()
+            .filter(|&at| at >= pos && at <= last)
+            .find(|&at| lines[at..at + expected.len()] == expected[..])
+            .ok_or_else(|| anyhow!("Hunk {} does not match the target file", i + 1))?;
+
+        out.extend(&lines[pos..found]);
+        out.extend(hunk.new_lines());
+        pos = found + expected.len();
+        offset = found as isize - stated as isize;
+    }
+    out.extend(&lines[pos..]);
+
+    let mut result = out.join("\n");
+    if !result.is_empty() && (original.ends_with('\n') || original.is_empty()) {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+impl Workspace {
+    /// Apply the open patch to a file chosen by the user, after a preview.
+    pub fn apply_patch(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let text = self.get_editor_text(cx);
+        let patches = match parse_patch(&text) {
+            Ok(patches) if !patches.is_empty() => patches,
+            Ok(_) => {
+                warn!("No hunks found in patch");
+                return;
+            }
+            Err(err) => {
+                warn!(error = %err, "Failed to parse patch");
+                return;
+            }
+        };
+        let directory = self.current_file(cx).as_deref().and_then(Path::parent).map(Path::to_path_buf);
+
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let target = cx.background_spawn(async move {
+                    let mut dialog = AsyncFileDialog::new().set_title("Apply Patch To");
+                    if let Some(directory) = directory {
+                        dialog = dialog.set_directory(directory);
+                    }
+                    dialog.pick_file().await.map(|file| file.path().to_path_buf())
+                }).await;
+                let Some(target) = target else {
+                    let _ = cx.update(|_, _| {});
+                    return;
+                };
+
+                let original = match fs::read_to_string(&target) {
+                    Ok(original) => original,
+                    Err(err) => {
+                        warn!(path = ?target, error = %err, "Failed to read patch target");
+                        return;
+                    }
+                };
+                // Prefer the file section whose path names the chosen target
+                let name = target.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                let patch = patches.iter()
+                    .find(|p| p.path.as_deref().is_some_and(|path| Path::new(path).ends_with(name)))
+                    .unwrap_or(&patches[0])
+                    .clone();
+                let result = apply_hunks(&original, &patch.hunks);
+
+                with_workspace_async(&mut cx, |_this, window, cx_ws| {
+                    Self::show_patch_preview(target, patch, result, window, cx_ws);
+                });
+            }
+        }).detach();
+    }
+
+    fn show_patch_preview(
+        target: PathBuf,
+        patch: FilePatch,
+        result: anyhow::Result<String>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let name = target.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        let status = match &result {
+            Ok(_) => format!("{} hunk(s) will be applied to {}.", patch.hunks.len(), name),
+            Err(err) => format!("{}. Nothing will be changed.", err),
+        };
+        let patched = result.ok();
+        let hunks = Rc::new(patch.hunks);
+
+        window.open_dialog(cx, move |dialog, _window, cx| {
+            let colors = Theme::global(cx).colors;
+            let preview = hunks.iter().flat_map(|hunk| {
+                let header = format!("@@ -{},{} +{},{} @@", hunk.old_start, hunk.old_len, hunk.new_start, hunk.new_len);
+                std::iter::once(div().text_color(colors.info).child(header))
+                    .chain(hunk.lines.iter().map(move |line| match line {
+                        HunkLine::Added(text) => div().text_color(colors.success).child(format!("+{}", text)),
+                        HunkLine::Removed(text) => div().text_color(colors.danger).child(format!("-{}", text)),
+                        HunkLine::Context(text) => div().child(format!(" {}", text)),
+                    }))
+            });
+
+            let dialog = dialog
+                .title("Apply Patch")
+                .w(px(640.0))
+                .child(
+                    v_flex()
+                        .gap_2()
+                        .child(status.clone())
+                        .child(
+                            div()
+                                .id("patch-preview")
+                                .max_h(px(360.0))
+                                .overflow_y_scroll()
+                                .font_family("monospace")
+                                .text_sm()
+                                .children(preview),
+                        ),
+                );
+
+            let Some(patched) = patched.clone() else {
+                return dialog.alert();
+            };
+            let target = target.clone();
+            dialog
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Apply"))
+                .on_ok(move |_, _window, _cx| {
+                    match fs::write(&target, &patched) {
+                        Ok(_) => info!(path = ?target, "Patch applied"),
+                        Err(err) => warn!(path = ?target, error = %err, "Failed to write patched file"),
+                    }
+                    true
+                })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_hunks, parse_patch, HunkLine};
+
+    const PATCH: &str = "\
+--- a/notes.txt
++++ b/notes.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+@@ -5,2 +5,3 @@
+ five
+ six
++seven
+";
+
+    #[test]
+    fn test_parse_patch() {
+        let files = parse_patch(PATCH).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path.as_deref(), Some("notes.txt"));
+        assert_eq!(files[0].hunks.len(), 2);
+        assert_eq!(files[0].hunks[0].lines[1], HunkLine::Removed("two".into()));
+        assert_eq!(files[0].hunks[1].lines[2], HunkLine::Added("seven".into()));
+    }
+
+    #[test]
+    fn test_apply_hunks() {
+        let files = parse_patch(PATCH).unwrap();
+        let original = "one\ntwo\nthree\nfour\nfive\nsix\n";
+        let patched = apply_hunks(original, &files[0].hunks).unwrap();
+        assert_eq!(patched, "one\nTWO\nthree\nfour\nfive\nsix\nseven\n");
+    }
+
+    #[test]
+    fn test_apply_hunks_with_offset() {
+        let files = parse_patch(PATCH).unwrap();
+        let original = "zero\none\ntwo\nthree\nfour\nfive\nsix\n";
+        let patched = apply_hunks(original, &files[0].hunks).unwrap();
+        assert_eq!(patched, "zero\none\nTWO\nthree\nfour\nfive\nsix\nseven\n");
+    }
+
+    #[test]
+    fn test_apply_hunks_rejects_mismatch() {
+        let files = parse_patch(PATCH).unwrap();
+        let err = apply_hunks("something else\n", &files[0].hunks).unwrap_err();
+        assert!(err.to_string().contains("Hunk 1"));
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_hunk() {
+        assert!(parse_patch("@@ -1,3 +1,3 @@\n one\n").is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! PDF export options dialog.
+
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::button::{Button, ButtonGroup};
+use gpui_component::checkbox::Checkbox;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::input::{Input, InputState};
+use gpui_component::select::{SearchableVec, Select, SelectState};
+use gpui_component::{h_flex, v_flex, IndexPath, Selectable, Sizable, WindowExt};
+use std::ops::RangeInclusive;
+
+use super::Workspace;
+use crate::editor::{is_markdown_file, Orientation, PageSize};
+use crate::settings::PdfExportSettings;
+
+const MARGIN_RANGE: RangeInclusive<f32> = 0.0..=216.0;
+const FONT_SIZE_RANGE: RangeInclusive<f32> = 4.0..=72.0;
+/// First entry of the font list, standing for the embedded font.
+const BUILT_IN_FONT: &str = "Noto Sans (built in)";
+
+/// Dialog body holding the options being edited.
+pub struct PdfOptionsPanel {
+    options: PdfExportSettings,
+    margin_input: Entity<InputState>,
+    font_size_input: Entity<InputState>,
+    header_input: Entity<InputState>,
+    footer_input: Entity<InputState>,
+    font_select: Entity<SelectState<SearchableVec<String>>>,
+    /// Whether the document is Markdown, which offers rendering it.
+    markdown: bool,
+}
+
+impl PdfOptionsPanel {
+    pub fn new(options: PdfExportSettings, markdown: bool, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let margin_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(options.margin.to_string())
+        });
+        let font_size_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(options.font_size.to_string())
+        });
+        let header_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(options.header_template.clone())
+        });
+        let footer_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder("None").default_value(options.footer_template.clone())
+        });
+        let mut fonts = cx.text_system().all_font_names();
+        fonts.sort();
+        fonts.dedup();
+        fonts.insert(0, BUILT_IN_FONT.to_string());
+        let font_index = match &options.font_family {
+            Some(family) => fonts.iter().position(|f| f == family),
+            None => Some(0),
+        };
+        let font_select = cx.new(|cx| {
+            SelectState::new(SearchableVec::new(fonts), font_index.map(IndexPath::new), window, cx).searchable(true)
+        });
+
+        Self {
+            options,
+            margin_input,
+            font_size_input,
+            header_input,
+            footer_input,
+            font_select,
+            markdown,
+        }
+    }
+
+    /// Current options; invalid numeric fields keep their previous values.
+    pub fn options(&self, cx: &App) -> PdfExportSettings {
+        let mut options = self.options.clone();
+        if let Some(margin) = parse_points(&self.margin_input.read(cx).value(), MARGIN_RANGE) {
+            options.margin = margin;
+        }
+        if let Some(size) = parse_points(&self.font_size_input.read(cx).value(), FONT_SIZE_RANGE) {
+            options.font_size = size;
+        }
+        options.header_template = self.header_input.read(cx).value().to_string();
+        options.footer_template = self.footer_input.read(cx).value().to_string();
+        if let Some(font) = self.font_select.read(cx).selected_value() {
+            options.font_family = Some(font.clone()).filter(|f| f != BUILT_IN_FONT);
+        }
+        options
+    }
+
+    fn row(label: &'static str, control: impl IntoElement) -> impl IntoElement {
+        h_flex()
+            .gap_3()
+            .items_center()
+            .child(div().w(px(110.0)).child(label))
+            .child(control)
+    }
+}
+
+/// Parse a point value, rejecting anything outside `range`.
+fn parse_points(text: &str, range: RangeInclusive<f32>) -> Option<f32> {
+    text.trim().parse::<f32>().ok().filter(|v| range.contains(v))
+}
+
+impl Render for PdfOptionsPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let page_size = self.options.page_size;
+        let orientation = self.options.orientation;
+
+        v_flex()
+            .gap_3()
+            .child(Self::row(
+                "Page size",
+                ButtonGroup::new("pdf-page-size")
+                    .small()
+                    .children(PageSize::ALL.iter().map(|size| {
+                        Button::new(size.label())
+                            .label(size.label())
+                            .selected(*size == page_size)
+                    }))
+                    .on_click(cx.listener(|this, clicks: &Vec<usize>, _, cx| {
+                        if let Some(size) = clicks.first().and_then(|&i| PageSize::ALL.get(i)) {
+                            this.options.page_size = *size;
+                            cx.notify();
+                        }
+                    })),
+            ))
+            .child(Self::row(
+                "Orientation",
+                ButtonGroup::new("pdf-orientation")
+                    .small()
+                    .child(Button::new("portrait").label("Portrait").selected(orientation == Orientation::Portrait))
+                    .child(Button::new("landscape").label("Landscape").selected(orientation == Orientation::Landscape))
+                    .on_click(cx.listener(|this, clicks: &Vec<usize>, _, cx| {
+                        this.options.orientation = if clicks.first() == Some(&1) {
+                            Orientation::Landscape
+                        } else {
+                            Orientation::Portrait
+                        };
+                        cx.notify();
+                    })),
+            ))
+            .child(Self::row("Margin (pt)", div().w(px(100.0)).child(Input::new(&self.margin_input))))
+            .child(Self::row("Font", div().w(px(220.0)).child(Select::new(&self.font_select))))
+            .child(Self::row("Font size (pt)", div().w(px(100.0)).child(Input::new(&self.font_size_input))))
+            .child(
+                Checkbox::new("pdf-line-numbers")
+                    .label("Line numbers")
+                    .checked(self.options.line_numbers)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.options.line_numbers = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Checkbox::new("pdf-syntax-colors")
+                    .label("Syntax colors")
+                    .checked(self.options.syntax_colors)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.options.syntax_colors = *checked;
+                        cx.notify();
+                    })),
+            )
+            .when(self.markdown, |this| {
+                this.child(
+                    Checkbox::new("pdf-render-markdown")
+                        .label("Render Markdown")
+                        .checked(self.options.render_markdown)
+                        .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                            this.options.render_markdown = *checked;
+                            cx.notify();
+                        })),
+                )
+            })
+            .child(
+                Checkbox::new("pdf-selection-only")
+                    .label("Selection only")
+                    .checked(self.options.selection_only)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.options.selection_only = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Checkbox::new("pdf-header")
+                    .label("Header")
+                    .checked(self.options.include_header)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.options.include_header = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(Self::row("Header text", div().flex_grow().child(Input::new(&self.header_input))))
+            .child(Self::row("Footer text", div().flex_grow().child(Input::new(&self.footer_input))))
+            .child(
+                div()
+                    .text_xs()
+                    .child("{filename}, {path}, {date}, {page} and {pages} are filled in on each page."),
+            )
+    }
+}
+
+impl Workspace {
+    /// Show the PDF export options, then export with the chosen settings.
+    pub fn export_pdf(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let markdown = is_markdown_file(self.current_file(cx).as_deref());
+        let panel = cx.new(|cx| PdfOptionsPanel::new(self.settings.pdf_export.clone(), markdown, window, cx));
+        let workspace = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let panel_for_ok = panel.clone();
+            let workspace = workspace.clone();
+            dialog
+                .title("Export to PDF")
+                .child(panel.clone())
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Export..."))
+                .on_ok(move |_, window, cx| {
+                    let options = panel_for_ok.read(cx).options(cx);
+                    let _ = workspace.update(cx, |this, cx| {
+                        this.settings.pdf_export = options.clone();
+                        this.settings.save();
+                        this.with_editor(cx, |ed, cx| ed.export_pdf(&options, window, cx));
+                    });
+                    true
+                })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_points, FONT_SIZE_RANGE, MARGIN_RANGE};
+
+    #[test]
+    fn test_parse_points_accepts_values_in_range() {
+        assert_eq!(parse_points(" 36 ", MARGIN_RANGE), Some(36.0));
+        assert_eq!(parse_points("10.5", FONT_SIZE_RANGE), Some(10.5));
+    }
+
+    #[test]
+    fn test_parse_points_rejects_invalid_values() {
+        assert_eq!(parse_points("abc", MARGIN_RANGE), None);
+        assert_eq!(parse_points("-1", MARGIN_RANGE), None);
+        assert_eq!(parse_points("200", FONT_SIZE_RANGE), None);
+    }
+}
@@ -0,0 +1,545 @@
+//! Preferences dialog for editing `AppSettings`.
+
+use gpui::*;
+use gpui_component::button::{Button, ButtonGroup};
+use gpui_component::checkbox::Checkbox;
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::input::{Input, InputState};
+use gpui_component::select::{SearchableVec, Select, SelectState};
+use gpui_component::{h_flex, v_flex, IndexPath, Selectable, Sizable, ThemeRegistry, WindowExt};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::{install_key_bindings, Workspace};
+use super::schedule::parse_clock;
+use crate::editor::{TabHandling, WrapIndent};
+use crate::keymap::{self, KeymapPreset};
+use crate::settings::{AppSettings, FileFilter, SaveConflictPolicy, SessionOverrides};
+
+const FONT_SIZE_RANGE: RangeInclusive<f32> = 6.0..=72.0;
+const AUTOSAVE_RANGE: RangeInclusive<u64> = 0..=3600;
+const TAB_WIDTH_RANGE: RangeInclusive<usize> = 1..=16;
+const FRAME_RATE_RANGE: RangeInclusive<u32> = 0..=240;
+const CURSOR_BLINK_RANGE: RangeInclusive<u64> = 0..=5000;
+const HANGING_INDENT_RANGE: RangeInclusive<usize> = 0..=16;
+
+const CONFLICT_POLICIES: [(SaveConflictPolicy, &str); 3] = [
+    (SaveConflictPolicy::Prompt, "Ask"),
+    (SaveConflictPolicy::KeepNewer, "Keep newer"),
+    (SaveConflictPolicy::KeepBuffer, "Keep mine"),
+];
+
+/// Dialog body holding the settings being edited.
+pub struct PreferencesPanel {
+    settings: AppSettings,
+    theme_select: Entity<SelectState<Vec<String>>>,
+    light_theme_select: Entity<SelectState<Vec<String>>>,
+    dark_theme_select: Entity<SelectState<Vec<String>>>,
+    light_from_input: Entity<InputState>,
+    dark_from_input: Entity<InputState>,
+    font_select: Entity<SelectState<SearchableVec<String>>>,
+    font_size_input: Entity<InputState>,
+    autosave_input: Entity<InputState>,
+    tab_width_input: Entity<InputState>,
+    frame_rate_input: Entity<InputState>,
+    cursor_blink_input: Entity<InputState>,
+    hanging_indent_input: Entity<InputState>,
+    extension_input: Entity<InputState>,
+    todo_markers_input: Entity<InputState>,
+    file_filters_input: Entity<InputState>,
+    save_dir_input: Entity<InputState>,
+}
+
+impl PreferencesPanel {
+    pub fn new(settings: AppSettings, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let mut themes: Vec<String> = ThemeRegistry::global(cx)
+            .themes()
+            .keys()
+            .map(|name| name.to_string())
+            .collect();
+        themes.sort();
+        let theme_index = |name: &str| themes.iter().position(|t| t == name).map(IndexPath::new);
+        let schedule = &settings.theme_schedule;
+        let light_index = theme_index(&schedule.light_theme);
+        let dark_index = theme_index(&schedule.dark_theme);
+        let current_index = theme_index(&settings.theme);
+        let light_theme_select = cx.new(|cx| SelectState::new(themes.clone(), light_index, window, cx));
+        let dark_theme_select = cx.new(|cx| SelectState::new(themes.clone(), dark_index, window, cx));
+        let theme_select = cx.new(|cx| SelectState::new(themes, current_index, window, cx));
+        let light_from_input = cx.new(|cx| InputState::new(window, cx).default_value(schedule.light_from.clone()));
+        let dark_from_input = cx.new(|cx| InputState::new(window, cx).default_value(schedule.dark_from.clone()));
+
+        let mut fonts = cx.text_system().all_font_names();
+        fonts.sort();
+        fonts.dedup();
+        let font_index = fonts.iter().position(|f| *f == settings.font_family).map(IndexPath::new);
+        let font_select = cx.new(|cx| {
+            SelectState::new(SearchableVec::new(fonts), font_index, window, cx).searchable(true)
+        });
+
+        let font_size_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(settings.font_size.to_string())
+        });
+        let autosave_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(settings.autosave_interval_secs.to_string())
+        });
+        let tab_width_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(settings.tab_width.to_string())
+        });
+        let frame_rate_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(settings.frame_rate_cap.to_string())
+        });
+        let cursor_blink_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(settings.cursor_blink_ms.to_string())
+        });
+        let hanging_indent_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(settings.wrap_hanging_indent.to_string())
+        });
+        let extension_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(settings.default_extension.clone())
+        });
+        let todo_markers_input = cx.new(|cx| {
+            InputState::new(window, cx).default_value(settings.todo_markers.join(", "))
+        });
+        let save_dir_input = cx.new(|cx| {
+            let dir = settings.default_save_dir.as_ref().map(|d| d.display().to_string()).unwrap_or_default();
+            InputState::new(window, cx).placeholder("Last used folder").default_value(dir)
+        });
+        let file_filters_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Text: txt text; Logs: log")
+                .default_value(format_file_filters(&settings.file_filters))
+        });
+
+        Self {
+            settings,
+            theme_select,
+            light_theme_select,
+            dark_theme_select,
+            light_from_input,
+            dark_from_input,
+            font_select,
+            font_size_input,
+            autosave_input,
+            tab_width_input,
+            frame_rate_input,
+            cursor_blink_input,
+            hanging_indent_input,
+            extension_input,
+            todo_markers_input,
+            file_filters_input,
+            save_dir_input,
+        }
+    }
+
+    /// Current settings; invalid fields keep their previous values.
+    pub fn settings(&self, cx: &App) -> AppSettings {
+        let mut settings = self.settings.clone();
+        if let Some(theme) = self.theme_select.read(cx).selected_value() {
+            settings.theme = theme.clone();
+        }
+        let schedule = &mut settings.theme_schedule;
+        if let Some(theme) = self.light_theme_select.read(cx).selected_value() {
+            schedule.light_theme = theme.clone();
+        }
+        if let Some(theme) = self.dark_theme_select.read(cx).selected_value() {
+            schedule.dark_theme = theme.clone();
+        }
+        let light_from = self.light_from_input.read(cx).value();
+        if parse_clock(&light_from).is_some() {
+            schedule.light_from = light_from.trim().to_string();
+        }
+        let dark_from = self.dark_from_input.read(cx).value();
+        if parse_clock(&dark_from).is_some() {
+            schedule.dark_from = dark_from.trim().to_string();
+        }
+        if let Some(font) = self.font_select.read(cx).selected_value() {
+            settings.font_family = font.clone();
+        }
+        if let Some(size) = parse_in_range(&self.font_size_input.read(cx).value(), FONT_SIZE_RANGE) {
+            settings.font_size = size;
+        }
+        if let Some(secs) = parse_in_range(&self.autosave_input.read(cx).value(), AUTOSAVE_RANGE) {
+            settings.autosave_interval_secs = secs;
+        }
+        if let Some(width) = parse_in_range(&self.tab_width_input.read(cx).value(), TAB_WIDTH_RANGE) {
+            settings.tab_width = width;
+        }
+        if let Some(cap) = parse_in_range(&self.frame_rate_input.read(cx).value(), FRAME_RATE_RANGE) {
+            settings.frame_rate_cap = cap;
+        }
+        if let Some(ms) = parse_in_range(&self.cursor_blink_input.read(cx).value(), CURSOR_BLINK_RANGE) {
+            settings.cursor_blink_ms = ms;
+        }
+        if let Some(columns) = parse_in_range(&self.hanging_indent_input.read(cx).value(), HANGING_INDENT_RANGE) {
+            settings.wrap_hanging_indent = columns;
+        }
+        let extension = self.extension_input.read(cx).value();
+        let extension = extension.trim().trim_start_matches('.');
+        if !extension.contains(['/', '\\', '.']) {
+            settings.default_extension = extension.to_string();
+        }
+        let markers = parse_markers(&self.todo_markers_input.read(cx).value());
+        if !markers.is_empty() {
+            settings.todo_markers = markers;
+        }
+        let save_dir = self.save_dir_input.read(cx).value();
+        let save_dir = save_dir.trim();
+        if save_dir.is_empty() {
+            settings.default_save_dir = None;
+        } else if Path::new(save_dir).is_dir() {
+            settings.default_save_dir = Some(PathBuf::from(save_dir));
+        }
+        let filters = parse_file_filters(&self.file_filters_input.read(cx).value());
+        if !filters.is_empty() {
+            settings.file_filters = filters;
+        }
+        settings
+    }
+
+    fn row(label: &'static str, control: impl IntoElement) -> impl IntoElement {
+        h_flex()
+            .gap_3()
+            .items_center()
+            .child(div().w(px(140.0)).child(label))
+            .child(control)
+    }
+}
+
+/// Split a comma- or space-separated list of TODO markers, dropping repeats.
+fn parse_markers(text: &str) -> Vec<String> {
+    let mut markers: Vec<String> = Vec::new();
+    for marker in text.split([',', ' ']).map(str::trim).filter(|m| !m.is_empty()) {
+        if !markers.iter().any(|m| m == marker) {
+            markers.push(marker.to_string());
+        }
+    }
+    markers
+}
+
+/// File filters as `Name: ext ext; Name: ext`.
+fn format_file_filters(filters: &[FileFilter]) -> String {
+    filters
+        .iter()
+        .map(|f| format!("{}: {}", f.name, f.extensions.join(" ")))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Read filters written as `format_file_filters` does. Entries without a
+/// name or extensions are dropped; extensions may have a leading dot.
+fn parse_file_filters(text: &str) -> Vec<FileFilter> {
+    text.split(';')
+        .filter_map(|entry| {
+            let (name, extensions) = entry.split_once(':')?;
+            let extensions: Vec<String> = extensions
+                .split([',', ' '])
+                .map(|e| e.trim().trim_start_matches('.'))
+                .filter(|e| !e.is_empty())
+                .map(str::to_string)
+                .collect();
+            let name = name.trim();
+            (!name.is_empty() && !extensions.is_empty()).then(|| FileFilter { name: name.to_string(), extensions })
+        })
+        .collect()
+}
+
+/// Parse a number, rejecting anything outside `range`.
+fn parse_in_range<T: std::str::FromStr + PartialOrd>(text: &str, range: RangeInclusive<T>) -> Option<T> {
+    text.trim().parse::<T>().ok().filter(|v| range.contains(v))
+}
+
+impl Render for PreferencesPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let policy = self.settings.autosave_conflict_policy;
+        let keymap = self.settings.keymap_preset;
+        let tab_handling = self.settings.tab_handling;
+        let wrap_indent = self.settings.wrap_indent;
+
+        v_flex()
+            .gap_3()
+            .child(Self::row("Theme", div().w(px(220.0)).child(Select::new(&self.theme_select))))
+            .child(
+                Checkbox::new("prefs-theme-schedule")
+                    .label("Switch themes by time of day")
+                    .checked(self.settings.theme_schedule.enabled)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.theme_schedule.enabled = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(Self::row(
+                "Day theme from",
+                h_flex()
+                    .gap_2()
+                    .child(div().w(px(70.0)).child(Input::new(&self.light_from_input)))
+                    .child(div().w(px(220.0)).child(Select::new(&self.light_theme_select))),
+            ))
+            .child(Self::row(
+                "Night theme from",
+                h_flex()
+                    .gap_2()
+                    .child(div().w(px(70.0)).child(Input::new(&self.dark_from_input)))
+                    .child(div().w(px(220.0)).child(Select::new(&self.dark_theme_select))),
+            ))
+            .child(Self::row("Font", div().w(px(220.0)).child(Select::new(&self.font_select))))
+            .child(Self::row("Font size (px)", div().w(px(100.0)).child(Input::new(&self.font_size_input))))
+            .child(Self::row(
+                "Tabs",
+                ButtonGroup::new("prefs-tab-handling")
+                    .small()
+                    .children(TabHandling::ALL.iter().map(|mode| {
+                        Button::new(mode.label()).label(mode.label()).selected(*mode == tab_handling)
+                    }))
+                    .on_click(cx.listener(|this, clicks: &Vec<usize>, _, cx| {
+                        if let Some(mode) = clicks.first().and_then(|&i| TabHandling::ALL.get(i)) {
+                            this.settings.tab_handling = *mode;
+                            cx.notify();
+                        }
+                    })),
+            ))
+            .child(Self::row("Tab width", div().w(px(100.0)).child(Input::new(&self.tab_width_input))))
+            .child(Self::row(
+                "Wrapped lines",
+                ButtonGroup::new("prefs-wrap-indent")
+                    .small()
+                    .children(WrapIndent::ALL.iter().map(|indent| {
+                        Button::new(indent.label()).label(indent.label()).selected(*indent == wrap_indent)
+                    }))
+                    .on_click(cx.listener(|this, clicks: &Vec<usize>, _, cx| {
+                        if let Some(indent) = clicks.first().and_then(|&i| WrapIndent::ALL.get(i)) {
+                            this.settings.wrap_indent = *indent;
+                            cx.notify();
+                        }
+                    })),
+            ))
+            .child(Self::row("Hanging indent (columns)", div().w(px(100.0)).child(Input::new(&self.hanging_indent_input))))
+            .child(Self::row("Autosave (seconds)", div().w(px(100.0)).child(Input::new(&self.autosave_input))))
+            .child(Self::row(
+                "When file changed",
+                ButtonGroup::new("prefs-conflict-policy")
+                    .small()
+                    .children(CONFLICT_POLICIES.iter().map(|(p, label)| {
+                        Button::new(*label).label(*label).selected(*p == policy)
+                    }))
+                    .on_click(cx.listener(|this, clicks: &Vec<usize>, _, cx| {
+                        if let Some((policy, _)) = clicks.first().and_then(|&i| CONFLICT_POLICIES.get(i)) {
+                            this.settings.autosave_conflict_policy = *policy;
+                            cx.notify();
+                        }
+                    })),
+            ))
+            .child(Self::row(
+                "Keyboard",
+                ButtonGroup::new("prefs-keymap-preset")
+                    .small()
+                    .children(KeymapPreset::ALL.iter().map(|p| {
+                        Button::new(p.label()).label(p.label()).selected(*p == keymap)
+                    }))
+                    .on_click(cx.listener(|this, clicks: &Vec<usize>, _, cx| {
+                        if let Some(preset) = clicks.first().and_then(|&i| KeymapPreset::ALL.get(i)) {
+                            this.settings.keymap_preset = *preset;
+                            cx.notify();
+                        }
+                    })),
+            ))
+            .child(
+                Checkbox::new("prefs-trim-on-save")
+                    .label("Trim trailing whitespace on save")
+                    .checked(self.settings.trim_trailing_whitespace_on_save)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.trim_trailing_whitespace_on_save = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Checkbox::new("prefs-final-newline")
+                    .label("End files with a single newline on save")
+                    .checked(self.settings.final_newline_on_save)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.final_newline_on_save = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Checkbox::new("prefs-backup-on-save")
+                    .label("Keep a .bak copy of the previous version on save")
+                    .checked(self.settings.backup_on_save)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.backup_on_save = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(Self::row("Default extension", div().w(px(100.0)).child(Input::new(&self.extension_input))))
+            .child(Self::row("Save untitled in", div().w(px(320.0)).child(Input::new(&self.save_dir_input))))
+            .child(
+                Checkbox::new("prefs-name-from-first-line")
+                    .label("Name untitled documents after their first line")
+                    .checked(self.settings.name_untitled_from_first_line)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.name_untitled_from_first_line = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(Self::row("Dialog file types", div().w(px(320.0)).child(Input::new(&self.file_filters_input))))
+            .child(
+                Checkbox::new("prefs-html-syntax-colors")
+                    .label("Color source code in HTML exports")
+                    .checked(self.settings.html_syntax_colors)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.html_syntax_colors = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Checkbox::new("prefs-markdown-assist")
+                    .label("Markdown table formatting and Tab navigation")
+                    .checked(self.settings.markdown_assist)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.markdown_assist = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Checkbox::new("prefs-auto-pair")
+                    .label("Close brackets and quotes as they are typed")
+                    .checked(self.settings.auto_pair_brackets)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.auto_pair_brackets = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Checkbox::new("prefs-match-brackets")
+                    .label("Highlight the matching bracket")
+                    .checked(self.settings.highlight_matching_brackets)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.highlight_matching_brackets = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(Self::row("TODO markers", div().w(px(240.0)).child(Input::new(&self.todo_markers_input))))
+            .child(Self::row("Frame rate cap (0 = none)", div().w(px(100.0)).child(Input::new(&self.frame_rate_input))))
+            .child(Self::row("Cursor blink ms (0 = steady)", div().w(px(100.0)).child(Input::new(&self.cursor_blink_input))))
+            .child(
+                Checkbox::new("prefs-power-saving")
+                    .label("Power saving: no redraws while unfocused or idle")
+                    .checked(self.settings.power_saving)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.power_saving = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Checkbox::new("prefs-software-rendering")
+                    .label("Software rendering (after restart)")
+                    .checked(self.settings.software_rendering)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.software_rendering = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(
+                Checkbox::new("prefs-unsaved-protection")
+                    .label("Warn about unsaved changes")
+                    .checked(self.settings.enable_unsaved_changes_protection)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.settings.enable_unsaved_changes_protection = *checked;
+                        cx.notify();
+                    })),
+            )
+    }
+}
+
+impl Workspace {
+    /// Show the preferences, then apply and save the chosen settings.
+    pub fn open_preferences(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let panel = cx.new(|cx| PreferencesPanel::new(self.settings.clone(), window, cx));
+        let workspace = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let panel_for_ok = panel.clone();
+            let workspace = workspace.clone();
+            dialog
+                .title("Preferences")
+                .w(px(520.0))
+                .child(panel.clone())
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Save"))
+                .on_ok(move |_, window, cx| {
+                    let settings = panel_for_ok.read(cx).settings(cx);
+                    let _ = workspace.update(cx, |this, cx| this.apply_settings(settings, window, cx));
+                    true
+                })
+        });
+    }
+
+    /// Pick up settings.json after another window or program changed it.
+    pub(crate) fn reload_settings(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(settings) = AppSettings::try_load() else { return };
+        if settings != self.settings {
+            info!("Settings changed on disk; applying");
+            self.apply_settings(settings, window, cx);
+        }
+    }
+
+    /// Switch to `settings`, updating everything that depends on them, and save.
+    pub(crate) fn apply_settings(&mut self, settings: AppSettings, window: &mut Window, cx: &mut Context<Self>) {
+        let autosave_changed = settings.autosave_interval_secs != self.settings.autosave_interval_secs;
+        let schedule_changed = settings.theme_schedule != self.settings.theme_schedule;
+        let keys_changed =
+            settings.keymap_preset != self.settings.keymap_preset || settings.key_bindings != self.settings.key_bindings;
+        let theme = settings.theme.clone();
+        self.settings = settings;
+        // A theme given on the command line stays for the whole run
+        if SessionOverrides::get().theme.is_none() {
+            self.apply_theme(theme, window, cx);
+        }
+        self.preserving_views(window, cx, |this, window, cx| this.apply_editor_settings(window, cx));
+        self.load_dictionary(window, cx);
+        if autosave_changed {
+            self.start_autosave(window, cx);
+        }
+        if schedule_changed {
+            self.start_theme_schedule(window, cx);
+        }
+        if keys_changed {
+            keymap::reset(cx);
+            install_key_bindings(&self.settings, cx);
+        }
+        self.settings.save();
+        info!("Preferences saved");
+        cx.notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_file_filters, parse_file_filters, parse_in_range, parse_markers, AUTOSAVE_RANGE, FONT_SIZE_RANGE};
+    use crate::settings::AppSettings;
+
+    #[test]
+    fn test_parse_in_range() {
+        assert_eq!(parse_in_range(" 16 ", FONT_SIZE_RANGE), Some(16.0));
+        assert_eq!(parse_in_range("0", AUTOSAVE_RANGE), Some(0));
+        assert_eq!(parse_in_range("2", FONT_SIZE_RANGE), None);
+        assert_eq!(parse_in_range("-5", AUTOSAVE_RANGE), None);
+        assert_eq!(parse_in_range("big", FONT_SIZE_RANGE), None);
+    }
+
+    #[test]
+    fn test_parse_markers() {
+        assert_eq!(parse_markers("TODO, FIXME HACK,,TODO"), ["TODO", "FIXME", "HACK"]);
+        assert!(parse_markers(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_file_filters_round_trip() {
+        let filters = AppSettings::default().file_filters;
+        assert_eq!(parse_file_filters(&format_file_filters(&filters)), filters);
+        let parsed = parse_file_filters("Code: .rs, toml;; Nameless ; : txt");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].extensions, ["rs", "toml"]);
+    }
+}
@@ -0,0 +1,275 @@
+//! Quick Open: a palette that fuzzy-matches files in the current file's
+//! folder and recently opened files.
+
+use gpui::*;
+use gpui::prelude::FluentBuilder;
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::{h_flex, v_flex, Theme, WindowExt};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use super::Workspace;
+
+/// Recently opened files kept in settings.
+const MAX_RECENT: usize = 20;
+/// Files the folder index stops at.
+const MAX_INDEXED: usize = 10_000;
+/// Folder levels below the current file's folder that are indexed.
+const MAX_DEPTH: usize = 4;
+/// Matches listed at a time.
+const SHOWN: usize = 50;
+
+/// How well `query` matches `candidate`, case-insensitively, or `None` if
+/// its characters don't all appear in order. Runs of consecutive characters
+/// and characters at the start of a word or of the file name score higher.
+fn fuzzy_score(candidate: &str, query: &str) -> Option<i32> {
+    let chars: Vec<char> = candidate.chars().collect();
+    let name_start = chars.iter().rposition(|&c| c == '/' || c == '\\').map_or(0, |i| i + 1);
+    let mut score = 0;
+    let mut at = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase) {
+        let found = (at..chars.len()).find(|&i| chars[i].to_lowercase().eq(std::iter::once(wanted)))?;
+        score += 1;
+        if previous == Some(found.wrapping_sub(1)) {
+            score += 5;
+        }
+        if found == name_start {
+            score += 8;
+        } else if found == 0 || !chars[found - 1].is_alphanumeric() {
+            score += 4;
+        }
+        if found >= name_start {
+            score += 2;
+        }
+        previous = Some(found);
+        at = found + 1;
+    }
+    // Shorter paths win ties
+    Some(score * 100 - chars.len() as i32)
+}
+
+/// Files under `root`, breadth first, skipping hidden entries.
+fn index_folder(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = VecDeque::from([(root.to_path_buf(), 0)]);
+    while let Some((dir, depth)) = dirs.pop_front() {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        let mut entries: Vec<_> = entries.flatten().collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(kind) = entry.file_type() else { continue };
+            if kind.is_dir() && depth < MAX_DEPTH {
+                dirs.push_back((entry.path(), depth + 1));
+            } else if kind.is_file() {
+                files.push(entry.path());
+                if files.len() >= MAX_INDEXED {
+                    return files;
+                }
+            }
+        }
+    }
+    files
+}
+
+/// The Quick Open palette's contents.
+struct QuickOpenPanel {
+    query: Entity<InputState>,
+    root: Option<PathBuf>,
+    recent: Vec<PathBuf>,
+    indexed: Vec<PathBuf>,
+    indexing: bool,
+    selected: usize,
+    workspace: WeakEntity<Workspace>,
+    _subscription: Subscription,
+    _index_task: Task<()>,
+}
+
+impl QuickOpenPanel {
+    /// How `path` is listed: relative to the indexed folder when inside it.
+    fn label(&self, path: &Path) -> String {
+        self.root.as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+
+    /// Recent files first, then the folder's, best match first.
+    fn matches(&self, cx: &App) -> Vec<(PathBuf, String)> {
+        let query = self.query.read(cx).value();
+        let candidates = self.recent.iter()
+            .chain(self.indexed.iter().filter(|p| !self.recent.contains(p)));
+        let mut scored: Vec<(i32, PathBuf, String)> = candidates
+            .filter_map(|path| {
+                let label = self.label(path);
+                let score = fuzzy_score(&label, &query)?;
+                Some((score, path.clone(), label))
+            })
+            .collect();
+        if !query.trim().is_empty() {
+            scored.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+        }
+        scored.into_iter().take(SHOWN).map(|(_, path, label)| (path, label)).collect()
+    }
+
+    fn open_selected(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let Some((path, _)) = self.matches(cx).into_iter().nth(self.selected) else { return false };
+        window.close_dialog(cx);
+        let _ = self.workspace.update(cx, |ws, cx| ws.open_file(path, window, cx));
+        true
+    }
+
+    fn key_down(&mut self, event: &KeyDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        let count = self.matches(cx).len();
+        match event.keystroke.key.as_str() {
+            "down" if self.selected + 1 < count => self.selected += 1,
+            "up" => self.selected = self.selected.saturating_sub(1),
+            _ => return,
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+}
+
+impl Render for QuickOpenPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let colors = Theme::global(cx).colors;
+        let matches = self.matches(cx);
+        let status = if self.indexing {
+            Some("Indexing folder...")
+        } else if matches.is_empty() {
+            Some("No matching files.")
+        } else {
+            None
+        };
+        let rows = matches.into_iter().enumerate().map(|(i, (_, label))| {
+            h_flex()
+                .id(("quick-open", i))
+                .px_2()
+                .py_1()
+                .cursor_pointer()
+                .when(i == self.selected, |this| this.bg(colors.accent))
+                .hover(move |this| this.bg(colors.accent))
+                .on_click(cx.listener(move |this, _, window, cx| {
+                    this.selected = i;
+                    this.open_selected(window, cx);
+                }))
+                .child(div().overflow_hidden().whitespace_nowrap().child(label))
+        });
+
+        v_flex()
+            .gap_2()
+            .capture_key_down(cx.listener(Self::key_down))
+            .child(Input::new(&self.query))
+            .child(div().id("quick-open-files").max_h(px(320.0)).overflow_y_scroll().children(rows))
+            .children(status.map(|status| div().text_color(colors.muted_foreground).child(status)))
+    }
+}
+
+impl Workspace {
+    /// Put `path` at the top of the recent files.
+    pub(super) fn add_recent_file(&mut self, path: &Path) {
+        let recent = &mut self.settings.recent_files;
+        if recent.first().is_some_and(|p| p == path) {
+            return;
+        }
+        recent.retain(|p| p != path);
+        recent.insert(0, path.to_path_buf());
+        recent.truncate(MAX_RECENT);
+        self.settings.save();
+    }
+
+    /// Show the Quick Open palette, indexing the current file's folder in
+    /// the background.
+    pub fn quick_open(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let root = self.current_file(cx).as_deref().and_then(Path::parent).map(Path::to_path_buf);
+        let recent: Vec<PathBuf> = self.settings.recent_files.iter().filter(|p| p.is_file()).cloned().collect();
+        let workspace = cx.entity().downgrade();
+
+        let panel = cx.new(|cx| {
+            let query = cx.new(|cx| InputState::new(window, cx).placeholder("Type to find a file"));
+            let subscription = cx.subscribe_in(&query, window, |this: &mut QuickOpenPanel, _, event: &InputEvent, window, cx| {
+                match event {
+                    InputEvent::Change => {
+                        this.selected = 0;
+                        cx.notify();
+                    }
+                    InputEvent::PressEnter { .. } => {
+                        this.open_selected(window, cx);
+                    }
+                    _ => {}
+                }
+            });
+            let index_task = match root.clone() {
+                Some(root) => cx.spawn(async move |this, cx| {
+                    let files = cx.background_spawn(async move { index_folder(&root) }).await;
+                    debug!(files = files.len(), "Indexed folder for Quick Open");
+                    let _ = this.update(cx, |this, cx| {
+                        this.indexed = files;
+                        this.indexing = false;
+                        cx.notify();
+                    });
+                }),
+                None => Task::ready(()),
+            };
+            QuickOpenPanel {
+                query,
+                indexing: root.is_some(),
+                root,
+                recent,
+                indexed: Vec::new(),
+                selected: 0,
+                workspace,
+                _subscription: subscription,
+                _index_task: index_task,
+            }
+        });
+        let query = panel.read(cx).query.clone();
+        query.update(cx, |state, cx| state.focus(window, cx));
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            dialog
+                .title("Quick Open")
+                .w(px(560.0))
+                .child(panel.clone())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_score, index_folder};
+    use std::fs;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("notes/todo.txt", "tdo").is_some());
+        assert!(fuzzy_score("notes/todo.txt", "xyz").is_none());
+        assert!(fuzzy_score("notes/todo.txt", "TODO").is_some());
+        // Matching the file name beats matching scattered letters in the folder
+        assert!(fuzzy_score("src/main.rs", "main") > fuzzy_score("mail/admin.rs", "main"));
+        // Consecutive letters beat spread-out ones
+        assert!(fuzzy_score("readme.md", "read") > fuzzy_score("r-e-a-d.md", "read"));
+        // Everything matches an empty query, shorter paths first
+        assert!(fuzzy_score("a.txt", "") > fuzzy_score("long/a.txt", ""));
+    }
+
+    #[test]
+    fn test_index_folder_skips_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("sub")).unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join("b.txt"), "").unwrap();
+        fs::write(dir.path().join("sub/a.txt"), "").unwrap();
+        fs::write(dir.path().join(".git/config"), "").unwrap();
+        fs::write(dir.path().join(".hidden"), "").unwrap();
+        let files = index_folder(dir.path());
+        assert_eq!(files, vec![dir.path().join("b.txt"), dir.path().join("sub/a.txt")]);
+    }
+}
@@ -0,0 +1,92 @@
+//! Renderer diagnostics and the software rendering fallback.
+
+use gpui::*;
+use gpui_component::{h_flex, v_flex, Theme, WindowExt};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::info;
+
+use super::Workspace;
+
+/// Graphics API gpui renders with on this platform.
+const BACKEND: &str = if cfg!(target_os = "windows") {
+    "DirectX 11"
+} else if cfg!(target_os = "macos") {
+    "Metal"
+} else {
+    "Vulkan"
+};
+
+/// How frames are presented on this platform.
+const VSYNC: &str = if cfg!(target_os = "windows") {
+    "Off (frames presented immediately)"
+} else if cfg!(target_os = "macos") {
+    "On (display link)"
+} else {
+    "Mailbox (newest frame each refresh)"
+};
+
+/// Whether this run asked for software rendering.
+static SOFTWARE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Ask the graphics stack for a CPU renderer. Must run before the
+/// application starts. On Linux this selects Mesa's lavapipe through the
+/// device-select layer; on Windows it turns off DirectComposition, the
+/// usual culprit with broken drivers. macOS has no fallback.
+pub fn force_software_rendering() {
+    info!("Requesting software rendering");
+    SOFTWARE_REQUESTED.store(true, Ordering::Relaxed);
+    if cfg!(target_os = "windows") {
+        std::env::set_var("GPUI_DISABLE_DIRECT_COMPOSITION", "1");
+    } else {
+        // lavapipe's vendor:device ID
+        std::env::set_var("MESA_VK_DEVICE_SELECT", "10005:0");
+        std::env::set_var("MESA_VK_DEVICE_SELECT_FORCE_DEFAULT_DEVICE", "1");
+    }
+}
+
+/// Label/value rows describing the renderer of `window`.
+fn renderer_rows(window: &Window) -> Vec<(&'static str, String)> {
+    let software_requested = SOFTWARE_REQUESTED.load(Ordering::Relaxed);
+    let specs = window.gpu_specs();
+    let adapter = specs.as_ref().map_or("Unknown".to_string(), |s| s.device_name.clone());
+    let driver = specs.as_ref().map_or("Unknown".to_string(), |s| {
+        if s.driver_info.is_empty() { s.driver_name.clone() } else { format!("{} ({})", s.driver_name, s.driver_info) }
+    });
+    let software = match specs.as_ref().map(|s| s.is_software_emulated) {
+        Some(true) => "Yes",
+        Some(false) if software_requested => "Requested, but a GPU is in use",
+        Some(false) => "No",
+        None => "Unknown",
+    };
+    vec![
+        ("Backend", BACKEND.to_string()),
+        ("Adapter", adapter),
+        ("Driver", driver),
+        ("Software rendering", software.to_string()),
+        ("Vsync", VSYNC.to_string()),
+        ("Scale factor", format!("{:.2}x", window.scale_factor())),
+    ]
+}
+
+impl Workspace {
+    /// Show the GPU backend, adapter, vsync mode and display scale.
+    pub fn show_renderer_info(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let rows = renderer_rows(window);
+        let muted = Theme::global(cx).colors.muted_foreground;
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            dialog
+                .title("Renderer Info")
+                .w(px(440.0))
+                .child(
+                    v_flex().gap_2().children(rows.iter().map(|(label, value)| {
+                        h_flex()
+                            .gap_3()
+                            .child(div().w(px(140.0)).text_color(muted).child(*label))
+                            .child(value.clone())
+                    })),
+                )
+                .alert()
+        });
+    }
+}
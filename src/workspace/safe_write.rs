@@ -0,0 +1,94 @@
+//! Crash-safe file writes: the new contents go to a temporary file beside
+//! the target, are flushed to disk, and then renamed over it, so the file
+//! is always either the old version or the new one.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Backup path for `path`: the same name with `.bak` appended.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// A temporary file in `dir` that doesn't exist yet.
+fn create_temp(dir: &Path, name: &str) -> io::Result<(PathBuf, File)> {
+    for attempt in 0..100 {
+        let temp = dir.join(format!(".{}.{}.{}.tmp", name, std::process::id(), attempt));
+        match OpenOptions::new().write(true).create_new(true).open(&temp) {
+            Ok(file) => return Ok((temp, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::AlreadyExists, "no free temporary file name"))
+}
+
+/// Replace `path` with `bytes`, optionally copying the old file to
+/// `<name>.bak` first.
+pub fn write_atomic(path: &Path, bytes: &[u8], backup: bool) -> io::Result<()> {
+    // Write through symlinks rather than replacing them
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let existing = fs::metadata(&path).ok();
+
+    let (temp, mut file) = create_temp(&dir, name)?;
+    let written = (|| {
+        file.write_all(bytes)?;
+        if let Some(metadata) = &existing {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()
+    })();
+    drop(file);
+    if let Err(err) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+
+    if backup && existing.is_some() {
+        if let Err(err) = fs::copy(&path, backup_path(&path)) {
+            let _ = fs::remove_file(&temp);
+            return Err(err);
+        }
+    }
+    if let Err(err) = fs::rename(&temp, &path) {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+    // Make the rename itself durable; not every platform can open a directory
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(&dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{backup_path, write_atomic};
+    use std::fs;
+
+    #[test]
+    fn test_write_atomic_with_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        write_atomic(&path, b"first", true).unwrap();
+        assert!(!backup_path(&path).exists());
+
+        write_atomic(&path, b"second", true).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read(backup_path(&path)).unwrap(), b"first");
+
+        write_atomic(&path, b"third", false).unwrap();
+        assert_eq!(fs::read(backup_path(&path)).unwrap(), b"first");
+        // No temporary files left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
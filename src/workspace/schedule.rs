@@ -0,0 +1,114 @@
+//! Switching between the day and night themes by the clock.
+
+use chrono::{Local, NaiveTime, Timelike};
+use gpui::*;
+use gpui_component::Theme;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info};
+
+use super::Workspace;
+use super::file_ops::with_workspace_async;
+use crate::settings::{SessionOverrides, ThemeSchedule};
+
+/// How often the schedule is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Parse an "HH:MM" time of day.
+pub fn parse_clock(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text.trim(), "%H:%M").ok()
+}
+
+/// The theme the schedule picks at `now`, or `None` when it is off or its
+/// times don't parse.
+pub fn scheduled_theme(schedule: &ThemeSchedule, now: NaiveTime) -> Option<&str> {
+    if !schedule.enabled {
+        return None;
+    }
+    let light = parse_clock(&schedule.light_from)?;
+    let dark = parse_clock(&schedule.dark_from)?;
+    let now = now.with_second(0).unwrap_or(now);
+    // Either period may run past midnight
+    let is_light = if light <= dark {
+        light <= now && now < dark
+    } else {
+        !(dark <= now && now < light)
+    };
+    Some(if is_light { &schedule.light_theme } else { &schedule.dark_theme })
+}
+
+impl Workspace {
+    /// (Re)start the schedule timer from the current settings.
+    pub(crate) fn start_theme_schedule(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.settings.theme_schedule.enabled {
+            self.theme_schedule_task = None;
+            return;
+        }
+        self.apply_scheduled_theme(window, cx);
+
+        self.theme_schedule_task = Some(cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                loop {
+                    let started = SystemTime::now();
+                    cx.background_executor().timer(CHECK_INTERVAL).await;
+                    // The timer doesn't advance while the machine sleeps, so
+                    // a late tick means the period may have changed meanwhile
+                    let woke = started.elapsed().is_ok_and(|elapsed| elapsed > CHECK_INTERVAL * 2);
+                    let checked = with_workspace_async(&mut cx, |this, window, cx_ws| {
+                        if woke {
+                            debug!("Woke from sleep; re-evaluating the theme schedule");
+                        }
+                        this.apply_scheduled_theme(window, cx_ws);
+                    });
+                    if checked.is_none() {
+                        break;
+                    }
+                }
+            }
+        }));
+    }
+
+    /// Switch to the theme the schedule picks for now, if it isn't the one
+    /// saved or the one showing.
+    pub(crate) fn apply_scheduled_theme(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        // A theme given on the command line wins for the whole run
+        if SessionOverrides::get().theme.is_some() {
+            return;
+        }
+        let Some(theme) = scheduled_theme(&self.settings.theme_schedule, Local::now().time()) else { return };
+        // Another window may have saved the setting without this one showing it
+        if theme != self.settings.theme || theme != Theme::global(cx).theme_name().as_ref() {
+            info!("Switching to scheduled theme {}", theme);
+            self.apply_theme(theme.to_string(), window, cx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_clock, scheduled_theme};
+    use crate::settings::ThemeSchedule;
+
+    fn at(text: &str) -> chrono::NaiveTime {
+        parse_clock(text).unwrap()
+    }
+
+    #[test]
+    fn test_scheduled_theme() {
+        let mut schedule = ThemeSchedule { enabled: true, ..ThemeSchedule::default() };
+        assert_eq!(scheduled_theme(&schedule, at("06:59")), Some("Default Dark"));
+        assert_eq!(scheduled_theme(&schedule, at("07:00")), Some("Default Light"));
+        assert_eq!(scheduled_theme(&schedule, at("19:00")), Some("Default Dark"));
+
+        // Light period across midnight
+        schedule.light_from = "22:00".to_string();
+        schedule.dark_from = "06:00".to_string();
+        assert_eq!(scheduled_theme(&schedule, at("23:30")), Some("Default Light"));
+        assert_eq!(scheduled_theme(&schedule, at("12:00")), Some("Default Dark"));
+
+        schedule.dark_from = "25:00".to_string();
+        assert_eq!(scheduled_theme(&schedule, at("12:00")), None);
+        schedule.enabled = false;
+        assert_eq!(scheduled_theme(&schedule, at("12:00")), None);
+    }
+}
@@ -0,0 +1,98 @@
+//! Stepping to the previous or next text file in the current file's folder.
+
+use gpui::*;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use super::Workspace;
+use super::compression::Compression;
+
+/// Bytes read to decide whether a file is text.
+const SNIFF_LEN: usize = 8 * 1024;
+
+/// Whether `path` looks like something the editor can show: compressed, or
+/// without NUL bytes near the start.
+fn looks_like_text(path: &Path) -> bool {
+    if !matches!(Compression::from_path(path), Compression::None) {
+        return true;
+    }
+    let Ok(file) = File::open(path) else { return false };
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    if file.take(SNIFF_LEN as u64).read_to_end(&mut head).is_err() {
+        return false;
+    }
+    !head.contains(&0)
+}
+
+/// Sort key putting names in alphabetical order regardless of case.
+fn sort_key(path: &Path) -> (String, PathBuf) {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+    (name, path.to_path_buf())
+}
+
+/// The alphabetically previous or next text file beside `current`, skipping
+/// hidden files. `None` at either end of the folder.
+pub fn sibling_file(current: &Path, forward: bool) -> Option<PathBuf> {
+    let dir = current.parent()?;
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && !path.file_name().unwrap_or_default().to_string_lossy().starts_with('.'))
+        .collect();
+    files.sort_by_key(|path| sort_key(path));
+
+    let key = sort_key(current);
+    if forward {
+        files.into_iter().filter(|path| sort_key(path) > key).find(|path| looks_like_text(path))
+    } else {
+        files.into_iter().rev().filter(|path| sort_key(path) < key).find(|path| looks_like_text(path))
+    }
+}
+
+impl Workspace {
+    /// Open the previous (or next) text file in the current file's folder.
+    pub fn open_sibling_file(&mut self, forward: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(current) = self.current_file(cx) else {
+            debug!("No file open; nothing to step from");
+            return;
+        };
+        let Some(path) = sibling_file(&current, forward) else {
+            debug!(path = ?current, forward, "No more files in this folder");
+            return;
+        };
+        self.handle_unsaved_changes(window, cx, move |this, window, cx| {
+            if !path.exists() {
+                warn!(path = ?path, "File disappeared before it could be opened");
+                return;
+            }
+            this.load_file(path, false, window, cx);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sibling_file;
+    use std::fs;
+
+    #[test]
+    fn test_sibling_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        fs::write(path("a.txt"), "a").unwrap();
+        fs::write(path("B.log"), "b").unwrap();
+        fs::write(path("c.bin"), [0u8, 1, 2]).unwrap();
+        fs::write(path("d.md"), "d").unwrap();
+        fs::write(path(".hidden"), "h").unwrap();
+        fs::create_dir(path("e-dir")).unwrap();
+
+        assert_eq!(sibling_file(&path("a.txt"), true), Some(path("B.log")));
+        // Binary files are skipped
+        assert_eq!(sibling_file(&path("B.log"), true), Some(path("d.md")));
+        assert_eq!(sibling_file(&path("d.md"), false), Some(path("B.log")));
+        assert_eq!(sibling_file(&path("d.md"), true), None);
+        assert_eq!(sibling_file(&path("a.txt"), false), None);
+    }
+}
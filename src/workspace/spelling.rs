@@ -0,0 +1,43 @@
+//! Turning spell checking on and loading its dictionary.
+
+use gpui::*;
+use gpui_component::notification::Notification;
+use gpui_component::WindowExt;
+use tracing::warn;
+
+use super::file_ops::with_workspace_async;
+use super::Workspace;
+use crate::editor::SpellChecker;
+
+impl Workspace {
+    /// Turn spell checking on or off and remember the choice.
+    pub fn toggle_spell_check(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.settings.spell_check = !self.settings.spell_check;
+        self.settings.save();
+        self.apply_editor_settings(window, cx);
+        self.load_dictionary(window, cx);
+        cx.notify();
+    }
+
+    /// Load the dictionary in the background if spell checking is on and it
+    /// isn't loaded yet, and say so if there is none.
+    pub(super) fn load_dictionary(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let language = self.settings.spell_check_language.clone();
+        if !self.settings.spell_check || SpellChecker::has_language(cx, &language) {
+            return;
+        }
+        let task = SpellChecker::load(language, cx);
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                if let Err(err) = task.await {
+                    warn!(error = %err, "Failed to load dictionary");
+                    with_workspace_async(&mut cx, |_this, window, cx| {
+                        window.push_notification(Notification::warning(err.to_string()).title("Spell Check"), cx);
+                    });
+                }
+            }
+        })
+        .detach();
+    }
+}
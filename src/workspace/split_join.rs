@@ -0,0 +1,513 @@
+//! Splitting a file into numbered parts and joining files back together,
+//! for data too big to handle or send in one piece.
+
+use gpui::*;
+use gpui_component::button::{Button, ButtonGroup};
+use gpui_component::dialog::DialogButtonProps;
+use gpui_component::input::{Input, InputState};
+use gpui_component::progress::Progress;
+use gpui_component::{h_flex, v_flex, Disableable, Selectable, Sizable, Theme, WindowExt};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use super::Workspace;
+use super::file_ops::{file_dialog, remember_dialog_dir, with_workspace_async};
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Where to cut a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitBy {
+    /// This many lines per part.
+    Lines(usize),
+    /// At most this many bytes per part.
+    Bytes(usize),
+}
+
+/// A split or join running in the background.
+pub struct FileJob {
+    label: String,
+    /// Bytes handled so far, updated by the worker.
+    done: Arc<AtomicU64>,
+    total: u64,
+}
+
+impl FileJob {
+    fn percent(&self) -> f32 {
+        match self.total {
+            0 => 100.0,
+            total => (self.done.load(Ordering::Relaxed) as f64 / total as f64 * 100.0).min(100.0) as f32,
+        }
+    }
+}
+
+/// Byte ranges of the parts of `bytes`. Parts end at line ends; by size, a
+/// line longer than a whole part is cut, though never inside a character.
+pub fn split_ranges(bytes: &[u8], by: SplitBy) -> Vec<Range<usize>> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    match by {
+        SplitBy::Lines(lines) => {
+            let mut count = 0;
+            for (i, _) in bytes.iter().enumerate().filter(|(_, b)| **b == b'\n') {
+                count += 1;
+                if count == lines.max(1) {
+                    parts.push(start..i + 1);
+                    start = i + 1;
+                    count = 0;
+                }
+            }
+        }
+        SplitBy::Bytes(limit) => {
+            let limit = limit.max(4);
+            while bytes.len() - start > limit {
+                let end = match bytes[start..start + limit].iter().rposition(|&b| b == b'\n') {
+                    Some(i) => start + i + 1,
+                    None => {
+                        let mut end = start + limit;
+                        while end > start + 1 && bytes[end] & 0xC0 == 0x80 {
+                            end -= 1;
+                        }
+                        end
+                    }
+                };
+                parts.push(start..end);
+                start = end;
+            }
+        }
+    }
+    if start < bytes.len() || parts.is_empty() {
+        parts.push(start..bytes.len());
+    }
+    parts
+}
+
+/// Path of part `index` (0-based) of `count` split from `path`:
+/// `data.csv` becomes `data.part001.csv`, `data.part002.csv`, ...
+pub fn part_path(path: &Path, index: usize, count: usize) -> PathBuf {
+    let width = count.to_string().len().max(3);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.part{:0width$}.{}", stem, index + 1, ext.to_string_lossy(), width = width),
+        None => format!("{}.part{:0width$}", stem, index + 1, width = width),
+    };
+    path.with_file_name(name)
+}
+
+/// Write the parts of `path`, adding the bytes written to `done`. Returns
+/// the part files; none are written if any already exists.
+fn split_file(path: &Path, by: SplitBy, done: &AtomicU64) -> io::Result<Vec<PathBuf>> {
+    let bytes = fs::read(path)?;
+    let ranges = split_ranges(&bytes, by);
+    let parts: Vec<PathBuf> = (0..ranges.len()).map(|i| part_path(path, i, ranges.len())).collect();
+    if let Some(existing) = parts.iter().find(|p| p.exists()) {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", existing.display())));
+    }
+    for (part, range) in parts.iter().zip(ranges) {
+        fs::write(part, &bytes[range.clone()])?;
+        done.fetch_add(range.len() as u64, Ordering::Relaxed);
+    }
+    Ok(parts)
+}
+
+/// Copy `sources` one after another into `out`, adding the bytes copied to
+/// `done`.
+fn join_into(sources: &[PathBuf], out: &mut impl Write, done: &AtomicU64) -> io::Result<()> {
+    let mut chunk = vec![0; CHUNK_SIZE];
+    for source in sources {
+        let mut file = File::open(source)?;
+        loop {
+            let n = file.read(&mut chunk)?;
+            if n == 0 {
+                break;
+            }
+            out.write_all(&chunk[..n])?;
+            done.fetch_add(n as u64, Ordering::Relaxed);
+        }
+    }
+    out.flush()
+}
+
+/// Where joined files go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JoinTarget {
+    /// Inserted at the caret.
+    Buffer,
+    /// Written to a file picked next.
+    File,
+}
+
+struct JoinPanel {
+    files: Vec<PathBuf>,
+    target: JoinTarget,
+    /// Whether the buffer takes edits.
+    can_insert: bool,
+}
+
+impl Render for JoinPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let muted = Theme::global(cx).colors.muted_foreground;
+        let list = self.files.iter().enumerate().map(|(i, path)| {
+            h_flex()
+                .gap_2()
+                .child(div().w(px(32.0)).text_color(muted).child(format!("{}.", i + 1)))
+                .child(path.file_name().unwrap_or_default().to_string_lossy().to_string())
+        });
+        v_flex()
+            .gap_3()
+            .child("The files are joined in this order.")
+            .child(div().id("join-files").max_h(px(280.0)).overflow_y_scroll().text_sm().children(list))
+            .child(
+                ButtonGroup::new("join-target")
+                    .small()
+                    .child(
+                        Button::new("join-into-buffer")
+                            .label("Insert at caret")
+                            .selected(self.target == JoinTarget::Buffer)
+                            .disabled(!self.can_insert),
+                    )
+                    .child(Button::new("join-into-file").label("Save as new file").selected(self.target == JoinTarget::File))
+                    .on_click(cx.listener(|this, clicks: &Vec<usize>, _, cx| {
+                        this.target = match clicks.first() {
+                            Some(0) if this.can_insert => JoinTarget::Buffer,
+                            _ => JoinTarget::File,
+                        };
+                        cx.notify();
+                    })),
+            )
+    }
+}
+
+struct SplitPanel {
+    path: PathBuf,
+    by_lines: bool,
+    amount: Entity<InputState>,
+}
+
+impl SplitPanel {
+    /// The split asked for, if the amount is a positive number.
+    fn split_by(&self, cx: &App) -> Option<SplitBy> {
+        let amount = self.amount.read(cx).value().trim().parse::<usize>().ok().filter(|&n| n > 0)?;
+        Some(match self.by_lines {
+            true => SplitBy::Lines(amount),
+            false => SplitBy::Bytes(amount.saturating_mul(1024)),
+        })
+    }
+}
+
+impl Render for SplitPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let unit = if self.by_lines { "lines per part" } else { "KB per part" };
+        v_flex()
+            .gap_3()
+            .child(format!("File: {}", self.path.display()))
+            .child(
+                ButtonGroup::new("split-by")
+                    .small()
+                    .child(Button::new("split-by-lines").label("By lines").selected(self.by_lines))
+                    .child(Button::new("split-by-size").label("By size").selected(!self.by_lines))
+                    .on_click(cx.listener(|this, clicks: &Vec<usize>, _, cx| {
+                        this.by_lines = clicks.first() == Some(&0);
+                        cx.notify();
+                    })),
+            )
+            .child(h_flex().gap_3().items_center().child(div().w(px(120.0)).child(Input::new(&self.amount))).child(unit))
+    }
+}
+
+impl Workspace {
+    /// Pick a file and cut it into numbered parts beside it.
+    pub fn split_file_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let dialog = file_dialog(self.current_file(cx).as_deref(), None, &self.settings.file_filters).set_title("Split File");
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let picked = cx.background_spawn(async move { dialog.pick_file().await.map(|f| f.path().to_path_buf()) });
+                let Some(path) = picked.await else {
+                    debug!("Split file canceled");
+                    return;
+                };
+                remember_dialog_dir(&path);
+                with_workspace_async(&mut cx, |this, window, cx| this.split_options_dialog(path, window, cx));
+            }
+        })
+        .detach();
+    }
+
+    fn split_options_dialog(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        let amount = cx.new(|cx| InputState::new(window, cx).default_value("1000"));
+        let panel = cx.new(|_| SplitPanel { path, by_lines: true, amount });
+        let workspace = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let panel_for_ok = panel.clone();
+            let workspace = workspace.clone();
+            dialog
+                .title("Split File")
+                .w(px(520.0))
+                .child(panel.clone())
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Split"))
+                .on_ok(move |_, window, cx| {
+                    let panel = panel_for_ok.read(cx);
+                    let Some(by) = panel.split_by(cx) else { return false };
+                    let path = panel.path.clone();
+                    let _ = workspace.update(cx, |this, cx| this.run_split(path, by, window, cx));
+                    true
+                })
+        });
+    }
+
+    fn run_split(&mut self, path: PathBuf, by: SplitBy, window: &mut Window, cx: &mut Context<Self>) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let total = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let done = self.start_file_job(format!("Splitting {}", name), total, window, cx);
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let result = cx.background_spawn({
+                    let path = path.clone();
+                    async move { split_file(&path, by, &done) }
+                }).await;
+                let message = match result {
+                    Ok(parts) => {
+                        info!(path = ?path, parts = parts.len(), "Split file");
+                        let first = parts.first().and_then(|p| p.file_name()).unwrap_or_default().to_string_lossy().to_string();
+                        format!("Split {} into {} parts ({}, ...).", name, parts.len(), first)
+                    }
+                    Err(err) => {
+                        warn!(path = ?path, error = %err, "Failed to split file");
+                        format!("Couldn't split {}: {}", name, err)
+                    }
+                };
+                with_workspace_async(&mut cx, |this, window, cx| this.finish_file_job("Split File", message, window, cx));
+            }
+        })
+        .detach();
+    }
+
+    /// Pick files and put them together, in name order, at the caret or in
+    /// a new file.
+    pub fn join_files_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let dialog = file_dialog(self.current_file(cx).as_deref(), None, &self.settings.file_filters).set_title("Join Files");
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let picked = cx.background_spawn(async move {
+                    dialog.pick_files().await.map(|files| files.iter().map(|f| f.path().to_path_buf()).collect::<Vec<_>>())
+                });
+                let Some(mut files) = picked.await.filter(|files| !files.is_empty()) else {
+                    debug!("Join files canceled");
+                    return;
+                };
+                // Numbered parts come back in order
+                files.sort();
+                remember_dialog_dir(&files[0]);
+                with_workspace_async(&mut cx, |this, window, cx| this.join_options_dialog(files, window, cx));
+            }
+        })
+        .detach();
+    }
+
+    fn join_options_dialog(&mut self, files: Vec<PathBuf>, window: &mut Window, cx: &mut Context<Self>) {
+        let can_insert = self.editor_entity.as_ref().is_some_and(|e| !e.read(cx).read_only);
+        let target = if can_insert { JoinTarget::Buffer } else { JoinTarget::File };
+        let count = files.len();
+        let panel = cx.new(|_| JoinPanel { files, target, can_insert });
+        let workspace = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let panel_for_ok = panel.clone();
+            let workspace = workspace.clone();
+            dialog
+                .title(format!("Join {} Files", count))
+                .w(px(520.0))
+                .child(panel.clone())
+                .confirm()
+                .button_props(DialogButtonProps::default().ok_text("Join"))
+                .on_ok(move |_, window, cx| {
+                    let panel = panel_for_ok.read(cx);
+                    let (files, target) = (panel.files.clone(), panel.target);
+                    let _ = workspace.update(cx, |this, cx| this.join_files(files, target, window, cx));
+                    true
+                })
+        });
+    }
+
+    fn join_files(&mut self, files: Vec<PathBuf>, target: JoinTarget, window: &mut Window, cx: &mut Context<Self>) {
+        let total = files.iter().filter_map(|p| fs::metadata(p).ok()).map(|m| m.len()).sum();
+        let count = files.len();
+        let dialog = file_dialog(files.first().map(PathBuf::as_path), None, &self.settings.file_filters).set_title("Save Joined File");
+        cx.spawn_in(window, move |this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let dest = match target {
+                    JoinTarget::Buffer => None,
+                    JoinTarget::File => {
+                        let picked = cx.background_spawn(async move { dialog.save_file().await.map(|f| f.path().to_path_buf()) });
+                        let Some(dest) = picked.await else {
+                            debug!("Join files canceled");
+                            return;
+                        };
+                        if files.contains(&dest) {
+                            with_workspace_async(&mut cx, |this, window, cx| {
+                                this.finish_file_job("Join Files", "The joined file can't be one of the files being joined.".into(), window, cx)
+                            });
+                            return;
+                        }
+                        Some(dest)
+                    }
+                };
+                let Some(done) = cx.update(|window, cx| {
+                    this.update(cx, |this, cx| this.start_file_job(format!("Joining {} files", count), total, window, cx)).ok()
+                }).ok().flatten() else {
+                    return;
+                };
+
+                let result = cx.background_spawn({
+                    let dest = dest.clone();
+                    async move {
+                        match dest {
+                            Some(dest) => {
+                                let mut out = io::BufWriter::new(File::create(&dest)?);
+                                join_into(&files, &mut out, &done).map(|_| None)
+                            }
+                            None => {
+                                let mut bytes = Vec::new();
+                                join_into(&files, &mut bytes, &done)?;
+                                String::from_utf8(bytes)
+                                    .map(Some)
+                                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "the files aren't all UTF-8 text"))
+                            }
+                        }
+                    }
+                }).await;
+
+                with_workspace_async(&mut cx, |this, window, cx| {
+                    let message = match (result, &dest) {
+                        (Ok(Some(text)), _) => {
+                            this.with_editor(cx, |ed, cx| ed.insert_text(&text, window, cx));
+                            info!(files = count, "Joined files into the buffer");
+                            format!("Inserted {} files at the caret.", count)
+                        }
+                        (Ok(None), Some(dest)) => {
+                            info!(files = count, dest = ?dest, "Joined files");
+                            format!("Joined {} files into {}.", count, dest.display())
+                        }
+                        (Ok(None), None) => unreachable!("joining into the buffer returns its text"),
+                        (Err(err), _) => {
+                            warn!(error = %err, "Failed to join files");
+                            format!("Couldn't join the files: {}", err)
+                        }
+                    };
+                    this.finish_file_job("Join Files", message, window, cx);
+                });
+            }
+        })
+        .detach();
+    }
+
+    /// Show a progress strip for a job over `total` bytes, repainted until
+    /// the job finishes. Returns the counter the worker adds to.
+    fn start_file_job(&mut self, label: String, total: u64, window: &mut Window, cx: &mut Context<Self>) -> Arc<AtomicU64> {
+        let done = Arc::new(AtomicU64::new(0));
+        self.file_job = Some(FileJob { label, done: done.clone(), total });
+        cx.notify();
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                loop {
+                    cx.background_executor().timer(PROGRESS_INTERVAL).await;
+                    let running = with_workspace_async(&mut cx, |this, _window, cx| {
+                        cx.notify();
+                        this.file_job.is_some()
+                    });
+                    if running != Some(true) {
+                        break;
+                    }
+                }
+            }
+        })
+        .detach();
+        done
+    }
+
+    fn finish_file_job(&mut self, title: &'static str, message: String, window: &mut Window, cx: &mut Context<Self>) {
+        self.file_job = None;
+        cx.notify();
+        window.open_dialog(cx, move |dialog, _window, _cx| dialog.title(title).child(message.clone()).alert());
+    }
+
+    pub(super) fn render_file_job(&self, cx: &App) -> Option<impl IntoElement> {
+        let job = self.file_job.as_ref()?;
+        let percent = job.percent();
+        Some(
+            h_flex()
+                .gap_2()
+                .px_2()
+                .py_1()
+                .text_sm()
+                .text_color(Theme::global(cx).colors.muted_foreground)
+                .child(format!("{}... {:.0}%", job.label, percent))
+                .child(div().flex_1().child(Progress::new().value(percent))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{join_into, part_path, split_file, split_ranges, SplitBy};
+    use std::fs;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_split_by_lines() {
+        let text = b"a\nb\nc\nd\ne";
+        assert_eq!(split_ranges(text, SplitBy::Lines(2)), vec![0..4, 4..8, 8..9]);
+        assert_eq!(split_ranges(b"a\nb\n", SplitBy::Lines(2)), vec![0..4]);
+        assert_eq!(split_ranges(b"", SplitBy::Lines(2)), vec![0..0]);
+    }
+
+    #[test]
+    fn test_split_by_size() {
+        // Parts end at the last line end that fits
+        assert_eq!(split_ranges(b"aaaa\nbb\ncccccc\n", SplitBy::Bytes(8)), vec![0..8, 8..15]);
+        // A long line is cut, but not inside a character
+        let text = "abcdeé".as_bytes();
+        assert_eq!(split_ranges(text, SplitBy::Bytes(6)), vec![0..5, 5..7]);
+    }
+
+    #[test]
+    fn test_part_path() {
+        assert_eq!(part_path(Path::new("/d/data.csv"), 0, 12), Path::new("/d/data.part001.csv"));
+        assert_eq!(part_path(Path::new("/d/log"), 41, 1200), Path::new("/d/log.part0042"));
+    }
+
+    #[test]
+    fn test_split_then_join_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("notes.txt");
+        let text = "one\ntwo\nthree\nfour\nfive\n";
+        fs::write(&source, text).unwrap();
+
+        let done = AtomicU64::new(0);
+        let parts = split_file(&source, SplitBy::Lines(2), &done).unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(done.load(Ordering::Relaxed), text.len() as u64);
+        assert_eq!(fs::read_to_string(&parts[1]).unwrap(), "three\nfour\n");
+        // Existing parts are never overwritten
+        assert!(split_file(&source, SplitBy::Lines(2), &done).is_err());
+
+        let mut joined = Vec::new();
+        join_into(&parts, &mut joined, &AtomicU64::new(0)).unwrap();
+        assert_eq!(joined, text.as_bytes());
+    }
+}
@@ -0,0 +1,160 @@
+//! Watch-folder intake: new .txt files dropped into a chosen folder open
+//! in a window of their own.
+
+use gpui::*;
+use gpui_component::notification::Notification;
+use gpui_component::WindowExt;
+use notify::event::{CreateKind, ModifyKind, RenameMode};
+use notify::EventKind;
+use rfd::AsyncFileDialog;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use super::Workspace;
+use super::file_ops::with_workspace_async;
+use crate::watcher::DirWatcher;
+
+/// How long a new file is left alone so the program writing it can finish.
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// A folder being watched for new files.
+pub(super) struct FolderWatch {
+    pub dir: PathBuf,
+    _task: Task<()>,
+}
+
+fn is_intake_file(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("txt"))
+}
+
+/// Text files that `event` reports as newly appearing, by creation or by
+/// being renamed into place.
+fn new_text_files(event: &notify::Event) -> Vec<PathBuf> {
+    let appeared = match event.kind {
+        EventKind::Create(CreateKind::File | CreateKind::Any) => true,
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => true,
+        // Renames within the folder report the old and new path together
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            return event.paths.get(1).filter(|p| is_intake_file(p)).cloned().into_iter().collect();
+        }
+        _ => false,
+    };
+    if !appeared {
+        return Vec::new();
+    }
+    event.paths.iter().filter(|p| is_intake_file(p)).cloned().collect()
+}
+
+impl Workspace {
+    pub fn watch_folder_enabled(&self) -> bool {
+        self.folder_watch.is_some()
+    }
+
+    /// Start watching the configured folder, asking for one if none is set,
+    /// or stop watching.
+    pub fn toggle_watch_folder(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(watch) = self.folder_watch.take() {
+            info!(dir = ?watch.dir, "Stopped watching folder");
+            cx.notify();
+            return;
+        }
+        match self.settings.watch_folder.clone().filter(|dir| dir.is_dir()) {
+            Some(dir) => self.start_watch_folder(dir, window, cx),
+            None => self.choose_watch_folder(window, cx),
+        }
+    }
+
+    /// Pick the folder to watch, remember it and start watching it.
+    pub fn choose_watch_folder(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let mut dialog = AsyncFileDialog::new().set_title("Watch Folder");
+        if let Some(dir) = self.settings.watch_folder.as_deref().filter(|d| d.is_dir()) {
+            dialog = dialog.set_directory(dir);
+        }
+        cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                let picked = cx.background_spawn(async move { dialog.pick_folder().await.map(|d| d.path().to_path_buf()) });
+                let Some(dir) = picked.await else {
+                    debug!("Watch folder canceled");
+                    return;
+                };
+                with_workspace_async(&mut cx, |this, window, cx| {
+                    this.settings.watch_folder = Some(dir.clone());
+                    this.settings.save();
+                    this.start_watch_folder(dir, window, cx);
+                });
+            }
+        })
+        .detach();
+    }
+
+    fn start_watch_folder(&mut self, dir: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        let mut watcher = match DirWatcher::new(&dir, |event| new_text_files(&event)) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                warn!(dir = ?dir, error = %err, "Failed to watch folder");
+                window.push_notification(Notification::error(format!("Can't watch {}: {}", dir.display(), err)), cx);
+                return;
+            }
+        };
+        info!(dir = ?dir, "Watching folder for new text files");
+
+        let task = cx.spawn_in(window, move |_this: WeakEntity<Self>, cx: &mut AsyncWindowContext| {
+            let mut cx = cx.clone();
+            async move {
+                // Writers often create a file and then modify it; open each once
+                let mut opened = HashSet::new();
+                while let Some(paths) = watcher.next_batch(SETTLE, cx.background_executor()).await {
+                    for path in paths {
+                        if !opened.insert(path.clone()) || !path.is_file() {
+                            continue;
+                        }
+                        let shown = with_workspace_async(&mut cx, |this, window, cx| this.open_intake_file(path, window, cx));
+                        if shown.is_none() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+        self.folder_watch = Some(FolderWatch { dir, _task: task });
+        cx.notify();
+    }
+
+    /// Open a file that appeared in the watched folder in a new window.
+    fn open_intake_file(&mut self, path: PathBuf, window: &mut Window, cx: &mut Context<Self>) {
+        info!(path = ?path, "Opening new file from watched folder");
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        window.push_notification(Notification::info(format!("Opened {} from the watched folder.", name)), cx);
+        let settings = self.settings.clone();
+        cx.defer(move |cx| {
+            crate::open_workspace_window(cx, settings, Some(path), false, None);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::new_text_files;
+    use notify::event::{CreateKind, ModifyKind, RemoveKind, RenameMode};
+    use notify::{Event, EventKind};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_new_text_files() {
+        let created = Event::new(EventKind::Create(CreateKind::File))
+            .add_path(PathBuf::from("/in/report.TXT"))
+            .add_path(PathBuf::from("/in/image.png"));
+        assert_eq!(new_text_files(&created), vec![PathBuf::from("/in/report.TXT")]);
+
+        let renamed = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(PathBuf::from("/in/report.tmp"))
+            .add_path(PathBuf::from("/in/report.txt"));
+        assert_eq!(new_text_files(&renamed), vec![PathBuf::from("/in/report.txt")]);
+
+        let removed = Event::new(EventKind::Remove(RemoveKind::File)).add_path(PathBuf::from("/in/old.txt"));
+        assert!(new_text_files(&removed).is_empty());
+    }
+}